@@ -0,0 +1,55 @@
+#ifndef ICON_FINDER_H
+#define ICON_FINDER_H
+
+/* Generated by cbindgen, do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Looks up icons in a theme, its parents and the hicolor fallback theme.
+typedef struct IconFinder IconFinder;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a finder for the theme with the given internal name, or for the
+// theme selected by the user if `theme` is null. Returns null if the theme
+// could not be loaded.
+//
+// # Safety
+// `theme` must be null or a valid null terminated string.
+IconFinder *icon_finder_new(const char *theme);
+
+// Looks up an icon, see `find_icon`. Returns the path of the icon, or null
+// if it is not found. The path has to be freed with
+// [`icon_finder_string_free`].
+//
+// # Safety
+// `finder` must be a finder returned by [`icon_finder_new`] that was not
+// freed yet, `icon` must be a valid null terminated string.
+char *icon_finder_find(const IconFinder *finder, const char *icon, int16_t size, int16_t scale);
+
+// Frees a finder returned by [`icon_finder_new`]. Does nothing if `finder`
+// is null.
+//
+// # Safety
+// `finder` must be null or a finder returned by [`icon_finder_new`] that
+// was not freed yet.
+void icon_finder_free(IconFinder *finder);
+
+// Frees a string returned by one of the other functions. Does nothing if
+// `string` is null.
+//
+// # Safety
+// `string` must be null or a string returned by one of the other functions
+// that was not freed yet.
+void icon_finder_string_free(char *string);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ICON_FINDER_H */
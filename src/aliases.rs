@@ -0,0 +1,271 @@
+//! # Icon name aliases
+//! Older applications still request icons by names that predate the Icon
+//! Naming Specification, like the GTK stock names (gtk-open), the GNOME 2
+//! names (stock_new-dir, gnome-fs-directory) and the KDE 3 names (fileopen).
+//! Current themes no longer ship these names, so an alias maps each of them
+//! to the name of the icon that replaced it. Aliases are only consulted once
+//! the name itself is not found, so themes that still provide the legacy
+//! icon keep being used.
+
+use std::collections::BTreeMap;
+
+/// The well-known legacy names and the names that replaced them.
+const BUILTIN_ALIASES: &[(&str, &str)] = &[
+    // GTK stock icons
+    ("gtk-about", "help-about"),
+    ("gtk-add", "list-add"),
+    ("gtk-bold", "format-text-bold"),
+    ("gtk-cdrom", "media-optical"),
+    ("gtk-clear", "edit-clear"),
+    ("gtk-close", "window-close"),
+    ("gtk-copy", "edit-copy"),
+    ("gtk-cut", "edit-cut"),
+    ("gtk-delete", "edit-delete"),
+    ("gtk-dialog-error", "dialog-error"),
+    ("gtk-dialog-info", "dialog-information"),
+    ("gtk-dialog-question", "dialog-question"),
+    ("gtk-dialog-warning", "dialog-warning"),
+    ("gtk-directory", "folder"),
+    ("gtk-execute", "system-run"),
+    ("gtk-file", "text-x-generic"),
+    ("gtk-find", "edit-find"),
+    ("gtk-find-and-replace", "edit-find-replace"),
+    ("gtk-fullscreen", "view-fullscreen"),
+    ("gtk-go-down", "go-down"),
+    ("gtk-go-up", "go-up"),
+    ("gtk-goto-bottom", "go-bottom"),
+    ("gtk-goto-first", "go-first"),
+    ("gtk-goto-last", "go-last"),
+    ("gtk-goto-top", "go-top"),
+    ("gtk-harddisk", "drive-harddisk"),
+    ("gtk-help", "help-browser"),
+    ("gtk-home", "go-home"),
+    ("gtk-indent", "format-indent-more"),
+    ("gtk-info", "dialog-information"),
+    ("gtk-italic", "format-text-italic"),
+    ("gtk-jump-to", "go-jump"),
+    ("gtk-justify-center", "format-justify-center"),
+    ("gtk-justify-fill", "format-justify-fill"),
+    ("gtk-justify-left", "format-justify-left"),
+    ("gtk-justify-right", "format-justify-right"),
+    ("gtk-leave-fullscreen", "view-restore"),
+    ("gtk-media-next", "media-skip-forward"),
+    ("gtk-media-pause", "media-playback-pause"),
+    ("gtk-media-play", "media-playback-start"),
+    ("gtk-media-previous", "media-skip-backward"),
+    ("gtk-media-record", "media-record"),
+    ("gtk-media-stop", "media-playback-stop"),
+    ("gtk-missing-image", "image-missing"),
+    ("gtk-network", "network-workgroup"),
+    ("gtk-new", "document-new"),
+    ("gtk-open", "document-open"),
+    ("gtk-paste", "edit-paste"),
+    ("gtk-preferences", "preferences-system"),
+    ("gtk-print", "document-print"),
+    ("gtk-print-preview", "document-print-preview"),
+    ("gtk-properties", "document-properties"),
+    ("gtk-quit", "application-exit"),
+    ("gtk-redo", "edit-redo"),
+    ("gtk-refresh", "view-refresh"),
+    ("gtk-remove", "list-remove"),
+    ("gtk-revert-to-saved", "document-revert"),
+    ("gtk-save", "document-save"),
+    ("gtk-save-as", "document-save-as"),
+    ("gtk-select-all", "edit-select-all"),
+    ("gtk-sort-ascending", "view-sort-ascending"),
+    ("gtk-sort-descending", "view-sort-descending"),
+    ("gtk-spell-check", "tools-check-spelling"),
+    ("gtk-stop", "process-stop"),
+    ("gtk-strikethrough", "format-text-strikethrough"),
+    ("gtk-underline", "format-text-underline"),
+    ("gtk-undo", "edit-undo"),
+    ("gtk-unindent", "format-indent-less"),
+    ("gtk-zoom-100", "zoom-original"),
+    ("gtk-zoom-fit", "zoom-fit-best"),
+    ("gtk-zoom-in", "zoom-in"),
+    ("gtk-zoom-out", "zoom-out"),
+    // GNOME 2 stock icons
+    ("stock_about", "help-about"),
+    ("stock_close", "window-close"),
+    ("stock_copy", "edit-copy"),
+    ("stock_cut", "edit-cut"),
+    ("stock_delete", "edit-delete"),
+    ("stock_exit", "application-exit"),
+    ("stock_file-properties", "document-properties"),
+    ("stock_folder", "folder"),
+    ("stock_help", "help-browser"),
+    ("stock_home", "go-home"),
+    ("stock_lock", "system-lock-screen"),
+    ("stock_mail-compose", "mail-message-new"),
+    ("stock_mail-forward", "mail-forward"),
+    ("stock_mail-reply", "mail-reply-sender"),
+    ("stock_mail-send", "mail-send"),
+    ("stock_new-dir", "folder-new"),
+    ("stock_new-text", "document-new"),
+    ("stock_open", "document-open"),
+    ("stock_paste", "edit-paste"),
+    ("stock_print", "document-print"),
+    ("stock_redo", "edit-redo"),
+    ("stock_refresh", "view-refresh"),
+    ("stock_save", "document-save"),
+    ("stock_save-as", "document-save-as"),
+    ("stock_search", "edit-find"),
+    ("stock_undo", "edit-undo"),
+    ("stock_volume", "audio-volume-medium"),
+    ("stock_volume-mute", "audio-volume-muted"),
+    ("stock_zoom-in", "zoom-in"),
+    ("stock_zoom-out", "zoom-out"),
+    // GNOME 2 theme icons
+    ("gnome-dev-cdrom", "media-optical"),
+    ("gnome-dev-computer", "computer"),
+    ("gnome-dev-floppy", "media-floppy"),
+    ("gnome-dev-harddisk", "drive-harddisk"),
+    ("gnome-dev-ipod", "multimedia-player"),
+    ("gnome-dev-memory", "media-flash"),
+    ("gnome-dev-printer", "printer"),
+    ("gnome-dev-removable", "drive-removable-media"),
+    ("gnome-fs-client", "computer"),
+    ("gnome-fs-desktop", "user-desktop"),
+    ("gnome-fs-directory", "folder"),
+    ("gnome-fs-executable", "application-x-executable"),
+    ("gnome-fs-home", "user-home"),
+    ("gnome-fs-network", "network-workgroup"),
+    ("gnome-fs-regular", "text-x-generic"),
+    ("gnome-fs-trash-empty", "user-trash"),
+    ("gnome-fs-trash-full", "user-trash-full"),
+    ("gnome-lockscreen", "system-lock-screen"),
+    ("gnome-logout", "system-log-out"),
+    ("gnome-mime-audio", "audio-x-generic"),
+    ("gnome-mime-image", "image-x-generic"),
+    ("gnome-mime-text", "text-x-generic"),
+    ("gnome-mime-video", "video-x-generic"),
+    ("gnome-package", "package-x-generic"),
+    ("gnome-run", "system-run"),
+    ("gnome-searchtool", "system-search"),
+    ("gnome-shutdown", "system-shutdown"),
+    ("gnome-terminal", "utilities-terminal"),
+    // KDE 3 icons
+    ("editcopy", "edit-copy"),
+    ("editcut", "edit-cut"),
+    ("editdelete", "edit-delete"),
+    ("editpaste", "edit-paste"),
+    ("exit", "application-exit"),
+    ("filenew", "document-new"),
+    ("fileopen", "document-open"),
+    ("fileprint", "document-print"),
+    ("filesave", "document-save"),
+    ("filesaveas", "document-save-as"),
+    ("folder_new", "folder-new"),
+    ("redo", "edit-redo"),
+    ("undo", "edit-undo"),
+];
+
+/// A map from legacy icon names to the names that replaced them, consulted
+/// when a name is not found, see [`LookupOptions::aliases`].
+///
+/// ```
+/// use icon_finder::IconAliases;
+///
+/// let aliases = IconAliases::builtin().alias("my-app-old", "my-app");
+/// assert_eq!(aliases.get("gtk-open"), Some("document-open"));
+/// assert_eq!(aliases.get("my-app-old"), Some("my-app"));
+/// ```
+///
+/// [`LookupOptions::aliases`]: crate::LookupOptions::aliases
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct IconAliases {
+    aliases: BTreeMap<String, String>,
+}
+
+impl IconAliases {
+    /// An empty map, without the built-in aliases.
+    pub fn new() -> IconAliases {
+        IconAliases::default()
+    }
+
+    /// The well-known aliases of the GTK stock icons and the icons of GNOME 2
+    /// and KDE 3, like gtk-open for document-open.
+    pub fn builtin() -> IconAliases {
+        IconAliases {
+            aliases: BUILTIN_ALIASES
+                .iter()
+                .map(|(legacy, name)| ((*legacy).to_owned(), (*name).to_owned()))
+                .collect(),
+        }
+    }
+
+    /// Adds an alias, replacing the one of the same legacy name.
+    pub fn alias(mut self, legacy: &str, name: &str) -> IconAliases {
+        self.insert(legacy, name);
+        self
+    }
+
+    /// Adds an alias, returning the name the legacy name was an alias of
+    /// before.
+    pub fn insert(&mut self, legacy: &str, name: &str) -> Option<String> {
+        self.aliases.insert(legacy.to_owned(), name.to_owned())
+    }
+
+    /// The name the legacy name is an alias of.
+    pub fn get(&self, legacy: &str) -> Option<&str> {
+        self.aliases.get(legacy).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.aliases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::tests::memory_themes;
+    use crate::{find_icon_in, FileSystem, LookupOptions, Theme};
+
+    #[test]
+    fn test_icon_aliases() {
+        let aliases = IconAliases::builtin();
+        assert_eq!(aliases.len(), BUILTIN_ALIASES.len());
+        assert_eq!(aliases.get("stock_new-dir"), Some("folder-new"));
+        assert_eq!(aliases.get("gnome-fs-directory"), Some("folder"));
+        assert_eq!(aliases.get("document-open"), None);
+
+        let mut aliases = aliases.alias("gtk-open", "folder-open");
+        assert_eq!(aliases.get("gtk-open"), Some("folder-open"));
+        assert_eq!(
+            aliases.insert("gtk-open", "document-open"),
+            Some("folder-open".to_owned())
+        );
+        assert!(IconAliases::new().is_empty());
+    }
+
+    #[test]
+    fn test_find_aliased_icon() {
+        let fs: Arc<dyn FileSystem> = memory_themes();
+        let theme = Theme::load_from("icon-finder-test", &*fs).unwrap();
+        let find = |icon: &str, aliases: Option<IconAliases>| {
+            let mut options = LookupOptions::new(48).with_file_system(Some(&fs));
+            if let Some(aliases) = aliases {
+                options = options.aliases(aliases);
+            }
+            find_icon_in(icon, &options, &theme, &[]).map(|result| result.path)
+        };
+        let aliases = || Some(IconAliases::builtin().alias("old-editor", "editor"));
+
+        assert_eq!(
+            find("old-editor", aliases()).as_deref(),
+            Some(Path::new(
+                "/usr/share/icons/icon-finder-test/48x48/apps/editor.png"
+            ))
+        );
+        assert_eq!(find("gtk-open", aliases()), None);
+        assert_eq!(find("old-editor", None), None);
+    }
+}
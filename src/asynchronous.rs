@@ -0,0 +1,262 @@
+//! # Async lookups
+//! A cold lookup reads index.theme files and scans theme directories, which
+//! blocks the calling thread. Async applications, like GUIs and compositors
+//! built on tokio, should not block their reactor on it. These variants run
+//! the lookup on the blocking thread pool of tokio instead. Dropping the
+//! future of an icon lookup cancels it, loading a theme runs to completion
+//! however.
+//!
+//! Changes to the themes are available as an [`EventStream`], so async
+//! applications can refresh their icons without a thread of their own. The
+//! stream carries the events of [`subscribe`](IconFinder::subscribe): icons
+//! added to or removed from a theme are reported as
+//! [`ThemeEvent::IconsChanged`]. The cache of the finder revalidates itself
+//! when the themes change, so there is no event for invalidating it.
+
+use std::panic;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::Stream;
+use tokio::sync::mpsc::{self, UnboundedReceiver};
+use tokio::task;
+
+use crate::watch::{self, Watcher};
+use crate::{
+    find_icon_with, CancellationToken, Error, IconFinder, LookupOptions, LookupResult, Theme,
+    ThemeEvent, POLL_INTERVAL,
+};
+
+/// Looks up an icon like [`find_icon`](crate::find_icon), without blocking
+/// the async runtime. Has to be called from within a tokio runtime.
+pub async fn find_icon_async(
+    icon: &str,
+    size: i16,
+    scale: i16,
+    user_selected_theme: Theme,
+) -> Option<PathBuf> {
+    let icon = icon.to_owned();
+    let mut options = LookupOptions::new(size).scale(scale);
+    let _guard = cancel_on_drop(&mut options);
+
+    blocking(move || find_icon_with(&icon, &options, user_selected_theme)).await
+}
+
+/// Loads a theme like [`Theme::load`], without blocking the async runtime.
+/// Has to be called from within a tokio runtime. The theme is loaded even if
+/// the future is dropped.
+pub async fn load_theme_async(name: &str) -> Result<Theme, Error> {
+    let name = name.to_owned();
+
+    blocking(move || Theme::load(&name)).await
+}
+
+impl IconFinder {
+    /// Looks up an icon like [`find_icon`](IconFinder::find_icon), without
+    /// blocking the async runtime. Has to be called from within a tokio
+    /// runtime.
+    pub async fn find_icon_async(
+        self: &Arc<IconFinder>,
+        icon: &str,
+        size: i16,
+        scale: i16,
+    ) -> Option<PathBuf> {
+        self.find_icon_with_async(icon, &LookupOptions::new(size).scale(scale))
+            .await
+    }
+
+    /// Looks up an icon like [`find_icon_with`](IconFinder::find_icon_with),
+    /// without blocking the async runtime.
+    pub async fn find_icon_with_async(
+        self: &Arc<IconFinder>,
+        icon: &str,
+        options: &LookupOptions,
+    ) -> Option<PathBuf> {
+        self.lookup_icon_with_async(icon, options)
+            .await
+            .map(|result| result.path)
+    }
+
+    /// Looks up an icon like
+    /// [`lookup_icon_with`](IconFinder::lookup_icon_with), without blocking
+    /// the async runtime.
+    pub async fn lookup_icon_with_async(
+        self: &Arc<IconFinder>,
+        icon: &str,
+        options: &LookupOptions,
+    ) -> Option<LookupResult> {
+        let finder = Arc::clone(self);
+        let icon = icon.to_owned();
+        let mut options = options.clone();
+        let _guard = cancel_on_drop(&mut options);
+
+        blocking(move || finder.lookup_icon_with(&icon, &options)).await
+    }
+
+    /// Watches for changes like [`subscribe`](IconFinder::subscribe), as an
+    /// async stream of events.
+    ///
+    /// ```no_run
+    /// # async fn refresh() {
+    /// let mut events = icon_finder::global().subscribe_stream();
+    /// while let Some(event) = events.next().await {
+    ///     // Look up the icons again.
+    /// }
+    /// # }
+    /// ```
+    pub fn subscribe_stream(&self) -> EventStream {
+        EventStream::spawn(self.watcher(), POLL_INTERVAL)
+    }
+}
+
+/// Stream of theme events, as returned by
+/// [`IconFinder::subscribe_stream`]. Dropping the stream stops watching for
+/// changes. Does not need to be polled from within a tokio runtime.
+pub struct EventStream {
+    receiver: UnboundedReceiver<ThemeEvent>,
+    /// Dropping the sender stops the worker. It is not joined, as that would
+    /// block the runtime dropping the stream.
+    _stop: Sender<()>,
+}
+
+impl EventStream {
+    fn spawn(watcher: Watcher, interval: Duration) -> EventStream {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let (stop, _) =
+            watch::spawn_worker(watcher, interval, move |event| sender.send(event).is_ok());
+
+        EventStream {
+            receiver,
+            _stop: stop,
+        }
+    }
+
+    /// Waits for the next event.
+    pub async fn next(&mut self) -> Option<ThemeEvent> {
+        self.receiver.recv().await
+    }
+}
+
+impl Stream for EventStream {
+    type Item = ThemeEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<ThemeEvent>> {
+        self.receiver.poll_recv(context)
+    }
+}
+
+/// Cancels the token when dropped, e.g. together with the future of a
+/// lookup.
+struct CancelOnDrop(CancellationToken);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Makes dropping the returned guard, together with the future of a lookup,
+/// stop the lookup, unless the caller cancels it through a token of their
+/// own.
+fn cancel_on_drop(options: &mut LookupOptions) -> Option<CancelOnDrop> {
+    if options.cancellation.0.is_some() {
+        return None;
+    }
+
+    let token = CancellationToken::new();
+    *options = options.clone().cancellation(&token);
+    Some(CancelOnDrop(token))
+}
+
+/// Runs a blocking function on the blocking thread pool, passing on panics.
+async fn blocking<T, F>(function: F) -> T
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    match task::spawn_blocking(function).await {
+        Ok(value) => value,
+        Err(error) if error.is_panic() => panic::resume_unwind(error.into_panic()),
+        // Blocking tasks are only cancelled when the runtime shuts down.
+        Err(error) => panic!("lookup did not complete: {}", error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileSystem, MemoryFileSystem};
+    use std::path::Path;
+    use tokio::runtime::Builder;
+
+    fn no_theme() -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_find_icon_async() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file(
+            "/usr/share/icons/icon-finder-async/index.theme",
+            "[Icon Theme]\nName=Async\nDirectories=48x48/apps\n\n\
+             [48x48/apps]\nSize=48\nType=Fixed\n",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-async/48x48/apps/editor.png",
+            "",
+        );
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+
+        let theme = Theme::load_from("icon-finder-async", &*fs).unwrap();
+        let finder = Arc::new(IconFinder::builder(theme).file_system(fs).build());
+        let runtime = Builder::new_current_thread().build().unwrap();
+
+        runtime.block_on(async {
+            assert_eq!(
+                finder.find_icon_async("editor", 48, 1).await.as_deref(),
+                Some(Path::new(
+                    "/usr/share/icons/icon-finder-async/48x48/apps/editor.png"
+                ))
+            );
+            assert_eq!(finder.find_icon_async("browser", 48, 1).await, None);
+            assert!(matches!(
+                load_theme_async("icon-finder-nonexistent-theme").await,
+                Err(Error::ThemeNotFound(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_cancel_on_drop() {
+        let mut options = LookupOptions::new(48);
+        let guard = cancel_on_drop(&mut options);
+        assert!(guard.is_some());
+        assert!(!options.is_cancelled());
+        drop(guard);
+        assert!(options.is_cancelled());
+
+        let token = CancellationToken::new();
+        let mut options = LookupOptions::new(48).cancellation(&token);
+        assert!(cancel_on_drop(&mut options).is_none());
+        assert!(!options.is_cancelled());
+    }
+
+    #[test]
+    fn test_event_stream() {
+        let fs = Arc::new(MemoryFileSystem::new());
+        let directory = PathBuf::from("/icons/icon-finder-stream");
+        let watcher = Watcher::new(no_theme, vec![directory.clone()], Vec::new(), fs.clone());
+        let mut events = EventStream::spawn(watcher, Duration::from_millis(10));
+
+        fs.add_dir(&directory);
+        let runtime = Builder::new_current_thread().build().unwrap();
+        assert_eq!(
+            runtime.block_on(events.next()),
+            Some(ThemeEvent::IconsChanged(directory))
+        );
+    }
+}
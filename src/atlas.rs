@@ -0,0 +1,183 @@
+//! # Icon atlases
+//! User interfaces drawing on the GPU, like games and Wayland bars, prefer a
+//! single texture holding all their icons over a texture per icon, as
+//! switching textures is expensive. An atlas packs the icons into a grid of
+//! equally sized cells, along with the rectangle every icon ended up in.
+//! Requires the `image` feature, and the `render` feature for SVG icons.
+
+use std::collections::BTreeMap;
+use std::io;
+
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+
+use crate::{IconPixels, Rectangle};
+
+/// Icons packed into a single image, see
+/// [`IconFinder::build_atlas`](crate::IconFinder::build_atlas).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconAtlas {
+    pub pixels: IconPixels,
+    /// The rectangle of every icon in the atlas, by name, in pixels from the
+    /// top left corner. The bottom right corner is exclusive.
+    pub icons: BTreeMap<String, Rectangle>,
+    /// The icons that were not found or could not be rasterized, in the
+    /// order they were given.
+    pub missing: Vec<String>,
+}
+
+fn image(pixels: IconPixels) -> io::Result<RgbaImage> {
+    RgbaImage::from_raw(pixels.width, pixels.height, pixels.data)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "incomplete pixel data"))
+}
+
+/// Scales the pixels to a square of the given width, e.g. PNG icons found at
+/// another size than the one they were looked up at.
+pub(crate) fn resize(pixels: IconPixels, size: u32) -> io::Result<IconPixels> {
+    if pixels.width == size && pixels.height == size {
+        return Ok(pixels);
+    }
+
+    let image = imageops::resize(&image(pixels)?, size, size, FilterType::Lanczos3);
+    Ok(IconPixels {
+        width: size,
+        height: size,
+        data: image.into_raw(),
+    })
+}
+
+/// Rasterizes every icon into a cell of `cell` × `cell` pixels and packs the
+/// cells into a grid as close to a square as possible.
+pub(crate) fn build_atlas(
+    icons: &[&str],
+    cell: u32,
+    mut rasterize: impl FnMut(&str) -> io::Result<Option<IconPixels>>,
+) -> io::Result<IconAtlas> {
+    let mut rasterized = Vec::new();
+    let mut missing = Vec::new();
+
+    for &icon in icons {
+        if rasterized.iter().any(|(name, _)| *name == icon) {
+            continue;
+        }
+
+        match rasterize(icon)
+            .and_then(|pixels| pixels.map(|pixels| resize(pixels, cell)).transpose())
+        {
+            Ok(Some(pixels)) => rasterized.push((icon, image(pixels)?)),
+            Ok(None) => missing.push(icon.to_owned()),
+            Err(_error) => {
+                #[cfg(feature = "log")]
+                log::debug!("leaving {} out of the atlas: {}", icon, _error);
+                missing.push(icon.to_owned());
+            }
+        }
+    }
+
+    let count = rasterized.len() as u32;
+    let columns = (0..)
+        .find(|columns| columns * columns >= count)
+        .unwrap_or(0);
+    let rows = if columns == 0 {
+        0
+    } else {
+        count.div_ceil(columns)
+    };
+    let mut atlas = RgbaImage::new(columns * cell, rows * cell);
+
+    let mut positions = BTreeMap::new();
+    for (index, (icon, image)) in (0..).zip(rasterized) {
+        let x = index % columns * cell;
+        let y = index / columns * cell;
+        imageops::replace(&mut atlas, &image, i64::from(x), i64::from(y));
+
+        let rectangle = Rectangle {
+            x0: x as i32,
+            y0: y as i32,
+            x1: (x + cell) as i32,
+            y1: (y + cell) as i32,
+        };
+        positions.insert(icon.to_owned(), rectangle);
+    }
+
+    Ok(IconAtlas {
+        pixels: IconPixels {
+            width: atlas.width(),
+            height: atlas.height(),
+            data: atlas.into_raw(),
+        },
+        icons: positions,
+        missing,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(size: u32, color: [u8; 4]) -> IconPixels {
+        IconPixels {
+            width: size,
+            height: size,
+            data: color.repeat((size * size) as usize),
+        }
+    }
+
+    #[test]
+    fn test_resize() {
+        let pixels = resize(filled(4, [255, 0, 0, 255]), 2).unwrap();
+        assert_eq!(pixels, filled(2, [255, 0, 0, 255]));
+
+        let broken = IconPixels {
+            width: 2,
+            height: 2,
+            data: vec![0; 3],
+        };
+        assert!(resize(broken, 4).is_err());
+    }
+
+    #[test]
+    fn test_build_atlas() {
+        let colors = [
+            ("red", [255, 0, 0, 255]),
+            ("green", [0, 255, 0, 255]),
+            ("blue", [0, 0, 255, 255]),
+        ];
+        let rasterize = |icon: &str| match icon {
+            "broken" => Err(io::Error::new(io::ErrorKind::InvalidData, "broken")),
+            // Found at twice the size of the cells.
+            _ => Ok(colors
+                .iter()
+                .find(|(name, _)| *name == icon)
+                .map(|(_, color)| filled(8, *color))),
+        };
+
+        let atlas = build_atlas(
+            &["red", "missing", "green", "red", "broken", "blue"],
+            4,
+            rasterize,
+        )
+        .unwrap();
+        assert_eq!((atlas.pixels.width, atlas.pixels.height), (8, 8));
+        assert_eq!(atlas.missing, ["missing", "broken"]);
+        assert_eq!(
+            atlas.icons["blue"],
+            Rectangle {
+                x0: 0,
+                y0: 4,
+                x1: 4,
+                y1: 8
+            }
+        );
+
+        let pixel = |x: u32, y: u32| &atlas.pixels.data[((y * 8 + x) * 4) as usize..][..4];
+        assert_eq!(pixel(1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(5, 2), [0, 255, 0, 255]);
+        assert_eq!(pixel(3, 7), [0, 0, 255, 255]);
+        assert_eq!(pixel(6, 6), [0, 0, 0, 0]);
+
+        let empty = build_atlas(&["missing"], 4, rasterize).unwrap();
+        assert_eq!((empty.pixels.width, empty.pixels.height), (0, 0));
+        assert!(empty.icons.is_empty());
+    }
+}
@@ -0,0 +1,647 @@
+//! # icon-finder
+//! Command line interface to the icon lookup, for shell scripts, bars like
+//! eww and waybar, and for debugging why an icon does (not) show up.
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::engine::{ArgValueCandidates, ArgValueCompleter, CompletionCandidate};
+use clap_complete::env::Shells;
+use clap_complete::CompleteEnv;
+use icon_finder::{
+    base_directories, check_coverage, check_size_gaps, compare_themes, default_socket_path,
+    find_duplicate_icons, global, identify_icon, list_themes, selected_icon_theme, serve,
+    theme_gallery, validate_theme, Context, DuplicateIcons, IconFinder, LookupOptions, Theme,
+    ThemeDirectory, ThemeDirectoryType,
+};
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Find icons according to the freedesktop.org specifications"
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Prints the path of an icon.
+    Find {
+        /// The name of the icon, e.g. firefox.
+        #[arg(add = ArgValueCompleter::new(icon_name_candidates))]
+        name: String,
+        /// The nominal size of the icon.
+        #[arg(long, default_value_t = 48)]
+        size: i16,
+        /// The scale the icon will be displayed at.
+        #[arg(long, default_value_t = 1)]
+        scale: i16,
+        /// The icon theme to search, defaults to the theme selected by the
+        /// user.
+        #[arg(long, add = ArgValueCandidates::new(theme_candidates))]
+        theme: Option<String>,
+    },
+    /// Prints the theme, directory and name an icon file belongs to.
+    Identify {
+        /// The path of the icon file.
+        path: PathBuf,
+    },
+    /// Lists the installed icon themes.
+    Themes {
+        /// Also lists themes that are hidden from the user.
+        #[arg(long)]
+        hidden: bool,
+    },
+    /// Shows how a theme is parsed and where it is stored, to debug a theme
+    /// that misbehaves.
+    Inspect {
+        /// The internal name of the theme, e.g. Adwaita.
+        #[arg(add = ArgValueCandidates::new(theme_candidates))]
+        theme: String,
+    },
+    /// Lists the standard icons of the Icon Naming Specification a theme
+    /// does not provide.
+    Coverage {
+        /// The internal name of the theme, e.g. Adwaita.
+        #[arg(add = ArgValueCandidates::new(theme_candidates))]
+        theme: String,
+        /// The sizes to check, may be given several times.
+        #[arg(long = "size", default_values_t = [16, 24, 32, 48])]
+        sizes: Vec<i16>,
+    },
+    /// Lists the icons of a theme that are missing at some of the common
+    /// sizes, and scaled up or down there.
+    Gaps {
+        /// The internal name of the theme, e.g. Adwaita.
+        #[arg(add = ArgValueCandidates::new(theme_candidates))]
+        theme: String,
+        /// The sizes to check, may be given several times.
+        #[arg(long = "size", default_values_t = [16, 24, 32, 48])]
+        sizes: Vec<i16>,
+    },
+    /// Lists the icons one theme provides and the other does not, per context
+    /// and size.
+    Compare {
+        /// The internal name of the first theme, e.g. Adwaita.
+        #[arg(add = ArgValueCandidates::new(theme_candidates))]
+        first: String,
+        /// The internal name of the second theme.
+        #[arg(add = ArgValueCandidates::new(theme_candidates))]
+        second: String,
+    },
+    /// Lists the icon files of a theme with the same contents.
+    Duplicates {
+        /// The internal name of the theme, e.g. Adwaita.
+        #[arg(add = ArgValueCandidates::new(theme_candidates))]
+        theme: String,
+    },
+    /// Writes an HTML page showing every icon of a theme, grouped by
+    /// context.
+    Gallery {
+        /// The internal name of the theme, e.g. Adwaita.
+        #[arg(add = ArgValueCandidates::new(theme_candidates))]
+        theme: String,
+        /// The file to write the page to, defaults to standard output.
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Checks the index.theme of a theme for violations of the
+    /// specification, e.g. before installing or publishing it.
+    Validate {
+        /// The directory of the theme, containing its index.theme.
+        directory: PathBuf,
+    },
+    /// Checks the environment for common reasons icons do not show up.
+    Doctor,
+    /// Answers lookups over a unix socket, keeping the theme in memory.
+    Daemon {
+        /// The socket to listen on, defaults to
+        /// $XDG_RUNTIME_DIR/icon-finder.sock.
+        #[arg(long)]
+        socket: Option<PathBuf>,
+        /// The icon theme to search, defaults to the theme selected by the
+        /// user.
+        #[arg(long, add = ArgValueCandidates::new(theme_candidates))]
+        theme: Option<String>,
+    },
+    /// Prints the completion script for a shell. The script completes the
+    /// names of installed themes as well, e.g. for bash:
+    /// `icon-finder completions bash > ~/.local/share/bash-completion/completions/icon-finder`
+    Completions {
+        #[arg(value_parser = Shells::builtins().names().collect::<Vec<_>>())]
+        shell: String,
+    },
+}
+
+/// The name of the binary, which the completion scripts call back into.
+const BIN_NAME: &str = "icon-finder";
+
+/// Environment variable the completion scripts set when calling back into
+/// the binary.
+const COMPLETE_VARIABLE: &str = "COMPLETE";
+
+fn main() -> ExitCode {
+    // Answers the completion scripts and exits, if called by one.
+    CompleteEnv::with_factory(Cli::command)
+        .var(COMPLETE_VARIABLE)
+        .complete();
+
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Find {
+            name,
+            size,
+            scale,
+            theme,
+        } => find(&name, size, scale, theme.as_deref()),
+        Command::Identify { path } => Ok(identify(&path)),
+        Command::Themes { hidden } => {
+            themes(hidden);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Inspect { theme } => inspect(&theme).map(|()| ExitCode::SUCCESS),
+        Command::Coverage { theme, sizes } => coverage(&theme, &sizes),
+        Command::Gaps { theme, sizes } => gaps(&theme, &sizes),
+        Command::Compare { first, second } => compare(&first, &second),
+        Command::Duplicates { theme } => duplicates(&theme),
+        Command::Gallery { theme, output } => gallery(&theme, output.as_deref()),
+        Command::Validate { directory } => validate(&directory),
+        Command::Doctor => {
+            doctor();
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Daemon { socket, theme } => daemon(socket, theme.as_deref()),
+        Command::Completions { shell } => completions(&shell),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("icon-finder: {}", message);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Prints the path of the icon, exiting with 1 when it is not found.
+fn find(name: &str, size: i16, scale: i16, theme: Option<&str>) -> Result<ExitCode, String> {
+    let loaded;
+    let finder = match theme {
+        Some(theme) => {
+            loaded = IconFinder::new(load_theme(theme)?);
+            &loaded
+        }
+        None => global(),
+    };
+
+    match finder.lookup_icon_with(name, &LookupOptions::new(size).scale(scale)) {
+        Some(result) => {
+            print_path(&result.path).map_err(|error| error.to_string())?;
+            Ok(ExitCode::SUCCESS)
+        }
+        None => Ok(ExitCode::FAILURE),
+    }
+}
+
+/// Prints where the icon file belongs, exiting with 1 for files that are
+/// not icons of a theme or unthemed icons.
+fn identify(path: &Path) -> ExitCode {
+    let location = match identify_icon(path) {
+        Some(location) => location,
+        None => return ExitCode::FAILURE,
+    };
+
+    println!("name: {}", location.name);
+    println!("format: {}", location.format.extension());
+    match (&location.theme, &location.directory) {
+        (Some(theme), Some(directory)) => {
+            println!("theme: {}", theme);
+            println!("directory: {}", directory.name);
+            println!("size: {}", directory.size);
+            println!("scale: {}", directory.scale.unwrap_or(1));
+            if let Some(context) = &directory.context {
+                println!("context: {}", context.as_str());
+            }
+        }
+        (Some(theme), None) => {
+            println!("theme: {}", theme);
+            println!("directory: not listed in the index.theme of the theme");
+        }
+        (None, _) => println!("theme: none, unthemed icon"),
+    }
+    println!("base directory: {}", location.base_directory.display());
+
+    ExitCode::SUCCESS
+}
+
+/// Prints the path on a line of its own. Paths are printed as they are, even
+/// if they are not valid UTF-8, so scripts can open the file.
+fn print_path(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::io::Write;
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(path.as_os_str().as_bytes())?;
+        stdout.write_all(b"\n")
+    }
+    #[cfg(not(unix))]
+    {
+        println!("{}", path.display());
+        Ok(())
+    }
+}
+
+/// Serves lookups until the daemon fails. Lookups are cached, so icons
+/// requested over and over again are answered from memory.
+fn daemon(socket: Option<PathBuf>, theme: Option<&str>) -> Result<ExitCode, String> {
+    let socket = socket
+        .or_else(default_socket_path)
+        .ok_or("no socket given and XDG_RUNTIME_DIR is not set")?;
+
+    let theme = match theme {
+        Some(theme) => load_theme(theme)?,
+        None => global().theme().clone(),
+    };
+    let finder = IconFinder::builder(theme).cache_capacity(4096).build();
+
+    serve(&finder, &socket).map_err(|error| format!("{}: {}", socket.display(), error))?;
+    Ok(ExitCode::SUCCESS)
+}
+
+fn load_theme(name: &str) -> Result<Theme, String> {
+    Theme::load(name).map_err(|error| error.to_string())
+}
+
+/// Prints the completion script of the shell. The script calls back into the
+/// binary to complete, so completions always match the installed themes.
+fn completions(shell: &str) -> Result<ExitCode, String> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(shell)
+        .ok_or_else(|| format!("unknown shell {}", shell))?;
+
+    completer
+        .write_registration(
+            COMPLETE_VARIABLE,
+            BIN_NAME,
+            BIN_NAME,
+            BIN_NAME,
+            &mut io::stdout(),
+        )
+        .map_err(|error| error.to_string())?;
+    Ok(ExitCode::SUCCESS)
+}
+
+/// The installed themes, including hidden ones, for completing theme names.
+fn theme_candidates() -> Vec<CompletionCandidate> {
+    list_themes(true)
+        .iter()
+        .map(|theme| {
+            CompletionCandidate::new(&theme.name)
+                .help(Some(theme.display_name(None).to_owned().into()))
+        })
+        .collect()
+}
+
+/// The names of the icons in the theme of the user starting with what was
+/// typed so far.
+fn icon_name_candidates(current: &OsStr) -> Vec<CompletionCandidate> {
+    let prefix = current.to_string_lossy();
+
+    global()
+        .complete_icon_names(&prefix)
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Prints every installed theme with its display name, the themes it
+/// inherits from and where it is stored.
+fn themes(include_hidden: bool) {
+    for theme in list_themes(include_hidden) {
+        let hidden = if theme.hidden { " (hidden)" } else { "" };
+        println!("{}{}: {}", theme.name, hidden, theme.display_name(None));
+
+        let chain = ancestry_names(&theme);
+        if chain.len() > 1 {
+            println!("    inherits: {}", chain[1..].join(" -> "));
+        }
+
+        for location in theme.locations() {
+            println!("    location: {}", location.display());
+        }
+    }
+}
+
+/// Prints the parsed index.theme of a theme, the themes it inherits from and
+/// the base directories that contribute icons to it.
+fn inspect(name: &str) -> Result<(), String> {
+    let theme = load_theme(name)?;
+
+    println!("{}: {}", theme.name, theme.display_name(None));
+    println!("comment: {}", theme.comment(None));
+    println!("hidden: {}", theme.hidden);
+    if let Some(example) = &theme.example {
+        println!("example: {}", example);
+    }
+
+    println!("lookup order: {}", ancestry_names(&theme).join(" -> "));
+
+    println!("locations:");
+    let locations = theme.locations();
+    // The index.theme file of the first location that has one is used.
+    let index_location = locations
+        .iter()
+        .position(|location| location.join("index.theme").is_file());
+    for (index, location) in locations.iter().enumerate() {
+        let directories = theme
+            .directories
+            .iter()
+            .filter(|directory| location.join(&directory.name).is_dir())
+            .count();
+        let index_theme = if Some(index) == index_location {
+            ", index.theme"
+        } else {
+            ""
+        };
+        println!(
+            "    {} ({} of {} directories{})",
+            location.display(),
+            directories,
+            theme.directories.len(),
+            index_theme
+        );
+    }
+
+    println!("directories:");
+    for directory in &theme.directories {
+        println!("    {}: {}", directory.name, describe_directory(directory));
+    }
+
+    Ok(())
+}
+
+/// Prints the standard icons the theme is missing, exiting with 1 when it
+/// misses any.
+fn coverage(name: &str, sizes: &[i16]) -> Result<ExitCode, String> {
+    let theme = load_theme(name)?;
+    let report = check_coverage(&theme, sizes);
+
+    for icon in &report.missing {
+        let sizes: Vec<String> = icon.sizes.iter().map(i16::to_string).collect();
+        println!("{}: missing at {}", icon.name, sizes.join(", "));
+    }
+    println!(
+        "{} of {} standard icons complete",
+        report.checked - report.missing.len(),
+        report.checked
+    );
+
+    if report.is_complete() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Prints the sizes the icons of the theme are missing at, marking the ones
+/// they are scaled up to, exiting with 1 when any icon has gaps.
+fn gaps(name: &str, sizes: &[i16]) -> Result<ExitCode, String> {
+    let theme = load_theme(name)?;
+    let gaps = check_size_gaps(&theme, sizes);
+
+    for gap in &gaps {
+        let upscaled = gap.upscaled();
+        let missing: Vec<String> = gap
+            .missing
+            .iter()
+            .map(|size| {
+                if upscaled.contains(size) {
+                    format!("{} (upscaled)", size)
+                } else {
+                    size.to_string()
+                }
+            })
+            .collect();
+        let context = gap.context.as_ref().map_or("no context", Context::as_str);
+        println!(
+            "{} ({}): missing at {}",
+            gap.name,
+            context,
+            missing.join(", ")
+        );
+    }
+    println!("{} icons with size gaps", gaps.len());
+
+    if gaps.is_empty() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Prints the icons only one of the themes provides, exiting with 1 when the
+/// themes differ.
+fn compare(first: &str, second: &str) -> Result<ExitCode, String> {
+    let comparison = compare_themes(&load_theme(first)?, &load_theme(second)?);
+
+    for (theme, differences) in [
+        (first, &comparison.only_in_first),
+        (second, &comparison.only_in_second),
+    ] {
+        for icon in differences {
+            let context = icon.context.as_ref().map_or("no context", Context::as_str);
+            let sizes: Vec<String> = icon
+                .sizes
+                .iter()
+                .map(|size| format!("{}@{}", size.size, size.scale))
+                .collect();
+            println!(
+                "only in {}: {} ({}) at {}",
+                theme,
+                icon.name,
+                context,
+                sizes.join(", ")
+            );
+        }
+    }
+
+    if comparison.is_identical() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Prints the groups of identical icon files, exiting with 1 when there are
+/// any.
+fn duplicates(name: &str) -> Result<ExitCode, String> {
+    let duplicates = find_duplicate_icons(&load_theme(name)?);
+
+    for group in &duplicates {
+        println!("{} bytes, {} copies:", group.size, group.paths.len());
+        for path in &group.paths {
+            println!("    {}", path.display());
+        }
+    }
+    let wasted: usize = duplicates.iter().map(DuplicateIcons::wasted_bytes).sum();
+    println!("{} bytes in duplicate files", wasted);
+
+    if duplicates.is_empty() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Writes the gallery of the theme to the file, or prints it.
+fn gallery(name: &str, output: Option<&Path>) -> Result<ExitCode, String> {
+    let html = theme_gallery(&load_theme(name)?);
+
+    match output {
+        Some(path) => fs::write(path, html)
+            .map_err(|error| format!("cannot write {}: {}", path.display(), error))?,
+        None => print!("{}", html),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints the problems found in the theme, exiting with 1 when there are
+/// any.
+fn validate(directory: &Path) -> Result<ExitCode, String> {
+    let diagnostics = validate_theme(directory)
+        .map_err(|error| format!("cannot validate {}: {}", directory.display(), error))?;
+
+    let index = directory.join("index.theme");
+    for diagnostic in &diagnostics {
+        println!("{}: {}", index.display(), diagnostic);
+    }
+
+    if diagnostics.is_empty() {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+/// Prints the desktop environment, the selected theme and the base
+/// directories, along with any problems found in the base directories.
+fn doctor() {
+    let desktop = env::var("XDG_CURRENT_DESKTOP").unwrap_or_else(|_| "unknown".to_owned());
+    println!("desktop environment: {}", desktop);
+
+    match selected_icon_theme() {
+        Some(name) => match Theme::load(&name) {
+            Ok(_) => println!("selected theme: {}", name),
+            Err(error) => println!("selected theme: {} (problem: {})", name, error),
+        },
+        None => println!("selected theme: none, hicolor is used"),
+    }
+
+    println!("base directories:");
+    for directory in base_directories() {
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                println!("    {} (does not exist)", directory.display());
+                continue;
+            }
+            Err(error) => {
+                println!("    {} (problem: {})", directory.display(), error);
+                continue;
+            }
+        };
+        println!("    {}", directory.display());
+
+        let mut themes: Vec<_> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect();
+        themes.sort();
+
+        for theme in themes {
+            for problem in theme_problems(&theme) {
+                println!("        problem: {}: {}", theme.display(), problem);
+            }
+        }
+    }
+}
+
+/// Problems with a theme directory in a base directory that can keep its
+/// icons from showing up.
+fn theme_problems(theme: &Path) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if let Err(error) = fs::read_dir(theme) {
+        problems.push(format!("cannot be read: {}", error));
+        return problems;
+    }
+
+    let index_theme = theme.join("index.theme");
+    if !index_theme.is_file() {
+        // Fine for a directory that adds icons to a theme of another base
+        // directory, but such a theme cannot be selected on its own.
+        problems.push("no index.theme".to_owned());
+    } else if let Err(error) = fs::File::open(&index_theme) {
+        problems.push(format!("index.theme cannot be read: {}", error));
+    }
+
+    // GTK ignores its cache when a directory of the theme is newer than the
+    // cache, and falls back to scanning all directories.
+    let modified = |path: &Path| fs::metadata(path).and_then(|metadata| metadata.modified());
+    if let Ok(cache) = modified(&theme.join("icon-theme.cache")) {
+        if modified(theme).is_ok_and(|modified| modified > cache) {
+            problems.push("icon-theme.cache is out of date, run gtk-update-icon-cache".to_owned());
+        }
+    }
+
+    problems
+}
+
+/// The size, scale, type and context of a theme directory on a single line.
+fn describe_directory(directory: &ThemeDirectory) -> String {
+    let size = match directory.r#type {
+        ThemeDirectoryType::Fixed => format!("fixed {}", directory.size),
+        ThemeDirectoryType::Scalable => format!(
+            "scalable {}, {}-{}",
+            directory.size,
+            directory.min_size.unwrap_or(directory.size),
+            directory.max_size.unwrap_or(directory.size)
+        ),
+        ThemeDirectoryType::Threshold => format!(
+            "threshold {} +/- {}",
+            directory.size,
+            directory.threshold.unwrap_or(2)
+        ),
+    };
+
+    let context = match &directory.context {
+        Some(Context::Other(context)) => context.clone(),
+        Some(context) => format!("{:?}", context),
+        None => "no context".to_owned(),
+    };
+
+    format!(
+        "{}, scale {}, {}",
+        size,
+        directory.scale.unwrap_or(1),
+        context
+    )
+}
+
+/// The names of the theme and its parents, ending with hicolor, in the
+/// order the lookup visits them.
+fn ancestry_names(theme: &Theme) -> Vec<&str> {
+    theme.ancestry().map(|theme| theme.name.as_str()).collect()
+}
@@ -0,0 +1,436 @@
+//! # Lookup cache
+//! Some icons are looked up over and over again, like the icons of a tray or
+//! the folder icon of a file manager. The finder can remember the results of
+//! recent lookups, so these do not have to go through the lookup algorithm
+//! every time. Icons that are not found are remembered as well, as these cost
+//! a walk through every directory of every theme in the inheritance chain.
+//!
+//! Like GTK, results are only trusted as long as the modification times of
+//! the theme directories did not change. Installing icons updates the
+//! icon-theme.cache file in the toplevel directory of a theme, which changes
+//! its modification time. Following the specification, the modification
+//! times are looked at again once every revalidation interval, not on every
+//! lookup.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::filesystem::FileSystem;
+use crate::{LookupOptions, LookupResult};
+
+type Key = (String, LookupOptions);
+
+/// Time between two checks for changes. The specification asks
+/// implementations not to look at the mtime more than once every 5 seconds.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// The modification times of the directories results depend on, at the time
+/// a result was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Stamp(Vec<Option<SystemTime>>);
+
+impl Stamp {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", name = "validate_cache", skip_all)
+    )]
+    pub(crate) fn new(directories: &[PathBuf], file_system: &dyn FileSystem) -> Stamp {
+        Stamp(
+            directories
+                .iter()
+                .map(|directory| file_system.modified(directory).ok())
+                .collect(),
+        )
+    }
+}
+
+/// Counters describing how effective the cache of a finder is, see
+/// [`IconFinder::stats`](crate::IconFinder::stats). Every lookup is counted as
+/// exactly one of a hit, a negative hit or a miss. Only lookups of a single
+/// icon name are cached and counted, see
+/// [`IconFinderBuilder::cache_capacity`](crate::IconFinderBuilder::cache_capacity).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// Lookups served by the finder, cached or not.
+    pub lookups: u64,
+    /// Lookups answered from the cache with an icon.
+    pub hits: u64,
+    /// Lookups answered from the cache with an icon that was not found
+    /// before.
+    pub negative_hits: u64,
+    /// Lookups that went through the lookup algorithm, including every
+    /// lookup while the cache is disabled.
+    pub misses: u64,
+    /// Cached results that were looked up again, because a theme directory
+    /// changed since they were found.
+    pub revalidations: u64,
+    /// Time spent in the lookup algorithm, scanning theme directories.
+    pub scan_time: Duration,
+}
+
+/// What [`IconFinder::refresh`](crate::IconFinder::refresh) and
+/// [`IconFinder::invalidate`](crate::IconFinder::invalidate) reloaded.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Reloaded {
+    /// The number of cached lookup results that were dropped, these icons
+    /// are looked up again the next time they are needed.
+    pub results: usize,
+    /// The theme directories whose listings were dropped or read again, in
+    /// alphabetical order. Listings are only kept for the file system of the
+    /// process, finders with their own file system never reload any.
+    pub directories: Vec<PathBuf>,
+}
+
+struct Entry {
+    last_used: u64,
+    result: Option<LookupResult>,
+    stamp: Stamp,
+}
+
+/// Bounded cache of lookup results, evicting the least recently used result
+/// once it is full. A cached `None` means the icon was not found.
+pub(crate) struct LookupCache {
+    capacity: usize,
+    revalidation_interval: Duration,
+    /// The modification times of the directories when they were last looked
+    /// at, and when that was.
+    checked: Option<(Instant, Stamp)>,
+    /// The number of refreshers looking at the directories in the
+    /// background, while there are any lookups never do.
+    refreshers: usize,
+    /// Incremented on every access, the entry with the lowest tick is the
+    /// least recently used one.
+    tick: u64,
+    entries: HashMap<Key, Entry>,
+    recency: BTreeMap<u64, Key>,
+    stats: CacheStats,
+}
+
+impl LookupCache {
+    pub(crate) fn new(capacity: usize, revalidation_interval: Duration) -> LookupCache {
+        LookupCache {
+            capacity,
+            revalidation_interval,
+            checked: None,
+            refreshers: 0,
+            tick: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            stats: CacheStats::default(),
+        }
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn revalidation_interval(&self) -> Duration {
+        self.revalidation_interval
+    }
+
+    pub(crate) fn set_revalidation_interval(&mut self, interval: Duration) {
+        self.revalidation_interval = interval;
+    }
+
+    /// How long lookups trust the listings of directories, like the cached
+    /// results: for the revalidation interval, or as long as a refresher
+    /// looks at them.
+    pub(crate) fn listing_interval(&self) -> Duration {
+        if self.refreshers > 0 {
+            return Duration::MAX;
+        }
+
+        self.revalidation_interval
+    }
+
+    /// The modification times of the directories, if they were looked at
+    /// less than the revalidation interval ago or a refresher looks at them.
+    pub(crate) fn recent_stamp(&self) -> Option<Stamp> {
+        self.checked
+            .as_ref()
+            .filter(|(checked, _)| {
+                self.refreshers > 0 || checked.elapsed() < self.revalidation_interval
+            })
+            .map(|(_, stamp)| stamp.clone())
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub(crate) fn add_refresher(&mut self) {
+        self.refreshers += 1;
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    pub(crate) fn remove_refresher(&mut self) {
+        self.refreshers -= 1;
+    }
+
+    /// Remembers the modification times of the directories, as looked at
+    /// just now.
+    pub(crate) fn set_stamp(&mut self, stamp: Stamp) {
+        self.checked = Some((Instant::now(), stamp));
+    }
+
+    /// The cached result of the lookup, or `None` if the lookup is not cached
+    /// or the directories changed since the result was found.
+    pub(crate) fn get(
+        &mut self,
+        icon: &str,
+        options: &LookupOptions,
+        stamp: &Stamp,
+    ) -> Option<Option<LookupResult>> {
+        let key = (icon.to_owned(), options.clone());
+        let tick = self.next_tick();
+
+        let entry = self.entries.get_mut(&key)?;
+        self.recency.remove(&entry.last_used);
+
+        if entry.stamp != *stamp {
+            self.entries.remove(&key);
+            self.stats.revalidations += 1;
+            return None;
+        }
+
+        entry.last_used = tick;
+        let result = entry.result.clone();
+        self.recency.insert(tick, key);
+
+        Some(result)
+    }
+
+    /// Counts a lookup answered from the cache, `found` tells whether with
+    /// an icon.
+    pub(crate) fn record_hit(&mut self, found: bool) {
+        self.stats.lookups += 1;
+        if found {
+            self.stats.hits += 1;
+        } else {
+            self.stats.negative_hits += 1;
+        }
+    }
+
+    /// Counts a lookup that went through the lookup algorithm, taking the
+    /// given time.
+    pub(crate) fn record_miss(&mut self, scan_time: Duration) {
+        self.stats.lookups += 1;
+        self.stats.misses += 1;
+        self.stats.scan_time += scan_time;
+    }
+
+    pub(crate) fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        icon: &str,
+        options: &LookupOptions,
+        result: Option<LookupResult>,
+        stamp: Stamp,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let key = (icon.to_owned(), options.clone());
+        let tick = self.next_tick();
+        let entry = Entry {
+            last_used: tick,
+            result,
+            stamp,
+        };
+
+        if let Some(replaced) = self.entries.insert(key.clone(), entry) {
+            self.recency.remove(&replaced.last_used);
+        }
+        self.recency.insert(tick, key);
+
+        self.evict();
+    }
+
+    /// Changes the capacity, evicting the least recently used results if the
+    /// cache holds more than the new capacity.
+    pub(crate) fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.evict();
+    }
+
+    /// Drops every result, returning how many there were.
+    pub(crate) fn clear(&mut self) -> usize {
+        let dropped = self.entries.len();
+        self.checked = None;
+        self.entries.clear();
+        self.recency.clear();
+        dropped
+    }
+
+    /// Drops the results found before the directories changed, returning how
+    /// many there were.
+    pub(crate) fn retain_current(&mut self, stamp: &Stamp) -> usize {
+        let recency = &mut self.recency;
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| {
+            let current = entry.stamp == *stamp;
+            if !current {
+                recency.remove(&entry.last_used);
+            }
+            current
+        });
+        before - self.entries.len()
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
+
+    fn evict(&mut self) {
+        while self.entries.len() > self.capacity {
+            match self.recency.pop_first() {
+                Some((_, key)) => {
+                    self.entries.remove(&key);
+                }
+                None => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IconFormat, MatchKind};
+
+    fn result(path: &str) -> Option<LookupResult> {
+        Some(LookupResult {
+            path: PathBuf::from(path),
+            theme: None,
+            directory: None,
+            match_kind: MatchKind::Unthemed,
+            format: IconFormat::Png,
+        })
+    }
+
+    #[test]
+    fn test_lookup_cache_evicts_least_recently_used() {
+        let options = LookupOptions::new(48);
+        let stamp = Stamp(Vec::new());
+        let mut cache = LookupCache::new(2, Duration::ZERO);
+
+        cache.insert("firefox", &options, result("firefox.png"), stamp.clone());
+        cache.insert(
+            "thunderbird",
+            &options,
+            result("thunderbird.png"),
+            stamp.clone(),
+        );
+        assert!(cache.get("firefox", &options, &stamp).is_some());
+
+        cache.insert("gimp", &options, result("gimp.png"), stamp.clone());
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.get("thunderbird", &options, &stamp).is_none());
+        assert!(cache.get("firefox", &options, &stamp).is_some());
+        assert!(cache.get("gimp", &options, &stamp).is_some());
+
+        // Different options are different lookups.
+        assert!(cache.get("gimp", &LookupOptions::new(16), &stamp).is_none());
+
+        cache.set_capacity(1);
+        assert_eq!(cache.entries.len(), 1);
+        assert!(cache.get("gimp", &options, &stamp).is_some());
+
+        assert_eq!(cache.clear(), 1);
+        assert_eq!(cache.entries.len(), 0);
+    }
+
+    #[test]
+    fn test_lookup_cache_disabled() {
+        let options = LookupOptions::new(48);
+        let stamp = Stamp(Vec::new());
+        let mut cache = LookupCache::new(0, Duration::ZERO);
+
+        cache.insert("firefox", &options, result("firefox.png"), stamp.clone());
+        assert!(cache.get("firefox", &options, &stamp).is_none());
+    }
+
+    #[test]
+    fn test_lookup_cache_negative_results() {
+        let options = LookupOptions::new(48);
+        let stamp = Stamp(vec![Some(SystemTime::UNIX_EPOCH)]);
+        let mut cache = LookupCache::new(2, Duration::ZERO);
+
+        cache.insert("some-app-tray", &options, None, stamp.clone());
+        assert_eq!(cache.get("some-app-tray", &options, &stamp), Some(None));
+
+        // The icon may have been installed once the directories changed.
+        let changed = Stamp(vec![Some(SystemTime::now())]);
+        assert_eq!(cache.get("some-app-tray", &options, &changed), None);
+        assert!(cache.entries.is_empty());
+        assert!(cache.recency.is_empty());
+    }
+
+    #[test]
+    fn test_lookup_cache_retain_current() {
+        let options = LookupOptions::new(48);
+        let old = Stamp(vec![Some(SystemTime::UNIX_EPOCH)]);
+        let current = Stamp(vec![Some(SystemTime::now())]);
+        let mut cache = LookupCache::new(4, Duration::ZERO);
+
+        cache.insert("firefox", &options, result("firefox.png"), old.clone());
+        cache.insert("gimp", &options, None, old);
+        cache.insert("thunderbird", &options, None, current.clone());
+
+        assert_eq!(cache.retain_current(&current), 2);
+        assert_eq!(cache.entries.len(), 1);
+        assert_eq!(cache.recency.len(), 1);
+        assert_eq!(cache.get("thunderbird", &options, &current), Some(None));
+        assert_eq!(cache.retain_current(&current), 0);
+    }
+
+    #[test]
+    fn test_lookup_cache_revalidation_interval() {
+        let stamp = Stamp(vec![Some(SystemTime::UNIX_EPOCH)]);
+        let mut cache = LookupCache::new(2, Duration::MAX);
+        assert_eq!(cache.recent_stamp(), None);
+
+        // Never looked at again.
+        cache.set_stamp(stamp.clone());
+        assert_eq!(cache.recent_stamp(), Some(stamp.clone()));
+
+        // Looked at on every lookup.
+        cache.set_revalidation_interval(Duration::ZERO);
+        assert_eq!(cache.recent_stamp(), None);
+
+        cache.set_revalidation_interval(Duration::from_secs(5));
+        assert_eq!(cache.recent_stamp(), Some(stamp));
+        cache.clear();
+        assert_eq!(cache.recent_stamp(), None);
+    }
+
+    #[test]
+    fn test_lookup_cache_stats() {
+        let options = LookupOptions::new(48);
+        let stamp = Stamp(vec![None]);
+        let mut cache = LookupCache::new(2, Duration::ZERO);
+
+        cache.insert("firefox", &options, result("firefox.png"), stamp.clone());
+        cache.record_hit(true);
+        cache.record_hit(false);
+        cache.record_miss(Duration::from_millis(3));
+
+        let changed = Stamp(vec![Some(SystemTime::UNIX_EPOCH)]);
+        assert!(cache.get("firefox", &options, &changed).is_none());
+
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                lookups: 3,
+                hits: 1,
+                negative_hits: 1,
+                misses: 1,
+                revalidations: 1,
+                scan_time: Duration::from_millis(3),
+            }
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! # Cancellation
+//! A cold lookup can take a while, e.g. when /usr/share is mounted over NFS.
+//! An application that no longer needs the icon, because the user navigated
+//! away, can cancel the lookup with a [`CancellationToken`]. Cancellation is
+//! cooperative: the lookup checks the token before reading every directory,
+//! and returns `None` once it is cancelled.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Cancels the lookups it is passed to, see
+/// [`LookupOptions::cancellation`](crate::LookupOptions::cancellation).
+/// Clones share their state, so a lookup running on another thread can be
+/// cancelled through a clone of its token.
+///
+/// ```
+/// use icon_finder::{CancellationToken, LookupOptions};
+///
+/// let token = CancellationToken::new();
+/// let options = LookupOptions::new(48).cancellation(&token);
+///
+/// // E.g. from another thread, while the lookup is running.
+/// token.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    pub fn new() -> CancellationToken {
+        CancellationToken::default()
+    }
+
+    /// Cancels every lookup using the token, now and in the future.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+}
+
+/// The token of a lookup, if any. Cancelled lookups are never cached, so the
+/// token does not affect the result and all tokens compare equal, letting
+/// lookups with and without a token share cached results.
+#[derive(Clone, Default)]
+pub(crate) struct Cancellation(pub(crate) Option<CancellationToken>);
+
+impl Cancellation {
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.as_ref().is_some_and(CancellationToken::is_cancelled)
+    }
+}
+
+impl PartialEq for Cancellation {
+    fn eq(&self, _: &Cancellation) -> bool {
+        true
+    }
+}
+
+impl Eq for Cancellation {}
+
+impl Hash for Cancellation {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl fmt::Debug for Cancellation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Cancellation")
+    }
+}
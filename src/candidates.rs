@@ -0,0 +1,225 @@
+//! # Candidates
+//! The lookup algorithm picks a single icon, but some applications have needs
+//! the algorithm does not cater for, like always wanting the largest icon
+//! available. Those applications can go through all candidates instead.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::vec;
+
+use crate::listing;
+use crate::{
+    directory_matches_size, directory_physical_distance, scan_directories, theme_result,
+    IconFormat, LookupFlags, LookupOptions, LookupResult, MatchKind, Theme, ThemeDirectory,
+};
+
+/// Iterator over every icon file matching a name, as returned by
+/// [`find_icon_candidates`](crate::find_icon_candidates). Themes are visited
+/// in lookup order, followed by unthemed icons. The candidates of a theme are
+/// ordered by their distance to the requested size, exact matches first.
+/// With [`LookupFlags::CANONICALIZE`](crate::LookupFlags::CANONICALIZE),
+/// files resolving to a file returned before are left out.
+pub struct Candidates<'a> {
+    icon: String,
+    options: LookupOptions,
+    themes: vec::IntoIter<&'a Theme>,
+    unthemed: bool,
+    current: vec::IntoIter<LookupResult>,
+    /// The canonical paths returned so far, if paths are canonicalized.
+    returned: HashSet<PathBuf>,
+}
+
+impl<'a> Candidates<'a> {
+    pub(crate) fn new(
+        icon: &str,
+        options: LookupOptions,
+        theme: &'a Theme,
+        fallbacks: &'a [Arc<Theme>],
+    ) -> Candidates<'a> {
+        Candidates {
+            icon: icon.to_owned(),
+            options,
+            themes: theme.ancestry_with(fallbacks).into_iter(),
+            unthemed: true,
+            current: Vec::new().into_iter(),
+            returned: HashSet::new(),
+        }
+    }
+}
+
+impl Iterator for Candidates<'_> {
+    type Item = LookupResult;
+
+    fn next(&mut self) -> Option<LookupResult> {
+        loop {
+            if let Some(candidate) = self.current.next() {
+                if !self.options.flags.contains(LookupFlags::CANONICALIZE) {
+                    return Some(candidate);
+                }
+
+                let candidate = self.options.canonical_result(candidate);
+                if self.returned.insert(candidate.path.clone()) {
+                    return Some(candidate);
+                }
+                continue;
+            }
+
+            let candidates = if let Some(theme) = self.themes.next() {
+                theme_candidates(&self.icon, &self.options, theme)
+            } else if self.unthemed {
+                self.unthemed = false;
+                unthemed_candidates(&self.icon, &self.options)
+            } else {
+                return None;
+            };
+
+            self.current = candidates.into_iter();
+        }
+    }
+}
+
+fn theme_candidates(icon: &str, options: &LookupOptions, theme: &Theme) -> Vec<LookupResult> {
+    let directories = scan_directories(theme, options, |subdir| {
+        let files = directory_files(icon, subdir, options, theme);
+        if files.is_empty() {
+            return None;
+        }
+
+        let (exact, distance) = match options.physical_size() {
+            Some(physical_size) => {
+                let distance = directory_physical_distance(subdir, physical_size);
+                (distance == 0.0, distance)
+            }
+            None => (
+                directory_matches_size(subdir, options.size, options.scale),
+                f32::from(options.size_distance(subdir)),
+            ),
+        };
+
+        let match_kind = if exact {
+            MatchKind::Exact
+        } else {
+            MatchKind::Closest
+        };
+
+        let candidates: Vec<_> = files
+            .into_iter()
+            .map(|(path, format)| {
+                let candidate = theme_result(path, format, theme, subdir, match_kind);
+                (!exact, distance, candidate)
+            })
+            .collect();
+        Some(candidates)
+    });
+
+    let mut candidates: Vec<_> = directories.into_iter().flatten().collect();
+
+    // The sort is stable, so candidates at the same distance keep the order
+    // of directories, base directories and format preference.
+    candidates.sort_by(|(a_inexact, a_distance, _), (b_inexact, b_distance, _)| {
+        a_inexact
+            .cmp(b_inexact)
+            .then(a_distance.total_cmp(b_distance))
+    });
+
+    candidates
+        .into_iter()
+        .map(|(_, _, candidate)| candidate)
+        .collect()
+}
+
+/// Every file of the icon in a theme directory, across all base directories
+/// and formats.
+fn directory_files(
+    icon: &str,
+    subdir: &ThemeDirectory,
+    options: &LookupOptions,
+    theme: &Theme,
+) -> Vec<(PathBuf, IconFormat)> {
+    options
+        .base_directories()
+        .flat_map(|directory| {
+            let directory = directory.join(&theme.name).join(&subdir.name);
+            listing::icon_files(&directory, icon, options)
+        })
+        .collect()
+}
+
+fn unthemed_candidates(icon: &str, options: &LookupOptions) -> Vec<LookupResult> {
+    options
+        .unthemed_directories()
+        .flat_map(|directory| listing::icon_files(directory, icon, options))
+        .map(|(path, format)| LookupResult {
+            path,
+            theme: None,
+            directory: None,
+            match_kind: MatchKind::Unthemed,
+            format,
+        })
+        .collect()
+}
+
+// The test links icon files with symbolic links.
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_canonicalized_candidates() {
+        use std::os::unix::fs::symlink;
+        use std::{env, fs, process};
+
+        use crate::{find_icon_in, ThemeDirectoryType, Translations};
+
+        let base_directory = env::temp_dir().join(format!("icon-finder-links-{}", process::id()));
+        let apps = base_directory.join("icon-finder-links/48x48/apps");
+        fs::create_dir_all(&apps).unwrap();
+        fs::create_dir_all(base_directory.join("icon-finder-links/16x16/apps")).unwrap();
+        fs::write(apps.join("editor.png"), "").unwrap();
+        symlink(
+            "../../48x48/apps/editor.png",
+            base_directory.join("icon-finder-links/16x16/apps/editor.png"),
+        )
+        .unwrap();
+
+        let directory = |name: &str, size| ThemeDirectory {
+            name: name.to_owned(),
+            size,
+            scale: None,
+            context: None,
+            r#type: ThemeDirectoryType::Fixed,
+            max_size: None,
+            min_size: None,
+            threshold: None,
+        };
+        let links = Theme {
+            name: "icon-finder-links".to_owned(),
+            comment: String::new(),
+            inherits: Vec::new(),
+            directories: vec![directory("16x16/apps", 16), directory("48x48/apps", 48)],
+            hidden: false,
+            translations: Translations::default(),
+            example: None,
+        };
+
+        let options = LookupOptions::new(16).theme_path(&base_directory);
+        let canonicalized = options.clone().flags(LookupFlags::CANONICALIZE);
+        let target = fs::canonicalize(apps.join("editor.png")).unwrap();
+
+        assert_eq!(
+            Candidates::new("editor", options.clone(), &links, &[]).count(),
+            2
+        );
+        let candidates: Vec<_> = Candidates::new("editor", canonicalized.clone(), &links, &[])
+            .map(|candidate| candidate.path)
+            .collect();
+        assert_eq!(candidates, vec![target.clone()]);
+
+        let path = |options| find_icon_in("editor", options, &links, &[]).unwrap().path;
+        assert!(path(&options).ends_with("16x16/apps/editor.png"));
+        assert_eq!(path(&canonicalized), target);
+
+        fs::remove_dir_all(&base_directory).unwrap();
+    }
+}
@@ -0,0 +1,176 @@
+//! # Theme comparison
+//! Forking a theme, or drawing a theme that inherits from another, raises the
+//! question which icons still have to be drawn. Comparing two themes lists
+//! the icons each of them provides that the other does not, per context and
+//! size. Only the directories of the themes themselves are compared, icons
+//! they inherit from other themes are not taken into account.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::enumerate::visit_directories;
+use crate::{Context, IconSize, LookupOptions, Theme, DEFAULT_SCALE};
+
+/// An icon one theme provides and the other does not, see
+/// [`compare_themes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconDifference {
+    pub name: String,
+    /// The context of the directories the icon is in, `None` for directories
+    /// without one.
+    pub context: Option<Context>,
+    /// The sizes the other theme does not provide the icon at in this
+    /// context. Icons the other theme does not provide at all are missing at
+    /// every size.
+    pub sizes: Vec<IconSize>,
+}
+
+/// The result of [`compare_themes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThemeComparison {
+    /// The icons of the first theme the second theme does not provide.
+    pub only_in_first: Vec<IconDifference>,
+    /// The icons of the second theme the first theme does not provide.
+    pub only_in_second: Vec<IconDifference>,
+}
+
+impl ThemeComparison {
+    /// Whether both themes provide the same icons at the same sizes.
+    pub fn is_identical(&self) -> bool {
+        self.only_in_first.is_empty() && self.only_in_second.is_empty()
+    }
+}
+
+/// The icons of a theme by context name and icon name, with the sizes they
+/// are provided at. Contexts are keyed by name, so the icons are ordered.
+type ProvidedIcons = BTreeMap<(String, String), (Option<Context>, BTreeSet<IconSize>)>;
+
+/// The key of a context in [`ProvidedIcons`].
+fn context_key(context: Option<&Context>) -> String {
+    context.map(Context::as_str).unwrap_or_default().to_owned()
+}
+
+fn provided_icons(theme: &Theme, options: &LookupOptions) -> ProvidedIcons {
+    let mut icons = ProvidedIcons::new();
+
+    visit_directories(theme, options, |subdir, _, names| {
+        let size = IconSize {
+            scale: subdir.scale.unwrap_or(DEFAULT_SCALE),
+            size: subdir.size,
+            r#type: subdir.r#type,
+        };
+
+        for name in names {
+            icons
+                .entry((context_key(subdir.context.as_ref()), name))
+                .or_insert_with(|| (subdir.context.clone(), BTreeSet::new()))
+                .1
+                .insert(size);
+        }
+    });
+
+    icons
+}
+
+/// The icons of `theme` that `other` does not provide at every size.
+fn differences(theme: &ProvidedIcons, other: &ProvidedIcons) -> Vec<IconDifference> {
+    theme
+        .iter()
+        .filter_map(|(key, (context, sizes))| {
+            let missing: Vec<IconSize> = match other.get(key) {
+                Some((_, other_sizes)) => sizes.difference(other_sizes).copied().collect(),
+                None => sizes.iter().copied().collect(),
+            };
+
+            (!missing.is_empty()).then(|| IconDifference {
+                name: key.1.clone(),
+                context: context.clone(),
+                sizes: missing,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn compare_themes_with(
+    first: &Theme,
+    second: &Theme,
+    options: &LookupOptions,
+) -> ThemeComparison {
+    let first = provided_icons(first, options);
+    let second = provided_icons(second, options);
+
+    ThemeComparison {
+        only_in_first: differences(&first, &second),
+        only_in_second: differences(&second, &first),
+    }
+}
+
+/// Compares the icons two themes provide themselves, per context and size,
+/// e.g. to see which icons of the original a fork does not draw yet. Sizes
+/// are compared together with their scale and directory type, so a scalable
+/// icon does not stand in for a fixed size one.
+pub fn compare_themes(first: &Theme, second: &Theme) -> ThemeComparison {
+    compare_themes_with(first, second, &LookupOptions::new(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+
+    fn theme(name: &str) -> ThemeFixture {
+        ThemeFixture::new(name)
+            .fixed_directory("16x16/apps", 16)
+            .context(Context::Applications)
+            .fixed_directory("48x48/apps", 48)
+            .context(Context::Applications)
+            .fixed_directory("48x48/mimetypes", 48)
+            .context(Context::MimeTypes)
+    }
+
+    #[test]
+    fn test_compare_themes() {
+        let fixture = Fixture::in_memory(&[
+            theme("icon-finder-original")
+                .icon("16x16/apps/editor.png")
+                .icon("48x48/apps/editor.png")
+                .icon("48x48/apps/browser.png")
+                .icon("48x48/mimetypes/text-x-generic.png"),
+            theme("icon-finder-fork")
+                .icon("48x48/apps/editor.png")
+                .icon("48x48/apps/browser.png")
+                .icon("48x48/apps/terminal.png"),
+        ]);
+        let original = fixture.load_theme("icon-finder-original").unwrap();
+        let fork = fixture.load_theme("icon-finder-fork").unwrap();
+        let options = fixture.lookup_options();
+
+        let comparison = compare_themes_with(&original, &fork, &options);
+        let names = |differences: &[IconDifference]| -> Vec<(String, Vec<i16>)> {
+            differences
+                .iter()
+                .map(|icon| {
+                    let sizes = icon.sizes.iter().map(|size| size.size).collect();
+                    (icon.name.clone(), sizes)
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            names(&comparison.only_in_first),
+            vec![
+                ("editor".to_owned(), vec![16]),
+                ("text-x-generic".to_owned(), vec![48]),
+            ]
+        );
+        assert_eq!(
+            comparison.only_in_first[1].context,
+            Some(Context::MimeTypes)
+        );
+        assert_eq!(
+            names(&comparison.only_in_second),
+            vec![("terminal".to_owned(), vec![48])]
+        );
+        assert!(!comparison.is_identical());
+        assert!(compare_themes_with(&fork, &fork, &options).is_identical());
+    }
+}
@@ -0,0 +1,112 @@
+//! # Coverage
+//! The Icon Naming Specification lists the icon names every theme is expected
+//! to provide. Theme authors can check which of these their theme is missing,
+//! taking the themes it inherits from into account.
+
+use crate::{find_icon_sizes_helper, icon_names, Context, LookupOptions, MatchKind, Theme};
+
+/// The standard icon names of the Icon Naming Specification, by context.
+const STANDARD_ICONS: &[(Context, &[&str])] = &[
+    (Context::Actions, icon_names::ACTIONS),
+    (Context::Animations, icon_names::ANIMATIONS),
+    (Context::Applications, icon_names::APPLICATIONS),
+    (Context::Categories, icon_names::CATEGORIES),
+    (Context::Devices, icon_names::DEVICES),
+    (Context::Emblems, icon_names::EMBLEMS),
+    (Context::Emotes, icon_names::EMOTES),
+    (Context::MimeTypes, icon_names::MIME_TYPES),
+    (Context::Places, icon_names::PLACES),
+    (Context::Status, icon_names::STATUS),
+];
+
+/// A standard icon a theme does not provide at every size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingIcon {
+    pub name: &'static str,
+    pub context: Context,
+    /// The checked sizes without an icon of exactly that size. Icons that are
+    /// missing completely are missing at every size.
+    pub sizes: Vec<i16>,
+}
+
+/// The result of [`check_coverage`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageReport {
+    /// The number of standard icons that were checked.
+    pub checked: usize,
+    pub missing: Vec<MissingIcon>,
+}
+
+impl CoverageReport {
+    /// Whether the theme provides every standard icon at every checked size.
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// Checks which standard icons of the Icon Naming Specification a theme and
+/// the themes it inherits from do not provide at the given sizes. An icon is
+/// only provided at a size if the lookup finds it in a directory matching the
+/// size, icons that would have to be scaled count as missing. The hicolor
+/// fallback and unthemed icons are not taken into account, unless the theme
+/// inherits from hicolor.
+pub fn check_coverage(theme: &Theme, sizes: &[i16]) -> CoverageReport {
+    let options = LookupOptions::new(0);
+    let mut checked = 0;
+    let mut missing = Vec::new();
+
+    for (context, names) in STANDARD_ICONS {
+        for &name in names.iter() {
+            checked += 1;
+
+            let missing_sizes = match find_icon_sizes_helper(name, sizes, &options, theme) {
+                Some(results) => sizes
+                    .iter()
+                    .zip(results)
+                    .filter(|(_, result)| {
+                        result
+                            .as_ref()
+                            .is_none_or(|result| result.match_kind != MatchKind::Exact)
+                    })
+                    .map(|(&size, _)| size)
+                    .collect(),
+                None => sizes.to_vec(),
+            };
+
+            if !missing_sizes.is_empty() {
+                missing.push(MissingIcon {
+                    name,
+                    context: context.clone(),
+                    sizes: missing_sizes,
+                });
+            }
+        }
+    }
+
+    CoverageReport { checked, missing }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Translations;
+
+    #[test]
+    fn test_check_coverage_of_empty_theme() {
+        let theme = Theme {
+            name: "empty".to_owned(),
+            comment: String::new(),
+            inherits: Vec::new(),
+            directories: Vec::new(),
+            hidden: false,
+            translations: Translations::default(),
+            example: None,
+        };
+
+        let report = check_coverage(&theme, &[16, 48]);
+
+        assert!(!report.is_complete());
+        assert_eq!(report.missing.len(), report.checked);
+        assert!(report.missing.iter().all(|icon| icon.sizes == vec![16, 48]));
+    }
+}
@@ -0,0 +1,265 @@
+//! # Daemon
+//! Bars and launchers look up icons constantly, usually from short-lived
+//! processes that would otherwise load the theme and scan its directories for
+//! every lookup. A daemon keeps an [`IconFinder`] with a warm cache in memory
+//! and answers lookups over a unix socket instead.
+//!
+//! Every message is a JSON object, preceded by its length in bytes as a 32 bit
+//! big endian integer. A request looks like
+//! `{"icon": "firefox", "size": 48, "scale": 1}`, it is answered with
+//! `{"path": "/usr/share/icons/hicolor/48x48/apps/firefox.png"}`, or with a
+//! `null` path when the icon is not found. Paths that are not valid UTF-8 are
+//! sent as an array of their bytes instead. A connection can be used for any
+//! number of lookups.
+
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{IconFinder, LookupOptions};
+
+/// Messages larger than this are rejected, no valid request comes close.
+const MAX_MESSAGE_LENGTH: u32 = 1 << 20;
+
+/// How long to wait before accepting connections again after it failed.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Request {
+    icon: String,
+    size: i16,
+    scale: i16,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Response {
+    path: Option<MessagePath>,
+}
+
+/// A path in a message, as a string if it is valid UTF-8, which JSON strings
+/// have to be, and as its bytes otherwise.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum MessagePath {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+impl From<PathBuf> for MessagePath {
+    fn from(path: PathBuf) -> MessagePath {
+        match path.into_os_string().into_string() {
+            Ok(path) => MessagePath::Text(path),
+            Err(path) => MessagePath::Bytes(path.into_vec()),
+        }
+    }
+}
+
+impl From<MessagePath> for PathBuf {
+    fn from(path: MessagePath) -> PathBuf {
+        match path {
+            MessagePath::Text(path) => PathBuf::from(path),
+            MessagePath::Bytes(bytes) => PathBuf::from(OsString::from_vec(bytes)),
+        }
+    }
+}
+
+/// The socket the daemon listens on by default,
+/// $XDG_RUNTIME_DIR/icon-finder.sock. `None` if $XDG_RUNTIME_DIR is not set.
+pub fn default_socket_path() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(|directory| PathBuf::from(directory).join("icon-finder.sock"))
+}
+
+/// Answers lookups on the socket at the given path using the finder. Only
+/// returns if the socket cannot be set up, connections that cannot be
+/// accepted are skipped. A stale socket left behind by an earlier daemon is
+/// replaced. Every connection is handled on its own thread.
+pub fn serve(finder: &IconFinder, path: &Path) -> io::Result<()> {
+    if path.exists() && UnixStream::connect(path).is_err() {
+        fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+
+    thread::scope(|scope| {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                // E.g. running out of file descriptors, which clears up once
+                // other clients disconnect. Waiting a little keeps the daemon
+                // from spinning meanwhile.
+                Err(_error) => {
+                    #[cfg(feature = "log")]
+                    log::warn!("could not accept a connection: {}", _error);
+                    thread::sleep(ACCEPT_RETRY_DELAY);
+                    continue;
+                }
+            };
+            // A misbehaving client only loses its own connection.
+            scope.spawn(move || handle_connection(finder, stream));
+        }
+
+        Ok(())
+    })
+}
+
+/// Answers the requests of a single client until it disconnects.
+fn handle_connection(finder: &IconFinder, mut stream: UnixStream) -> io::Result<()> {
+    while let Some(request) = read_message::<Request>(&mut stream)? {
+        let options = LookupOptions::new(request.size).scale(request.scale);
+        let path = finder
+            .lookup_icon_with(&request.icon, &options)
+            .map(|result| MessagePath::from(result.path));
+
+        write_message(&mut stream, &Response { path })?;
+    }
+
+    Ok(())
+}
+
+/// A connection to a daemon.
+///
+/// ```no_run
+/// use icon_finder::Client;
+///
+/// let mut client = Client::connect_default().unwrap();
+/// let path = client.find_icon("firefox", 48, 1).unwrap();
+/// ```
+pub struct Client {
+    stream: UnixStream,
+}
+
+impl Client {
+    /// Connects to the daemon listening on the given socket.
+    pub fn connect(path: &Path) -> io::Result<Client> {
+        Ok(Client {
+            stream: UnixStream::connect(path)?,
+        })
+    }
+
+    /// Connects to the daemon listening on the
+    /// [default socket](default_socket_path).
+    pub fn connect_default() -> io::Result<Client> {
+        let path = default_socket_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "XDG_RUNTIME_DIR is not set"))?;
+
+        Client::connect(&path)
+    }
+
+    /// Looks up an icon like [`find_icon`](crate::find_icon), in the theme of
+    /// the daemon.
+    pub fn find_icon(&mut self, icon: &str, size: i16, scale: i16) -> io::Result<Option<PathBuf>> {
+        let request = Request {
+            icon: icon.to_owned(),
+            size,
+            scale,
+        };
+        write_message(&mut self.stream, &request)?;
+
+        match read_message::<Response>(&mut self.stream)? {
+            Some(response) => Ok(response.path.map(PathBuf::from)),
+            None => Err(io::ErrorKind::UnexpectedEof.into()),
+        }
+    }
+}
+
+/// Reads a length-prefixed message, `None` if the connection was closed
+/// before the message started.
+fn read_message<T: for<'de> Deserialize<'de>>(stream: &mut impl Read) -> io::Result<Option<T>> {
+    let mut length = [0; 4];
+    match stream.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(error) if error.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(error) => return Err(error),
+    }
+
+    let length = u32::from_be_bytes(length);
+    if length > MAX_MESSAGE_LENGTH {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message is too long",
+        ));
+    }
+
+    let mut message = vec![0; length as usize];
+    stream.read_exact(&mut message)?;
+
+    serde_json::from_slice(&message)
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+fn write_message(stream: &mut impl Write, message: &impl Serialize) -> io::Result<()> {
+    let message = serde_json::to_vec(message)?;
+
+    stream.write_all(&(message.len() as u32).to_be_bytes())?;
+    stream.write_all(&message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Theme, Translations};
+
+    #[test]
+    fn test_client_round_trip() {
+        let theme = Theme {
+            name: "empty".to_owned(),
+            comment: String::new(),
+            inherits: Vec::new(),
+            directories: Vec::new(),
+            hidden: false,
+            translations: Translations::default(),
+            example: None,
+        };
+        let finder = IconFinder::new(theme);
+        let (client, server) = UnixStream::pair().unwrap();
+
+        thread::scope(|scope| {
+            let daemon = scope.spawn(|| handle_connection(&finder, server));
+
+            let mut client = Client { stream: client };
+            assert_eq!(
+                client
+                    .find_icon("icon-finder-nonexistent-icon", 48, 1)
+                    .unwrap(),
+                None
+            );
+            drop(client);
+
+            daemon.join().unwrap().unwrap();
+        });
+    }
+
+    #[test]
+    fn test_message_paths() {
+        let round_trip = |path: PathBuf| {
+            let message = serde_json::to_vec(&Response {
+                path: Some(MessagePath::from(path)),
+            })
+            .unwrap();
+            let response: Response = serde_json::from_slice(&message).unwrap();
+            PathBuf::from(response.path.unwrap())
+        };
+
+        let path = PathBuf::from("/usr/share/icons/hicolor/48x48/apps/firefox.png");
+        assert_eq!(round_trip(path.clone()), path);
+        let path = PathBuf::from(OsString::from_vec(b"/home/j\xf6rg/.icons/a.png".to_vec()));
+        assert_eq!(round_trip(path.clone()), path);
+    }
+
+    #[test]
+    fn test_read_message_rejects_long_messages() {
+        let message = (MAX_MESSAGE_LENGTH + 1).to_be_bytes();
+
+        assert!(read_message::<Request>(&mut &message[..]).is_err());
+        assert!(read_message::<Request>(&mut &[][..]).unwrap().is_none());
+    }
+}
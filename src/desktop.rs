@@ -0,0 +1,303 @@
+//! # Application icons
+//! Docks and taskbars only know the windows of applications by their Wayland
+//! app-id or X11 WM_CLASS. The icon of the application is named in its
+//! desktop entry, so the entry has to be found first. Well-behaved
+//! applications use the name of their desktop entry as app-id, or name the
+//! WM_CLASS in the StartupWMClass key of the entry. Others are matched on the
+//! executable or name of the application, ignoring case.
+//!
+//! Desktop entries are read from the applications directory of every data
+//! directory, entries of the user hiding system entries of the same ID. They
+//! are read again once one of these directories changes.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::cache::Stamp;
+use crate::filesystem::{file_system, FileSystem};
+use crate::index::parse_groups;
+use crate::paths::application_directories;
+use crate::{find_best_icon, Theme};
+
+const DESKTOP_ENTRY_GROUP: &str = "Desktop Entry";
+const DESKTOP_SUFFIX: &str = ".desktop";
+
+/// The desktop entry of an application, see [`find_desktop_entry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesktopEntry {
+    /// The desktop file ID, like org.gnome.Nautilus.desktop. Entries in
+    /// subdirectories have the subdirectory prepended with a dash.
+    pub id: String,
+    pub path: PathBuf,
+    pub name: String,
+    /// The name of the icon, or the absolute path of an icon file.
+    pub icon: Option<String>,
+    /// The WM_CLASS of the windows of the application.
+    pub startup_wm_class: Option<String>,
+    pub exec: Option<String>,
+}
+
+impl DesktopEntry {
+    /// Parses a desktop entry, `None` if it is not an application or is
+    /// hidden.
+    fn parse(id: String, path: PathBuf, contents: &str) -> Option<DesktopEntry> {
+        let groups = parse_groups(contents).ok()?;
+        let group = groups
+            .iter()
+            .find(|group| group.name == DESKTOP_ENTRY_GROUP)?;
+
+        if group.get("Type") != Some("Application") || group.get("Hidden") == Some("true") {
+            return None;
+        }
+
+        let value = |key| group.get(key).filter(|value| !value.is_empty());
+        Some(DesktopEntry {
+            id,
+            path,
+            name: value("Name").unwrap_or_default().to_owned(),
+            icon: value("Icon").map(str::to_owned),
+            startup_wm_class: value("StartupWMClass").map(str::to_owned),
+            exec: value("Exec").map(str::to_owned),
+        })
+    }
+
+    /// The file name of the executable, skipping `env` and the variables it
+    /// sets.
+    fn executable(&self) -> Option<&str> {
+        let command = self
+            .exec
+            .as_deref()?
+            .split_whitespace()
+            .map(|argument| argument.trim_matches('"'))
+            .find(|argument| *argument != "env" && !argument.contains('='))?;
+
+        command.rsplit('/').next()
+    }
+
+    fn stem(&self) -> &str {
+        self.id.strip_suffix(DESKTOP_SUFFIX).unwrap_or(&self.id)
+    }
+}
+
+/// Reads the desktop entries of the directories, in order of precedence.
+/// Hidden entries are only used to hide the entries of the same ID in
+/// directories of lower precedence.
+fn read_entries(directories: &[PathBuf], file_system: &dyn FileSystem) -> Vec<DesktopEntry> {
+    let mut ids = Vec::new();
+    let mut entries = Vec::new();
+
+    for directory in directories {
+        let mut files = Vec::new();
+        collect_desktop_files(directory, "", file_system, &mut files);
+
+        for (id, path) in files {
+            if ids.contains(&id) {
+                continue;
+            }
+            ids.push(id.clone());
+
+            let contents = match file_system.read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(_) => continue,
+            };
+            entries.extend(DesktopEntry::parse(id, path, &contents));
+        }
+    }
+
+    entries
+}
+
+fn collect_desktop_files(
+    directory: &Path,
+    prefix: &str,
+    file_system: &dyn FileSystem,
+    files: &mut Vec<(String, PathBuf)>,
+) {
+    let mut names = match file_system.read_dir(directory) {
+        Ok(names) => names,
+        Err(_) => return,
+    };
+    names.sort();
+
+    for name in names {
+        let name = match name.to_str() {
+            Some(name) => name,
+            None => continue,
+        };
+        let path = directory.join(name);
+
+        if name.ends_with(DESKTOP_SUFFIX) {
+            files.push((format!("{}{}", prefix, name), path));
+        } else if file_system.is_dir(&path) {
+            let prefix = format!("{}{}-", prefix, name);
+            collect_desktop_files(&path, &prefix, file_system, files);
+        }
+    }
+}
+
+/// The desktop entries of the installed applications, read again once an
+/// applications directory changed.
+fn installed_entries() -> Arc<Vec<DesktopEntry>> {
+    static ENTRIES: Mutex<Option<(Stamp, Arc<Vec<DesktopEntry>>)>> = Mutex::new(None);
+
+    let file_system = &**file_system();
+    let directories = application_directories();
+    let stamp = Stamp::new(directories, file_system);
+
+    let mut cached = ENTRIES.lock().unwrap_or_else(|error| error.into_inner());
+    match &*cached {
+        Some((cached_stamp, entries)) if *cached_stamp == stamp => Arc::clone(entries),
+        _ => {
+            let entries = Arc::new(read_entries(directories, file_system));
+            *cached = Some((stamp, Arc::clone(&entries)));
+            entries
+        }
+    }
+}
+
+/// The entry matching the app-id, trying the ways applications are known to
+/// relate their app-id to their desktop entry from the most to the least
+/// reliable one.
+fn match_entry<'a>(entries: &'a [DesktopEntry], app_id: &str) -> Option<&'a DesktopEntry> {
+    let app_id = app_id.trim();
+    let app_id = app_id.strip_suffix(DESKTOP_SUFFIX).unwrap_or(app_id);
+    if app_id.is_empty() {
+        return None;
+    }
+
+    fn last_component(id: &str) -> &str {
+        id.rsplit('.').next().unwrap_or(id)
+    }
+    let matches_ignoring_case =
+        |value: Option<&str>| value.is_some_and(|value| value.eq_ignore_ascii_case(app_id));
+
+    let heuristics: [&dyn Fn(&DesktopEntry) -> bool; 7] = [
+        &|entry| entry.stem() == app_id,
+        &|entry| entry.startup_wm_class.as_deref() == Some(app_id),
+        &|entry| entry.stem().eq_ignore_ascii_case(app_id),
+        &|entry| matches_ignoring_case(entry.startup_wm_class.as_deref()),
+        // Reverse DNS names on one side only, like org.gnome.Nautilus for
+        // the nautilus WM_CLASS.
+        &|entry| last_component(entry.stem()).eq_ignore_ascii_case(last_component(app_id)),
+        &|entry| matches_ignoring_case(entry.executable()),
+        &|entry| entry.name.eq_ignore_ascii_case(app_id),
+    ];
+
+    heuristics
+        .iter()
+        .find_map(|heuristic| entries.iter().find(|entry| heuristic(entry)))
+}
+
+/// Finds the desktop entry of the application with the Wayland app-id or
+/// X11 WM_CLASS.
+pub fn find_desktop_entry(app_id: &str) -> Option<DesktopEntry> {
+    match_entry(&installed_entries(), app_id).cloned()
+}
+
+/// Looks up the icon of the application with the app-id, using the first of
+/// the icon names that is found. The icon of a desktop entry can be the
+/// absolute path of an icon file, which is used as is.
+pub(crate) fn find_app_icon_with(
+    app_id: &str,
+    find_best_icon: impl FnOnce(&[&str]) -> Option<PathBuf>,
+) -> Option<PathBuf> {
+    let icon = find_desktop_entry(app_id).and_then(|entry| entry.icon);
+
+    if let Some(icon) = icon.as_deref().filter(|icon| icon.starts_with('/')) {
+        if file_system().is_file(Path::new(icon)) {
+            return Some(PathBuf::from(icon));
+        }
+    }
+
+    // Without a desktop entry, the app-id is often the name of the icon.
+    let lowercase = app_id.to_lowercase();
+    let names: Vec<&str> = icon
+        .as_deref()
+        .into_iter()
+        .chain([app_id, &lowercase])
+        .collect();
+
+    find_best_icon(&names)
+}
+
+/// Looks up the icon of the application with the Wayland app-id or X11
+/// WM_CLASS, like a taskbar shows it, see [`find_desktop_entry`].
+pub fn find_app_icon(
+    app_id: &str,
+    size: i16,
+    scale: i16,
+    user_selected_theme: Theme,
+) -> Option<PathBuf> {
+    find_app_icon_with(app_id, |names| {
+        find_best_icon(names.to_vec(), size, scale, user_selected_theme)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFileSystem;
+
+    #[test]
+    fn test_match_desktop_entry() {
+        let fs = MemoryFileSystem::new();
+        let entry = |path: &str, keys: &str| {
+            fs.add_file(path, format!("[Desktop Entry]\nType=Application\n{}", keys))
+        };
+
+        entry(
+            "/home/user/.local/share/applications/org.gnome.Nautilus.desktop",
+            "Name=Files\nHidden=true\n",
+        );
+        entry(
+            "/usr/share/applications/org.gnome.Nautilus.desktop",
+            "Name=Files\nIcon=org.gnome.Nautilus\n",
+        );
+        entry(
+            "/usr/share/applications/org.gnome.TextEditor.desktop",
+            "Name=Text Editor\nIcon=org.gnome.TextEditor\n",
+        );
+        entry(
+            "/usr/share/applications/code.desktop",
+            "Name=Visual Studio Code\nExec=/usr/bin/code %F\nStartupWMClass=Code\n",
+        );
+        entry(
+            "/usr/share/applications/kde/org.kde.konsole.desktop",
+            "Name=Konsole\nExec=env QT_SCALE_FACTOR=1 konsole\n",
+        );
+        entry(
+            "/usr/share/applications/gimp.desktop",
+            "Name=GNU Image Manipulation Program\nExec=gimp-2.10 %U\n",
+        );
+
+        let entries = read_entries(
+            &[
+                PathBuf::from("/home/user/.local/share/applications"),
+                PathBuf::from("/usr/share/applications"),
+            ],
+            &fs,
+        );
+        let matched = |app_id| match_entry(&entries, app_id).map(|entry| entry.id.as_str());
+
+        assert_eq!(
+            matched("org.gnome.TextEditor"),
+            Some("org.gnome.TextEditor.desktop")
+        );
+        assert_eq!(matched("Code"), Some("code.desktop"));
+        assert_eq!(matched("texteditor"), Some("org.gnome.TextEditor.desktop"));
+        assert_eq!(
+            matched("org.kde.Konsole"),
+            Some("kde-org.kde.konsole.desktop")
+        );
+        assert_eq!(matched("gimp-2.10"), Some("gimp.desktop"));
+        assert_eq!(
+            matched("Gnu Image Manipulation Program"),
+            Some("gimp.desktop")
+        );
+        // Hidden by the entry of the user.
+        assert_eq!(matched("org.gnome.Nautilus"), None);
+        assert_eq!(matched(""), None);
+
+        assert_eq!(entries[2].executable(), Some("konsole"));
+    }
+}
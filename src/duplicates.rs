@@ -0,0 +1,177 @@
+//! # Duplicate icons
+//! Themes often ship the same image under several names or in several
+//! directories, e.g. when packaging replaced the symbolic links of a theme
+//! with copies. Hashing the contents of every icon file of a theme finds these
+//! copies, so theme authors can replace them with links again and shrink
+//! their packages.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+use crate::walk::walk_theme_with;
+use crate::{FileSystem, LookupOptions, Theme};
+
+/// Icon files with the same contents, see [`find_duplicate_icons`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateIcons {
+    /// The size of each of the files in bytes.
+    pub size: usize,
+    /// The paths of the files, sorted.
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateIcons {
+    /// The bytes that would be saved by keeping only one of the files.
+    pub fn wasted_bytes(&self) -> usize {
+        self.size * (self.paths.len() - 1)
+    }
+}
+
+/// Splits files with the same size and hash into groups of files with the
+/// same contents, as files with different contents can share a hash.
+fn split_by_contents(file_system: &dyn FileSystem, paths: Vec<PathBuf>) -> Vec<Vec<PathBuf>> {
+    let mut groups: Vec<(Vec<u8>, Vec<PathBuf>)> = Vec::new();
+
+    for path in paths {
+        let contents = match file_system.read(&path) {
+            Ok(contents) => contents,
+            Err(_) => continue,
+        };
+        match groups.iter_mut().find(|(group, _)| *group == contents) {
+            Some((_, paths)) => paths.push(path),
+            None => groups.push((contents, vec![path])),
+        }
+    }
+
+    groups.into_iter().map(|(_, paths)| paths).collect()
+}
+
+pub(crate) fn find_duplicate_icons_with(
+    theme: &Theme,
+    options: &LookupOptions,
+) -> Vec<DuplicateIcons> {
+    let file_system = options.file_system();
+    let mut files: HashMap<(usize, u64), Vec<PathBuf>> = HashMap::new();
+    let mut targets = HashSet::new();
+
+    walk_theme_with(theme, options, |_, file| {
+        // Symbolic links to a file cost no space, only the file they point
+        // to is compared.
+        let target = file_system
+            .canonicalize(&file.path)
+            .unwrap_or_else(|_| file.path.clone());
+        if !targets.insert(target) {
+            return;
+        }
+
+        let contents = match file_system.read(&file.path) {
+            Ok(contents) => contents,
+            Err(_) => return,
+        };
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+
+        files
+            .entry((contents.len(), hasher.finish()))
+            .or_default()
+            .push(file.path.clone());
+    });
+
+    let mut duplicates: Vec<DuplicateIcons> = files
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .flat_map(|((size, _), mut paths)| {
+            paths.sort();
+            paths.dedup();
+            split_by_contents(file_system, paths)
+                .into_iter()
+                .map(move |paths| DuplicateIcons { size, paths })
+        })
+        .filter(|duplicates| duplicates.paths.len() > 1)
+        .collect();
+    duplicates.sort_by(|a, b| {
+        b.wasted_bytes()
+            .cmp(&a.wasted_bytes())
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+
+    duplicates
+}
+
+/// Finds the icon files of the theme with the same contents, the groups
+/// wasting the most space first. Only the directories of the theme itself
+/// are searched, not those of the themes it inherits from. Files with the
+/// same size and hash of their contents are compared byte by byte. Symbolic
+/// links are not duplicates of the file they point to.
+pub fn find_duplicate_icons(theme: &Theme) -> Vec<DuplicateIcons> {
+    find_duplicate_icons_with(theme, &LookupOptions::new(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+    use crate::MemoryFileSystem;
+
+    #[test]
+    fn test_find_duplicate_icons() {
+        let mut theme = ThemeFixture::new("icon-finder-duplicates")
+            .fixed_directory("16x16/apps", 16)
+            .fixed_directory("48x48/apps", 48);
+        for (icon, contents) in [
+            ("16x16/apps/editor.png", "small editor"),
+            ("16x16/apps/text-editor.png", "small editor"),
+            ("48x48/apps/editor.png", "large editor"),
+            ("48x48/apps/text-editor.png", "large editor"),
+            ("48x48/apps/accessories-text-editor.png", "large editor"),
+            ("48x48/apps/browser.png", "browser"),
+            ("48x48/apps/README", "browser"),
+        ] {
+            theme = theme.file(icon, contents);
+        }
+        let fixture = Fixture::in_memory(&[theme]);
+        let theme = fixture.load_theme("icon-finder-duplicates").unwrap();
+        let theme_directory = fixture.base_directory().join("icon-finder-duplicates");
+
+        let duplicates = find_duplicate_icons_with(&theme, &fixture.lookup_options());
+        assert_eq!(duplicates.len(), 2);
+        assert_eq!(
+            duplicates[0].paths,
+            vec![
+                theme_directory.join("48x48/apps/accessories-text-editor.png"),
+                theme_directory.join("48x48/apps/editor.png"),
+                theme_directory.join("48x48/apps/text-editor.png"),
+            ]
+        );
+        assert_eq!(duplicates[0].wasted_bytes(), 24);
+        assert_eq!(duplicates[1].size, 12);
+        assert_eq!(duplicates[1].paths.len(), 2);
+    }
+
+    #[test]
+    fn test_split_by_contents() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/icons/a.png", "abc");
+        fs.add_file("/icons/b.png", "xyz");
+        fs.add_file("/icons/c.png", "abc");
+        let paths = [
+            "/icons/a.png",
+            "/icons/b.png",
+            "/icons/c.png",
+            "/icons/d.png",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+
+        assert_eq!(
+            split_by_contents(&fs, paths),
+            vec![
+                vec![PathBuf::from("/icons/a.png"), PathBuf::from("/icons/c.png")],
+                vec![PathBuf::from("/icons/b.png")],
+            ]
+        );
+    }
+}
@@ -0,0 +1,180 @@
+//! # Emblems
+//! File managers overlay emblems onto the icons of files, like
+//! emblem-symbolic-link on links and emblem-readonly on files that cannot be
+//! written. Emblems are icons of the Emblems context, drawn at half the size
+//! of the icon in its corners. Like GTK, the first emblem goes into the
+//! bottom right corner, the next ones into the other corners clockwise.
+
+use std::path::PathBuf;
+
+use crate::{find_icon_in, Context, LookupOptions, Theme};
+
+/// The smallest size emblems are drawn at, smaller emblems are unrecognizable.
+const MIN_EMBLEM_SIZE: i16 = 8;
+
+/// The corner of the icon an emblem is drawn in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EmblemPosition {
+    BottomRight,
+    BottomLeft,
+    TopLeft,
+    TopRight,
+}
+
+impl EmblemPosition {
+    /// The corners in the order emblems are placed in.
+    const ALL: [EmblemPosition; 4] = [
+        EmblemPosition::BottomRight,
+        EmblemPosition::BottomLeft,
+        EmblemPosition::TopLeft,
+        EmblemPosition::TopRight,
+    ];
+
+    /// The offset of the top left corner of an emblem in this corner of the
+    /// icon.
+    pub fn offset(self, icon_size: i16, emblem_size: i16) -> (i16, i16) {
+        let far = icon_size - emblem_size;
+
+        match self {
+            EmblemPosition::BottomRight => (far, far),
+            EmblemPosition::BottomLeft => (0, far),
+            EmblemPosition::TopLeft => (0, 0),
+            EmblemPosition::TopRight => (far, 0),
+        }
+    }
+}
+
+/// An emblem of an [`EmblemedIcon`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Emblem {
+    pub name: String,
+    pub path: PathBuf,
+    /// The size the emblem is drawn at, the size it was looked up at.
+    pub size: i16,
+    pub position: EmblemPosition,
+}
+
+/// An icon with the emblems to draw onto it, see
+/// [`find_emblemed_icon`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmblemedIcon {
+    pub path: PathBuf,
+    pub size: i16,
+    pub emblems: Vec<Emblem>,
+}
+
+/// The size emblems are drawn at onto an icon of the given size.
+pub fn emblem_size(icon_size: i16) -> i16 {
+    (icon_size / 2).max(MIN_EMBLEM_SIZE).min(icon_size)
+}
+
+/// Looks up an icon with the given options and its emblems in the Emblems
+/// context at the emblem size, through the lookup function. Emblems that are
+/// not found are left out, emblems beyond the fourth as well, as every corner
+/// holds one emblem.
+pub(crate) fn find_emblemed_icon_with(
+    icon: &str,
+    emblems: &[&str],
+    options: &LookupOptions,
+    find_icon: impl Fn(&str, &LookupOptions) -> Option<PathBuf>,
+) -> Option<EmblemedIcon> {
+    let size = options.size;
+    let path = find_icon(icon, options)?;
+    let emblem_size = emblem_size(size);
+    let mut emblem_options = options.clone().context(Context::Emblems);
+    emblem_options.size = emblem_size;
+
+    let found = emblems
+        .iter()
+        .filter_map(|name| Some((*name, find_icon(name, &emblem_options)?)));
+    let emblems = found
+        .zip(EmblemPosition::ALL)
+        .map(|((name, path), position)| Emblem {
+            name: name.to_owned(),
+            path,
+            size: emblem_size,
+            position,
+        })
+        .collect();
+
+    Some(EmblemedIcon {
+        path,
+        size,
+        emblems,
+    })
+}
+
+/// Looks up an icon together with the emblems to draw onto it, like
+/// emblem-symbolic-link, at the size and in the corner they should be drawn
+/// at. Returns `None` if the icon itself is not found.
+pub fn find_emblemed_icon(
+    icon: &str,
+    emblems: &[&str],
+    size: i16,
+    scale: i16,
+    user_selected_theme: Theme,
+) -> Option<EmblemedIcon> {
+    let options = LookupOptions::new(size).scale(scale);
+    find_emblemed_icon_with(icon, emblems, &options, |name, options| {
+        find_icon_in(name, options, &user_selected_theme, &[]).map(|result| result.path)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_find_emblemed_icon() {
+        let find_icon = |name: &str, options: &LookupOptions| {
+            // Emblems are only found in their context.
+            let context = match options.context {
+                Some(Context::Emblems) => "emblems",
+                _ if name.starts_with("emblem-") => return None,
+                _ => "mimetypes",
+            };
+            (name != "emblem-missing")
+                .then(|| PathBuf::from(format!("/icons/{}/{}/{}.png", options.size, context, name)))
+        };
+        let options = LookupOptions::new(48);
+
+        let icon = find_emblemed_icon_with(
+            "text-x-generic",
+            &[
+                "emblem-symbolic-link",
+                "emblem-missing",
+                "emblem-readonly",
+                "emblem-shared",
+                "emblem-important",
+                "emblem-default",
+            ],
+            &options,
+            find_icon,
+        )
+        .unwrap();
+
+        assert_eq!(
+            icon.path,
+            Path::new("/icons/48/mimetypes/text-x-generic.png")
+        );
+        assert_eq!(icon.emblems.len(), 4);
+        assert_eq!(
+            icon.emblems[0].path,
+            Path::new("/icons/24/emblems/emblem-symbolic-link.png")
+        );
+        assert_eq!(icon.emblems[0].position, EmblemPosition::BottomRight);
+        assert_eq!(icon.emblems[1].name, "emblem-readonly");
+        assert_eq!(icon.emblems[1].position, EmblemPosition::BottomLeft);
+        assert_eq!(icon.emblems[3].name, "emblem-important");
+
+        assert_eq!(EmblemPosition::BottomLeft.offset(48, 24), (0, 24));
+        assert_eq!(emblem_size(12), 8);
+        assert_eq!(emblem_size(4), 4);
+
+        assert_eq!(
+            find_emblemed_icon_with("emblem-missing", &[], &options, find_icon),
+            None
+        );
+    }
+}
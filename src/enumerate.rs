@@ -0,0 +1,171 @@
+//! # Icon enumeration
+//! Icon chooser dialogs and theme previews show every icon a theme provides
+//! rather than looking up icons by name. A theme provides the icons in its own
+//! directories and those of the themes it inherits from. An icon provided by
+//! several themes is only available at the sizes of the first theme in the
+//! chain, as lookups never reach the others.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::listing;
+use crate::{context_directories, IconSize, LookupOptions, Theme, ThemeDirectory, DEFAULT_SCALE};
+
+/// Calls `visit` with the path and the names of the icons of every
+/// directory of the theme, in every base directory, in the context of the
+/// options if any.
+pub(crate) fn visit_directories(
+    theme: &Theme,
+    options: &LookupOptions,
+    mut visit: impl FnMut(&ThemeDirectory, &Path, Vec<String>),
+) {
+    for subdir in context_directories(theme, options.context.as_ref()) {
+        for base in options.base_directories() {
+            let directory = base.join(&theme.name).join(&subdir.name);
+            let names = listing::icon_names(&directory, options);
+            visit(subdir, &directory, names);
+        }
+    }
+}
+
+/// The names of all icons provided by the themes.
+fn icon_names(options: &LookupOptions, themes: &[&Theme]) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    for theme in themes {
+        visit_directories(theme, options, |_, _, icons| names.extend(icons));
+    }
+    names
+}
+
+/// The names of all icons provided by the theme chain, in the context of the
+/// options if any.
+pub(crate) fn theme_chain_icon_names(
+    options: &LookupOptions,
+    theme: &Theme,
+    fallbacks: &[Arc<Theme>],
+) -> BTreeSet<String> {
+    icon_names(options, &theme.ancestry_with(fallbacks))
+}
+
+/// The icons provided by the themes and the sizes they are available at,
+/// each at the sizes of the first theme providing it.
+fn icon_sizes(options: &LookupOptions, themes: &[&Theme]) -> BTreeMap<String, Vec<IconSize>> {
+    let mut icons = BTreeMap::new();
+
+    for theme in themes {
+        let mut theme_icons: BTreeMap<String, BTreeSet<IconSize>> = BTreeMap::new();
+        visit_directories(theme, options, |subdir, _, names| {
+            let size = IconSize {
+                scale: subdir.scale.unwrap_or(DEFAULT_SCALE),
+                size: subdir.size,
+                r#type: subdir.r#type,
+            };
+            for name in names {
+                theme_icons.entry(name).or_default().insert(size);
+            }
+        });
+
+        for (name, sizes) in theme_icons {
+            icons
+                .entry(name)
+                .or_insert_with(|| sizes.into_iter().collect());
+        }
+    }
+
+    icons
+}
+
+/// The names of the icons provided by the theme and the themes it inherits
+/// from, in alphabetical order.
+pub(crate) fn theme_icons(options: &LookupOptions, theme: &Theme) -> Vec<String> {
+    icon_names(options, &theme.inheritance_chain())
+        .into_iter()
+        .collect()
+}
+
+/// The icons provided by the theme and the themes it inherits from, with the
+/// sizes they are available at.
+pub(crate) fn theme_icon_sizes(
+    options: &LookupOptions,
+    theme: &Theme,
+) -> BTreeMap<String, Vec<IconSize>> {
+    icon_sizes(options, &theme.inheritance_chain())
+}
+
+/// The icons provided by the theme chain, with the sizes they are available
+/// at.
+pub(crate) fn theme_chain_icon_sizes(
+    options: &LookupOptions,
+    theme: &Theme,
+    fallbacks: &[Arc<Theme>],
+) -> BTreeMap<String, Vec<IconSize>> {
+    icon_sizes(options, &theme.ancestry_with(fallbacks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+    use crate::{Context, ThemeDirectoryType};
+
+    #[test]
+    fn test_theme_icons() {
+        let fixture = Fixture::in_memory(&[
+            ThemeFixture::new("icon-finder-enumerate")
+                .inherits("icon-finder-enumerate-base")
+                .fixed_directory("16x16/apps", 16)
+                .context(Context::Applications)
+                .fixed_directory("16x16/mimetypes", 16)
+                .context(Context::MimeTypes)
+                .scalable_directory("scalable/apps", 16, 48)
+                .context(Context::Applications)
+                .icon("16x16/apps/editor.png")
+                .icon("scalable/apps/editor.png")
+                .icon("scalable/apps/terminal.png")
+                .icon("16x16/apps/README")
+                .icon("16x16/mimetypes/text-x-generic.png"),
+            ThemeFixture::new("icon-finder-enumerate-base")
+                .fixed_directory("32x32/apps", 32)
+                .context(Context::Applications)
+                .icon("32x32/apps/editor.png")
+                .icon("32x32/apps/browser.png"),
+        ]);
+        let theme = fixture.load_theme("icon-finder-enumerate").unwrap();
+        let options = fixture.lookup_options();
+
+        assert_eq!(
+            theme_icons(&options, &theme),
+            vec!["browser", "editor", "terminal", "text-x-generic"]
+        );
+        let options_in = |context| options.clone().context(context);
+        assert_eq!(
+            theme_icons(&options_in(Context::MimeTypes), &theme),
+            vec!["text-x-generic"]
+        );
+        assert_eq!(
+            theme_icons(&options_in(Context::Applications), &theme),
+            vec!["browser", "editor", "terminal"]
+        );
+        assert!(theme_icons(&options_in(Context::Places), &theme).is_empty());
+
+        let sizes = theme_icon_sizes(&options, &theme);
+        // The sizes of the parent are never reached.
+        assert_eq!(
+            sizes["editor"],
+            vec![
+                IconSize {
+                    scale: 1,
+                    size: 16,
+                    r#type: ThemeDirectoryType::Fixed
+                },
+                IconSize {
+                    scale: 1,
+                    size: 48,
+                    r#type: ThemeDirectoryType::Scalable
+                },
+            ]
+        );
+        assert_eq!(sizes["browser"][0].size, 32);
+    }
+}
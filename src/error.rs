@@ -0,0 +1,42 @@
+//! # Errors
+//! Looking up icons never fails, a missing icon is simply not found. Loading
+//! themes from disk however can fail in a number of ways.
+
+use std::error;
+use std::fmt;
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    /// No base directory contains a theme with the given name.
+    ThemeNotFound(String),
+    /// Reading a theme file failed.
+    Io(io::Error),
+    /// An index.theme file does not follow the specification.
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ThemeNotFound(name) => write!(f, "icon theme {} not found", name),
+            Error::Io(error) => write!(f, "could not read theme: {}", error),
+            Error::Parse { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
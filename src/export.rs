@@ -0,0 +1,160 @@
+//! # Exporting icons
+//! Applications that ship their own icons, e.g. to look the same on every
+//! desktop or to run on systems without an icon theme, only need the handful
+//! of icons they use. Exporting looks up the icons at the requested sizes and
+//! copies the files found into a new theme, keeping the directories they were
+//! found in, along with an index.theme describing those directories. The
+//! exported theme can then be searched like any other, e.g. through
+//! [`LookupOptions::theme_path`](crate::LookupOptions::theme_path).
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use crate::filesystem::FileSystem;
+use crate::{IndexTheme, LookupResult, ThemeDirectory, ThemeDirectoryType, Translations};
+
+/// How the icon files end up in the exported theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExportMethod {
+    /// Copies the files.
+    #[default]
+    Copy,
+    /// Hard links the files, saving space. Files that cannot be linked, e.g.
+    /// because the export is on another file system, are copied instead.
+    HardLink,
+}
+
+/// The result of [`IconFinder::export_icons`](crate::IconFinder::export_icons).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ExportReport {
+    /// The icon files written, relative to the destination.
+    pub files: Vec<PathBuf>,
+    /// The icons that were not found, with the size they were looked up at.
+    pub missing: Vec<(String, i16)>,
+}
+
+/// Whether the directory name stays inside the theme, so a theme with a
+/// directory like "../apps" cannot make the export write elsewhere.
+fn is_relative(name: &str) -> bool {
+    Path::new(name)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// The directory unthemed icons are exported into, as they were not found
+/// in a theme directory.
+fn unthemed_directory(size: i16) -> ThemeDirectory {
+    ThemeDirectory {
+        name: format!("{}x{}", size, size),
+        size,
+        scale: None,
+        context: None,
+        r#type: ThemeDirectoryType::Threshold,
+        max_size: None,
+        min_size: None,
+        threshold: None,
+    }
+}
+
+fn write_file(
+    source: &Path,
+    target: &Path,
+    method: ExportMethod,
+    file_system: &dyn FileSystem,
+) -> io::Result<()> {
+    // Exporting again replaces the files of the earlier export.
+    if target.exists() {
+        fs::remove_file(target)?;
+    }
+
+    if method == ExportMethod::HardLink && fs::hard_link(source, target).is_ok() {
+        return Ok(());
+    }
+    fs::write(target, file_system.read(source)?)
+}
+
+/// Looks up every icon at every size with `lookup` and writes the files found
+/// into the theme at `destination`, read from the file system.
+pub(crate) fn export_icons(
+    icons: &[&str],
+    sizes: &[i16],
+    destination: &Path,
+    method: ExportMethod,
+    file_system: &dyn FileSystem,
+    mut lookup: impl FnMut(&str, i16) -> Option<LookupResult>,
+) -> io::Result<ExportReport> {
+    let mut report = ExportReport::default();
+    let mut directories: Vec<ThemeDirectory> = Vec::new();
+    let mut written = HashSet::new();
+
+    for &icon in icons {
+        for &size in sizes {
+            let result = match lookup(icon, size) {
+                Some(result) => result,
+                None => {
+                    report.missing.push((icon.to_owned(), size));
+                    continue;
+                }
+            };
+
+            let directory = result
+                .directory
+                .filter(|directory| is_relative(&directory.name))
+                .unwrap_or_else(|| unthemed_directory(size));
+            let file_name = match result.path.file_name() {
+                Some(file_name) => file_name,
+                None => continue,
+            };
+            let file = Path::new(&directory.name).join(file_name);
+            // Icons found in the same file at several sizes are written once.
+            if !written.insert(file.clone()) {
+                continue;
+            }
+
+            let target = destination.join(&file);
+            fs::create_dir_all(target.parent().unwrap_or(destination))?;
+            write_file(&result.path, &target, method, file_system)?;
+            report.files.push(file);
+
+            if !directories.iter().any(|known| known.name == directory.name) {
+                directories.push(directory);
+            }
+        }
+    }
+
+    let name = destination.file_name().map_or_else(
+        || "Exported".to_owned(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    let index = IndexTheme {
+        name: name.clone(),
+        comment: "Icons exported by icon-finder".to_owned(),
+        inherits: Vec::new(),
+        directories,
+        hidden: false,
+        translations: Translations {
+            name,
+            names: Vec::new(),
+            comments: Vec::new(),
+        },
+        example: None,
+    };
+    fs::create_dir_all(destination)?;
+    index.write(&destination.join("index.theme"))?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_relative() {
+        assert!(is_relative("48x48/apps"));
+        assert!(!is_relative("../apps"));
+        assert!(!is_relative("/usr/share/icons"));
+    }
+}
@@ -0,0 +1,133 @@
+//! # C bindings
+//! Lets C and C++ projects, and any other language with a C foreign function
+//! interface, use the lookup instead of implementing the specification again.
+//! The header is generated by cbindgen, see include/icon_finder.h.
+//!
+//! The crate is built as a Rust library only, the shared library for C is
+//! built with `cargo rustc --release --features ffi --crate-type cdylib`, or
+//! the static library with `--crate-type staticlib`.
+//!
+//! Strings are passed as null terminated UTF-8, except for the paths of
+//! icons, which are passed as the bytes the file system uses. Every string
+//! and finder returned by these functions is owned by the caller, and has to
+//! be freed with the matching free function.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::PathBuf;
+use std::ptr;
+
+use crate::{global, IconFinder, Theme};
+
+/// Creates a finder for the theme with the given internal name, or for the
+/// theme selected by the user if `theme` is null. Returns null if the theme
+/// could not be loaded.
+///
+/// # Safety
+/// `theme` must be null or a valid null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn icon_finder_new(theme: *const c_char) -> *mut IconFinder {
+    let theme = if theme.is_null() {
+        global().theme().clone()
+    } else {
+        let loaded = CStr::from_ptr(theme)
+            .to_str()
+            .ok()
+            .and_then(|name| Theme::load(name).ok());
+        match loaded {
+            Some(theme) => theme,
+            None => return ptr::null_mut(),
+        }
+    };
+
+    Box::into_raw(Box::new(IconFinder::new(theme)))
+}
+
+/// Looks up an icon, see `find_icon`. Returns the path of the icon, or null
+/// if it is not found. The path has to be freed with
+/// [`icon_finder_string_free`].
+///
+/// # Safety
+/// `finder` must be a finder returned by [`icon_finder_new`] that was not
+/// freed yet, `icon` must be a valid null terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn icon_finder_find(
+    finder: *const IconFinder,
+    icon: *const c_char,
+    size: i16,
+    scale: i16,
+) -> *mut c_char {
+    if finder.is_null() || icon.is_null() {
+        return ptr::null_mut();
+    }
+
+    CStr::from_ptr(icon)
+        .to_str()
+        .ok()
+        .and_then(|icon| (*finder).find_icon(icon, size, scale))
+        .and_then(path_c_string)
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+/// The path as a C string, with the bytes of the path on Unix, where paths
+/// need not be valid UTF-8.
+fn path_c_string(path: PathBuf) -> Option<CString> {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStringExt;
+        path.into_os_string().into_vec()
+    };
+    #[cfg(not(unix))]
+    let bytes = path.into_os_string().into_string().ok()?;
+
+    CString::new(bytes).ok()
+}
+
+/// Frees a finder returned by [`icon_finder_new`]. Does nothing if `finder`
+/// is null.
+///
+/// # Safety
+/// `finder` must be null or a finder returned by [`icon_finder_new`] that
+/// was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn icon_finder_free(finder: *mut IconFinder) {
+    if !finder.is_null() {
+        drop(Box::from_raw(finder));
+    }
+}
+
+/// Frees a string returned by one of the other functions. Does nothing if
+/// `string` is null.
+///
+/// # Safety
+/// `string` must be null or a string returned by one of the other functions
+/// that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn icon_finder_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_free() {
+        unsafe {
+            let theme = CString::new("icon-finder-nonexistent-theme").unwrap();
+            assert!(icon_finder_new(theme.as_ptr()).is_null());
+
+            let finder = icon_finder_new(ptr::null());
+            assert!(!finder.is_null());
+
+            let icon = CString::new("icon-finder-nonexistent-icon").unwrap();
+            let path = icon_finder_find(finder, icon.as_ptr(), 48, 1);
+            assert!(path.is_null());
+
+            icon_finder_string_free(path);
+            icon_finder_free(finder);
+        }
+    }
+}
@@ -0,0 +1,219 @@
+//! # File icons
+//! File browsers show every file with an icon. Directories get the folder
+//! icon, or the icon of the XDG user directory they are, like folder-download
+//! for the Downloads directory. Other files get the icon of their MIME type,
+//! guessed from the file name.
+//!
+//! The user directories are read from user-dirs.dirs in the configuration
+//! directory of the user, as written by xdg-user-dirs.
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::filesystem::{file_system, FileSystem};
+use crate::icon_names::{FOLDER, USER_DESKTOP, USER_HOME};
+use crate::mime::{MimeDatabase, UNKNOWN_MIME_TYPE};
+use crate::settings::config_home;
+use crate::{find_best_icon, Theme};
+
+/// The icons of the user directories, by their key in user-dirs.dirs.
+const USER_DIRECTORY_ICONS: [(&str, &str); 8] = [
+    ("DESKTOP", USER_DESKTOP),
+    ("DOCUMENTS", "folder-documents"),
+    ("DOWNLOAD", "folder-download"),
+    ("MUSIC", "folder-music"),
+    ("PICTURES", "folder-pictures"),
+    ("PUBLICSHARE", "folder-publicshare"),
+    ("TEMPLATES", "folder-templates"),
+    ("VIDEOS", "folder-videos"),
+];
+
+/// The home directory and the user directories in it.
+#[derive(Debug, Default)]
+struct UserDirectories {
+    home: Option<PathBuf>,
+    directories: Vec<(PathBuf, &'static str)>,
+}
+
+impl UserDirectories {
+    fn global() -> &'static UserDirectories {
+        static DIRECTORIES: OnceLock<UserDirectories> = OnceLock::new();
+
+        DIRECTORIES.get_or_init(|| {
+            let home = env::var_os("HOME").map(PathBuf::from);
+            let contents = config_home()
+                .and_then(|config| {
+                    file_system()
+                        .read_to_string(&config.join("user-dirs.dirs"))
+                        .ok()
+                })
+                .unwrap_or_default();
+
+            UserDirectories::parse(&contents, home)
+        })
+    }
+
+    /// Parses lines like `XDG_DOWNLOAD_DIR="$HOME/Downloads"`. Directories
+    /// set to the home directory itself are disabled, so they are skipped.
+    fn parse(contents: &str, home: Option<PathBuf>) -> UserDirectories {
+        let mut directories = Vec::new();
+
+        for line in contents.lines().map(str::trim) {
+            let (key, value) = match line.split_once('=') {
+                Some(entry) if !line.starts_with('#') => entry,
+                _ => continue,
+            };
+            let icon = USER_DIRECTORY_ICONS.iter().find(|(name, _)| {
+                key.strip_prefix("XDG_")
+                    .and_then(|key| key.strip_suffix("_DIR"))
+                    == Some(name)
+            });
+            let icon = match icon {
+                Some((_, icon)) => *icon,
+                None => continue,
+            };
+
+            let value = value.trim().trim_matches('"');
+            let directory = match (value.strip_prefix("$HOME"), &home) {
+                (Some(path), Some(home)) => home.join(path.trim_start_matches('/')),
+                (Some(_), None) => continue,
+                (None, _) if value.starts_with('/') => PathBuf::from(value),
+                (None, _) => continue,
+            };
+
+            if Some(&directory) != home.as_ref() {
+                directories.push((directory, icon));
+            }
+        }
+
+        UserDirectories { home, directories }
+    }
+
+    fn icon_name(&self, directory: &Path) -> Option<&'static str> {
+        if self.home.as_deref() == Some(directory) {
+            return Some(USER_HOME);
+        }
+
+        self.directories
+            .iter()
+            .find(|(user_directory, _)| user_directory == directory)
+            .map(|(_, icon)| *icon)
+    }
+}
+
+/// The names of the icons for a file, best first.
+fn icon_names(
+    path: &Path,
+    is_dir: bool,
+    user_directories: &UserDirectories,
+    database: &MimeDatabase,
+) -> Vec<String> {
+    if is_dir {
+        return user_directories
+            .icon_name(path)
+            .into_iter()
+            .chain([FOLDER])
+            .map(str::to_owned)
+            .collect();
+    }
+
+    let mime_type = path
+        .file_name()
+        .and_then(|name| database.guess_mime_type(&name.to_string_lossy()))
+        .unwrap_or(UNKNOWN_MIME_TYPE);
+
+    database.icon_names(mime_type)
+}
+
+/// The names of the icons for a file or directory, best first. Directories
+/// are recognized on the file system of the process, the MIME type of other
+/// files is guessed from their name, see
+/// [`guess_mime_type`](crate::guess_mime_type).
+pub fn path_icon_names(path: impl AsRef<Path>) -> Vec<String> {
+    path_icon_names_in(path.as_ref(), &**file_system())
+}
+
+/// The names of the icons for a file or directory like [`path_icon_names`],
+/// recognizing directories on the given file system.
+pub(crate) fn path_icon_names_in(path: &Path, file_system: &dyn FileSystem) -> Vec<String> {
+    icon_names(
+        path,
+        file_system.is_dir(path),
+        UserDirectories::global(),
+        MimeDatabase::global(),
+    )
+}
+
+/// Looks up the icon for a file or directory, like a file browser shows it,
+/// see [`path_icon_names`].
+pub fn find_icon_for_path(
+    path: impl AsRef<Path>,
+    size: i16,
+    scale: i16,
+    user_selected_theme: Theme,
+) -> Option<PathBuf> {
+    let names = path_icon_names(path);
+
+    find_best_icon(
+        names.iter().map(String::as_str).collect(),
+        size,
+        scale,
+        user_selected_theme,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFileSystem;
+
+    #[test]
+    fn test_path_icon_names() {
+        let user_directories = UserDirectories::parse(
+            "# written by xdg-user-dirs-update\n\
+             XDG_DESKTOP_DIR=\"$HOME\"\n\
+             XDG_DOWNLOAD_DIR=\"$HOME/Downloads\"\n\
+             XDG_MUSIC_DIR=\"/data/music\"\n\
+             XDG_VIDEOS_DIR=\"videos\"\n",
+            Some(PathBuf::from("/home/user")),
+        );
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/usr/share/mime/globs2", "50:text/rust:*.rs\n");
+        fs.add_file("/usr/share/mime/generic-icons", "text/rust:text-x-script\n");
+        let database = MimeDatabase::load(&[PathBuf::from("/usr/share/mime")], &fs);
+
+        let names = |path: &str, is_dir: bool| {
+            icon_names(Path::new(path), is_dir, &user_directories, &database)
+        };
+
+        assert_eq!(names("/home/user", true), vec!["user-home", "folder"]);
+        assert_eq!(
+            names("/home/user/Downloads/", true),
+            vec!["folder-download", "folder"]
+        );
+        assert_eq!(names("/data/music", true), vec!["folder-music", "folder"]);
+        assert_eq!(names("/home/user/videos", true), vec!["folder"]);
+        assert_eq!(
+            names("/home/user/src/main.rs", false),
+            vec!["text-rust", "text-x-script"]
+        );
+        assert_eq!(
+            names("/home/user/data.bin", false),
+            vec!["application-octet-stream", "application-x-generic"]
+        );
+    }
+
+    #[test]
+    fn test_path_icon_names_in() {
+        let fs = MemoryFileSystem::new();
+        fs.add_dir("/icon-finder-files/projects");
+
+        let names = path_icon_names_in(Path::new("/icon-finder-files/projects"), &fs);
+        assert_eq!(names.last().map(String::as_str), Some(FOLDER));
+        assert_ne!(
+            path_icon_names("/icon-finder-files/projects").last(),
+            names.last()
+        );
+    }
+}
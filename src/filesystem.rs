@@ -0,0 +1,415 @@
+//! # File systems
+//! Icons are normally looked up on disk, but the lookup does not touch the
+//! disk directly. It goes through a [`FileSystem`], so it can run where there
+//! is no disk, like in a browser when compiled to WebAssembly, and on themes
+//! that only exist in memory.
+//!
+//! The file system of the process defaults to the disk and can be replaced
+//! once with [`set_file_system`], before the first lookup. A finder can use a
+//! file system of its own, see
+//! [`IconFinderBuilder::file_system`](crate::IconFinderBuilder::file_system).
+//!
+//! The crate builds for the browser with
+//! `cargo check --target wasm32-unknown-unknown`. Watching for changes and
+//! the background refresher need threads and are left out there, the
+//! desktop settings are only read from configuration files. The clock of
+//! the standard library is not available on wasm32-unknown-unknown either,
+//! so icons are looked up with the functions of the crate, like
+//! [`find_icon_with`](crate::find_icon_with), after [`set_file_system`],
+//! rather than through a finder.
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, SystemTime};
+
+use crate::warning::{self, Warning};
+
+/// The file operations the lookup needs. Paths are always absolute.
+pub trait FileSystem: Send + Sync {
+    /// Whether a file or directory exists at the path.
+    fn exists(&self, path: &Path) -> bool;
+
+    /// Whether a directory exists at the path.
+    fn is_dir(&self, path: &Path) -> bool;
+
+    /// Whether a file exists at the path.
+    fn is_file(&self, path: &Path) -> bool {
+        self.exists(path) && !self.is_dir(path)
+    }
+
+    /// The names of the entries of a directory, in no particular order.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>>;
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// The contents of a file. Defaults to the contents read as text, file
+    /// systems holding binary files have to implement it.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.read_to_string(path).map(String::into_bytes)
+    }
+
+    /// The size of a file in bytes. Defaults to the length of its contents.
+    fn len(&self, path: &Path) -> io::Result<u64> {
+        self.read(path).map(|contents| contents.len() as u64)
+    }
+
+    /// The path with symbolic links resolved. Defaults to the path itself,
+    /// for file systems without links.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        Ok(path.to_path_buf())
+    }
+
+    /// The modification time of a file or directory. The modification time of
+    /// a directory has to change when entries are added or removed, lookup
+    /// results are only cached as long as it does not.
+    fn modified(&self, path: &Path) -> io::Result<SystemTime>;
+}
+
+/// The file system of the operating system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdFileSystem;
+
+impl FileSystem for StdFileSystem {
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    /// Leaves out symbolic links pointing to files that do not exist, which
+    /// would otherwise be found as icons that cannot be loaded.
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        let mut names = Vec::new();
+
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+            // Only links are checked, which saves looking up every file.
+            let is_link = entry.file_type().is_ok_and(|kind| kind.is_symlink());
+            if is_link && fs::metadata(entry.path()).is_err() {
+                warning::warn(Warning::BrokenLink(entry.path()));
+                continue;
+            }
+            names.push(entry.file_name());
+        }
+
+        Ok(names)
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn len(&self, path: &Path) -> io::Result<u64> {
+        Ok(fs::metadata(path)?.len())
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        fs::metadata(path)?.modified()
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    File(Vec<u8>),
+    Directory,
+}
+
+#[derive(Debug, Default)]
+struct Tree {
+    nodes: BTreeMap<PathBuf, (Node, SystemTime)>,
+    /// Incremented on every change, used as the modification time. The
+    /// clock of the system is not used, as it is not available everywhere,
+    /// e.g. not in a browser.
+    clock: u64,
+}
+
+/// A file system kept in memory, e.g. holding themes downloaded by a web
+/// application, or themes built by a test.
+///
+/// ```
+/// use icon_finder::{FileSystem, MemoryFileSystem};
+/// use std::path::Path;
+///
+/// let fs = MemoryFileSystem::new();
+/// fs.add_file("/usr/share/icons/hicolor/48x48/apps/firefox.png", "");
+///
+/// assert!(fs.is_dir(Path::new("/usr/share/icons/hicolor")));
+/// ```
+#[derive(Debug, Default)]
+pub struct MemoryFileSystem {
+    tree: RwLock<Tree>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> MemoryFileSystem {
+        MemoryFileSystem::default()
+    }
+
+    /// Adds a file, replacing the file at the path if there is one. Missing
+    /// parent directories are created. The contents can be text, like an
+    /// index.theme, or binary, like a PNG image.
+    pub fn add_file(&self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) {
+        let path = normalize(path.as_ref());
+        let mut tree = self.tree_mut();
+
+        if let Some(parent) = path.parent() {
+            create_dir_all(&mut tree, parent);
+        }
+        insert(&mut tree, path, Node::File(contents.into()));
+    }
+
+    /// Adds a directory and any missing parent directories.
+    pub fn add_dir(&self, path: impl AsRef<Path>) {
+        create_dir_all(&mut self.tree_mut(), &normalize(path.as_ref()));
+    }
+
+    /// Removes a file or a directory with everything in it.
+    pub fn remove(&self, path: impl AsRef<Path>) {
+        let path = normalize(path.as_ref());
+        let mut tree = self.tree_mut();
+
+        tree.nodes.retain(|node, _| !node.starts_with(&path));
+        if let Some(parent) = path.parent() {
+            touch(&mut tree, parent);
+        }
+    }
+
+    fn tree(&self) -> RwLockReadGuard<'_, Tree> {
+        self.tree.read().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn tree_mut(&self) -> RwLockWriteGuard<'_, Tree> {
+        self.tree.write().unwrap_or_else(|error| error.into_inner())
+    }
+
+    fn node(&self, path: &Path) -> Option<(Node, SystemTime)> {
+        self.tree().nodes.get(&normalize(path)).cloned()
+    }
+}
+
+fn create_dir_all(tree: &mut Tree, path: &Path) {
+    if tree.nodes.contains_key(path) {
+        return;
+    }
+
+    if let Some(parent) = path.parent() {
+        create_dir_all(tree, parent);
+    }
+    insert(tree, path.to_owned(), Node::Directory);
+}
+
+/// Inserts a node, updating the modification time of its parent.
+fn insert(tree: &mut Tree, path: PathBuf, node: Node) {
+    let modified = tick(tree);
+    if let Some(parent) = path.parent() {
+        touch(tree, parent);
+    }
+    tree.nodes.insert(path, (node, modified));
+}
+
+fn touch(tree: &mut Tree, path: &Path) {
+    let modified = tick(tree);
+    if let Some((_, node_modified)) = tree.nodes.get_mut(path) {
+        *node_modified = modified;
+    }
+}
+
+fn tick(tree: &mut Tree) -> SystemTime {
+    tree.clock += 1;
+    SystemTime::UNIX_EPOCH + Duration::from_nanos(tree.clock)
+}
+
+/// Removes `.` components and trailing slashes, so every path has a single
+/// key in the tree.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+fn not_found() -> io::Error {
+    io::ErrorKind::NotFound.into()
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn exists(&self, path: &Path) -> bool {
+        self.node(path).is_some()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        matches!(self.node(path), Some((Node::Directory, _)))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<OsString>> {
+        let path = normalize(path);
+        if !self.is_dir(&path) {
+            return Err(not_found());
+        }
+
+        Ok(self
+            .tree()
+            .nodes
+            .range(path.clone()..)
+            .skip(1)
+            .take_while(|(node, _)| node.starts_with(&path))
+            .filter(|(node, _)| node.parent() == Some(&path))
+            .filter_map(|(node, _)| node.file_name())
+            .map(OsString::from)
+            .collect())
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.node(path) {
+            Some((Node::File(contents), _)) => Ok(contents),
+            Some((Node::Directory, _)) => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "is a directory",
+            )),
+            None => Err(not_found()),
+        }
+    }
+
+    fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+        self.node(path)
+            .map(|(_, modified)| modified)
+            .ok_or_else(not_found)
+    }
+}
+
+static FILE_SYSTEM: OnceLock<Arc<dyn FileSystem>> = OnceLock::new();
+
+/// Replaces the file system of the process, e.g. with a
+/// [`MemoryFileSystem`] when running in a browser. Has to be called before
+/// the first lookup, fails with the given file system if the file system
+/// was set or used before.
+pub fn set_file_system(file_system: Arc<dyn FileSystem>) -> Result<(), Arc<dyn FileSystem>> {
+    FILE_SYSTEM.set(file_system)
+}
+
+/// The file system of the process, the disk unless replaced with
+/// [`set_file_system`].
+pub(crate) fn file_system() -> &'static Arc<dyn FileSystem> {
+    FILE_SYSTEM.get_or_init(|| Arc::new(StdFileSystem))
+}
+
+/// A file system of a finder. Finders are compared and hashed by the identity
+/// of their file system, not by its contents.
+#[derive(Clone)]
+pub(crate) struct FileSystemRef(pub(crate) Arc<dyn FileSystem>);
+
+impl PartialEq for FileSystemRef {
+    fn eq(&self, other: &FileSystemRef) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for FileSystemRef {}
+
+impl Hash for FileSystemRef {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (Arc::as_ptr(&self.0) as *const () as usize).hash(state);
+    }
+}
+
+impl fmt::Debug for FileSystemRef {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("FileSystem")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_file_system() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/icons/hicolor/index.theme", "[Icon Theme]\n");
+        fs.add_file("/icons/hicolor/48x48/apps/firefox.png", "");
+        fs.add_dir("/icons/Adwaita/");
+
+        assert!(fs.is_dir(Path::new("/icons/hicolor/48x48")));
+        assert!(fs.is_file(Path::new("/icons/hicolor/index.theme")));
+        assert!(!fs.exists(Path::new("/icons/breeze")));
+        assert_eq!(
+            fs.read_to_string(Path::new("/icons/hicolor/index.theme"))
+                .unwrap(),
+            "[Icon Theme]\n"
+        );
+        fs.add_file(
+            "/icons/hicolor/48x48/apps/editor.png",
+            [0x89, b'P', b'N', b'G'],
+        );
+        assert_eq!(
+            fs.read(Path::new("/icons/hicolor/48x48/apps/editor.png"))
+                .unwrap(),
+            b"\x89PNG"
+        );
+        assert!(fs
+            .read_to_string(Path::new("/icons/hicolor/48x48/apps/editor.png"))
+            .is_err());
+        fs.remove("/icons/hicolor/48x48/apps/editor.png");
+
+        let mut entries = fs.read_dir(Path::new("/icons")).unwrap();
+        entries.sort();
+        assert_eq!(entries, vec!["Adwaita", "hicolor"]);
+
+        let directory = Path::new("/icons/hicolor/48x48/apps");
+        let modified = fs.modified(directory).unwrap();
+        fs.remove("/icons/hicolor/48x48/apps/firefox.png");
+
+        assert!(fs.read_dir(directory).unwrap().is_empty());
+        assert_ne!(fs.modified(directory).unwrap(), modified);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_broken_links_are_skipped() {
+        use std::os::unix::fs::symlink;
+        use std::{env, process};
+
+        use crate::warning::capture_warnings;
+
+        let directory = env::temp_dir().join(format!("icon-finder-broken-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("editor.png"), "").unwrap();
+        symlink("editor.png", directory.join("text-editor.png")).unwrap();
+        symlink("removed.png", directory.join("browser.png")).unwrap();
+
+        let (entries, warnings) = capture_warnings(|| StdFileSystem.read_dir(&directory));
+        let mut entries = entries.unwrap();
+        entries.sort();
+        assert_eq!(entries, vec!["editor.png", "text-editor.png"]);
+        assert_eq!(
+            warnings,
+            [Warning::BrokenLink(directory.join("browser.png"))]
+        );
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
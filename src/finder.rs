@@ -0,0 +1,1512 @@
+//! # Icon Finder
+//! Applications usually look up many icons in the same theme. The finder holds
+//! on to the user selected theme, so it does not have to be passed along with
+//! every lookup.
+
+use std::collections::BTreeMap;
+#[cfg(any(feature = "image", feature = "render"))]
+use std::convert::TryFrom;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "image")]
+use crate::atlas::{self, IconAtlas};
+use crate::cache::{CacheStats, LookupCache, Reloaded, Stamp, POLL_INTERVAL};
+use crate::desktop;
+use crate::emblem;
+use crate::enumerate;
+use crate::export::{self, ExportMethod, ExportReport};
+use crate::files;
+use crate::filesystem::{self, FileSystem};
+use crate::identify;
+use crate::input;
+use crate::listing;
+#[cfg(any(feature = "image", feature = "render"))]
+use crate::load::IconPixels;
+use crate::load::{self, LoadedIcon};
+#[cfg(feature = "image")]
+use crate::pixels;
+use crate::profile::{Profile, Profiler};
+#[cfg(not(target_family = "wasm"))]
+use crate::refresher::Refresher;
+#[cfg(feature = "render")]
+use crate::render;
+#[cfg(feature = "render")]
+use crate::render_cache::{self, RenderCache};
+use crate::search;
+use crate::settings;
+#[cfg(not(target_family = "wasm"))]
+use crate::watch::{Subscription, Watcher};
+#[cfg(feature = "render")]
+use crate::SymbolicPalette;
+use crate::{
+    available_sizes_in, fallback_theme, find_best_icon_in, find_icon_in, find_icon_sizes_in,
+    mime_type_icon_names, Candidates, ColorScheme, Context, EmblemedIcon, IconAliases, IconFormat,
+    IconHandle, IconLocation, IconSize, LookupFlags, LookupOptions, LookupProfile, LookupResult,
+    Theme, ALLOWED_FORMATS,
+};
+
+/// Looks up icons in a theme, its parents and the hicolor fallback theme.
+pub struct IconFinder {
+    theme: Theme,
+    fallback_themes: Vec<Arc<Theme>>,
+    formats: Vec<IconFormat>,
+    prepended_paths: Vec<PathBuf>,
+    appended_paths: Vec<PathBuf>,
+    file_system: Option<Arc<dyn FileSystem>>,
+    profiler: Profiler,
+    lookup_profile: LookupProfile,
+    aliases: Option<Arc<IconAliases>>,
+    cache: Mutex<LookupCache>,
+    /// The directories whose modification times cached results depend on.
+    cache_directories: Vec<PathBuf>,
+    #[cfg(feature = "render")]
+    render_cache: Mutex<RenderCache>,
+}
+
+/// A finder for the icon theme selected by the user, created the first time it
+/// is used. The theme is read from the desktop settings, see
+/// [`selected_icon_theme`](crate::selected_icon_theme), and falls back to
+/// hicolor if no theme is selected or the selected theme is not installed.
+///
+/// ```no_run
+/// let path = icon_finder::global().find_icon("firefox", 48, 1);
+/// ```
+pub fn global() -> &'static IconFinder {
+    static FINDER: OnceLock<IconFinder> = OnceLock::new();
+
+    FINDER.get_or_init(|| {
+        let theme = settings::selected_icon_theme()
+            .and_then(|name| {
+                Theme::load(&name)
+                    .inspect_err(|_error| {
+                        #[cfg(feature = "log")]
+                        log::debug!("falling back to hicolor, {}: {}", name, _error);
+                    })
+                    .ok()
+            })
+            .unwrap_or_else(|| fallback_theme().clone());
+
+        IconFinder::new(theme)
+    })
+}
+
+/// Configures an [`IconFinder`] before it is created.
+///
+/// ```
+/// use icon_finder::{IconFinder, IconFormat, Theme, Translations};
+///
+/// let theme = Theme {
+///     name: "Adwaita".to_owned(),
+///     comment: "The default GNOME icon theme".to_owned(),
+///     inherits: Vec::new(),
+///     directories: Vec::new(),
+///     hidden: false,
+///     translations: Translations::default(),
+///     example: None,
+/// };
+///
+/// let finder = IconFinder::builder(theme)
+///     .format_preference(&[IconFormat::Svg, IconFormat::Png])
+///     .build();
+/// ```
+pub struct IconFinderBuilder {
+    theme: Theme,
+    fallback_themes: Vec<String>,
+    formats: Vec<IconFormat>,
+    cache_capacity: usize,
+    revalidation_interval: Duration,
+    prepended_paths: Vec<PathBuf>,
+    appended_paths: Vec<PathBuf>,
+    file_system: Option<Arc<dyn FileSystem>>,
+    profiling: bool,
+    color_scheme: ColorScheme,
+    lookup_profile: LookupProfile,
+    aliases: Option<IconAliases>,
+    #[cfg(feature = "render")]
+    render_cache_capacity: usize,
+    #[cfg(feature = "render")]
+    render_cache_directory: Option<PathBuf>,
+}
+
+impl IconFinderBuilder {
+    /// Sets the formats icons may be returned in, in order of preference. When
+    /// an icon is available in several formats for the same size, the format
+    /// listed first is returned. Lookups that set their own formats through
+    /// [`LookupOptions::formats`] are not affected.
+    pub fn format_preference(mut self, formats: &[IconFormat]) -> IconFinderBuilder {
+        self.formats = formats.to_vec();
+        self
+    }
+
+    /// Searches the given themes, in order, when the theme and its parents do
+    /// not provide an icon, before falling back to hicolor. Toolkits do the
+    /// same, GTK for example falls back to Adwaita. Themes that are not
+    /// installed are skipped.
+    pub fn fallback_themes(mut self, names: &[&str]) -> IconFinderBuilder {
+        self.fallback_themes = names.iter().map(|&name| name.to_owned()).collect();
+        self
+    }
+
+    /// Never returns icons in the given format, for applications that are not
+    /// able to load it at runtime, e.g. because an SVG renderer is missing.
+    pub fn disable_format(mut self, format: IconFormat) -> IconFinderBuilder {
+        self.formats.retain(|&allowed| allowed != format);
+        self
+    }
+
+    /// Searches the given directory for themes and unthemed icons before the
+    /// base directories, e.g. for icons bundled with the application. Icons in
+    /// this directory win over icons of the same theme in the base
+    /// directories. Paths added later are searched after paths added before.
+    pub fn prepend_search_path(mut self, path: impl AsRef<Path>) -> IconFinderBuilder {
+        self.prepended_paths.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Searches the given directory for themes and unthemed icons after the
+    /// base directories, e.g. for fallback icons bundled with the
+    /// application.
+    pub fn append_search_path(mut self, path: impl AsRef<Path>) -> IconFinderBuilder {
+        self.appended_paths.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Remembers the results of up to `capacity` recent lookups, so icons
+    /// that are looked up repeatedly do not go through the lookup algorithm
+    /// every time. Icons that were not found are remembered as well. The least
+    /// recently used result is evicted once the cache is full. Results are
+    /// forgotten once the toplevel directory of one of the themes changes,
+    /// see [`revalidation_interval`](IconFinderBuilder::revalidation_interval).
+    /// Caching is disabled by default.
+    ///
+    /// Only lookups of a single icon name are cached, like
+    /// [`find_icon`](IconFinder::find_icon) and
+    /// [`lookup_icon_with`](IconFinder::lookup_icon_with). Lookups of several
+    /// names or sizes at once, like
+    /// [`find_best_icon`](IconFinder::find_best_icon),
+    /// [`find_icon_sizes`](IconFinder::find_icon_sizes),
+    /// [`find_mime_type_icon`](IconFinder::find_mime_type_icon),
+    /// [`find_app_icon`](IconFinder::find_app_icon) and
+    /// [`find_icon_for_path`](IconFinder::find_icon_for_path), always go
+    /// through the lookup algorithm.
+    pub fn cache_capacity(mut self, capacity: usize) -> IconFinderBuilder {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    /// How long cached results and the listings of theme directories are
+    /// trusted before the directories are looked at again for changes, every
+    /// [`POLL_INTERVAL`](crate::POLL_INTERVAL) by default as the
+    /// specification suggests. [`Duration::ZERO`] looks at them on every
+    /// lookup, e.g. for icon editors, [`Duration::MAX`] never does, e.g. for
+    /// kiosks whose themes do not change. [`IconFinder::refresh`] looks at
+    /// them right away.
+    pub fn revalidation_interval(mut self, interval: Duration) -> IconFinderBuilder {
+        self.revalidation_interval = interval;
+        self
+    }
+
+    /// Looks up icons in the given file system instead of the file system of
+    /// the process, e.g. in themes kept in memory. The theme of the finder
+    /// should be loaded from the same file system, see
+    /// [`Theme::load_from`].
+    pub fn file_system(mut self, file_system: Arc<dyn FileSystem>) -> IconFinderBuilder {
+        self.file_system = Some(file_system);
+        self
+    }
+
+    /// Measures the time spent in every phase of the lookups, see
+    /// [`IconFinder::profile`]. Disabled by default, as measuring has a
+    /// small cost of its own.
+    pub fn profiling(mut self, enabled: bool) -> IconFinderBuilder {
+        self.profiling = enabled;
+        self
+    }
+
+    /// Uses the variant of the theme matching the color scheme, like
+    /// Papirus-Dark for Papirus when a dark scheme is preferred, if that
+    /// variant is installed. The scheme of the user is returned by
+    /// [`preferred_color_scheme`](crate::preferred_color_scheme). With
+    /// [`ColorScheme::Default`], the default, the theme is used as given.
+    pub fn color_scheme(mut self, color_scheme: ColorScheme) -> IconFinderBuilder {
+        self.color_scheme = color_scheme;
+        self
+    }
+
+    /// Follows the given lookup algorithm, see [`LookupProfile`]. Lookups
+    /// that set their own through [`LookupOptions::lookup_profile`] are not
+    /// affected.
+    pub fn lookup_profile(mut self, lookup_profile: LookupProfile) -> IconFinderBuilder {
+        self.lookup_profile = lookup_profile;
+        self
+    }
+
+    /// Looks up the name a legacy icon name is an alias of when the legacy
+    /// name is not found, see [`IconAliases`]. No aliases are used by
+    /// default, [`IconAliases::builtin`] has the well-known ones. Lookups
+    /// that set their own through [`LookupOptions::aliases`] are not
+    /// affected.
+    pub fn aliases(mut self, aliases: IconAliases) -> IconFinderBuilder {
+        self.aliases = Some(aliases);
+        self
+    }
+
+    /// Remembers up to `capacity` bitmaps rendered by
+    /// [`IconFinder::render_icon`], so icons that are rendered repeatedly at
+    /// the same size are only rendered once. A bitmap is rendered again once
+    /// the modification time of its image changes. Disabled by default.
+    /// Requires the `render` feature.
+    #[cfg(feature = "render")]
+    pub fn render_cache_capacity(mut self, capacity: usize) -> IconFinderBuilder {
+        self.render_cache_capacity = capacity;
+        self
+    }
+
+    /// Keeps rendered bitmaps in the given directory as well, so they survive
+    /// the process, see
+    /// [`default_render_cache_directory`](crate::default_render_cache_directory).
+    /// Bitmaps are only kept in memory by default. The bitmaps written
+    /// longest ago are removed once the directory holds more than 64 MiB of
+    /// them. Requires the `render` feature.
+    #[cfg(feature = "render")]
+    pub fn render_cache_directory(mut self, directory: impl AsRef<Path>) -> IconFinderBuilder {
+        self.render_cache_directory = Some(directory.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn build(self) -> IconFinder {
+        let file_system = self
+            .file_system
+            .clone()
+            .unwrap_or_else(|| Arc::clone(filesystem::file_system()));
+        let theme = color_scheme_variant(&self.theme, self.color_scheme, &*file_system)
+            .unwrap_or(self.theme);
+
+        let mut fallback_names = self.fallback_themes.clone();
+        // The hicolor theme of the process is loaded from the file system of
+        // the process, the finder needs its own.
+        if self.file_system.is_some() && !fallback_names.iter().any(|name| name == "hicolor") {
+            fallback_names.push("hicolor".to_owned());
+        }
+
+        let fallback_themes: Vec<Arc<Theme>> = fallback_names
+            .iter()
+            .filter_map(|name| {
+                Theme::load_from(name, &*file_system)
+                    .inspect_err(|_error| {
+                        #[cfg(feature = "log")]
+                        log::debug!("skipping fallback theme {}: {}", name, _error);
+                    })
+                    .ok()
+            })
+            .map(Arc::new)
+            .collect();
+
+        let mut finder = IconFinder {
+            theme,
+            fallback_themes,
+            formats: self.formats,
+            prepended_paths: self.prepended_paths,
+            appended_paths: self.appended_paths,
+            file_system: self.file_system,
+            profiler: if self.profiling {
+                Profiler::enabled()
+            } else {
+                Profiler::default()
+            },
+            lookup_profile: self.lookup_profile,
+            aliases: self.aliases.map(Arc::new),
+            cache: Mutex::new(LookupCache::new(
+                self.cache_capacity,
+                self.revalidation_interval,
+            )),
+            cache_directories: Vec::new(),
+            #[cfg(feature = "render")]
+            render_cache: Mutex::new(RenderCache::new(
+                self.render_cache_capacity,
+                self.render_cache_directory,
+            )),
+        };
+
+        let options = finder.options(&LookupOptions::new(0));
+        let mut cache_directories = finder.watched_directories();
+        // Unthemed icons live directly in the base directories and the
+        // pixmaps directories.
+        cache_directories.extend(options.unthemed_directories().map(PathBuf::from));
+        finder.cache_directories = cache_directories;
+
+        finder
+    }
+}
+
+impl IconFinder {
+    pub fn new(theme: Theme) -> IconFinder {
+        IconFinder::builder(theme).build()
+    }
+
+    pub fn builder(theme: Theme) -> IconFinderBuilder {
+        IconFinderBuilder {
+            theme,
+            fallback_themes: Vec::new(),
+            formats: ALLOWED_FORMATS.to_vec(),
+            cache_capacity: 0,
+            revalidation_interval: POLL_INTERVAL,
+            prepended_paths: Vec::new(),
+            appended_paths: Vec::new(),
+            file_system: None,
+            profiling: false,
+            color_scheme: ColorScheme::Default,
+            lookup_profile: LookupProfile::default(),
+            aliases: None,
+            #[cfg(feature = "render")]
+            render_cache_capacity: 0,
+            #[cfg(feature = "render")]
+            render_cache_directory: None,
+        }
+    }
+
+    /// The theme icons are looked up in first.
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    /// Looks up an icon by name, see [`find_icon`](crate::find_icon).
+    pub fn find_icon(&self, icon: &str, size: i16, scale: i16) -> Option<PathBuf> {
+        self.find_icon_with(icon, &LookupOptions::new(size).scale(scale))
+    }
+
+    /// Looks up an icon by name, configured by the given options, see
+    /// [`find_icon_with`](crate::find_icon_with).
+    pub fn find_icon_with(&self, icon: &str, options: &LookupOptions) -> Option<PathBuf> {
+        self.lookup_icon_with(icon, options)
+            .map(|result| result.path)
+    }
+
+    /// Looks up an icon by name and reads its file, with the format detected
+    /// from its contents. `Ok(None)` if the icon is not found, an error if
+    /// its file cannot be read.
+    pub fn load_icon(&self, icon: &str, size: i16, scale: i16) -> io::Result<Option<LoadedIcon>> {
+        self.load_icon_with(icon, &LookupOptions::new(size).scale(scale))
+    }
+
+    /// Looks up an icon by name, configured by the given options, and reads
+    /// its file, see [`load_icon`](IconFinder::load_icon).
+    pub fn load_icon_with(
+        &self,
+        icon: &str,
+        options: &LookupOptions,
+    ) -> io::Result<Option<LoadedIcon>> {
+        self.lookup_icon_with(icon, options)
+            .map(|result| load::load_result(result, &self.options(options)))
+            .transpose()
+    }
+
+    /// Looks up an icon by name and decodes its file into RGBA pixels, at
+    /// the size of the file. `Ok(None)` if the icon is not found, an error if
+    /// its file cannot be read or decoded, like SVG images. PNG and XPM
+    /// images are decoded. Requires the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn load_icon_pixels(
+        &self,
+        icon: &str,
+        size: i16,
+        scale: i16,
+    ) -> io::Result<Option<IconPixels>> {
+        self.load_icon(icon, size, scale)?
+            .map(|icon| pixels::decode(&icon))
+            .transpose()
+    }
+
+    /// Looks up the icons and packs them into a single image, every icon
+    /// scaled to `size` × `scale` pixels, see [`IconAtlas`]. Icons that are
+    /// not found or cannot be rasterized are listed as missing. SVG icons are
+    /// only used with the `render` feature. Requires the `image` feature.
+    #[cfg(feature = "image")]
+    pub fn build_atlas(&self, icons: &[&str], size: i16, scale: i16) -> io::Result<IconAtlas> {
+        let pixels = u32::try_from(i32::from(size) * i32::from(scale))
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "negative icon size"))?;
+        let formats: Vec<IconFormat> = self
+            .formats
+            .iter()
+            .copied()
+            .filter(|format| cfg!(feature = "render") || *format != IconFormat::Svg)
+            .collect();
+        let options = LookupOptions::new(size).scale(scale).formats(&formats);
+
+        atlas::build_atlas(icons, pixels, |icon| {
+            let icon = match self.load_icon_with(icon, &options)? {
+                Some(icon) => icon,
+                None => return Ok(None),
+            };
+
+            #[cfg(feature = "render")]
+            if icon.format == IconFormat::Svg {
+                return render::render(&icon, pixels).map(Some);
+            }
+
+            pixels::decode(&icon).map(Some)
+        })
+    }
+
+    /// Looks up the icons at every size and writes the files found into a
+    /// new theme at `destination`, along with an index.theme describing the
+    /// directories they were found in, so applications can ship exactly the
+    /// icons they use. Icons that are not found are listed in the report.
+    pub fn export_icons(
+        &self,
+        icons: &[&str],
+        sizes: &[i16],
+        destination: &Path,
+        method: ExportMethod,
+    ) -> io::Result<ExportReport> {
+        let options = self.options(&LookupOptions::new(0));
+
+        export::export_icons(
+            icons,
+            sizes,
+            destination,
+            method,
+            options.file_system(),
+            |icon, size| self.lookup_icon_with(icon, &LookupOptions::new(size)),
+        )
+    }
+
+    /// Looks up the SVG image of an icon and renders it into a bitmap of
+    /// exactly `size` × `scale` pixels. `Ok(None)` if the icon has no SVG
+    /// image, an error if it cannot be read or rendered. Rendered bitmaps are
+    /// cached when enabled, see
+    /// [`IconFinderBuilder::render_cache_capacity`]. Requires the `render`
+    /// feature.
+    #[cfg(feature = "render")]
+    pub fn render_icon(&self, icon: &str, size: i16, scale: i16) -> io::Result<Option<IconPixels>> {
+        self.render(icon, size, scale, None)
+    }
+
+    /// Looks up the SVG image of a symbolic icon and renders it in the colors
+    /// of the palette, see [`render_icon`](IconFinder::render_icon) and
+    /// [`LoadedIcon::recolor`]. Requires the `render` feature.
+    #[cfg(feature = "render")]
+    pub fn render_symbolic_icon(
+        &self,
+        icon: &str,
+        size: i16,
+        scale: i16,
+        palette: &SymbolicPalette,
+    ) -> io::Result<Option<IconPixels>> {
+        self.render(icon, size, scale, Some(palette))
+    }
+
+    #[cfg(feature = "render")]
+    fn render(
+        &self,
+        icon: &str,
+        size: i16,
+        scale: i16,
+        palette: Option<&SymbolicPalette>,
+    ) -> io::Result<Option<IconPixels>> {
+        let pixels = u32::try_from(i32::from(size) * i32::from(scale))
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "negative icon size"))?;
+        let options = LookupOptions::new(size)
+            .scale(scale)
+            .formats(&[IconFormat::Svg]);
+        let result = match self.lookup_icon_with(icon, &options) {
+            Some(result) => result,
+            None => return Ok(None),
+        };
+        let options = self.options(&options);
+
+        let key = self
+            .render_cache()
+            .is_enabled()
+            .then(|| options.file_system().modified(&result.path).ok())
+            .flatten()
+            .map(|modified| render_cache::Key {
+                path: result.path.clone(),
+                size,
+                scale,
+                modified,
+                palette: palette.copied(),
+            });
+        if let Some(pixels) = key.as_ref().and_then(|key| self.render_cache().get(key)) {
+            return Ok(Some(pixels));
+        }
+
+        let mut loaded = load::load_result(result, &options)?;
+        if let Some(palette) = palette {
+            loaded = loaded.recolor(palette)?;
+        }
+        let pixels = render::render(&loaded, pixels)?;
+        if let Some(key) = key {
+            self.render_cache().insert(key, &pixels);
+        }
+
+        Ok(Some(pixels))
+    }
+
+    /// Looks up an icon by name, returning where it was found along with its
+    /// path, see [`lookup_icon_with`](crate::lookup_icon_with).
+    pub fn lookup_icon_with(&self, icon: &str, options: &LookupOptions) -> Option<LookupResult> {
+        let mut options = self.options(options);
+        let bypass_negative_cache = options.flags.contains(LookupFlags::BYPASS_NEGATIVE_CACHE);
+        // The flag does not affect the result, so the result is cached under
+        // the same options.
+        options.flags.remove(LookupFlags::BYPASS_NEGATIVE_CACHE);
+
+        // The theme path of a lookup is a temporary directory the finder does
+        // not watch, so results from it are not remembered. Neither are icons
+        // given as a file, looking them up is cheaper than caching them.
+        if self.cache_capacity() == 0
+            || options.theme_path.is_some()
+            || input::file_path(icon).is_some()
+        {
+            return self.scan(icon, &options);
+        }
+
+        let stamp = self.stamp(&options);
+        // Not matched on directly, the lock would be held in the arms.
+        let cached = self.cache().get(icon, &options, &stamp);
+        match cached {
+            Some(Some(result)) => {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(icon, "cache hit");
+                self.cache().record_hit(true);
+                return Some(result);
+            }
+            Some(None) if !bypass_negative_cache => {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(icon, "negative cache hit");
+                self.cache().record_hit(false);
+                return None;
+            }
+            _ => {}
+        }
+        #[cfg(feature = "tracing")]
+        tracing::trace!(icon, "cache miss");
+
+        let result = self.scan(icon, &options);
+        // A cancelled lookup did not finish, its result says nothing about
+        // the icon.
+        if !options.is_cancelled() {
+            self.cache().insert(icon, &options, result.clone(), stamp);
+        }
+        result
+    }
+
+    /// Goes through the lookup algorithm, counting the lookup as a miss.
+    fn scan(&self, icon: &str, options: &LookupOptions) -> Option<LookupResult> {
+        let start = Instant::now();
+        let result = self.profiler.time_lookup(icon, || {
+            find_icon_in(icon, options, &self.theme, &self.fallback_themes)
+        });
+        self.cache().record_miss(start.elapsed());
+
+        result
+    }
+
+    /// Counters describing how often lookups were answered from the cache,
+    /// and how much time went into the lookups that were not, e.g. for
+    /// monitoring whether the cache capacity is large enough. Lookups of
+    /// several icons at once, like [`find_best_icon`](IconFinder::find_best_icon),
+    /// are not counted.
+    pub fn stats(&self) -> CacheStats {
+        self.cache().stats()
+    }
+
+    /// The time spent in every phase of the lookups since the finder was
+    /// created or the profile was reset, or `None` if the finder was not
+    /// built with [`profiling`](IconFinderBuilder::profiling) enabled.
+    ///
+    /// ```no_run
+    /// use icon_finder::{IconFinder, Theme};
+    ///
+    /// let finder = IconFinder::builder(Theme::load("Adwaita").unwrap())
+    ///     .profiling(true)
+    ///     .build();
+    /// finder.find_icon("firefox", 48, 1);
+    ///
+    /// println!("{}", finder.profile().unwrap());
+    /// ```
+    pub fn profile(&self) -> Option<Profile> {
+        self.profiler.profile()
+    }
+
+    /// Forgets the timings recorded so far.
+    pub fn reset_profile(&self) {
+        self.profiler.reset();
+    }
+
+    /// The maximum number of lookup results that are remembered, see
+    /// [`IconFinderBuilder::cache_capacity`].
+    pub fn cache_capacity(&self) -> usize {
+        self.cache().capacity()
+    }
+
+    /// Changes the number of lookup results that are remembered, evicting the
+    /// least recently used results if needed. A capacity of 0 disables the
+    /// cache.
+    pub fn set_cache_capacity(&self, capacity: usize) {
+        self.cache().set_capacity(capacity);
+    }
+
+    /// How long cached results are trusted before the theme directories are
+    /// looked at again, see [`IconFinderBuilder::revalidation_interval`].
+    pub fn revalidation_interval(&self) -> Duration {
+        self.cache().revalidation_interval()
+    }
+
+    /// Changes how long cached results are trusted before the theme
+    /// directories are looked at again.
+    pub fn set_revalidation_interval(&self, interval: Duration) {
+        self.cache().set_revalidation_interval(interval);
+    }
+
+    /// Forgets all remembered lookup results, e.g. after icons were installed
+    /// or removed.
+    pub fn clear_cache(&self) {
+        self.cache().clear();
+    }
+
+    /// Drops everything the finder remembers about its themes: the cached
+    /// lookup results and the listings of the theme directories. Icons are
+    /// looked up from scratch afterwards, even if no directory changed.
+    pub fn invalidate(&self) -> Reloaded {
+        let options = self.options(&LookupOptions::new(0));
+        let directories =
+            listing::forget_listings(options.shared_file_system(), self.lists_directory());
+
+        Reloaded {
+            results: self.cache().clear(),
+            directories,
+        }
+    }
+
+    /// Looks for changes in the themes right away, instead of once the
+    /// revalidation interval passed. The listings of the theme directories
+    /// that changed are read again, and cached lookup results found before
+    /// the change are dropped.
+    pub fn refresh(&self) -> Reloaded {
+        let options = self.options(&LookupOptions::new(0));
+        let directories =
+            listing::refresh_listings(options.shared_file_system(), self.lists_directory());
+        let stamp = Stamp::new(&self.cache_directories, options.file_system());
+
+        let mut cache = self.cache();
+        let results = cache.retain_current(&stamp);
+        cache.set_stamp(stamp);
+        Reloaded {
+            results,
+            directories,
+        }
+    }
+
+    /// The modification times of the directories cached results depend on,
+    /// looked at again once the revalidation interval passed.
+    fn stamp(&self, options: &LookupOptions) -> Stamp {
+        if let Some(stamp) = self.cache().recent_stamp() {
+            return stamp;
+        }
+
+        let stamp = Stamp::new(&self.cache_directories, options.file_system());
+        self.cache().set_stamp(stamp.clone());
+        stamp
+    }
+
+    /// Refreshes the finder every `interval` on a thread of its own, see
+    /// [`refresh`](IconFinder::refresh), so lookups never check the theme
+    /// directories for changes themselves. Lookups trust their cached results
+    /// in between, regardless of the revalidation interval, until the
+    /// refresher is stopped. Lookups missing the cache still search the theme
+    /// directories, through the listings of them the finder keeps. Intervals
+    /// shorter than 10 milliseconds are taken as 10 milliseconds.
+    ///
+    /// ```no_run
+    /// use std::sync::Arc;
+    /// use std::time::Duration;
+    /// use icon_finder::{IconFinder, Theme};
+    ///
+    /// let theme = Theme::load("Adwaita").unwrap();
+    /// let finder = Arc::new(IconFinder::builder(theme).cache_capacity(256).build());
+    /// let refresher = finder.spawn_refresher(Duration::from_secs(5));
+    /// // Look up icons from any thread, then shut down.
+    /// refresher.stop();
+    /// ```
+    #[cfg(not(target_family = "wasm"))]
+    pub fn spawn_refresher(self: &Arc<IconFinder>, interval: Duration) -> Refresher {
+        Refresher::spawn(self, interval)
+    }
+
+    /// Whether lookups of the finder list the directory, a directory of one
+    /// of its themes or a directory of unthemed icons.
+    fn lists_directory(&self) -> impl Fn(&Path) -> bool {
+        let themes = self.watched_directories();
+        let unthemed: Vec<PathBuf> = self
+            .options(&LookupOptions::new(0))
+            .unthemed_directories()
+            .map(PathBuf::from)
+            .collect();
+
+        move |directory| {
+            themes.iter().any(|theme| directory.starts_with(theme))
+                || unthemed.iter().any(|unthemed| directory == unthemed)
+        }
+    }
+
+    /// The options of a lookup, completed with the configuration of the
+    /// finder.
+    fn options(&self, options: &LookupOptions) -> LookupOptions {
+        let listing_interval = self.cache().listing_interval();
+
+        options
+            .with_default_formats(&self.formats)
+            .with_search_paths(&self.prepended_paths, &self.appended_paths)
+            .with_file_system(self.file_system.as_ref())
+            .with_profiler(&self.profiler)
+            .with_default_lookup_profile(self.lookup_profile)
+            .with_default_aliases(self.aliases.as_ref())
+            .with_revalidation_interval(listing_interval)
+    }
+
+    pub(crate) fn cache(&self) -> MutexGuard<'_, LookupCache> {
+        // The cache is never left in an inconsistent state, so a panic in
+        // another thread does not make it unusable.
+        self.cache.lock().unwrap_or_else(|error| error.into_inner())
+    }
+
+    #[cfg(feature = "render")]
+    fn render_cache(&self) -> MutexGuard<'_, RenderCache> {
+        self.render_cache
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+    }
+
+    /// Looks up an icon by name, with the lookup algorithm modified by the
+    /// given flags, see [`find_icon_with_flags`](crate::find_icon_with_flags).
+    pub fn find_icon_with_flags(
+        &self,
+        icon: &str,
+        size: i16,
+        scale: i16,
+        flags: LookupFlags,
+    ) -> Option<PathBuf> {
+        self.find_icon_with(icon, &LookupOptions::new(size).scale(scale).flags(flags))
+    }
+
+    /// Looks up an icon by name for display at a fractional scale, see
+    /// [`find_icon_fractional`](crate::find_icon_fractional).
+    pub fn find_icon_fractional(&self, icon: &str, size: i16, scale: f32) -> Option<PathBuf> {
+        self.find_icon_with(icon, &LookupOptions::new(size).fractional_scale(scale))
+    }
+
+    /// Looks up an icon by name in theme directories of the given context,
+    /// see [`find_icon_in_context`](crate::find_icon_in_context).
+    pub fn find_icon_in_context(
+        &self,
+        icon: &str,
+        size: i16,
+        scale: i16,
+        context: Context,
+    ) -> Option<PathBuf> {
+        self.find_icon_with(
+            icon,
+            &LookupOptions::new(size).scale(scale).context(context),
+        )
+    }
+
+    /// Looks up the first available icon of a list of names, see
+    /// [`find_best_icon`](crate::find_best_icon).
+    pub fn find_best_icon(&self, icon_list: &[&str], size: i16, scale: i16) -> Option<PathBuf> {
+        find_best_icon_in(
+            icon_list,
+            &self.options(&LookupOptions::new(size).scale(scale)),
+            &self.theme,
+            &self.fallback_themes,
+        )
+        .map(|result| result.path)
+    }
+
+    /// Finds the theme and directory an icon file belongs to, searching the
+    /// search paths of the finder as well, see
+    /// [`identify_icon`](crate::identify_icon).
+    pub fn identify_icon(&self, path: &Path) -> Option<IconLocation> {
+        identify::identify_icon_with(path, &self.options(&LookupOptions::new(0)))
+    }
+
+    /// Resolves an icon once for looking it up at many sizes, see
+    /// [`resolve_icon`](crate::resolve_icon).
+    pub fn resolve_icon(&self, icon: &str) -> IconHandle {
+        self.resolve_icon_with(icon, &LookupOptions::new(0))
+    }
+
+    /// Resolves an icon like [`resolve_icon`](IconFinder::resolve_icon),
+    /// configured by the given options. Their size and scale are replaced by
+    /// the ones the handle is asked for.
+    pub fn resolve_icon_with(&self, icon: &str, options: &LookupOptions) -> IconHandle {
+        IconHandle::resolve(
+            icon,
+            &self.options(options),
+            &self.theme,
+            &self.fallback_themes,
+        )
+    }
+
+    /// The theme, the themes it inherits from and the fallback themes, in
+    /// the order icons are looked up in them, see
+    /// [`Theme::ancestry`](crate::Theme::ancestry).
+    pub fn ancestry(&self) -> impl Iterator<Item = &Theme> {
+        self.theme.ancestry_with(&self.fallback_themes).into_iter()
+    }
+
+    /// Searches the names of the icons provided by the theme, its parents
+    /// and the fallback themes, best match first, see
+    /// [`search_icons`](crate::search_icons).
+    pub fn search_icons(&self, query: &str) -> Vec<String> {
+        search::search_icons_in(
+            query,
+            &self.options(&LookupOptions::new(0)),
+            &self.theme,
+            &self.fallback_themes,
+        )
+    }
+
+    /// The names of the icons starting with the prefix, provided by the
+    /// theme, its parents and the fallback themes, see
+    /// [`complete_icon_names`](crate::complete_icon_names).
+    pub fn complete_icon_names(&self, prefix: &str) -> impl Iterator<Item = String> {
+        search::complete_icon_names_in(
+            prefix,
+            &self.options(&LookupOptions::new(0)),
+            &self.theme,
+            &self.fallback_themes,
+        )
+    }
+
+    /// Looks up the icon for a MIME type, falling back to its generic icon,
+    /// see [`mime_type_icon_names`](crate::mime_type_icon_names).
+    pub fn find_mime_type_icon(&self, mime_type: &str, size: i16, scale: i16) -> Option<PathBuf> {
+        let names = mime_type_icon_names(mime_type);
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        self.find_best_icon(&names, size, scale)
+    }
+
+    /// Looks up the icon for a file or directory, like a file browser shows
+    /// it, see [`path_icon_names`](crate::path_icon_names). Directories are
+    /// recognized on the file system of the finder.
+    pub fn find_icon_for_path(
+        &self,
+        path: impl AsRef<Path>,
+        size: i16,
+        scale: i16,
+    ) -> Option<PathBuf> {
+        let options = self.options(&LookupOptions::new(size));
+        let names = files::path_icon_names_in(path.as_ref(), options.file_system());
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        self.find_best_icon(&names, size, scale)
+    }
+
+    /// Looks up the icon of the application with the Wayland app-id or X11
+    /// WM_CLASS, see [`find_app_icon`](crate::find_app_icon).
+    pub fn find_app_icon(&self, app_id: &str, size: i16, scale: i16) -> Option<PathBuf> {
+        desktop::find_app_icon_with(app_id, |names| self.find_best_icon(names, size, scale))
+    }
+
+    /// Looks up an icon together with the emblems to draw onto it, see
+    /// [`find_emblemed_icon`](crate::find_emblemed_icon).
+    pub fn find_emblemed_icon(
+        &self,
+        icon: &str,
+        emblems: &[&str],
+        size: i16,
+        scale: i16,
+    ) -> Option<EmblemedIcon> {
+        emblem::find_emblemed_icon_with(
+            icon,
+            emblems,
+            &LookupOptions::new(size).scale(scale),
+            |name, options| self.find_icon_with(name, options),
+        )
+    }
+
+    /// Looks up an icon at several sizes at once, see
+    /// [`find_icon_sizes`](crate::find_icon_sizes).
+    pub fn find_icon_sizes(&self, icon: &str, sizes: &[i16], scale: i16) -> Vec<Option<PathBuf>> {
+        find_icon_sizes_in(
+            icon,
+            sizes,
+            &self.options(&LookupOptions::new(0).scale(scale)),
+            &self.theme,
+            &self.fallback_themes,
+        )
+        .into_iter()
+        .map(|result| result.map(|result| result.path))
+        .collect()
+    }
+
+    /// Lists every icon file matching the name, see
+    /// [`find_icon_candidates`](crate::find_icon_candidates).
+    pub fn find_icon_candidates(&self, icon: &str, size: i16, scale: i16) -> Candidates<'_> {
+        Candidates::new(
+            icon,
+            self.options(&LookupOptions::new(size).scale(scale)),
+            &self.theme,
+            &self.fallback_themes,
+        )
+    }
+
+    /// Lists the sizes an icon is available at, see
+    /// [`available_sizes`](crate::available_sizes).
+    pub fn available_sizes(&self, icon: &str) -> Vec<IconSize> {
+        available_sizes_in(
+            icon,
+            &self.options(&LookupOptions::new(0)),
+            &self.theme,
+            &self.fallback_themes,
+        )
+    }
+
+    /// The names of the icons provided by the theme, its parents and the
+    /// fallback themes, in alphabetical order, see [`Theme::icons`].
+    pub fn all_icons(&self) -> Vec<String> {
+        enumerate::theme_chain_icon_names(
+            &self.options(&LookupOptions::new(0)),
+            &self.theme,
+            &self.fallback_themes,
+        )
+        .into_iter()
+        .collect()
+    }
+
+    /// The names of the icons the theme, its parents and the fallback themes
+    /// provide in the given context, in alphabetical order, see
+    /// [`Theme::icons_in_context`].
+    pub fn icons_in_context(&self, context: Context) -> Vec<String> {
+        enumerate::theme_chain_icon_names(
+            &self.options(&LookupOptions::new(0).context(context)),
+            &self.theme,
+            &self.fallback_themes,
+        )
+        .into_iter()
+        .collect()
+    }
+
+    /// The icons provided by the theme, its parents and the fallback themes,
+    /// with the sizes each is available at, see [`Theme::icon_sizes`].
+    pub fn all_icon_sizes(&self) -> BTreeMap<String, Vec<IconSize>> {
+        enumerate::theme_chain_icon_sizes(
+            &self.options(&LookupOptions::new(0)),
+            &self.theme,
+            &self.fallback_themes,
+        )
+    }
+
+    /// Watches for changes that affect the icons returned by this finder.
+    /// Events are sent when the user selects a different icon theme in their
+    /// desktop settings, when one of the themes in the inheritance chain
+    /// changes on disk, or when a theme is installed or removed. Changes are
+    /// checked for every [`POLL_INTERVAL`](crate::POLL_INTERVAL), until the
+    /// subscription is dropped. All subscriptions share a single reading of
+    /// the desktop settings per interval.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn subscribe(&self) -> Subscription {
+        Subscription::spawn(self.watcher(), POLL_INTERVAL)
+    }
+
+    /// Remembers the current state of everything the finder depends on, for
+    /// detecting changes.
+    #[cfg(not(target_family = "wasm"))]
+    pub(crate) fn watcher(&self) -> Watcher {
+        let file_system = self
+            .file_system
+            .clone()
+            .unwrap_or_else(|| Arc::clone(filesystem::file_system()));
+
+        Watcher::new(
+            settings::selected_icon_theme,
+            self.watched_directories(),
+            self.base_directories(),
+            file_system,
+        )
+    }
+
+    /// The toplevel directories of every theme in the inheritance chain, in
+    /// every base directory. Directories that do not exist yet are included, so
+    /// newly installed themes are noticed as well.
+    fn watched_directories(&self) -> Vec<PathBuf> {
+        theme_directories(
+            &self.theme,
+            &self.fallback_themes,
+            &self.options(&LookupOptions::new(0)),
+        )
+    }
+
+    /// The directories the finder searches themes in, including its search
+    /// paths, in order of precedence.
+    #[cfg(not(target_family = "wasm"))]
+    fn base_directories(&self) -> Vec<PathBuf> {
+        self.options(&LookupOptions::new(0))
+            .base_directories()
+            .map(PathBuf::from)
+            .collect()
+    }
+}
+
+/// The toplevel directories of every theme in the inheritance chain and the
+/// fallback themes, in every base directory.
+fn theme_directories(
+    theme: &Theme,
+    fallback_themes: &[Arc<Theme>],
+    options: &LookupOptions,
+) -> Vec<PathBuf> {
+    let chain = theme.ancestry_with(fallback_themes);
+
+    options
+        .base_directories()
+        .flat_map(|directory| chain.iter().map(move |theme| directory.join(&theme.name)))
+        .collect()
+}
+
+/// The names of the variants of a theme for the color scheme, in order of
+/// preference. Dark variants are conventionally named like Papirus-Dark or
+/// breeze-dark, light variants like Papirus-Light or just after the theme.
+fn color_scheme_variant_names(name: &str, color_scheme: ColorScheme) -> Vec<String> {
+    let lowercase = name.to_ascii_lowercase();
+    let base = ["-dark", "-light"]
+        .iter()
+        .find_map(|suffix| lowercase.strip_suffix(suffix))
+        .map_or(name, |base| &name[..base.len()]);
+
+    match color_scheme {
+        ColorScheme::Default => Vec::new(),
+        ColorScheme::PreferDark => vec![format!("{}-Dark", base), format!("{}-dark", base)],
+        ColorScheme::PreferLight => vec![
+            format!("{}-Light", base),
+            format!("{}-light", base),
+            base.to_owned(),
+        ],
+    }
+}
+
+/// The installed variant of the theme for the color scheme, `None` if the
+/// theme already is the variant or it has none.
+fn color_scheme_variant(
+    theme: &Theme,
+    color_scheme: ColorScheme,
+    file_system: &dyn FileSystem,
+) -> Option<Theme> {
+    for name in color_scheme_variant_names(&theme.name, color_scheme) {
+        if name == theme.name {
+            return None;
+        }
+        if let Ok(variant) = Theme::load_from(&name, file_system) {
+            return Some(variant);
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profile::Phase;
+    use crate::{MemoryFileSystem, Translations};
+
+    #[test]
+    fn test_finder_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+
+        assert_send_sync::<IconFinder>();
+    }
+
+    #[test]
+    fn test_watched_directories() {
+        let finder = IconFinder::new(Theme {
+            name: "Papirus".to_owned(),
+            comment: "Papirus icon theme".to_owned(),
+            inherits: vec![Arc::new(Theme {
+                name: "breeze".to_owned(),
+                comment: "Breeze".to_owned(),
+                inherits: Vec::new(),
+                directories: Vec::new(),
+                hidden: false,
+                translations: Translations::default(),
+                example: None,
+            })],
+            directories: Vec::new(),
+            hidden: false,
+            translations: Translations::default(),
+            example: None,
+        });
+
+        let directories = finder.watched_directories();
+
+        assert_eq!(
+            directories.len(),
+            3 * crate::paths::base_directories().len()
+        );
+        assert!(directories.contains(&PathBuf::from("/usr/share/icons/Papirus")));
+        assert!(directories.contains(&PathBuf::from("/usr/share/icons/breeze")));
+        assert!(directories.contains(&PathBuf::from("/usr/share/icons/hicolor")));
+    }
+
+    #[test]
+    fn test_color_scheme_variant() {
+        assert_eq!(
+            color_scheme_variant_names("breeze-dark", ColorScheme::PreferLight),
+            vec!["breeze-Light", "breeze-light", "breeze"]
+        );
+        assert!(color_scheme_variant_names("Papirus", ColorScheme::Default).is_empty());
+
+        let fs = MemoryFileSystem::new();
+        for name in ["Papirus", "Papirus-Dark"] {
+            fs.add_file(
+                format!("/usr/share/icons/{}/index.theme", name),
+                format!("[Icon Theme]\nName={}\nDirectories=\n", name),
+            );
+        }
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+        let theme = Theme::load_from("Papirus", &*fs).unwrap();
+        let finder = |color_scheme| {
+            IconFinder::builder(theme.clone())
+                .file_system(Arc::clone(&fs))
+                .color_scheme(color_scheme)
+                .build()
+        };
+
+        assert_eq!(finder(ColorScheme::PreferDark).theme().name, "Papirus-Dark");
+        // Papirus-Light is not installed, Papirus is the light variant.
+        assert_eq!(finder(ColorScheme::PreferLight).theme().name, "Papirus");
+    }
+
+    #[test]
+    #[cfg(feature = "svg")]
+    fn test_load_icon() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file(
+            "/usr/share/icons/icon-finder-load/index.theme",
+            "[Icon Theme]\nName=Load\nComment=Load\nDirectories=48x48/apps\n\n\
+             [48x48/apps]\nSize=48\nType=Fixed\n",
+        );
+        let directory = "/usr/share/icons/icon-finder-load/48x48/apps";
+        fs.add_file(format!("{}/editor.svg", directory), "<svg/>");
+        // Named like a PNG image, but an SVG image.
+        fs.add_file(format!("{}/browser.png", directory), "<?xml?><svg/>");
+        fs.add_file(format!("{}/terminal.png", directory), "not an image");
+        fs.add_file(format!("{}/clock.png", directory), b"\x89PNG\r\n\x1a\n");
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+        let theme = Theme::load_from("icon-finder-load", &*fs).unwrap();
+        let finder = IconFinder::builder(theme).file_system(fs).build();
+
+        let editor = finder.load_icon("editor", 48, 1).unwrap().unwrap();
+        assert_eq!(editor.data, b"<svg/>");
+        assert_eq!(editor.path, Path::new(directory).join("editor.svg"));
+
+        let format = |icon| finder.load_icon(icon, 48, 1).unwrap().unwrap().format;
+        assert_eq!(format("editor"), IconFormat::Svg);
+        assert_eq!(format("browser"), IconFormat::Svg);
+        assert_eq!(format("terminal"), IconFormat::Png);
+        let clock = finder.load_icon("clock", 48, 1).unwrap().unwrap();
+        assert_eq!(clock.data, b"\x89PNG\r\n\x1a\n");
+        assert_eq!(finder.load_icon("calculator", 48, 1).unwrap(), None);
+    }
+
+    #[cfg(all(feature = "image", feature = "xpm"))]
+    #[test]
+    fn test_build_atlas() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file(
+            "/usr/share/icons/icon-finder-atlas/index.theme",
+            "[Icon Theme]\nName=Atlas\nComment=Atlas\nDirectories=2x2/apps\n\n\
+             [2x2/apps]\nSize=2\nType=Fixed\n",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-atlas/2x2/apps/editor.xpm",
+            "/* XPM */\nstatic char *editor[] = {\"2 2 1 1\", \". c #ff0000\", \"..\", \"..\"};",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-atlas/2x2/apps/browser.xpm",
+            "/* XPM */\nstatic char *browser[] = {\"2 2 1 1\", \". c #0000ff\", \"..\", \"..\"};",
+        );
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+        let theme = Theme::load_from("icon-finder-atlas", &*fs).unwrap();
+        let finder = IconFinder::builder(theme).file_system(fs).build();
+
+        let atlas = finder
+            .build_atlas(&["editor", "calculator", "browser"], 2, 2)
+            .unwrap();
+        assert_eq!((atlas.pixels.width, atlas.pixels.height), (8, 4));
+        assert_eq!(atlas.missing, ["calculator"]);
+        assert_eq!(atlas.icons["browser"].x0, 4);
+        assert_eq!(atlas.pixels.data[..4], [255, 0, 0, 255]);
+        assert_eq!(atlas.pixels.data[7 * 4..][..4], [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_ancestry() {
+        let fs = MemoryFileSystem::new();
+        for (name, inherits) in [
+            ("icon-finder-child", "icon-finder-parent"),
+            ("icon-finder-parent", "hicolor"),
+            ("icon-finder-fallback", ""),
+            ("hicolor", ""),
+        ] {
+            fs.add_file(
+                format!("/usr/share/icons/{}/index.theme", name),
+                format!("[Icon Theme]\nName={}\nInherits={}\n", name, inherits),
+            );
+        }
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+        let theme = Theme::load_from("icon-finder-child", &*fs).unwrap();
+        let finder = IconFinder::builder(theme)
+            .file_system(fs)
+            .fallback_themes(&["icon-finder-fallback", "icon-finder-parent"])
+            .build();
+
+        let names: Vec<&str> = finder.ancestry().map(|theme| theme.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "icon-finder-child",
+                "icon-finder-parent",
+                "hicolor",
+                "icon-finder-fallback"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_profile_generic_fallback() {
+        let fs = MemoryFileSystem::new();
+        for (name, inherits) in [
+            ("icon-finder-child", "icon-finder-parent"),
+            ("icon-finder-parent", ""),
+        ] {
+            fs.add_file(
+                format!("/usr/share/icons/{}/index.theme", name),
+                format!(
+                    "[Icon Theme]\nName={}\nInherits={}\nDirectories=48x48/mimetypes\n\n\
+                     [48x48/mimetypes]\nSize=48\nType=Fixed\n",
+                    name, inherits
+                ),
+            );
+        }
+        fs.add_file(
+            "/usr/share/icons/icon-finder-parent/48x48/mimetypes/text-x.png",
+            "",
+        );
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+        let theme = Theme::load_from("icon-finder-child", &*fs).unwrap();
+        let finder = IconFinder::builder(theme)
+            .file_system(fs)
+            .profiling(true)
+            .build();
+        let options = LookupOptions::new(48).flags(LookupFlags::GENERIC_FALLBACK);
+
+        assert!(finder.find_icon_with("text-x-python", &options).is_some());
+        let profile = finder.profile().unwrap();
+        assert_eq!(profile.lookups().count, 1);
+        assert_eq!(profile.phase(Phase::Parents).count, 1);
+        assert_eq!(profile.phase(Phase::Unthemed).count, 0);
+
+        assert!(finder.find_icon_with("audio-x-flac", &options).is_none());
+        let profile = finder.profile().unwrap();
+        assert_eq!(profile.phase(Phase::Parents).count, 2);
+        assert_eq!(profile.phase(Phase::Unthemed).count, 1);
+    }
+
+    #[test]
+    fn test_refresh_and_invalidate() {
+        let memory = Arc::new(MemoryFileSystem::new());
+        memory.add_file(
+            "/usr/share/icons/icon-finder-refresh/index.theme",
+            "[Icon Theme]\nName=Refresh\nDirectories=48x48/apps\n\n\
+             [48x48/apps]\nSize=48\nType=Fixed\n",
+        );
+        let directory = "/usr/share/icons/icon-finder-refresh";
+        memory.add_file(format!("{}/48x48/apps/editor.png", directory), "");
+        let fs: Arc<dyn FileSystem> = memory.clone();
+        let theme = Theme::load_from("icon-finder-refresh", &*fs).unwrap();
+        let finder = IconFinder::builder(theme)
+            .file_system(fs)
+            .cache_capacity(16)
+            .build();
+
+        assert!(finder.find_icon("editor", 48, 1).is_some());
+        assert!(finder.find_icon("browser", 48, 1).is_none());
+        assert_eq!(finder.refresh(), Reloaded::default());
+
+        // Installing icons updates the cache file of the theme.
+        let apps = PathBuf::from(format!("{}/48x48/apps", directory));
+        memory.add_file(apps.join("browser.png"), "");
+        memory.add_file(format!("{}/icon-theme.cache", directory), "");
+        let reloaded = finder.refresh();
+        assert_eq!(
+            (reloaded.results, reloaded.directories),
+            (2, vec![apps.clone()])
+        );
+        assert!(finder.find_icon("browser", 48, 1).is_some());
+
+        let invalidated = finder.invalidate();
+        assert_eq!(invalidated.results, 1);
+        assert!(invalidated.directories.contains(&apps));
+        assert_eq!(finder.invalidate(), Reloaded::default());
+    }
+
+    #[test]
+    fn test_revalidation_interval() {
+        let memory = Arc::new(MemoryFileSystem::new());
+        memory.add_file(
+            "/usr/share/icons/icon-finder-revalidate/index.theme",
+            "[Icon Theme]\nName=Revalidate\nDirectories=48x48/apps\n\n\
+             [48x48/apps]\nSize=48\nType=Fixed\n",
+        );
+        let directory = "/usr/share/icons/icon-finder-revalidate";
+        let fs: Arc<dyn FileSystem> = memory.clone();
+        let theme = Theme::load_from("icon-finder-revalidate", &*fs).unwrap();
+        let finder = IconFinder::builder(theme)
+            .file_system(fs)
+            .cache_capacity(16)
+            .revalidation_interval(Duration::MAX)
+            .build();
+        assert_eq!(finder.revalidation_interval(), Duration::MAX);
+
+        assert!(finder.find_icon("browser", 48, 1).is_none());
+        memory.add_file(format!("{}/48x48/apps/browser.png", directory), "");
+        memory.add_file(format!("{}/icon-theme.cache", directory), "");
+        // The theme is never looked at again.
+        assert!(finder.find_icon("browser", 48, 1).is_none());
+
+        finder.set_revalidation_interval(Duration::ZERO);
+        assert!(finder.find_icon("browser", 48, 1).is_some());
+        assert_eq!(finder.stats().revalidations, 1);
+    }
+
+    #[test]
+    fn test_export_icons() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file(
+            "/usr/share/icons/icon-finder-export/index.theme",
+            "[Icon Theme]\nName=Export\nComment=Export\nDirectories=16x16/apps,48x48/apps\n\n\
+             [16x16/apps]\nSize=16\nContext=Applications\nType=Fixed\n\n\
+             [48x48/apps]\nSize=48\nContext=Applications\nType=Threshold\n",
+        );
+        let directory = "/usr/share/icons/icon-finder-export";
+        fs.add_file(format!("{}/16x16/apps/editor.png", directory), "16");
+        fs.add_file(format!("{}/48x48/apps/editor.png", directory), "48");
+        fs.add_file(format!("{}/48x48/apps/browser.png", directory), "browser");
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+        let theme = Theme::load_from("icon-finder-export", &*fs).unwrap();
+        let finder = IconFinder::builder(theme).file_system(fs).build();
+
+        let destination =
+            std::env::temp_dir().join(format!("icon-finder-export-{}", std::process::id()));
+        let report = finder
+            .export_icons(
+                &["editor", "browser", "calculator"],
+                &[16, 48],
+                &destination,
+                ExportMethod::HardLink,
+            )
+            .unwrap();
+
+        // The browser icon is found in the same file at both sizes.
+        assert_eq!(
+            report.files,
+            [
+                Path::new("16x16/apps/editor.png"),
+                Path::new("48x48/apps/editor.png"),
+                Path::new("48x48/apps/browser.png"),
+            ]
+        );
+        assert_eq!(
+            report.missing,
+            [("calculator".to_owned(), 16), ("calculator".to_owned(), 48)]
+        );
+        let read = |file| std::fs::read_to_string(destination.join(file)).unwrap();
+        assert_eq!(read("48x48/apps/browser.png"), "browser");
+
+        let index = crate::IndexTheme::parse(&read("index.theme")).unwrap();
+        assert_eq!(index.directories, finder.theme().directories);
+
+        std::fs::remove_dir_all(destination).unwrap();
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn test_render_cache() {
+        let svg = |color| {
+            format!(
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"16\" height=\"16\">\
+                 <rect width=\"16\" height=\"16\" fill=\"{}\"/></svg>",
+                color
+            )
+        };
+        let fs = MemoryFileSystem::new();
+        fs.add_file(
+            "/usr/share/icons/icon-finder-render/index.theme",
+            "[Icon Theme]\nName=Render\nComment=Render\nDirectories=scalable/apps\n\n\
+             [scalable/apps]\nSize=16\nType=Scalable\nMinSize=8\nMaxSize=512\n",
+        );
+        let icon = "/usr/share/icons/icon-finder-render/scalable/apps/editor.svg";
+        fs.add_file(icon, svg("#ff0000"));
+        let fs = Arc::new(fs);
+        let theme = Theme::load_from("icon-finder-render", &*fs).unwrap();
+        let finder = IconFinder::builder(theme)
+            .file_system(fs.clone())
+            .render_cache_capacity(4)
+            .build();
+
+        let color = || finder.render_icon("editor", 16, 1).unwrap().unwrap().data[..4].to_vec();
+        assert_eq!(color(), [255, 0, 0, 255]);
+        assert_eq!(color(), [255, 0, 0, 255]);
+
+        // Changing the image changes its modification time.
+        fs.add_file(icon, svg("#0000ff"));
+        assert_eq!(color(), [0, 0, 255, 255]);
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn test_render_symbolic_icon() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file(
+            "/usr/share/icons/icon-finder-symbolic/index.theme",
+            "[Icon Theme]\nName=Symbolic\nComment=Symbolic\nDirectories=scalable/actions\n\n\
+             [scalable/actions]\nSize=16\nType=Scalable\nMinSize=8\nMaxSize=512\n",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-symbolic/scalable/actions/dialog-symbolic.svg",
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"16\" height=\"16\">\
+             <rect width=\"8\" height=\"16\" fill=\"#bebebe\"/>\
+             <rect class=\"error\" x=\"8\" width=\"8\" height=\"16\" fill=\"#bebebe\"/></svg>",
+        );
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+        let theme = Theme::load_from("icon-finder-symbolic", &*fs).unwrap();
+        let finder = IconFinder::builder(theme)
+            .file_system(fs)
+            .render_cache_capacity(4)
+            .build();
+
+        let palette = SymbolicPalette {
+            error: [0, 0, 255, 255],
+            ..SymbolicPalette::new([0, 255, 0, 255])
+        };
+        let pixels = finder
+            .render_symbolic_icon("dialog-symbolic", 16, 1, &palette)
+            .unwrap()
+            .unwrap();
+        assert_eq!(pixels.data[..4], [0, 255, 0, 255]);
+        assert_eq!(pixels.data[15 * 4..][..4], [0, 0, 255, 255]);
+
+        // Rendered without the palette, the icon keeps its own colors.
+        let pixels = finder
+            .render_icon("dialog-symbolic", 16, 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(pixels.data[..4], [190, 190, 190, 255]);
+    }
+}
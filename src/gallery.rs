@@ -0,0 +1,250 @@
+//! # Theme galleries
+//! Theme authors publish previews of their themes, and users choosing a theme
+//! want to see its icons rather than its name. A gallery is a single HTML
+//! page showing every icon of a theme, grouped by context, with the name of
+//! every icon and the sizes it is provided at. The images are linked from
+//! where they are installed, the largest one of every icon is shown. Like
+//! [`compare_themes`](crate::compare_themes), only the directories of the
+//! theme itself are shown, not those of the themes it inherits from.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use crate::enumerate::visit_directories;
+use crate::listing;
+use crate::{IconSize, LookupOptions, Theme, ThemeDirectoryType, DEFAULT_SCALE};
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}\
+    nav a{margin-right:1em}\
+    .icons{display:flex;flex-wrap:wrap;gap:1em}\
+    figure{width:8em;margin:0;text-align:center}\
+    img{width:48px;height:48px;object-fit:contain}\
+    figcaption{overflow-wrap:anywhere}\
+    small{display:block;color:gray}";
+
+struct GalleryIcon {
+    sizes: BTreeSet<IconSize>,
+    /// The largest image of the icon, scalable ones first.
+    preview: Option<(bool, i32, PathBuf)>,
+}
+
+/// Escapes text for use in HTML, in elements as well as attribute values.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The file URL of an absolute path, percent-encoding every byte that is not
+/// unreserved, except for the slashes and the @ of scaled directories.
+fn file_url(path: &Path) -> String {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str()).to_vec();
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().replace('\\', "/").into_bytes();
+
+    let mut url = String::from("file://");
+    for byte in bytes {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b'@' => {
+                url.push(byte as char)
+            }
+            byte => write!(url, "%{:02X}", byte).unwrap(),
+        }
+    }
+    url
+}
+
+fn size_label(size: &IconSize) -> String {
+    match (size.r#type, size.scale) {
+        (ThemeDirectoryType::Scalable, _) => "scalable".to_owned(),
+        (_, DEFAULT_SCALE) => size.size.to_string(),
+        (_, scale) => format!("{}@{}", size.size, scale),
+    }
+}
+
+pub(crate) fn theme_gallery_with(theme: &Theme, options: &LookupOptions) -> String {
+    let mut contexts: BTreeMap<String, BTreeMap<String, GalleryIcon>> = BTreeMap::new();
+
+    visit_directories(theme, options, |subdir, directory, names| {
+        let size = IconSize {
+            scale: subdir.scale.unwrap_or(DEFAULT_SCALE),
+            size: subdir.size,
+            r#type: subdir.r#type,
+        };
+        let context = subdir
+            .context
+            .as_ref()
+            .map_or("Other", |context| context.as_str());
+
+        for name in names {
+            let icon = contexts
+                .entry(context.to_owned())
+                .or_default()
+                .entry(name.clone())
+                .or_insert_with(|| GalleryIcon {
+                    sizes: BTreeSet::new(),
+                    preview: None,
+                });
+            icon.sizes.insert(size);
+
+            let rank = (
+                size.r#type == ThemeDirectoryType::Scalable,
+                i32::from(size.size) * i32::from(size.scale),
+            );
+            if icon
+                .preview
+                .as_ref()
+                .is_some_and(|(scalable, pixels, _)| (*scalable, *pixels) >= rank)
+            {
+                continue;
+            }
+            if let Some((path, _)) = listing::icon_files(directory, &name, options)
+                .into_iter()
+                .next()
+            {
+                icon.preview = Some((rank.0, rank.1, path));
+            }
+        }
+    });
+
+    let title = escape(theme.display_name(None));
+    let mut html = String::new();
+    // Writing to a string cannot fail.
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>{} icons</title>\n<style>{}</style>\n</head>\n<body>\n\
+         <h1>{}</h1>\n<p>{}</p>\n<nav>",
+        title,
+        STYLE,
+        title,
+        escape(theme.comment(None))
+    );
+    for (context, icons) in &contexts {
+        let _ = write!(
+            html,
+            "<a href=\"#{}\">{} ({})</a>",
+            escape(context),
+            escape(context),
+            icons.len()
+        );
+    }
+    html.push_str("</nav>\n");
+
+    for (context, icons) in &contexts {
+        let _ = write!(
+            html,
+            "<section id=\"{}\">\n<h2>{}</h2>\n<div class=\"icons\">\n",
+            escape(context),
+            escape(context)
+        );
+        for (name, icon) in icons {
+            let image = icon
+                .preview
+                .as_ref()
+                .map_or_else(String::new, |(_, _, path)| {
+                    format!(
+                        "<img src=\"{}\" alt=\"\" loading=\"lazy\">",
+                        escape(&file_url(path))
+                    )
+                });
+            let mut sizes: Vec<&IconSize> = icon.sizes.iter().collect();
+            sizes.sort_by_key(|size| {
+                (
+                    size.r#type == ThemeDirectoryType::Scalable,
+                    size.size,
+                    size.scale,
+                )
+            });
+            let mut sizes: Vec<String> = sizes.into_iter().map(size_label).collect();
+            sizes.dedup();
+            let _ = writeln!(
+                html,
+                "<figure>{}<figcaption>{}<small>{}</small></figcaption></figure>",
+                image,
+                escape(name),
+                escape(&sizes.join(", "))
+            );
+        }
+        html.push_str("</div>\n</section>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Renders an HTML page showing every icon of the theme, grouped by context,
+/// with their names and the sizes they are provided at. Icons in directories
+/// without a context are shown under Other. The images are linked by file
+/// URL, so the page only shows them on the machine the theme is installed on.
+pub fn theme_gallery(theme: &Theme) -> String {
+    theme_gallery_with(theme, &LookupOptions::new(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+    use crate::Context;
+
+    #[test]
+    fn test_file_url() {
+        assert_eq!(
+            file_url(Path::new("/usr/share/icons/a b/c#d.png")),
+            "file:///usr/share/icons/a%20b/c%23d.png"
+        );
+    }
+
+    #[test]
+    fn test_theme_gallery() {
+        let fixture = Fixture::in_memory(&[ThemeFixture::new("icon-finder-gallery")
+            .display_name("Gallery & Co")
+            .comment("A <small> theme")
+            .fixed_directory("16x16/apps", 16)
+            .context(Context::Applications)
+            .fixed_directory("32x32@2/apps", 32)
+            .scale(2)
+            .context(Context::Applications)
+            .scalable_directory("scalable/apps", 16, 48)
+            .context(Context::Applications)
+            .fixed_directory("misc", 16)
+            .icon("16x16/apps/editor.png")
+            .icon("32x32@2/apps/editor.png")
+            .icon("16x16/apps/browser.png")
+            .icon("32x32@2/apps/browser.png")
+            .icon("scalable/apps/browser.png")
+            .icon("misc/unknown.png")]);
+        let theme = fixture.load_theme("icon-finder-gallery").unwrap();
+        let directory = fixture.base_directory().join("icon-finder-gallery");
+
+        let html = theme_gallery_with(&theme, &fixture.lookup_options());
+        assert!(html.contains("<title>Gallery &amp; Co icons</title>"));
+        assert!(html.contains("<p>A &lt;small&gt; theme</p>"));
+        assert!(html.contains("<a href=\"#Applications\">Applications (2)</a>"));
+        assert!(html.contains("<a href=\"#Other\">Other (1)</a>"));
+        assert!(html.contains(&format!(
+            "<img src=\"file://{}/scalable/apps/browser.png\" alt=\"\" loading=\"lazy\">\
+             <figcaption>browser<small>16, 32@2, scalable</small>",
+            directory.display()
+        )));
+        assert!(html.contains(&format!(
+            "<img src=\"file://{}/32x32@2/apps/editor.png\" alt=\"\" loading=\"lazy\">\
+             <figcaption>editor<small>16, 32@2</small>",
+            directory.display()
+        )));
+        // Contexts and icons are in alphabetical order.
+        assert!(html.find("id=\"Applications\"") < html.find("id=\"Other\""));
+        assert!(html.find(">browser<") < html.find(">editor<"));
+    }
+}
@@ -0,0 +1,162 @@
+//! # Size gaps
+//! An icon drawn at 16 and 32 pixels but not at 48 is scaled up wherever
+//! applications ask for 48, which looks blurry. Themes rarely intend these
+//! gaps, they happen when icons are added at some sizes only. Checking a
+//! theme lists its icons missing at any of the common sizes, so theme
+//! authors and CI scripts can find them. Like
+//! [`find_duplicate_icons`](crate::find_duplicate_icons), only the
+//! directories of the theme itself are checked.
+
+use std::collections::BTreeMap;
+
+use crate::walk::walk_theme_with;
+use crate::{
+    directory_matches_size, Context, LookupOptions, Theme, ThemeDirectoryType, DEFAULT_SCALE,
+};
+
+/// An icon a theme does not provide at every checked size, see
+/// [`check_size_gaps`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeGap {
+    pub name: String,
+    /// The context of the first directory the icon is in.
+    pub context: Option<Context>,
+    /// The checked sizes the icon is available at.
+    pub available: Vec<i16>,
+    /// The checked sizes the icon is missing at.
+    pub missing: Vec<i16>,
+    /// The largest size in pixels the icon is drawn at, `None` if it is
+    /// scalable and never has to be scaled up.
+    pub largest: Option<i16>,
+}
+
+impl SizeGap {
+    /// The missing sizes the icon is scaled up to, which look blurry. At the
+    /// other missing sizes a larger icon is scaled down.
+    pub fn upscaled(&self) -> Vec<i16> {
+        match self.largest {
+            Some(largest) => self
+                .missing
+                .iter()
+                .copied()
+                .filter(|&size| size > largest)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+pub(crate) fn check_size_gaps_with(
+    theme: &Theme,
+    sizes: &[i16],
+    options: &LookupOptions,
+) -> Vec<SizeGap> {
+    let mut icons: BTreeMap<String, SizeGap> = BTreeMap::new();
+
+    walk_theme_with(theme, options, |subdir, file| {
+        let icon = icons.entry(file.name.clone()).or_insert_with(|| SizeGap {
+            name: file.name.clone(),
+            context: subdir.context.clone(),
+            available: Vec::new(),
+            missing: Vec::new(),
+            largest: Some(0),
+        });
+
+        for &size in sizes {
+            if directory_matches_size(subdir, size, DEFAULT_SCALE)
+                && !icon.available.contains(&size)
+            {
+                icon.available.push(size);
+            }
+        }
+
+        let scale = subdir.scale.unwrap_or(DEFAULT_SCALE);
+        icon.largest = match subdir.r#type {
+            ThemeDirectoryType::Scalable => None,
+            _ => icon
+                .largest
+                .map(|largest| largest.max(subdir.size.saturating_mul(scale))),
+        };
+    });
+
+    icons
+        .into_values()
+        .map(|mut icon| {
+            icon.available.sort_unstable();
+            icon.missing = sizes
+                .iter()
+                .copied()
+                .filter(|size| !icon.available.contains(size))
+                .collect();
+            icon
+        })
+        .filter(|icon| !icon.missing.is_empty())
+        .collect()
+}
+
+/// Lists the icons of the theme that are missing at some of the sizes, at
+/// scale 1, in alphabetical order. An icon counts as available at a size if
+/// a lookup at that size finds it in a directory matching the size, icons in
+/// scalable directories are available at every size the directory covers.
+pub fn check_size_gaps(theme: &Theme, sizes: &[i16]) -> Vec<SizeGap> {
+    check_size_gaps_with(theme, sizes, &LookupOptions::new(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+
+    #[test]
+    fn test_check_size_gaps() {
+        let mut theme = ThemeFixture::new("icon-finder-gaps")
+            .fixed_directory("16x16/apps", 16)
+            .context(Context::Applications)
+            .fixed_directory("32x32/apps", 32)
+            .context(Context::Applications)
+            .fixed_directory("48x48/apps", 48)
+            .context(Context::Applications)
+            .fixed_directory("24x24@2/apps", 24)
+            .scale(2)
+            .context(Context::Applications)
+            .scalable_directory("scalable/apps", 8, 512);
+        for icon in [
+            "16x16/apps/editor.png",
+            "32x32/apps/editor.png",
+            "16x16/apps/browser.png",
+            "24x24@2/apps/browser.png",
+            "48x48/apps/terminal.png",
+            "16x16/apps/complete.png",
+            "32x32/apps/complete.png",
+            "48x48/apps/complete.png",
+            "16x16/apps/scalable.png",
+            "scalable/apps/scalable.png",
+        ] {
+            theme = theme.icon(icon);
+        }
+        let fixture = Fixture::in_memory(&[theme]);
+        let theme = fixture.load_theme("icon-finder-gaps").unwrap();
+        let options = fixture.lookup_options();
+
+        let gaps = check_size_gaps_with(&theme, &[16, 32, 48], &options);
+        let names: Vec<&str> = gaps.iter().map(|gap| gap.name.as_str()).collect();
+        assert_eq!(names, ["browser", "editor", "terminal"]);
+
+        let browser = &gaps[0];
+        assert_eq!(browser.missing, [32, 48]);
+        // Drawn at 48 pixels for scale 2, so no size is scaled up.
+        assert_eq!((browser.largest, browser.upscaled()), (Some(48), vec![]));
+
+        let editor = &gaps[1];
+        assert_eq!(editor.context, Some(Context::Applications));
+        assert_eq!(
+            (editor.available.clone(), editor.missing.clone()),
+            (vec![16, 32], vec![48])
+        );
+        assert_eq!(editor.upscaled(), [48]);
+
+        let terminal = &gaps[2];
+        assert_eq!(terminal.missing, [16, 32]);
+        assert!(terminal.upscaled().is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+//! # Icon handles
+//! Interfaces that zoom, like file managers and docks with magnification,
+//! look up the same icons at ever changing sizes, and every lookup searches
+//! the directories of the theme and its parents again. Resolving an icon
+//! searches them once, keeping the files of the icon in every theme of the
+//! chain. Picking the file for a size afterwards only compares the
+//! directories holding these files, without touching the file system.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::{
+    context_directories, directional_names, find_in_directory, input, lookup_fallback_icon,
+    lookup_names, select_icon, IconFormat, LookupOptions, LookupResult, Theme, Translations,
+};
+
+/// The files of one of the names of the icon in a theme.
+#[derive(Debug, Clone)]
+struct ResolvedTheme {
+    /// The theme with only the directories holding a file of the name.
+    theme: Theme,
+    /// The file of the name in each of the directories.
+    files: Vec<(PathBuf, IconFormat)>,
+}
+
+/// The files of a list of names that are looked up together, in the themes
+/// and unthemed.
+#[derive(Debug, Clone)]
+struct ResolvedNames {
+    /// The files of every name in every theme of the chain, in the order a
+    /// lookup tries them: the names in the theme before its parents.
+    themes: Vec<ResolvedTheme>,
+    /// The file used when no theme has any of the names, an unthemed icon or
+    /// the file the icon was given as.
+    unthemed: Option<LookupResult>,
+}
+
+impl ResolvedNames {
+    fn resolve(
+        names: &[&str],
+        options: &LookupOptions,
+        theme: &Theme,
+        fallbacks: &[Arc<Theme>],
+    ) -> ResolvedNames {
+        let names = directional_names(names, options);
+        let mut themes = Vec::new();
+
+        for theme in theme.ancestry_with(fallbacks) {
+            for name in &names {
+                let mut directories = Vec::new();
+                let mut files = Vec::new();
+                for subdir in context_directories(theme, options.context.as_ref()) {
+                    if let Some(file) = find_in_directory(name, subdir, options, theme) {
+                        directories.push(subdir.clone());
+                        files.push(file);
+                    }
+                }
+
+                if !files.is_empty() {
+                    let theme = Theme {
+                        name: theme.name.clone(),
+                        comment: String::new(),
+                        inherits: Vec::new(),
+                        directories,
+                        hidden: theme.hidden,
+                        translations: Translations::default(),
+                        example: None,
+                    };
+                    themes.push(ResolvedTheme { theme, files });
+                }
+            }
+        }
+        let unthemed = names
+            .iter()
+            .find_map(|name| lookup_fallback_icon(name, options));
+
+        ResolvedNames { themes, unthemed }
+    }
+
+    fn lookup_at(&self, options: &LookupOptions) -> Option<LookupResult> {
+        self.themes
+            .iter()
+            .find_map(|resolved| {
+                select_icon(options, &resolved.theme, &|subdir| {
+                    let index = resolved
+                        .theme
+                        .directories
+                        .iter()
+                        .position(|directory| std::ptr::eq(directory, subdir))?;
+                    Some(resolved.files[index].clone())
+                })
+            })
+            .or_else(|| self.unthemed.clone())
+    }
+}
+
+/// An icon resolved by [`resolve_icon`](crate::resolve_icon) or
+/// [`IconFinder::resolve_icon`](crate::IconFinder::resolve_icon), picking
+/// the file for any size like a lookup would. The names a lookup tries are
+/// resolved as well: the generic names, the variants for the text direction,
+/// the regular icons of symbolic icons for GTK and the names the icon is an
+/// alias of. Files added to or removed from the themes afterwards are not
+/// noticed, resolve the icon again when the themes change.
+#[derive(Debug, Clone)]
+pub struct IconHandle {
+    name: String,
+    options: LookupOptions,
+    /// The names of the icon, followed by the names they are aliases of,
+    /// which are only used when none of the names is found.
+    resolved: Vec<ResolvedNames>,
+}
+
+impl IconHandle {
+    pub(crate) fn resolve(
+        icon: &str,
+        options: &LookupOptions,
+        theme: &Theme,
+        fallbacks: &[Arc<Theme>],
+    ) -> IconHandle {
+        let mut handle = IconHandle {
+            name: icon.to_owned(),
+            options: options.clone(),
+            resolved: Vec::new(),
+        };
+
+        // Icons given as a file are used as is, they are never looked up in
+        // themes.
+        if let Some(path) = input::file_path(icon) {
+            handle.resolved.push(ResolvedNames {
+                themes: Vec::new(),
+                unthemed: input::file_result(path, options),
+            });
+            return handle;
+        }
+
+        let names = lookup_names(input::icon_name(icon, options), options);
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        handle
+            .resolved
+            .push(ResolvedNames::resolve(&names, options, theme, fallbacks));
+
+        let aliases: Vec<&str> = names
+            .iter()
+            .filter_map(|name| options.alias(name))
+            .collect();
+        if !aliases.is_empty() {
+            handle
+                .resolved
+                .push(ResolvedNames::resolve(&aliases, options, theme, fallbacks));
+        }
+
+        handle
+    }
+
+    /// The name of the icon.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether a file was found for the icon, at any size.
+    pub fn is_found(&self) -> bool {
+        self.resolved
+            .iter()
+            .any(|resolved| !resolved.themes.is_empty() || resolved.unthemed.is_some())
+    }
+
+    /// The file of the icon for the given size and scale, the one a lookup
+    /// with the options the icon was resolved with would find.
+    pub fn at(&self, size: i16, scale: i16) -> Option<PathBuf> {
+        self.lookup_at(size, scale).map(|result| result.path)
+    }
+
+    /// Picks the file of the icon like [`at`](IconHandle::at), returning
+    /// where it was found and how well it matches along with its path.
+    pub fn lookup_at(&self, size: i16, scale: i16) -> Option<LookupResult> {
+        let mut options = self.options.clone().scale(scale);
+        options.size = size;
+
+        self.resolved
+            .iter()
+            .find_map(|resolved| resolved.lookup_at(&options))
+            .map(|result| options.canonical_result(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+    use crate::{IconAliases, LookupFlags, LookupProfile, MatchKind};
+
+    #[test]
+    fn test_icon_handle() {
+        let fixture = Fixture::in_memory(&[
+            ThemeFixture::new("icon-finder-handle")
+                .inherits("icon-finder-handle-parent")
+                .fixed_directory("16x16/places", 16)
+                .fixed_directory("32x32/places", 32)
+                .fixed_directory("48x48/places", 48)
+                .icon("16x16/places/folder.png")
+                .icon("48x48/places/folder.png"),
+            ThemeFixture::new("icon-finder-handle-parent")
+                .fixed_directory("64x64/places", 64)
+                .icon("64x64/places/folder.png"),
+        ]);
+        let theme = fixture.load_theme("icon-finder-handle").unwrap();
+        let options = fixture.lookup_options();
+        let directory = fixture.base_directory().join("icon-finder-handle");
+
+        let handle = IconHandle::resolve("folder", &options, &theme, &[]);
+        assert!(handle.is_found());
+        assert_eq!(handle.name(), "folder");
+        // The parent is only used when the theme has no file at all.
+        assert_eq!(
+            handle.at(64, 1),
+            Some(directory.join("48x48/places/folder.png"))
+        );
+        assert_eq!(
+            handle.at(20, 1),
+            Some(directory.join("16x16/places/folder.png"))
+        );
+        assert_eq!(
+            handle.lookup_at(16, 1).unwrap().match_kind,
+            MatchKind::Exact
+        );
+
+        // Every size picks what a lookup picks.
+        for size in [8, 16, 24, 32, 40, 48, 64, 128] {
+            for scale in [1, 2] {
+                let mut options = options.clone().scale(scale);
+                options.size = size;
+                assert_eq!(
+                    handle.lookup_at(size, scale),
+                    crate::find_icon_in("folder", &options, &theme, &[])
+                );
+            }
+        }
+
+        let exact = options.clone().flags(LookupFlags::EXACT_ONLY);
+        let handle = IconHandle::resolve("folder", &exact, &theme, &[]);
+        assert_eq!(
+            handle.at(64, 1),
+            Some(
+                fixture
+                    .base_directory()
+                    .join("icon-finder-handle-parent/64x64/places/folder.png")
+            )
+        );
+        assert_eq!(handle.at(32, 1), None);
+
+        let missing = IconHandle::resolve("missing", &options, &theme, &[]);
+        assert!(!missing.is_found());
+        assert_eq!(missing.at(16, 1), None);
+    }
+
+    #[test]
+    fn test_icon_handle_names() {
+        let fixture = Fixture::in_memory(&[ThemeFixture::new("icon-finder-handle-names")
+            .fixed_directory("16x16/apps", 16)
+            .fixed_directory("48x48/apps", 48)
+            .icon("16x16/apps/text-x.png")
+            .icon("48x48/apps/go-next.png")
+            .icon("16x16/apps/go-next-rtl.png")
+            .icon("48x48/apps/edit.png")
+            .icon("16x16/apps/folder.png")]);
+        let theme = fixture.load_theme("icon-finder-handle-names").unwrap();
+        let options = fixture.lookup_options();
+
+        let cases = [
+            (
+                "text-x-python",
+                options.clone().flags(LookupFlags::GENERIC_FALLBACK),
+            ),
+            ("go-next", options.clone().flags(LookupFlags::DIR_RTL)),
+            (
+                "edit-symbolic",
+                options.clone().lookup_profile(LookupProfile::GtkCompatible),
+            ),
+            (
+                "old-folder",
+                options
+                    .clone()
+                    .aliases(IconAliases::new().alias("old-folder", "folder")),
+            ),
+        ];
+        for (icon, options) in cases {
+            let handle = IconHandle::resolve(icon, &options, &theme, &[]);
+            assert!(handle.is_found(), "{}", icon);
+            for size in [16, 32, 48] {
+                let mut options = options.clone();
+                options.size = size;
+                assert_eq!(
+                    handle.lookup_at(size, 1),
+                    crate::find_icon_in(icon, &options, &theme, &[]),
+                    "{} at {}",
+                    icon,
+                    size
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! # GTK icon caches
+//! GTK does not list the directories of a theme that has an icon-theme.cache
+//! file in its toplevel directory, it looks icons up in the cache instead.
+//! The cache is normally generated by gtk-update-icon-cache after installing
+//! icons. Writing the cache lets packaging tools update it themselves.
+//!
+//! The cache is a binary file of big-endian numbers. It starts with a header
+//! pointing to a hash table of icon names and to the list of directories.
+//! Every icon in the hash table has a list of images, one for every
+//! directory the icon is in, with flags telling the extensions of its files.
+//! Like gtk-update-icon-cache without --include-image-data, no pixel data is
+//! stored in the cache.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::IndexTheme;
+
+const MAJOR_VERSION: u16 = 1;
+const MINOR_VERSION: u16 = 0;
+
+/// Offsets pointing nowhere, e.g. at the end of a hash chain.
+const NO_OFFSET: u32 = 0xffff_ffff;
+
+const HAS_SUFFIX_XPM: u16 = 1;
+const HAS_SUFFIX_SVG: u16 = 2;
+const HAS_SUFFIX_PNG: u16 = 4;
+const HAS_ICON_FILE: u16 = 8;
+
+/// The hash GTK uses for icon names, computed over signed characters.
+fn icon_name_hash(name: &str) -> u32 {
+    let mut bytes = name.bytes().map(|byte| byte as i8 as u32);
+    let first = bytes.next().unwrap_or(0);
+    bytes.fold(first, |hash, byte| {
+        (hash << 5).wrapping_sub(hash).wrapping_add(byte)
+    })
+}
+
+/// The number of hash buckets for the icons, a prime close to a third of
+/// their number like gtk-update-icon-cache uses.
+fn bucket_count(icons: usize) -> u32 {
+    let target = (icons / 3).max(2) as u32;
+    (target..)
+        .find(|n| (2..).take_while(|d| d * d <= *n).all(|d| n % d != 0))
+        .unwrap_or(target)
+}
+
+fn suffix_flag(file_name: &str) -> Option<(&str, u16)> {
+    let (name, extension) = file_name.rsplit_once('.')?;
+    let flag = match extension {
+        "png" => HAS_SUFFIX_PNG,
+        "svg" => HAS_SUFFIX_SVG,
+        "xpm" => HAS_SUFFIX_XPM,
+        "icon" => HAS_ICON_FILE,
+        _ => return None,
+    };
+    Some((name, flag))
+}
+
+/// A cache file being built, with numbers to fill in once the offsets they
+/// point to are known.
+struct CacheFile(Vec<u8>);
+
+impl CacheFile {
+    fn offset(&self) -> u32 {
+        self.0.len() as u32
+    }
+
+    fn push_u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn push_u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn set_u32(&mut self, offset: u32, value: u32) {
+        let offset = offset as usize;
+        self.0[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    /// Appends a NUL-terminated string, padded to a multiple of four bytes.
+    fn push_string(&mut self, string: &str) -> u32 {
+        let offset = self.offset();
+        self.0.extend_from_slice(string.as_bytes());
+        self.0.push(0);
+        while !self.0.len().is_multiple_of(4) {
+            self.0.push(0);
+        }
+        offset
+    }
+}
+
+/// The contents of the cache for the icons, given by name with the index of
+/// every directory they are in and the flags of their files there.
+fn build_cache(directories: &[String], icons: &BTreeMap<String, BTreeMap<u16, u16>>) -> Vec<u8> {
+    let mut cache = CacheFile(Vec::new());
+    cache.push_u16(MAJOR_VERSION);
+    cache.push_u16(MINOR_VERSION);
+    cache.push_u32(0);
+    cache.push_u32(0);
+
+    let buckets = bucket_count(icons.len());
+    let hash_offset = cache.offset();
+    cache.set_u32(4, hash_offset);
+    cache.push_u32(buckets);
+    for _ in 0..buckets {
+        cache.push_u32(NO_OFFSET);
+    }
+
+    let mut chains: Vec<Vec<(&String, &BTreeMap<u16, u16>)>> = vec![Vec::new(); buckets as usize];
+    for (name, images) in icons {
+        chains[(icon_name_hash(name) % buckets) as usize].push((name, images));
+    }
+
+    for (bucket, chain) in (0..).zip(&chains) {
+        // The offset of the number pointing to the next icon in the chain.
+        let mut link = hash_offset + 4 + bucket * 4;
+        for (name, images) in chain {
+            let icon_offset = cache.offset();
+            cache.set_u32(link, icon_offset);
+            link = icon_offset;
+            cache.push_u32(NO_OFFSET);
+            cache.push_u32(0);
+            cache.push_u32(0);
+
+            let name_offset = cache.push_string(name);
+            cache.set_u32(icon_offset + 4, name_offset);
+
+            let images_offset = cache.offset();
+            cache.set_u32(icon_offset + 8, images_offset);
+            cache.push_u32(images.len() as u32);
+            for (&directory, &flags) in images.iter() {
+                cache.push_u16(directory);
+                cache.push_u16(flags);
+                // No image data.
+                cache.push_u32(0);
+            }
+        }
+    }
+
+    let directories_offset = cache.offset();
+    cache.set_u32(8, directories_offset);
+    cache.push_u32(directories.len() as u32);
+    for _ in directories {
+        cache.push_u32(0);
+    }
+    for (index, directory) in (0..).zip(directories) {
+        let offset = cache.push_string(directory);
+        cache.set_u32(directories_offset + 4 + index * 4, offset);
+    }
+
+    cache.0
+}
+
+/// Writes the icon-theme.cache file of the theme in the directory, listing
+/// the icons in the directories of its index.theme, like
+/// gtk-update-icon-cache. The file is replaced at once, so GTK never reads a
+/// partially written cache, and the modification time of the directory is
+/// set to that of the cache, so GTK does not take the cache as outdated.
+pub fn write_icon_cache(theme_directory: &Path) -> io::Result<()> {
+    let index = IndexTheme::parse(&fs::read_to_string(theme_directory.join("index.theme"))?)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+
+    let mut directories = Vec::new();
+    let mut icons: BTreeMap<String, BTreeMap<u16, u16>> = BTreeMap::new();
+    for directory in &index.directories {
+        let entries = match fs::read_dir(theme_directory.join(&directory.name)) {
+            Ok(entries) => entries,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => return Err(error),
+        };
+        if directories.contains(&directory.name) {
+            continue;
+        }
+        let index = u16::try_from(directories.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "too many directories"))?;
+        directories.push(directory.name.clone());
+
+        for entry in entries {
+            let file_name = entry?.file_name();
+            // Names that are not UTF-8 cannot be looked up by GTK either.
+            if let Some((name, flag)) = file_name.to_str().and_then(suffix_flag) {
+                *icons
+                    .entry(name.to_owned())
+                    .or_default()
+                    .entry(index)
+                    .or_default() |= flag;
+            }
+        }
+    }
+
+    let cache = build_cache(&directories, &icons);
+    let path = theme_directory.join("icon-theme.cache");
+    let temporary = theme_directory.join(format!(".icon-theme.cache.{}", std::process::id()));
+    if let Err(error) = fs::write(&temporary, cache).and_then(|_| fs::rename(&temporary, &path)) {
+        let _ = fs::remove_file(&temporary);
+        return Err(error);
+    }
+
+    // Renaming the cache into place touches the theme directory. GTK ignores
+    // caches older than their directory, so the directory gets the time of
+    // the cache like gtk-update-icon-cache does.
+    let modified = fs::metadata(&path)?.modified()?;
+    fs::File::open(theme_directory)?.set_modified(modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::TryInto;
+
+    fn u16_at(cache: &[u8], offset: u32) -> u16 {
+        let offset = offset as usize;
+        u16::from_be_bytes([cache[offset], cache[offset + 1]])
+    }
+
+    fn u32_at(cache: &[u8], offset: u32) -> u32 {
+        let offset = offset as usize;
+        u32::from_be_bytes(cache[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn string_at(cache: &[u8], offset: u32) -> &str {
+        let string = &cache[offset as usize..];
+        std::str::from_utf8(&string[..string.iter().position(|&b| b == 0).unwrap()]).unwrap()
+    }
+
+    /// Looks the icon up like GTK, returning its directories and flags.
+    fn lookup(cache: &[u8], icon: &str) -> Vec<(String, u16)> {
+        let hash = u32_at(cache, 4);
+        let directories = u32_at(cache, 8);
+        let bucket = icon_name_hash(icon) % u32_at(cache, hash);
+
+        let mut offset = u32_at(cache, hash + 4 + bucket * 4);
+        while offset != NO_OFFSET {
+            if string_at(cache, u32_at(cache, offset + 4)) == icon {
+                let images = u32_at(cache, offset + 8);
+                return (0..u32_at(cache, images))
+                    .map(|image| {
+                        let image = images + 4 + image * 8;
+                        let directory = u32::from(u16_at(cache, image));
+                        let name = u32_at(cache, directories + 4 + directory * 4);
+                        (string_at(cache, name).to_owned(), u16_at(cache, image + 2))
+                    })
+                    .collect();
+            }
+            offset = u32_at(cache, offset);
+        }
+        Vec::new()
+    }
+
+    #[test]
+    fn test_icon_name_hash() {
+        assert_eq!(icon_name_hash(""), 0);
+        assert_eq!(icon_name_hash("a"), 97);
+        assert_eq!(icon_name_hash("ab"), 97 * 31 + 98);
+        // Bytes above 127 are negative as signed characters, é is C3 A9.
+        assert_eq!(icon_name_hash("a\u{e9}"), (97 * 31 - 61) * 31 - 87);
+    }
+
+    #[test]
+    fn test_write_icon_cache() {
+        let theme =
+            std::env::temp_dir().join(format!("icon-finder-icon-cache-{}", std::process::id()));
+        fs::create_dir_all(theme.join("16x16/apps")).unwrap();
+        fs::create_dir_all(theme.join("scalable/apps")).unwrap();
+        fs::write(
+            theme.join("index.theme"),
+            "[Icon Theme]\nName=Cache\nDirectories=16x16/apps,scalable/apps,missing\n\n\
+             [16x16/apps]\nSize=16\n\n[scalable/apps]\nSize=48\nType=Scalable\n\n\
+             [missing]\nSize=24\n",
+        )
+        .unwrap();
+        for file in [
+            "16x16/apps/editor.png",
+            "16x16/apps/editor.icon",
+            "16x16/apps/terminal.xpm",
+            "16x16/apps/README",
+            "scalable/apps/editor.svg",
+        ] {
+            fs::write(theme.join(file), "").unwrap();
+        }
+        // Enough icons for several buckets.
+        for index in 0..20 {
+            fs::write(theme.join(format!("scalable/apps/icon-{}.svg", index)), "").unwrap();
+        }
+
+        write_icon_cache(&theme).unwrap();
+        let cache = fs::read(theme.join("icon-theme.cache")).unwrap();
+        let modified = |path: &Path| fs::metadata(path).unwrap().modified().unwrap();
+        // GTK only uses a cache at least as new as its directory.
+        assert_eq!(modified(&theme), modified(&theme.join("icon-theme.cache")));
+        let entries = fs::read_dir(&theme).unwrap().count();
+        fs::remove_dir_all(&theme).unwrap();
+
+        // The temporary file was renamed into place.
+        assert_eq!(entries, 4);
+
+        assert_eq!((u16_at(&cache, 0), u16_at(&cache, 2)), (1, 0));
+        assert_eq!(
+            lookup(&cache, "editor"),
+            [
+                ("16x16/apps".to_owned(), HAS_SUFFIX_PNG | HAS_ICON_FILE),
+                ("scalable/apps".to_owned(), HAS_SUFFIX_SVG),
+            ]
+        );
+        assert_eq!(
+            lookup(&cache, "terminal"),
+            [("16x16/apps".to_owned(), HAS_SUFFIX_XPM)]
+        );
+        assert_eq!(
+            lookup(&cache, "icon-17"),
+            [("scalable/apps".to_owned(), HAS_SUFFIX_SVG)]
+        );
+        assert!(lookup(&cache, "README").is_empty());
+        assert!(lookup(&cache, "calculator").is_empty());
+    }
+
+    #[test]
+    fn test_write_icon_cache_failure() {
+        let theme = std::env::temp_dir().join(format!(
+            "icon-finder-icon-cache-failure-{}",
+            std::process::id()
+        ));
+        // A directory in the way of the cache.
+        fs::create_dir_all(theme.join("icon-theme.cache/apps")).unwrap();
+        fs::write(theme.join("index.theme"), "[Icon Theme]\nName=Cache\n").unwrap();
+
+        let result = write_icon_cache(&theme);
+        let entries = fs::read_dir(&theme).unwrap().count();
+        fs::remove_dir_all(&theme).unwrap();
+
+        assert!(result.is_err());
+        // The temporary file is removed again.
+        assert_eq!(entries, 2);
+    }
+}
@@ -0,0 +1,147 @@
+//! # Icon data files
+//! From: https://specifications.freedesktop.org/icon-theme-spec/latest/#file_formats
+//! Icons can have an optional data file next to them, with the same name and
+//! the .icon extension. It follows the syntax of the Desktop Entry
+//! Specification and contains extra information about the icon, like a
+//! rectangle text can be drawn in, e.g. for badging document icons.
+
+use std::path::Path;
+
+use crate::filesystem::file_system;
+use crate::index::{parse_groups, Group};
+use crate::{localized, Error};
+
+const ICON_DATA_GROUP: &str = "Icon Data";
+
+/// A rectangle in the coordinate system of the icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rectangle {
+    pub x0: i32,
+    pub y0: i32,
+    pub x1: i32,
+    pub y1: i32,
+}
+
+/// The contents of a .icon data file.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct IconData {
+    /// The untranslated name of the icon.
+    pub display_name: Option<String>,
+    /// Translations of the DisplayName key, by locale.
+    pub display_names: Vec<(String, String)>,
+    /// The rectangle in which text can be drawn, if the icon is large
+    /// enough to display text.
+    pub embedded_text_rectangle: Option<Rectangle>,
+    /// Points emblems can be attached to.
+    pub attach_points: Vec<(i32, i32)>,
+}
+
+impl IconData {
+    /// Reads and parses a .icon file.
+    pub fn read(path: &Path) -> Result<IconData, Error> {
+        IconData::parse(&file_system().read_to_string(path)?)
+    }
+
+    /// Parses the contents of a .icon file.
+    pub fn parse(contents: &str) -> Result<IconData, Error> {
+        let groups = parse_groups(contents)?;
+        let group = groups
+            .iter()
+            .find(|group| group.name == ICON_DATA_GROUP)
+            .ok_or_else(|| Error::Parse {
+                line: 1,
+                message: format!("missing [{}] group", ICON_DATA_GROUP),
+            })?;
+
+        let embedded_text_rectangle = match group.get("EmbeddedTextRectangle") {
+            Some(value) => match parse_numbers(group, value, ',')?.as_slice() {
+                &[x0, y0, x1, y1] => Some(Rectangle { x0, y0, x1, y1 }),
+                _ => return Err(invalid_value(group, "EmbeddedTextRectangle", value)),
+            },
+            None => None,
+        };
+
+        let mut attach_points = Vec::new();
+        for point in group.get("AttachPoints").unwrap_or_default().split('|') {
+            let point = point.trim();
+            if point.is_empty() {
+                continue;
+            }
+
+            match parse_numbers(group, point, ',')?.as_slice() {
+                &[x, y] => attach_points.push((x, y)),
+                _ => return Err(invalid_value(group, "AttachPoints", point)),
+            }
+        }
+
+        Ok(IconData {
+            display_name: group.get("DisplayName").map(str::to_owned),
+            display_names: group.localized("DisplayName"),
+            embedded_text_rectangle,
+            attach_points,
+        })
+    }
+
+    /// The name of the icon, translated for the given locale, or for the
+    /// locale set in the environment if `None`.
+    pub fn display_name(&self, locale: Option<&str>) -> Option<&str> {
+        localized(&self.display_names, locale).or(self.display_name.as_deref())
+    }
+}
+
+fn parse_numbers(group: &Group, value: &str, separator: char) -> Result<Vec<i32>, Error> {
+    value
+        .split(separator)
+        .map(|number| {
+            number
+                .trim()
+                .parse()
+                .map_err(|_| invalid_value(group, "coordinate", number))
+        })
+        .collect()
+}
+
+fn invalid_value(group: &Group, key: &str, value: &str) -> Error {
+    Error::Parse {
+        line: group.line,
+        message: format!("invalid {} in [{}]: {}", key, group.name, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_icon_data() {
+        let data = IconData::parse(
+            "[Icon Data]
+DisplayName=Text document
+DisplayName[nl]=Tekstdocument
+EmbeddedTextRectangle=10,12,38,40
+AttachPoints=0,0|48,48
+",
+        )
+        .unwrap();
+
+        assert_eq!(data.display_name(Some("nl_NL")), Some("Tekstdocument"));
+        assert_eq!(data.display_name(Some("C")), Some("Text document"));
+        assert_eq!(
+            data.embedded_text_rectangle,
+            Some(Rectangle {
+                x0: 10,
+                y0: 12,
+                x1: 38,
+                y1: 40
+            })
+        );
+        assert_eq!(data.attach_points, vec![(0, 0), (48, 48)]);
+    }
+
+    #[test]
+    fn test_parse_icon_data_errors() {
+        assert!(IconData::parse("DisplayName=Text").is_err());
+        assert!(IconData::parse("[Icon Data]\nEmbeddedTextRectangle=1,2,3\n").is_err());
+        assert!(IconData::parse("[Icon Data]\nAttachPoints=1,a\n").is_err());
+    }
+}
@@ -0,0 +1,639 @@
+//! # Standard icon names
+//! The icon names listed by the Icon Naming Specification, grouped by the
+//! context they belong to. Using these instead of string literals lets the
+//! compiler catch typos in icon names. The International context is left
+//! out, its icons are named after countries rather than listed.
+//!
+//! ```no_run
+//! use icon_finder::icon_names;
+//!
+//! let path = icon_finder::global().find_icon(icon_names::EDIT_COPY, 16, 1);
+//! ```
+
+// Actions
+pub const ADDRESS_BOOK_NEW: &str = "address-book-new";
+pub const APPLICATION_EXIT: &str = "application-exit";
+pub const APPOINTMENT_NEW: &str = "appointment-new";
+pub const CALL_START: &str = "call-start";
+pub const CALL_STOP: &str = "call-stop";
+pub const CONTACT_NEW: &str = "contact-new";
+pub const DOCUMENT_NEW: &str = "document-new";
+pub const DOCUMENT_OPEN: &str = "document-open";
+pub const DOCUMENT_OPEN_RECENT: &str = "document-open-recent";
+pub const DOCUMENT_PAGE_SETUP: &str = "document-page-setup";
+pub const DOCUMENT_PRINT: &str = "document-print";
+pub const DOCUMENT_PRINT_PREVIEW: &str = "document-print-preview";
+pub const DOCUMENT_PROPERTIES: &str = "document-properties";
+pub const DOCUMENT_REVERT: &str = "document-revert";
+pub const DOCUMENT_SAVE: &str = "document-save";
+pub const DOCUMENT_SAVE_AS: &str = "document-save-as";
+pub const DOCUMENT_SEND: &str = "document-send";
+pub const EDIT_CLEAR: &str = "edit-clear";
+pub const EDIT_COPY: &str = "edit-copy";
+pub const EDIT_CUT: &str = "edit-cut";
+pub const EDIT_DELETE: &str = "edit-delete";
+pub const EDIT_FIND: &str = "edit-find";
+pub const EDIT_FIND_REPLACE: &str = "edit-find-replace";
+pub const EDIT_PASTE: &str = "edit-paste";
+pub const EDIT_REDO: &str = "edit-redo";
+pub const EDIT_SELECT_ALL: &str = "edit-select-all";
+pub const EDIT_UNDO: &str = "edit-undo";
+pub const FOLDER_NEW: &str = "folder-new";
+pub const FORMAT_INDENT_LESS: &str = "format-indent-less";
+pub const FORMAT_INDENT_MORE: &str = "format-indent-more";
+pub const FORMAT_JUSTIFY_CENTER: &str = "format-justify-center";
+pub const FORMAT_JUSTIFY_FILL: &str = "format-justify-fill";
+pub const FORMAT_JUSTIFY_LEFT: &str = "format-justify-left";
+pub const FORMAT_JUSTIFY_RIGHT: &str = "format-justify-right";
+pub const FORMAT_TEXT_DIRECTION_LTR: &str = "format-text-direction-ltr";
+pub const FORMAT_TEXT_DIRECTION_RTL: &str = "format-text-direction-rtl";
+pub const FORMAT_TEXT_BOLD: &str = "format-text-bold";
+pub const FORMAT_TEXT_ITALIC: &str = "format-text-italic";
+pub const FORMAT_TEXT_UNDERLINE: &str = "format-text-underline";
+pub const FORMAT_TEXT_STRIKETHROUGH: &str = "format-text-strikethrough";
+pub const GO_BOTTOM: &str = "go-bottom";
+pub const GO_DOWN: &str = "go-down";
+pub const GO_FIRST: &str = "go-first";
+pub const GO_HOME: &str = "go-home";
+pub const GO_JUMP: &str = "go-jump";
+pub const GO_LAST: &str = "go-last";
+pub const GO_NEXT: &str = "go-next";
+pub const GO_PREVIOUS: &str = "go-previous";
+pub const GO_TOP: &str = "go-top";
+pub const GO_UP: &str = "go-up";
+pub const HELP_ABOUT: &str = "help-about";
+pub const HELP_CONTENTS: &str = "help-contents";
+pub const HELP_FAQ: &str = "help-faq";
+pub const INSERT_IMAGE: &str = "insert-image";
+pub const INSERT_LINK: &str = "insert-link";
+pub const INSERT_OBJECT: &str = "insert-object";
+pub const INSERT_TEXT: &str = "insert-text";
+pub const LIST_ADD: &str = "list-add";
+pub const LIST_REMOVE: &str = "list-remove";
+pub const MAIL_FORWARD: &str = "mail-forward";
+pub const MAIL_MARK_IMPORTANT: &str = "mail-mark-important";
+pub const MAIL_MARK_JUNK: &str = "mail-mark-junk";
+pub const MAIL_MARK_NOTJUNK: &str = "mail-mark-notjunk";
+pub const MAIL_MARK_READ: &str = "mail-mark-read";
+pub const MAIL_MARK_UNREAD: &str = "mail-mark-unread";
+pub const MAIL_MESSAGE_NEW: &str = "mail-message-new";
+pub const MAIL_REPLY_ALL: &str = "mail-reply-all";
+pub const MAIL_REPLY_SENDER: &str = "mail-reply-sender";
+pub const MAIL_SEND: &str = "mail-send";
+pub const MAIL_SEND_RECEIVE: &str = "mail-send-receive";
+pub const MEDIA_EJECT: &str = "media-eject";
+pub const MEDIA_PLAYBACK_PAUSE: &str = "media-playback-pause";
+pub const MEDIA_PLAYBACK_START: &str = "media-playback-start";
+pub const MEDIA_PLAYBACK_STOP: &str = "media-playback-stop";
+pub const MEDIA_RECORD: &str = "media-record";
+pub const MEDIA_SEEK_BACKWARD: &str = "media-seek-backward";
+pub const MEDIA_SEEK_FORWARD: &str = "media-seek-forward";
+pub const MEDIA_SKIP_BACKWARD: &str = "media-skip-backward";
+pub const MEDIA_SKIP_FORWARD: &str = "media-skip-forward";
+pub const OBJECT_FLIP_HORIZONTAL: &str = "object-flip-horizontal";
+pub const OBJECT_FLIP_VERTICAL: &str = "object-flip-vertical";
+pub const OBJECT_ROTATE_LEFT: &str = "object-rotate-left";
+pub const OBJECT_ROTATE_RIGHT: &str = "object-rotate-right";
+pub const PROCESS_STOP: &str = "process-stop";
+pub const SYSTEM_LOCK_SCREEN: &str = "system-lock-screen";
+pub const SYSTEM_LOG_OUT: &str = "system-log-out";
+pub const SYSTEM_RUN: &str = "system-run";
+pub const SYSTEM_SEARCH: &str = "system-search";
+pub const SYSTEM_REBOOT: &str = "system-reboot";
+pub const SYSTEM_SHUTDOWN: &str = "system-shutdown";
+pub const TOOLS_CHECK_SPELLING: &str = "tools-check-spelling";
+pub const VIEW_FULLSCREEN: &str = "view-fullscreen";
+pub const VIEW_REFRESH: &str = "view-refresh";
+pub const VIEW_RESTORE: &str = "view-restore";
+pub const VIEW_SORT_ASCENDING: &str = "view-sort-ascending";
+pub const VIEW_SORT_DESCENDING: &str = "view-sort-descending";
+pub const WINDOW_CLOSE: &str = "window-close";
+pub const WINDOW_NEW: &str = "window-new";
+pub const ZOOM_FIT_BEST: &str = "zoom-fit-best";
+pub const ZOOM_IN: &str = "zoom-in";
+pub const ZOOM_ORIGINAL: &str = "zoom-original";
+pub const ZOOM_OUT: &str = "zoom-out";
+
+/// The standard icon names of the Actions context.
+pub const ACTIONS: &[&str] = &[
+    ADDRESS_BOOK_NEW,
+    APPLICATION_EXIT,
+    APPOINTMENT_NEW,
+    CALL_START,
+    CALL_STOP,
+    CONTACT_NEW,
+    DOCUMENT_NEW,
+    DOCUMENT_OPEN,
+    DOCUMENT_OPEN_RECENT,
+    DOCUMENT_PAGE_SETUP,
+    DOCUMENT_PRINT,
+    DOCUMENT_PRINT_PREVIEW,
+    DOCUMENT_PROPERTIES,
+    DOCUMENT_REVERT,
+    DOCUMENT_SAVE,
+    DOCUMENT_SAVE_AS,
+    DOCUMENT_SEND,
+    EDIT_CLEAR,
+    EDIT_COPY,
+    EDIT_CUT,
+    EDIT_DELETE,
+    EDIT_FIND,
+    EDIT_FIND_REPLACE,
+    EDIT_PASTE,
+    EDIT_REDO,
+    EDIT_SELECT_ALL,
+    EDIT_UNDO,
+    FOLDER_NEW,
+    FORMAT_INDENT_LESS,
+    FORMAT_INDENT_MORE,
+    FORMAT_JUSTIFY_CENTER,
+    FORMAT_JUSTIFY_FILL,
+    FORMAT_JUSTIFY_LEFT,
+    FORMAT_JUSTIFY_RIGHT,
+    FORMAT_TEXT_DIRECTION_LTR,
+    FORMAT_TEXT_DIRECTION_RTL,
+    FORMAT_TEXT_BOLD,
+    FORMAT_TEXT_ITALIC,
+    FORMAT_TEXT_UNDERLINE,
+    FORMAT_TEXT_STRIKETHROUGH,
+    GO_BOTTOM,
+    GO_DOWN,
+    GO_FIRST,
+    GO_HOME,
+    GO_JUMP,
+    GO_LAST,
+    GO_NEXT,
+    GO_PREVIOUS,
+    GO_TOP,
+    GO_UP,
+    HELP_ABOUT,
+    HELP_CONTENTS,
+    HELP_FAQ,
+    INSERT_IMAGE,
+    INSERT_LINK,
+    INSERT_OBJECT,
+    INSERT_TEXT,
+    LIST_ADD,
+    LIST_REMOVE,
+    MAIL_FORWARD,
+    MAIL_MARK_IMPORTANT,
+    MAIL_MARK_JUNK,
+    MAIL_MARK_NOTJUNK,
+    MAIL_MARK_READ,
+    MAIL_MARK_UNREAD,
+    MAIL_MESSAGE_NEW,
+    MAIL_REPLY_ALL,
+    MAIL_REPLY_SENDER,
+    MAIL_SEND,
+    MAIL_SEND_RECEIVE,
+    MEDIA_EJECT,
+    MEDIA_PLAYBACK_PAUSE,
+    MEDIA_PLAYBACK_START,
+    MEDIA_PLAYBACK_STOP,
+    MEDIA_RECORD,
+    MEDIA_SEEK_BACKWARD,
+    MEDIA_SEEK_FORWARD,
+    MEDIA_SKIP_BACKWARD,
+    MEDIA_SKIP_FORWARD,
+    OBJECT_FLIP_HORIZONTAL,
+    OBJECT_FLIP_VERTICAL,
+    OBJECT_ROTATE_LEFT,
+    OBJECT_ROTATE_RIGHT,
+    PROCESS_STOP,
+    SYSTEM_LOCK_SCREEN,
+    SYSTEM_LOG_OUT,
+    SYSTEM_RUN,
+    SYSTEM_SEARCH,
+    SYSTEM_REBOOT,
+    SYSTEM_SHUTDOWN,
+    TOOLS_CHECK_SPELLING,
+    VIEW_FULLSCREEN,
+    VIEW_REFRESH,
+    VIEW_RESTORE,
+    VIEW_SORT_ASCENDING,
+    VIEW_SORT_DESCENDING,
+    WINDOW_CLOSE,
+    WINDOW_NEW,
+    ZOOM_FIT_BEST,
+    ZOOM_IN,
+    ZOOM_ORIGINAL,
+    ZOOM_OUT,
+];
+
+// Animations
+pub const PROCESS_WORKING: &str = "process-working";
+
+/// The standard icon names of the Animations context.
+pub const ANIMATIONS: &[&str] = &[PROCESS_WORKING];
+
+// Applications
+pub const ACCESSORIES_CALCULATOR: &str = "accessories-calculator";
+pub const ACCESSORIES_CHARACTER_MAP: &str = "accessories-character-map";
+pub const ACCESSORIES_DICTIONARY: &str = "accessories-dictionary";
+pub const ACCESSORIES_TEXT_EDITOR: &str = "accessories-text-editor";
+pub const HELP_BROWSER: &str = "help-browser";
+pub const MULTIMEDIA_VOLUME_CONTROL: &str = "multimedia-volume-control";
+pub const PREFERENCES_DESKTOP_ACCESSIBILITY: &str = "preferences-desktop-accessibility";
+pub const PREFERENCES_DESKTOP_FONT: &str = "preferences-desktop-font";
+pub const PREFERENCES_DESKTOP_KEYBOARD: &str = "preferences-desktop-keyboard";
+pub const PREFERENCES_DESKTOP_LOCALE: &str = "preferences-desktop-locale";
+pub const PREFERENCES_DESKTOP_MULTIMEDIA: &str = "preferences-desktop-multimedia";
+pub const PREFERENCES_DESKTOP_SCREENSAVER: &str = "preferences-desktop-screensaver";
+pub const PREFERENCES_DESKTOP_THEME: &str = "preferences-desktop-theme";
+pub const PREFERENCES_DESKTOP_WALLPAPER: &str = "preferences-desktop-wallpaper";
+pub const SYSTEM_FILE_MANAGER: &str = "system-file-manager";
+pub const SYSTEM_SOFTWARE_INSTALL: &str = "system-software-install";
+pub const SYSTEM_SOFTWARE_UPDATE: &str = "system-software-update";
+pub const UTILITIES_SYSTEM_MONITOR: &str = "utilities-system-monitor";
+pub const UTILITIES_TERMINAL: &str = "utilities-terminal";
+
+/// The standard icon names of the Applications context.
+pub const APPLICATIONS: &[&str] = &[
+    ACCESSORIES_CALCULATOR,
+    ACCESSORIES_CHARACTER_MAP,
+    ACCESSORIES_DICTIONARY,
+    ACCESSORIES_TEXT_EDITOR,
+    HELP_BROWSER,
+    MULTIMEDIA_VOLUME_CONTROL,
+    PREFERENCES_DESKTOP_ACCESSIBILITY,
+    PREFERENCES_DESKTOP_FONT,
+    PREFERENCES_DESKTOP_KEYBOARD,
+    PREFERENCES_DESKTOP_LOCALE,
+    PREFERENCES_DESKTOP_MULTIMEDIA,
+    PREFERENCES_DESKTOP_SCREENSAVER,
+    PREFERENCES_DESKTOP_THEME,
+    PREFERENCES_DESKTOP_WALLPAPER,
+    SYSTEM_FILE_MANAGER,
+    SYSTEM_SOFTWARE_INSTALL,
+    SYSTEM_SOFTWARE_UPDATE,
+    UTILITIES_SYSTEM_MONITOR,
+    UTILITIES_TERMINAL,
+];
+
+// Categories
+pub const APPLICATIONS_ACCESSORIES: &str = "applications-accessories";
+pub const APPLICATIONS_DEVELOPMENT: &str = "applications-development";
+pub const APPLICATIONS_ENGINEERING: &str = "applications-engineering";
+pub const APPLICATIONS_GAMES: &str = "applications-games";
+pub const APPLICATIONS_GRAPHICS: &str = "applications-graphics";
+pub const APPLICATIONS_INTERNET: &str = "applications-internet";
+pub const APPLICATIONS_MULTIMEDIA: &str = "applications-multimedia";
+pub const APPLICATIONS_OFFICE: &str = "applications-office";
+pub const APPLICATIONS_OTHER: &str = "applications-other";
+pub const APPLICATIONS_SCIENCE: &str = "applications-science";
+pub const APPLICATIONS_SYSTEM: &str = "applications-system";
+pub const APPLICATIONS_UTILITIES: &str = "applications-utilities";
+pub const PREFERENCES_DESKTOP: &str = "preferences-desktop";
+pub const PREFERENCES_DESKTOP_PERIPHERALS: &str = "preferences-desktop-peripherals";
+pub const PREFERENCES_DESKTOP_PERSONAL: &str = "preferences-desktop-personal";
+pub const PREFERENCES_OTHER: &str = "preferences-other";
+pub const PREFERENCES_SYSTEM: &str = "preferences-system";
+pub const PREFERENCES_SYSTEM_NETWORK: &str = "preferences-system-network";
+pub const SYSTEM_HELP: &str = "system-help";
+
+/// The standard icon names of the Categories context.
+pub const CATEGORIES: &[&str] = &[
+    APPLICATIONS_ACCESSORIES,
+    APPLICATIONS_DEVELOPMENT,
+    APPLICATIONS_ENGINEERING,
+    APPLICATIONS_GAMES,
+    APPLICATIONS_GRAPHICS,
+    APPLICATIONS_INTERNET,
+    APPLICATIONS_MULTIMEDIA,
+    APPLICATIONS_OFFICE,
+    APPLICATIONS_OTHER,
+    APPLICATIONS_SCIENCE,
+    APPLICATIONS_SYSTEM,
+    APPLICATIONS_UTILITIES,
+    PREFERENCES_DESKTOP,
+    PREFERENCES_DESKTOP_PERIPHERALS,
+    PREFERENCES_DESKTOP_PERSONAL,
+    PREFERENCES_OTHER,
+    PREFERENCES_SYSTEM,
+    PREFERENCES_SYSTEM_NETWORK,
+    SYSTEM_HELP,
+];
+
+// Devices
+pub const AUDIO_CARD: &str = "audio-card";
+pub const AUDIO_INPUT_MICROPHONE: &str = "audio-input-microphone";
+pub const BATTERY: &str = "battery";
+pub const CAMERA_PHOTO: &str = "camera-photo";
+pub const CAMERA_VIDEO: &str = "camera-video";
+pub const CAMERA_WEB: &str = "camera-web";
+pub const COMPUTER: &str = "computer";
+pub const DRIVE_HARDDISK: &str = "drive-harddisk";
+pub const DRIVE_OPTICAL: &str = "drive-optical";
+pub const DRIVE_REMOVABLE_MEDIA: &str = "drive-removable-media";
+pub const INPUT_GAMING: &str = "input-gaming";
+pub const INPUT_KEYBOARD: &str = "input-keyboard";
+pub const INPUT_MOUSE: &str = "input-mouse";
+pub const INPUT_TABLET: &str = "input-tablet";
+pub const MEDIA_FLASH: &str = "media-flash";
+pub const MEDIA_FLOPPY: &str = "media-floppy";
+pub const MEDIA_OPTICAL: &str = "media-optical";
+pub const MEDIA_TAPE: &str = "media-tape";
+pub const MODEM: &str = "modem";
+pub const MULTIMEDIA_PLAYER: &str = "multimedia-player";
+pub const NETWORK_WIRED: &str = "network-wired";
+pub const NETWORK_WIRELESS: &str = "network-wireless";
+pub const PDA: &str = "pda";
+pub const PHONE: &str = "phone";
+pub const PRINTER: &str = "printer";
+pub const SCANNER: &str = "scanner";
+pub const VIDEO_DISPLAY: &str = "video-display";
+
+/// The standard icon names of the Devices context.
+pub const DEVICES: &[&str] = &[
+    AUDIO_CARD,
+    AUDIO_INPUT_MICROPHONE,
+    BATTERY,
+    CAMERA_PHOTO,
+    CAMERA_VIDEO,
+    CAMERA_WEB,
+    COMPUTER,
+    DRIVE_HARDDISK,
+    DRIVE_OPTICAL,
+    DRIVE_REMOVABLE_MEDIA,
+    INPUT_GAMING,
+    INPUT_KEYBOARD,
+    INPUT_MOUSE,
+    INPUT_TABLET,
+    MEDIA_FLASH,
+    MEDIA_FLOPPY,
+    MEDIA_OPTICAL,
+    MEDIA_TAPE,
+    MODEM,
+    MULTIMEDIA_PLAYER,
+    NETWORK_WIRED,
+    NETWORK_WIRELESS,
+    PDA,
+    PHONE,
+    PRINTER,
+    SCANNER,
+    VIDEO_DISPLAY,
+];
+
+// Emblems
+pub const EMBLEM_DEFAULT: &str = "emblem-default";
+pub const EMBLEM_DOCUMENTS: &str = "emblem-documents";
+pub const EMBLEM_DOWNLOADS: &str = "emblem-downloads";
+pub const EMBLEM_FAVORITE: &str = "emblem-favorite";
+pub const EMBLEM_IMPORTANT: &str = "emblem-important";
+pub const EMBLEM_MAIL: &str = "emblem-mail";
+pub const EMBLEM_PHOTOS: &str = "emblem-photos";
+pub const EMBLEM_READONLY: &str = "emblem-readonly";
+pub const EMBLEM_SHARED: &str = "emblem-shared";
+pub const EMBLEM_SYMBOLIC_LINK: &str = "emblem-symbolic-link";
+pub const EMBLEM_SYNCHRONIZED: &str = "emblem-synchronized";
+pub const EMBLEM_SYSTEM: &str = "emblem-system";
+pub const EMBLEM_UNREADABLE: &str = "emblem-unreadable";
+
+/// The standard icon names of the Emblems context.
+pub const EMBLEMS: &[&str] = &[
+    EMBLEM_DEFAULT,
+    EMBLEM_DOCUMENTS,
+    EMBLEM_DOWNLOADS,
+    EMBLEM_FAVORITE,
+    EMBLEM_IMPORTANT,
+    EMBLEM_MAIL,
+    EMBLEM_PHOTOS,
+    EMBLEM_READONLY,
+    EMBLEM_SHARED,
+    EMBLEM_SYMBOLIC_LINK,
+    EMBLEM_SYNCHRONIZED,
+    EMBLEM_SYSTEM,
+    EMBLEM_UNREADABLE,
+];
+
+// Emotes
+pub const FACE_ANGEL: &str = "face-angel";
+pub const FACE_ANGRY: &str = "face-angry";
+pub const FACE_COOL: &str = "face-cool";
+pub const FACE_CRYING: &str = "face-crying";
+pub const FACE_DEVILISH: &str = "face-devilish";
+pub const FACE_EMBARRASSED: &str = "face-embarrassed";
+pub const FACE_KISS: &str = "face-kiss";
+pub const FACE_LAUGH: &str = "face-laugh";
+pub const FACE_MONKEY: &str = "face-monkey";
+pub const FACE_PLAIN: &str = "face-plain";
+pub const FACE_RASPBERRY: &str = "face-raspberry";
+pub const FACE_SAD: &str = "face-sad";
+pub const FACE_SICK: &str = "face-sick";
+pub const FACE_SMILE: &str = "face-smile";
+pub const FACE_SMILE_BIG: &str = "face-smile-big";
+pub const FACE_SMIRK: &str = "face-smirk";
+pub const FACE_SURPRISE: &str = "face-surprise";
+pub const FACE_TIRED: &str = "face-tired";
+pub const FACE_UNCERTAIN: &str = "face-uncertain";
+pub const FACE_WINK: &str = "face-wink";
+pub const FACE_WORRIED: &str = "face-worried";
+
+/// The standard icon names of the Emotes context.
+pub const EMOTES: &[&str] = &[
+    FACE_ANGEL,
+    FACE_ANGRY,
+    FACE_COOL,
+    FACE_CRYING,
+    FACE_DEVILISH,
+    FACE_EMBARRASSED,
+    FACE_KISS,
+    FACE_LAUGH,
+    FACE_MONKEY,
+    FACE_PLAIN,
+    FACE_RASPBERRY,
+    FACE_SAD,
+    FACE_SICK,
+    FACE_SMILE,
+    FACE_SMILE_BIG,
+    FACE_SMIRK,
+    FACE_SURPRISE,
+    FACE_TIRED,
+    FACE_UNCERTAIN,
+    FACE_WINK,
+    FACE_WORRIED,
+];
+
+// MimeTypes
+pub const APPLICATION_X_EXECUTABLE: &str = "application-x-executable";
+pub const AUDIO_X_GENERIC: &str = "audio-x-generic";
+pub const FONT_X_GENERIC: &str = "font-x-generic";
+pub const IMAGE_X_GENERIC: &str = "image-x-generic";
+pub const PACKAGE_X_GENERIC: &str = "package-x-generic";
+pub const TEXT_HTML: &str = "text-html";
+pub const TEXT_X_GENERIC: &str = "text-x-generic";
+pub const TEXT_X_GENERIC_TEMPLATE: &str = "text-x-generic-template";
+pub const TEXT_X_SCRIPT: &str = "text-x-script";
+pub const VIDEO_X_GENERIC: &str = "video-x-generic";
+pub const X_OFFICE_ADDRESS_BOOK: &str = "x-office-address-book";
+pub const X_OFFICE_CALENDAR: &str = "x-office-calendar";
+pub const X_OFFICE_DOCUMENT: &str = "x-office-document";
+pub const X_OFFICE_PRESENTATION: &str = "x-office-presentation";
+pub const X_OFFICE_SPREADSHEET: &str = "x-office-spreadsheet";
+
+/// The standard icon names of the MimeTypes context.
+pub const MIME_TYPES: &[&str] = &[
+    APPLICATION_X_EXECUTABLE,
+    AUDIO_X_GENERIC,
+    FONT_X_GENERIC,
+    IMAGE_X_GENERIC,
+    PACKAGE_X_GENERIC,
+    TEXT_HTML,
+    TEXT_X_GENERIC,
+    TEXT_X_GENERIC_TEMPLATE,
+    TEXT_X_SCRIPT,
+    VIDEO_X_GENERIC,
+    X_OFFICE_ADDRESS_BOOK,
+    X_OFFICE_CALENDAR,
+    X_OFFICE_DOCUMENT,
+    X_OFFICE_PRESENTATION,
+    X_OFFICE_SPREADSHEET,
+];
+
+// Places
+pub const FOLDER: &str = "folder";
+pub const FOLDER_REMOTE: &str = "folder-remote";
+pub const NETWORK_SERVER: &str = "network-server";
+pub const NETWORK_WORKGROUP: &str = "network-workgroup";
+pub const START_HERE: &str = "start-here";
+pub const USER_BOOKMARKS: &str = "user-bookmarks";
+pub const USER_DESKTOP: &str = "user-desktop";
+pub const USER_HOME: &str = "user-home";
+pub const USER_TRASH: &str = "user-trash";
+
+/// The standard icon names of the Places context.
+pub const PLACES: &[&str] = &[
+    FOLDER,
+    FOLDER_REMOTE,
+    NETWORK_SERVER,
+    NETWORK_WORKGROUP,
+    START_HERE,
+    USER_BOOKMARKS,
+    USER_DESKTOP,
+    USER_HOME,
+    USER_TRASH,
+];
+
+// Status
+pub const APPOINTMENT_MISSED: &str = "appointment-missed";
+pub const APPOINTMENT_SOON: &str = "appointment-soon";
+pub const AUDIO_VOLUME_HIGH: &str = "audio-volume-high";
+pub const AUDIO_VOLUME_LOW: &str = "audio-volume-low";
+pub const AUDIO_VOLUME_MEDIUM: &str = "audio-volume-medium";
+pub const AUDIO_VOLUME_MUTED: &str = "audio-volume-muted";
+pub const BATTERY_CAUTION: &str = "battery-caution";
+pub const BATTERY_LOW: &str = "battery-low";
+pub const DIALOG_ERROR: &str = "dialog-error";
+pub const DIALOG_INFORMATION: &str = "dialog-information";
+pub const DIALOG_PASSWORD: &str = "dialog-password";
+pub const DIALOG_QUESTION: &str = "dialog-question";
+pub const DIALOG_WARNING: &str = "dialog-warning";
+pub const FOLDER_DRAG_ACCEPT: &str = "folder-drag-accept";
+pub const FOLDER_OPEN: &str = "folder-open";
+pub const FOLDER_VISITING: &str = "folder-visiting";
+pub const IMAGE_LOADING: &str = "image-loading";
+pub const IMAGE_MISSING: &str = "image-missing";
+pub const MAIL_ATTACHMENT: &str = "mail-attachment";
+pub const MAIL_UNREAD: &str = "mail-unread";
+pub const MAIL_READ: &str = "mail-read";
+pub const MAIL_REPLIED: &str = "mail-replied";
+pub const MAIL_SIGNED: &str = "mail-signed";
+pub const MAIL_SIGNED_VERIFIED: &str = "mail-signed-verified";
+pub const MEDIA_PLAYLIST_REPEAT: &str = "media-playlist-repeat";
+pub const MEDIA_PLAYLIST_SHUFFLE: &str = "media-playlist-shuffle";
+pub const NETWORK_ERROR: &str = "network-error";
+pub const NETWORK_IDLE: &str = "network-idle";
+pub const NETWORK_OFFLINE: &str = "network-offline";
+pub const NETWORK_RECEIVE: &str = "network-receive";
+pub const NETWORK_TRANSMIT: &str = "network-transmit";
+pub const NETWORK_TRANSMIT_RECEIVE: &str = "network-transmit-receive";
+pub const PRINTER_ERROR: &str = "printer-error";
+pub const PRINTER_PRINTING: &str = "printer-printing";
+pub const SECURITY_HIGH: &str = "security-high";
+pub const SECURITY_MEDIUM: &str = "security-medium";
+pub const SECURITY_LOW: &str = "security-low";
+pub const SOFTWARE_UPDATE_AVAILABLE: &str = "software-update-available";
+pub const SOFTWARE_UPDATE_URGENT: &str = "software-update-urgent";
+pub const SYNC_ERROR: &str = "sync-error";
+pub const SYNC_SYNCHRONIZING: &str = "sync-synchronizing";
+pub const TASK_DUE: &str = "task-due";
+pub const TASK_PAST_DUE: &str = "task-past-due";
+pub const USER_AVAILABLE: &str = "user-available";
+pub const USER_AWAY: &str = "user-away";
+pub const USER_IDLE: &str = "user-idle";
+pub const USER_OFFLINE: &str = "user-offline";
+pub const USER_TRASH_FULL: &str = "user-trash-full";
+pub const WEATHER_CLEAR: &str = "weather-clear";
+pub const WEATHER_CLEAR_NIGHT: &str = "weather-clear-night";
+pub const WEATHER_FEW_CLOUDS: &str = "weather-few-clouds";
+pub const WEATHER_FEW_CLOUDS_NIGHT: &str = "weather-few-clouds-night";
+pub const WEATHER_FOG: &str = "weather-fog";
+pub const WEATHER_OVERCAST: &str = "weather-overcast";
+pub const WEATHER_SEVERE_ALERT: &str = "weather-severe-alert";
+pub const WEATHER_SHOWERS: &str = "weather-showers";
+pub const WEATHER_SHOWERS_SCATTERED: &str = "weather-showers-scattered";
+pub const WEATHER_SNOW: &str = "weather-snow";
+pub const WEATHER_STORM: &str = "weather-storm";
+
+/// The standard icon names of the Status context.
+pub const STATUS: &[&str] = &[
+    APPOINTMENT_MISSED,
+    APPOINTMENT_SOON,
+    AUDIO_VOLUME_HIGH,
+    AUDIO_VOLUME_LOW,
+    AUDIO_VOLUME_MEDIUM,
+    AUDIO_VOLUME_MUTED,
+    BATTERY_CAUTION,
+    BATTERY_LOW,
+    DIALOG_ERROR,
+    DIALOG_INFORMATION,
+    DIALOG_PASSWORD,
+    DIALOG_QUESTION,
+    DIALOG_WARNING,
+    FOLDER_DRAG_ACCEPT,
+    FOLDER_OPEN,
+    FOLDER_VISITING,
+    IMAGE_LOADING,
+    IMAGE_MISSING,
+    MAIL_ATTACHMENT,
+    MAIL_UNREAD,
+    MAIL_READ,
+    MAIL_REPLIED,
+    MAIL_SIGNED,
+    MAIL_SIGNED_VERIFIED,
+    MEDIA_PLAYLIST_REPEAT,
+    MEDIA_PLAYLIST_SHUFFLE,
+    NETWORK_ERROR,
+    NETWORK_IDLE,
+    NETWORK_OFFLINE,
+    NETWORK_RECEIVE,
+    NETWORK_TRANSMIT,
+    NETWORK_TRANSMIT_RECEIVE,
+    PRINTER_ERROR,
+    PRINTER_PRINTING,
+    SECURITY_HIGH,
+    SECURITY_MEDIUM,
+    SECURITY_LOW,
+    SOFTWARE_UPDATE_AVAILABLE,
+    SOFTWARE_UPDATE_URGENT,
+    SYNC_ERROR,
+    SYNC_SYNCHRONIZING,
+    TASK_DUE,
+    TASK_PAST_DUE,
+    USER_AVAILABLE,
+    USER_AWAY,
+    USER_IDLE,
+    USER_OFFLINE,
+    USER_TRASH_FULL,
+    WEATHER_CLEAR,
+    WEATHER_CLEAR_NIGHT,
+    WEATHER_FEW_CLOUDS,
+    WEATHER_FEW_CLOUDS_NIGHT,
+    WEATHER_FOG,
+    WEATHER_OVERCAST,
+    WEATHER_SEVERE_ALERT,
+    WEATHER_SHOWERS,
+    WEATHER_SHOWERS_SCATTERED,
+    WEATHER_SNOW,
+    WEATHER_STORM,
+];
@@ -0,0 +1,168 @@
+//! # Identifying icon files
+//! The reverse of a lookup: given the path of an icon file, like
+//! /usr/share/icons/Papirus/48x48/apps/firefox.svg, find the theme and the
+//! directory it belongs to, and the name it is looked up by. Useful for
+//! debugging why an icon shows up, and for tools taking inventory of the
+//! files themes installed. Paths are compared as given, symbolic links are
+//! not followed.
+
+use std::path::{Component, Path, PathBuf};
+
+use crate::{IconFormat, IndexTheme, LookupOptions, ThemeDirectory, DEFAULT_SCALE};
+
+/// Where an icon file belongs, see [`identify_icon`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct IconLocation {
+    /// The name the icon is looked up by.
+    pub name: String,
+    pub format: IconFormat,
+    /// Internal name of the theme the file belongs to, `None` for unthemed
+    /// icons.
+    pub theme: Option<String>,
+    /// The directory of the theme the file is in, `None` for unthemed icons
+    /// and for directories the index.theme of the theme does not list, which
+    /// lookups never search.
+    pub directory: Option<ThemeDirectory>,
+    /// The base directory the theme, or the unthemed icon, is in.
+    pub base_directory: PathBuf,
+}
+
+impl IconLocation {
+    /// The nominal size of the icon, as given by its theme directory.
+    pub fn size(&self) -> Option<i16> {
+        self.directory.as_ref().map(|directory| directory.size)
+    }
+
+    /// The scale of the icon, as given by its theme directory.
+    pub fn scale(&self) -> Option<i16> {
+        self.directory
+            .as_ref()
+            .map(|directory| directory.scale.unwrap_or(DEFAULT_SCALE))
+    }
+}
+
+/// The index.theme of the theme, from the first base directory that has one.
+fn read_index(theme: &str, options: &LookupOptions) -> Option<IndexTheme> {
+    let file_system = options.file_system();
+    options.base_directories().find_map(|directory| {
+        let path = directory.join(theme).join("index.theme");
+        if !file_system.is_file(&path) {
+            return None;
+        }
+        IndexTheme::parse(&file_system.read_to_string(&path).ok()?).ok()
+    })
+}
+
+pub(crate) fn identify_icon_with(path: &Path, options: &LookupOptions) -> Option<IconLocation> {
+    let format = IconFormat::from_extension(path.extension()?.to_str()?)?;
+    let name = path.file_stem()?.to_string_lossy().into_owned();
+
+    for base_directory in options.base_directories() {
+        let relative = match path.strip_prefix(base_directory) {
+            Ok(relative) => relative,
+            Err(_) => continue,
+        };
+        let mut components = Vec::new();
+        for component in relative.parent()?.components() {
+            match component {
+                Component::Normal(component) => components.push(component.to_str()?),
+                _ => return None,
+            }
+        }
+        // Files directly in the base directory are unthemed.
+        let (theme, directory) = match components.split_first() {
+            Some((theme, directory)) if !directory.is_empty() => (theme, directory.join("/")),
+            _ => continue,
+        };
+
+        let index = match read_index(theme, options) {
+            Some(index) => index,
+            None => continue,
+        };
+        return Some(IconLocation {
+            name,
+            format,
+            theme: Some((*theme).to_owned()),
+            directory: index
+                .directories
+                .into_iter()
+                .find(|subdir| subdir.name == directory),
+            base_directory: base_directory.to_owned(),
+        });
+    }
+
+    let parent = path.parent()?;
+    options
+        .unthemed_directories()
+        .find(|directory| *directory == parent)
+        .map(|directory| IconLocation {
+            name,
+            format,
+            theme: None,
+            directory: None,
+            base_directory: directory.to_owned(),
+        })
+}
+
+/// Finds the theme and the directory of the theme an icon file is in, along
+/// with the name the icon is looked up by. `None` for files that are not
+/// icons, or that are neither in a theme nor unthemed icons.
+///
+/// ```no_run
+/// let path = std::path::Path::new("/usr/share/icons/hicolor/48x48/apps/firefox.png");
+/// if let Some(location) = icon_finder::identify_icon(path) {
+///     println!("{} from {:?} at {:?}", location.name, location.theme, location.size());
+/// }
+/// ```
+pub fn identify_icon(path: &Path) -> Option<IconLocation> {
+    identify_icon_with(path, &LookupOptions::new(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+    use crate::Context;
+
+    #[test]
+    fn test_identify_icon() {
+        let fixture = Fixture::in_memory(&[ThemeFixture::new("icon-finder-identify")
+            .fixed_directory("48x48/apps", 48)
+            .context(Context::Applications)
+            .fixed_directory("48x48@2/apps", 48)
+            .scale(2)
+            .context(Context::Applications)]);
+        let options = fixture.lookup_options();
+        let identify = |path: &str| identify_icon_with(Path::new(path), &options);
+
+        let location = identify("/icons/icon-finder-identify/48x48@2/apps/firefox.png").unwrap();
+        assert_eq!(location.name, "firefox");
+        assert_eq!(location.format, IconFormat::Png);
+        assert_eq!(location.theme.as_deref(), Some("icon-finder-identify"));
+        assert_eq!((location.size(), location.scale()), (Some(48), Some(2)));
+        assert_eq!(
+            location.directory.unwrap().context,
+            Some(Context::Applications)
+        );
+        assert_eq!(location.base_directory, fixture.base_directory());
+
+        // Directories the theme does not list still belong to the theme.
+        let unlisted = identify("/icons/icon-finder-identify/old/firefox.png").unwrap();
+        assert_eq!(unlisted.theme.as_deref(), Some("icon-finder-identify"));
+        assert_eq!(unlisted.directory, None);
+
+        let unthemed = identify("/usr/share/pixmaps/firefox.png").unwrap();
+        assert_eq!((unthemed.theme.as_deref(), unthemed.size()), (None, None));
+        assert_eq!(unthemed.format, IconFormat::Png);
+
+        assert_eq!(identify("/icons/no-theme/48x48/apps/a.png"), None);
+        assert_eq!(
+            identify("/icons/icon-finder-identify/48x48/apps/README"),
+            None
+        );
+        assert_eq!(
+            identify("/icons/icon-finder-identify/../hicolor/a.png"),
+            None
+        );
+    }
+}
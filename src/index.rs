@@ -0,0 +1,600 @@
+//! # Theme description file
+//! From: https://specifications.freedesktop.org/icon-theme-spec/latest/#file_formats
+//! Every theme has an index.theme file in its directory, describing the
+//! theme. The file follows the syntax of the Desktop Entry Specification: it
+//! consists of groups of key/value pairs. The [Icon Theme] group describes
+//! the theme itself, every directory of the theme has a group of its own.
+//!
+//! Themes in the wild do not always follow the specification, e.g. listing
+//! directories they have no group for. By default parsing recovers from such
+//! problems and reports them as warnings, strict parsing fails instead.
+//!
+//! Index files can be written as well, e.g. by tools generating themes.
+//! Directories with a scale are written under the ScaledDirectories key, so
+//! implementations that do not support scaled directories skip them.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::filesystem::file_system;
+use crate::validate::{check_groups, directory_names, Diagnostic, DiagnosticKind};
+use crate::warning::{self, Warning};
+use crate::{
+    Context, Error, Theme, ThemeDirectory, ThemeDirectoryType, Translations, DEFAULT_SCALE,
+};
+
+pub(crate) const ICON_THEME_GROUP: &str = "Icon Theme";
+
+/// The contents of an index.theme file. Unlike a [`Theme`](crate::Theme),
+/// the themes it inherits from are only known by name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexTheme {
+    /// Short name of the icon theme, used in e.g. lists when selecting themes.
+    pub name: String,
+    /// Longer string describing the theme.
+    pub comment: String,
+    /// The names of the themes that this theme inherits from.
+    pub inherits: Vec<String>,
+    /// The directories of the theme, including the ones listed under the
+    /// ScaledDirectories key.
+    pub directories: Vec<ThemeDirectory>,
+    /// Whether the theme should be hidden from the user.
+    pub hidden: bool,
+    /// Translations of the Name and Comment keys.
+    pub translations: Translations,
+    /// The name of an icon that is representative of the theme.
+    pub example: Option<String>,
+}
+
+/// How parsing deals with problems in index.theme files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Recovers from the problems found in real-world themes where possible,
+    /// like lines that are not key/value pairs, duplicate keys, listed
+    /// directories without a group and sizes that are not numbers.
+    /// Directories that cannot be used, like those without a size, are left
+    /// out. Themes without an [Icon Theme] group or Name key still fail.
+    #[default]
+    Lenient,
+    /// Fails on the first problem.
+    Strict,
+}
+
+impl IndexTheme {
+    /// Reads and parses an index.theme file. The problems recovered from are
+    /// reported to the warning hook, see [`ParseMode::Lenient`].
+    pub fn read(path: &Path) -> Result<IndexTheme, Error> {
+        let (index, warnings) =
+            IndexTheme::parse_with(&file_system().read_to_string(path)?, ParseMode::Lenient)?;
+        warn_parse_problems(path, warnings);
+        Ok(index)
+    }
+
+    /// Parses the contents of an index.theme file, recovering from problems
+    /// where possible, see [`ParseMode::Lenient`].
+    pub fn parse(contents: &str) -> Result<IndexTheme, Error> {
+        IndexTheme::parse_with(contents, ParseMode::Lenient).map(|(index, _)| index)
+    }
+
+    /// Parses the contents of an index.theme file in the given mode,
+    /// returning the problems recovered from along with the theme, see
+    /// [`validate_theme`](crate::validate_theme) for the problems found.
+    /// These are always empty in strict mode, the first problem fails the
+    /// parse.
+    pub fn parse_with(
+        contents: &str,
+        mode: ParseMode,
+    ) -> Result<(IndexTheme, Vec<Diagnostic>), Error> {
+        let (groups, mut diagnostics) = parse_groups_with(contents);
+        diagnostics.extend(check_groups(&groups));
+        if mode == ParseMode::Strict && !diagnostics.is_empty() {
+            return Err(diagnostics.remove(0).into());
+        }
+
+        let theme_group = groups
+            .iter()
+            .find(|group| group.name == ICON_THEME_GROUP)
+            .ok_or_else(|| Diagnostic {
+                line: 1,
+                kind: DiagnosticKind::MissingGroup(ICON_THEME_GROUP.to_owned()),
+            })?;
+
+        let directories = directory_names(theme_group)
+            .filter_map(|name| groups.iter().find(|group| group.name == name))
+            .filter_map(parse_directory)
+            .collect();
+
+        let name = theme_group.required("Name")?;
+
+        let index = IndexTheme {
+            name: name.to_owned(),
+            comment: theme_group.get("Comment").unwrap_or_default().to_owned(),
+            inherits: theme_group.list("Inherits").map(str::to_owned).collect(),
+            directories,
+            hidden: theme_group.boolean("Hidden").unwrap_or(false),
+            translations: Translations {
+                name: name.to_owned(),
+                names: theme_group.localized("Name"),
+                comments: theme_group.localized("Comment"),
+            },
+            example: theme_group.get("Example").map(str::to_owned),
+        };
+        Ok((index, diagnostics))
+    }
+}
+
+/// Reports the problems recovered from while parsing the index.theme file to
+/// the warning hook.
+pub(crate) fn warn_parse_problems(path: &Path, problems: Vec<Diagnostic>) {
+    for problem in problems {
+        warning::warn(Warning::InvalidIndexTheme {
+            path: path.to_owned(),
+            line: problem.line,
+            message: problem.kind.to_string(),
+        });
+    }
+}
+
+impl IndexTheme {
+    /// Writes the index.theme file, with the contents given by the `Display`
+    /// implementation.
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+/// The index.theme of the theme, with the names of the themes it inherits
+/// from.
+impl From<&Theme> for IndexTheme {
+    fn from(theme: &Theme) -> IndexTheme {
+        IndexTheme {
+            name: theme.translations.name.clone(),
+            comment: theme.comment.clone(),
+            inherits: theme
+                .inherits
+                .iter()
+                .map(|parent| parent.name.clone())
+                .collect(),
+            directories: theme.directories.clone(),
+            hidden: theme.hidden,
+            translations: theme.translations.clone(),
+            example: theme.example.clone(),
+        }
+    }
+}
+
+/// Values cannot span lines, line breaks are written as spaces.
+fn value(value: &str) -> String {
+    value.replace(['\n', '\r'], " ")
+}
+
+/// The contents of the index.theme file, which parse back into the same
+/// `IndexTheme`, except that directories with a scale come last.
+impl fmt::Display for IndexTheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[{}]", ICON_THEME_GROUP)?;
+        writeln!(f, "Name={}", value(&self.name))?;
+        for (locale, name) in &self.translations.names {
+            writeln!(f, "Name[{}]={}", locale, value(name))?;
+        }
+        writeln!(f, "Comment={}", value(&self.comment))?;
+        for (locale, comment) in &self.translations.comments {
+            writeln!(f, "Comment[{}]={}", locale, value(comment))?;
+        }
+        if !self.inherits.is_empty() {
+            writeln!(f, "Inherits={}", self.inherits.join(","))?;
+        }
+
+        let (scaled, unscaled): (Vec<&ThemeDirectory>, Vec<&ThemeDirectory>) = self
+            .directories
+            .iter()
+            .partition(|directory| directory.scale.unwrap_or(DEFAULT_SCALE) > DEFAULT_SCALE);
+        let names = |directories: &[&ThemeDirectory]| {
+            directories
+                .iter()
+                .map(|directory| directory.name.as_str())
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        writeln!(f, "Directories={}", names(&unscaled))?;
+        if !scaled.is_empty() {
+            writeln!(f, "ScaledDirectories={}", names(&scaled))?;
+        }
+
+        if self.hidden {
+            writeln!(f, "Hidden=true")?;
+        }
+        if let Some(example) = &self.example {
+            writeln!(f, "Example={}", value(example))?;
+        }
+
+        for directory in unscaled.iter().chain(&scaled) {
+            write_directory(f, directory)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_directory(f: &mut fmt::Formatter<'_>, directory: &ThemeDirectory) -> fmt::Result {
+    let r#type = match directory.r#type {
+        ThemeDirectoryType::Fixed => "Fixed",
+        ThemeDirectoryType::Scalable => "Scalable",
+        ThemeDirectoryType::Threshold => "Threshold",
+    };
+    writeln!(f, "\n[{}]", directory.name)?;
+    writeln!(f, "Size={}", directory.size)?;
+
+    let keys = [
+        ("Scale", directory.scale),
+        ("MinSize", directory.min_size),
+        ("MaxSize", directory.max_size),
+        ("Threshold", directory.threshold),
+    ];
+    for (key, number) in keys {
+        if let Some(number) = number {
+            writeln!(f, "{}={}", key, number)?;
+        }
+    }
+    if let Some(context) = &directory.context {
+        writeln!(f, "Context={}", value(context.as_str()))?;
+    }
+    writeln!(f, "Type={}", r#type)
+}
+
+/// The directory of the group, `None` if it cannot be used. Values that
+/// cannot be used are left out, see [`check_groups`].
+fn parse_directory(group: &Group) -> Option<ThemeDirectory> {
+    let r#type = match group.get("Type") {
+        Some("Fixed") => ThemeDirectoryType::Fixed,
+        Some("Scalable") => ThemeDirectoryType::Scalable,
+        // Threshold is the default type.
+        _ => ThemeDirectoryType::Threshold,
+    };
+
+    Some(ThemeDirectory {
+        name: group.name.to_owned(),
+        size: group.number("Size")?,
+        scale: group
+            .number("Scale")
+            .or_else(|| scale_from_name(group.name)),
+        context: group.get("Context").map(Context::from),
+        r#type,
+        max_size: group.number("MaxSize"),
+        min_size: group.number("MinSize"),
+        threshold: group.number("Threshold"),
+    })
+}
+
+/// Directories for scaled icons are conventionally named after their scale,
+/// like "48x48@2x/apps" or "apps/48@2". Not every theme sets the Scale key
+/// for these directories, so the scale is derived from the name instead.
+fn scale_from_name(name: &str) -> Option<i16> {
+    name.split('/').find_map(|component| {
+        let (_, scale) = component.split_once('@')?;
+        scale.trim_end_matches('x').parse().ok()
+    })
+}
+
+/// A group of key/value pairs, starting with a `[Group Name]` header.
+pub(crate) struct Group<'a> {
+    pub(crate) name: &'a str,
+    pub(crate) line: usize,
+    entries: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Group<'a> {
+    pub(crate) fn get(&self, key: &str) -> Option<&'a str> {
+        self.entries
+            .iter()
+            .find(|(entry_key, _)| *entry_key == key)
+            .map(|(_, value)| *value)
+    }
+
+    /// Translations of the key, stored under keys like `Name[de]`.
+    pub(crate) fn localized(&self, key: &str) -> Vec<(String, String)> {
+        self.entries
+            .iter()
+            .filter_map(|(entry_key, value)| {
+                let locale = entry_key.strip_prefix(key)?.strip_prefix('[')?;
+                Some((locale.strip_suffix(']')?.to_owned(), (*value).to_owned()))
+            })
+            .collect()
+    }
+
+    fn required(&self, key: &'static str) -> Result<&'a str, Diagnostic> {
+        self.get(key).ok_or_else(|| Diagnostic {
+            line: self.line,
+            kind: DiagnosticKind::MissingKey {
+                group: self.name.to_owned(),
+                key,
+            },
+        })
+    }
+
+    /// Values of list keys are separated by commas.
+    pub(crate) fn list(&self, key: &str) -> impl Iterator<Item = &'a str> {
+        self.get(key)
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// The keys set more than once in the group.
+    pub(crate) fn duplicate_keys(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(move |(index, (key, _))| {
+                self.entries[..*index].iter().any(|(other, _)| other == key)
+            })
+            .map(|(_, (key, _))| *key)
+    }
+
+    /// The value of a boolean key, `None` if it is not set or neither true
+    /// nor false.
+    fn boolean(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// The value of a number key, `None` if it is not set or not a number.
+    fn number(&self, key: &str) -> Option<i16> {
+        self.get(key)?.parse().ok()
+    }
+}
+
+/// Parses the groups of a key file, failing on lines that are neither group
+/// headers nor key/value pairs.
+pub(crate) fn parse_groups(contents: &str) -> Result<Vec<Group<'_>>, Error> {
+    let (groups, diagnostics) = parse_groups_with(contents);
+    match diagnostics.into_iter().next() {
+        Some(diagnostic) => Err(diagnostic.into()),
+        None => Ok(groups),
+    }
+}
+
+/// Parses the groups of a key file, skipping the lines that are neither
+/// group headers nor key/value pairs.
+fn parse_groups_with(contents: &str) -> (Vec<Group<'_>>, Vec<Diagnostic>) {
+    let mut groups: Vec<Group> = Vec::new();
+    let mut diagnostics = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            groups.push(Group {
+                name: &line[1..line.len() - 1],
+                line: index + 1,
+                entries: Vec::new(),
+            });
+            continue;
+        }
+
+        let entry = line
+            .split_once('=')
+            .map(|(key, value)| (key.trim(), value.trim()));
+
+        match (groups.last_mut(), entry) {
+            (Some(group), Some(entry)) => group.entries.push(entry),
+            _ => diagnostics.push(Diagnostic {
+                line: index + 1,
+                kind: DiagnosticKind::UnexpectedLine(line.to_owned()),
+            }),
+        }
+    }
+
+    (groups, diagnostics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INDEX_THEME: &str = "
+[Icon Theme]
+Name=Test
+Name[de]=Test-Thema
+Comment=Test theme
+Comment[nl]=Testthema
+Example=folder
+Inherits=breeze,hicolor
+Directories=48x48/apps,scalable/apps
+ScaledDirectories=48x48@2x/apps,apps/48@2
+
+# Regular directories
+[48x48/apps]
+Size=48
+Context=Applications
+Type=Fixed
+
+[scalable/apps]
+Size=48
+Context=Applications
+Type=Scalable
+MinSize=16
+MaxSize=512
+
+[48x48@2x/apps]
+Size=48
+Scale=2
+Context=Applications
+Type=Fixed
+
+[apps/48@2]
+Size=48
+Context=Applications
+";
+
+    #[test]
+    fn test_parse_index_theme() {
+        let theme = IndexTheme::parse(INDEX_THEME).unwrap();
+
+        assert_eq!(theme.name, "Test");
+        assert_eq!(theme.comment, "Test theme");
+        assert_eq!(theme.inherits, vec!["breeze", "hicolor"]);
+        assert!(!theme.hidden);
+        assert_eq!(theme.example.as_deref(), Some("folder"));
+        assert_eq!(
+            theme.translations.names,
+            vec![("de".to_owned(), "Test-Thema".to_owned())]
+        );
+        assert_eq!(
+            theme.translations.comments,
+            vec![("nl".to_owned(), "Testthema".to_owned())]
+        );
+
+        let directories: Vec<(&str, Option<i16>)> = theme
+            .directories
+            .iter()
+            .map(|directory| (directory.name.as_str(), directory.scale))
+            .collect();
+        assert_eq!(
+            directories,
+            vec![
+                ("48x48/apps", None),
+                ("scalable/apps", None),
+                ("48x48@2x/apps", Some(2)),
+                ("apps/48@2", Some(2)),
+            ]
+        );
+
+        let scalable = &theme.directories[1];
+        assert_eq!(scalable.r#type, ThemeDirectoryType::Scalable);
+        assert_eq!(scalable.min_size, Some(16));
+        assert_eq!(scalable.max_size, Some(512));
+        assert_eq!(theme.directories[3].r#type, ThemeDirectoryType::Threshold);
+    }
+
+    #[test]
+    fn test_parse_index_theme_errors() {
+        let strict = |contents| IndexTheme::parse_with(contents, ParseMode::Strict);
+        assert!(strict("Name=Test").is_err());
+        assert!(strict("[Icon Theme]\nName=Test\nDirectories=apps\n").is_err());
+        assert!(strict("[Icon Theme]\nName=Test\nDirectories=apps\n[apps]\nSize=large\n").is_err());
+        assert!(strict("[Icon Theme]\nName=Test\nHidden=yes\n").is_err());
+        assert!(strict("[Icon Theme]\nName=Test\nName=Other\n").is_err());
+
+        // Not even lenient parsing recovers from these.
+        assert!(IndexTheme::parse("Name=Test").is_err());
+        assert!(IndexTheme::parse("[Icon Theme]\nComment=Test\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_index_theme_leniently() {
+        let contents = "stray line\n\
+                        [Icon Theme]\nName=Test\nName=Other\nHidden=yes\n\
+                        Directories=16x16/apps,missing,large,24x24/apps,scalable/apps\n\n\
+                        [16x16/apps]\nSize=16\nType=Fixed\n\n\
+                        [large]\nSize=large\n\n\
+                        [24x24/apps]\nSize=24\nType=Stretched\nThreshold=a few\n\n\
+                        [scalable/apps]\nType=Scalable\n";
+        let (theme, warnings) = IndexTheme::parse_with(contents, ParseMode::Lenient).unwrap();
+        assert_eq!(theme.name, "Test");
+        assert!(!theme.hidden);
+        let directories: Vec<(&str, i16, ThemeDirectoryType, Option<i16>)> = theme
+            .directories
+            .iter()
+            .map(|directory| {
+                (
+                    directory.name.as_str(),
+                    directory.size,
+                    directory.r#type,
+                    directory.threshold,
+                )
+            })
+            .collect();
+        assert_eq!(
+            directories,
+            [
+                ("16x16/apps", 16, ThemeDirectoryType::Fixed, None),
+                ("24x24/apps", 24, ThemeDirectoryType::Threshold, None),
+            ]
+        );
+
+        let warnings: Vec<String> = warnings.iter().map(Diagnostic::to_string).collect();
+        assert_eq!(
+            warnings,
+            [
+                "line 1: unexpected line: stray line",
+                "line 2: duplicate Name key in [Icon Theme]",
+                "line 2: Hidden in [Icon Theme] is not a boolean: yes",
+                "line 2: missing [missing] group",
+                "line 12: Size in [large] is not a number: large",
+                "line 15: Threshold in [24x24/apps] is not a number: a few",
+                "line 15: unknown directory type Stretched in [24x24/apps]",
+                "line 20: missing Size key in [scalable/apps]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_index_theme() {
+        let theme = IndexTheme::parse(INDEX_THEME).unwrap();
+        assert_eq!(IndexTheme::parse(&theme.to_string()).unwrap(), theme);
+
+        let generated = IndexTheme {
+            name: "Generated".to_owned(),
+            comment: "Two\nlines".to_owned(),
+            inherits: Vec::new(),
+            directories: vec![
+                ThemeDirectory {
+                    name: "16x16@2/apps".to_owned(),
+                    size: 16,
+                    scale: Some(2),
+                    context: Some(Context::Other("Custom".to_owned())),
+                    r#type: ThemeDirectoryType::Fixed,
+                    max_size: None,
+                    min_size: None,
+                    threshold: None,
+                },
+                ThemeDirectory {
+                    name: "24x24".to_owned(),
+                    size: 24,
+                    scale: None,
+                    context: None,
+                    r#type: ThemeDirectoryType::Threshold,
+                    max_size: None,
+                    min_size: None,
+                    threshold: Some(4),
+                },
+            ],
+            hidden: true,
+            translations: Translations {
+                name: "Generated".to_owned(),
+                names: Vec::new(),
+                comments: Vec::new(),
+            },
+            example: None,
+        };
+        let contents = generated.to_string();
+        assert!(contents.contains("Comment=Two lines\nDirectories=24x24\n"));
+        assert!(contents.contains("ScaledDirectories=16x16@2/apps\n"));
+
+        let parsed = IndexTheme::parse(&contents).unwrap();
+        assert!(parsed.hidden);
+        assert_eq!(
+            parsed.directories,
+            [
+                generated.directories[1].clone(),
+                generated.directories[0].clone()
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_hidden() {
+        let theme = IndexTheme::parse("[Icon Theme]\nName=Test\nHidden=true\n").unwrap();
+        assert!(theme.hidden);
+    }
+}
@@ -0,0 +1,173 @@
+//! # Icon name input
+//! Icon names come from desktop files, notifications and the like, which do
+//! not always contain a plain icon name. Some specify the icon as the path of
+//! an icon file instead, either as an absolute path or as a file:// URI.
+//! Legacy applications also tend to include the extension in the name.
+
+use std::path::PathBuf;
+
+use crate::{IconFormat, LookupFlags, LookupOptions, LookupResult, MatchKind};
+
+/// The path of the icon file, if the icon is given as an absolute path or a
+/// local file:// URI instead of a name.
+pub(crate) fn file_path(icon: &str) -> Option<PathBuf> {
+    if icon.starts_with('/') {
+        return Some(PathBuf::from(icon));
+    }
+
+    let path = icon.strip_prefix("file://")?;
+    // Only local files can be used, the host is either empty or localhost.
+    let path = path.strip_prefix("localhost").unwrap_or(path);
+    if !path.starts_with('/') {
+        return None;
+    }
+
+    percent_decode(path)
+}
+
+/// The icon file at the given path, if it exists and is in one of the allowed
+/// formats.
+pub(crate) fn file_result(path: PathBuf, options: &LookupOptions) -> Option<LookupResult> {
+    let format = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .and_then(IconFormat::from_extension)
+        .filter(|format| options.allowed_formats().any(|allowed| allowed == *format))?;
+
+    if !options.file_system().is_file(&path) {
+        return None;
+    }
+
+    Some(LookupResult {
+        path,
+        theme: None,
+        directory: None,
+        match_kind: MatchKind::File,
+        format,
+    })
+}
+
+/// The icon name to look up, without the extension of a known format if the
+/// lookup strips extensions. Extensions of formats disabled through cargo
+/// features are stripped as well, the icon may exist in another format.
+pub(crate) fn icon_name<'a>(icon: &'a str, options: &LookupOptions) -> &'a str {
+    if !options.flags.contains(LookupFlags::STRIP_EXTENSION) {
+        return icon;
+    }
+
+    [IconFormat::Png, IconFormat::Svg, IconFormat::Xpm]
+        .iter()
+        .find_map(|format| {
+            icon.strip_suffix(format.extension())
+                .and_then(|name| name.strip_suffix('.'))
+                .filter(|name| !name.is_empty())
+        })
+        .unwrap_or(icon)
+}
+
+/// Decodes the %-escaped bytes of a URI path. `None` if an escape is
+/// malformed.
+fn percent_decode(path: &str) -> Option<PathBuf> {
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut remaining = path.as_bytes();
+
+    while let Some((&byte, rest)) = remaining.split_first() {
+        if byte == b'%' {
+            let hex = std::str::from_utf8(rest.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            remaining = &rest[2..];
+        } else {
+            bytes.push(byte);
+            remaining = rest;
+        }
+    }
+
+    path_from_bytes(bytes)
+}
+
+/// The path of the given bytes. Paths are arbitrary bytes on Unix, elsewhere
+/// they have to be valid UTF-8.
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt;
+
+    Some(PathBuf::from(OsString::from_vec(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> Option<PathBuf> {
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{FileSystem, MemoryFileSystem};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_file_path() {
+        assert_eq!(
+            file_path("/usr/share/pixmaps/debian-logo.png"),
+            Some(PathBuf::from("/usr/share/pixmaps/debian-logo.png"))
+        );
+        assert_eq!(
+            file_path("file:///opt/My%20App/icon.svg"),
+            Some(PathBuf::from("/opt/My App/icon.svg"))
+        );
+        assert_eq!(
+            file_path("file://localhost/opt/icon.png"),
+            Some(PathBuf::from("/opt/icon.png"))
+        );
+        assert_eq!(file_path("file://example.com/opt/icon.png"), None);
+        assert_eq!(file_path("file:///opt/icon%2.png"), None);
+        assert_eq!(file_path("firefox"), None);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+
+            let path = file_path("file:///opt/caf%E9.png").unwrap();
+            assert_eq!(path.as_os_str().as_bytes(), b"/opt/caf\xe9.png");
+        }
+    }
+
+    #[test]
+    fn test_icon_name() {
+        let options = LookupOptions::new(48);
+        assert_eq!(icon_name("firefox.png", &options), "firefox.png");
+
+        let options = options.flags(LookupFlags::STRIP_EXTENSION);
+        assert_eq!(icon_name("firefox.png", &options), "firefox");
+        assert_eq!(
+            icon_name("org.gnome.Nautilus", &options),
+            "org.gnome.Nautilus"
+        );
+        assert_eq!(icon_name(".png", &options), ".png");
+    }
+
+    #[test]
+    fn test_file_result() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/opt/app/icon.png", "");
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+
+        let options = LookupOptions::new(48).with_file_system(Some(&fs));
+        let result = file_result(PathBuf::from("/opt/app/icon.png"), &options).unwrap();
+        assert_eq!(result.match_kind, MatchKind::File);
+        assert_eq!(result.format, IconFormat::Png);
+
+        assert_eq!(
+            file_result(PathBuf::from("/opt/app/other.png"), &options),
+            None
+        );
+        assert_eq!(file_result(PathBuf::from("/opt/app"), &options), None);
+
+        let options = options.formats(&[IconFormat::Svg]);
+        assert_eq!(
+            file_result(PathBuf::from("/opt/app/icon.png"), &options),
+            None
+        );
+    }
+}
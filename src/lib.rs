@@ -1,34 +1,495 @@
-/// Icon Theme Specification
-/// ========================
-/// Find icons for applications according to the freedesktop.org specifications
+//! Icon Theme Specification
+//! ========================
+//! Find icons for applications according to the freedesktop.org specifications
 
-use std::path::Path;
+use std::collections::{BTreeMap, HashMap};
+use std::iter;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
 
+use crate::filesystem::file_system;
+
+mod aliases;
+#[cfg(all(feature = "tokio", not(target_family = "wasm")))]
+mod asynchronous;
+#[cfg(feature = "image")]
+mod atlas;
+mod cache;
+mod cancel;
+mod candidates;
+mod compare;
+mod coverage;
+#[cfg(feature = "daemon")]
+mod daemon;
+mod desktop;
+mod duplicates;
+mod emblem;
+mod enumerate;
+mod error;
+mod export;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod files;
+mod filesystem;
+mod finder;
+mod gallery;
+mod gaps;
+mod handle;
+mod icon_cache;
+mod icon_data;
+pub mod icon_names;
+mod identify;
+mod index;
+mod input;
+mod listing;
+mod load;
+mod locale;
+mod mime;
+mod options;
+mod paths;
+#[cfg(feature = "image")]
+mod pixels;
+mod profile;
+#[cfg(feature = "python")]
+mod python;
+#[cfg(not(target_family = "wasm"))]
+mod refresher;
+#[cfg(feature = "render")]
+mod render;
+#[cfg(feature = "render")]
+mod render_cache;
+mod result;
+mod sandbox;
+mod search;
+mod settings;
+mod stats;
+mod status;
+mod symbolic;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod validate;
+mod walk;
+mod warning;
+#[cfg(not(target_family = "wasm"))]
+mod watch;
+#[cfg(all(feature = "image", feature = "xpm"))]
+mod xpm;
+
+pub use aliases::IconAliases;
+#[cfg(all(feature = "tokio", not(target_family = "wasm")))]
+pub use asynchronous::{find_icon_async, load_theme_async, EventStream};
+#[cfg(feature = "image")]
+pub use atlas::IconAtlas;
+pub use cache::{CacheStats, Reloaded, POLL_INTERVAL};
+pub use cancel::CancellationToken;
+pub use candidates::Candidates;
+pub use compare::{compare_themes, IconDifference, ThemeComparison};
+pub use coverage::{check_coverage, CoverageReport, MissingIcon};
+#[cfg(feature = "daemon")]
+pub use daemon::{default_socket_path, serve, Client};
+pub use desktop::{find_app_icon, find_desktop_entry, DesktopEntry};
+pub use duplicates::{find_duplicate_icons, DuplicateIcons};
+pub use emblem::{emblem_size, find_emblemed_icon, Emblem, EmblemPosition, EmblemedIcon};
+pub use error::Error;
+pub use export::{ExportMethod, ExportReport};
+pub use files::{find_icon_for_path, path_icon_names};
+pub use filesystem::{set_file_system, FileSystem, MemoryFileSystem, StdFileSystem};
+pub use finder::{global, IconFinder, IconFinderBuilder};
+pub use gallery::theme_gallery;
+pub use gaps::{check_size_gaps, SizeGap};
+pub use handle::IconHandle;
+pub use icon_cache::write_icon_cache;
+pub use icon_data::{IconData, Rectangle};
+pub use identify::{identify_icon, IconLocation};
+pub use index::{IndexTheme, ParseMode};
+pub use load::{IconPixels, LoadedIcon};
+pub use locale::Translations;
+pub use mime::{find_mime_type_icon, guess_mime_type, mime_type_icon_names};
+pub use options::{
+    ClosestMatchPolicy, DistanceStrategy, LookupFlags, LookupOptions, LookupProfile,
+};
+pub use paths::base_directories;
+pub use profile::{Phase, PhaseTimings, Profile};
+#[cfg(not(target_family = "wasm"))]
+pub use refresher::Refresher;
+#[cfg(feature = "render")]
+pub use render_cache::default_render_cache_directory;
+pub use result::{LookupResult, MatchKind};
+pub use search::{complete_icon_names, search_icons};
+pub use settings::{preferred_color_scheme, selected_icon_theme, ColorScheme};
+pub use stats::ThemeStats;
+pub use status::{
+    battery_icon_name, battery_icon_names, network_signal_icon_name, network_signal_icon_names,
+};
+pub use symbolic::SymbolicPalette;
+pub use validate::{validate_theme, Diagnostic, DiagnosticKind};
+pub use walk::{walk_theme, IconFile};
+pub use warning::{set_warning_hook, Warning};
+#[cfg(not(target_family = "wasm"))]
+pub use watch::{Subscription, ThemeEvent};
+
+/// Returns the name of the icon theme selected by the user, hicolor if no
+/// theme is selected.
+#[deprecated(note = "use selected_icon_theme, which tells whether a theme is selected")]
 pub fn get_user_selected_theme() -> String {
-    // TODO: Actually fetch the theme
-    return "/usr/share/themes/Adwaita/index.theme".to_string();
+    selected_icon_theme().unwrap_or_else(|| FALLBACK_THEME.to_owned())
 }
 
 /// # Icon Theme
 /// An icon theme is a named set of icons. It is used to map from an iconname
 /// and size to a file. Themes may inherit from other themes as a way to extend
-/// them.
+/// them. Parents are shared, so a theme inherited through several paths, like
+/// hicolor, is only kept in memory once.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Theme {
     pub name: String,
     pub comment: String,
-    pub inherits: Vec<Theme>,
+    pub inherits: Vec<Arc<Theme>>,
     pub directories: Vec<ThemeDirectory>,
+    /// Whether the theme should be hidden from the user, e.g. in lists of
+    /// themes to choose from. Used by themes that only exist to be inherited
+    /// from.
+    pub hidden: bool,
+    pub translations: Translations,
+    /// The name of an icon that is representative of the theme.
+    pub example: Option<String>,
+}
+
+impl Theme {
+    /// The human readable name of the theme, translated for the given locale,
+    /// or for the locale set in the environment if `None`. Falls back to the
+    /// untranslated name, and to the internal name for themes without one.
+    pub fn display_name(&self, locale: Option<&str>) -> &str {
+        let name = localized(&self.translations.names, locale).unwrap_or(&self.translations.name);
+
+        if name.is_empty() {
+            &self.name
+        } else {
+            name
+        }
+    }
+
+    /// The example icon of the theme at the given size, looked up like any
+    /// other icon, e.g. for showing a preview of every theme in a theme
+    /// picker.
+    pub fn example_icon(&self, size: i16) -> Option<PathBuf> {
+        let example = self.example.as_ref()?;
+
+        find_icon_in(example, &LookupOptions::new(size), self, &[]).map(|result| result.path)
+    }
+
+    /// The names of the icons provided by the theme and the themes it
+    /// inherits from, in alphabetical order, e.g. for an icon chooser that
+    /// shows every icon of the theme. The icons are listed on the file
+    /// system of the process, see [`icons_from`](Theme::icons_from) for
+    /// themes loaded from another file system.
+    pub fn icons(&self) -> Vec<String> {
+        enumerate::theme_icons(&LookupOptions::new(0), self)
+    }
+
+    /// The icons of the theme like [`icons`](Theme::icons), listed on the
+    /// given file system, e.g. the one the theme was loaded from with
+    /// [`load_from`](Theme::load_from).
+    pub fn icons_from(&self, file_system: &Arc<dyn FileSystem>) -> Vec<String> {
+        enumerate::theme_icons(
+            &LookupOptions::new(0).with_file_system(Some(file_system)),
+            self,
+        )
+    }
+
+    /// The names of the icons the theme and the themes it inherits from
+    /// provide in the given context, in alphabetical order, e.g. to check
+    /// which MIME types a theme has icons for.
+    pub fn icons_in_context(&self, context: Context) -> Vec<String> {
+        enumerate::theme_icons(&LookupOptions::new(0).context(context), self)
+    }
+
+    /// The icons provided by the theme and the themes it inherits from, with
+    /// the sizes each is available at, see [`available_sizes`].
+    pub fn icon_sizes(&self) -> BTreeMap<String, Vec<IconSize>> {
+        enumerate::theme_icon_sizes(&LookupOptions::new(0), self)
+    }
+
+    /// The comment describing the theme, translated like
+    /// [`display_name`](Theme::display_name).
+    pub fn comment(&self, locale: Option<&str>) -> &str {
+        localized(&self.translations.comments, locale).unwrap_or(&self.comment)
+    }
+
+    /// The directories the theme is stored in, in order of precedence. A
+    /// theme can be spread over several base directories, the index.theme
+    /// file of the first one is the one that is used. The directories are
+    /// looked for on the file system of the process, see
+    /// [`locations_from`](Theme::locations_from) for themes loaded from
+    /// another file system.
+    pub fn locations(&self) -> Vec<PathBuf> {
+        self.locations_from(&**file_system())
+    }
+
+    /// The directories the theme is stored in like
+    /// [`locations`](Theme::locations), on the given file system, e.g. the one
+    /// the theme was loaded from with [`load_from`](Theme::load_from).
+    pub fn locations_from(&self, file_system: &dyn FileSystem) -> Vec<PathBuf> {
+        base_directories()
+            .iter()
+            .map(|directory| directory.join(&self.name))
+            .filter(|path| file_system.is_dir(path))
+            .collect()
+    }
+
+    /// How the theme is composed: the number of icons per context, size and
+    /// format, and the space its files take. Only the directories of the
+    /// theme itself are counted, not those of the themes it inherits from.
+    pub fn stats(&self) -> ThemeStats {
+        stats::theme_stats_with(self, &LookupOptions::new(0))
+    }
+
+    /// The theme followed by the themes it inherits from, in the order icons
+    /// are looked up in them, e.g. to show where icons come from. Themes
+    /// inherited through several paths are only yielded the first time.
+    /// Ends with hicolor, unless the theme inherits from it before another
+    /// theme.
+    pub fn ancestry(&self) -> impl Iterator<Item = &Theme> {
+        self.ancestry_with(&[]).into_iter()
+    }
+
+    /// The ancestry of the theme, searching the given fallback themes and
+    /// the themes they inherit from before hicolor, like a lookup does.
+    pub(crate) fn ancestry_with<'a>(&'a self, fallbacks: &'a [Arc<Theme>]) -> Vec<&'a Theme> {
+        let mut chain = self.inheritance_chain();
+        for fallback in fallback_themes(self, fallbacks) {
+            visit_ancestors(fallback, &mut chain);
+        }
+        chain
+    }
+
+    /// The theme followed by the themes it inherits from, without the
+    /// fallback themes, every theme only once.
+    pub(crate) fn inheritance_chain(&self) -> Vec<&Theme> {
+        let mut chain = Vec::new();
+        visit_ancestors(self, &mut chain);
+        chain
+    }
+}
+
+/// Adds the theme and the themes it inherits from to the chain, unless
+/// already in it.
+fn visit_ancestors<'a>(theme: &'a Theme, chain: &mut Vec<&'a Theme>) {
+    if chain.iter().any(|visited| visited.name == theme.name) {
+        return;
+    }
+    chain.push(theme);
+    for parent in &theme.inherits {
+        visit_ancestors(parent, chain);
+    }
+}
+
+fn localized<'a>(translations: &'a [(String, String)], locale: Option<&str>) -> Option<&'a str> {
+    match locale {
+        Some(locale) => locale::translation(translations, locale),
+        None => locale::translation(translations, &locale::current_locale()?),
+    }
+}
+
+/// Themes loaded while loading an inheritance chain, by name.
+type LoadedThemes = Mutex<HashMap<String, Arc<Theme>>>;
+
+/// Reads the index.theme file of the theme with the given name.
+type ReadIndex<'a> = &'a (dyn Fn(&str) -> Result<IndexTheme, Error> + Sync);
+
+impl Theme {
+    /// Loads the theme with the given name from the first base directory
+    /// containing it, together with the themes it inherits from. Icons are
+    /// still looked up in the theme directories of every base directory, so
+    /// a user can override single icons of a system theme. Parents that
+    /// are not installed are left out, as are parents that would make the
+    /// theme inherit from itself.
+    pub fn load(name: &str) -> Result<Theme, Error> {
+        Theme::load_from(name, &**file_system())
+    }
+
+    /// Loads a theme like [`load`](Theme::load), from the given file system
+    /// instead of the file system of the process.
+    pub fn load_from(name: &str, file_system: &dyn FileSystem) -> Result<Theme, Error> {
+        Theme::load_in(name, file_system, base_directories(), ParseMode::Lenient)
+    }
+
+    /// Loads a theme like [`load_from`](Theme::load_from), parsing the
+    /// index.theme files of the theme and its parents in the given mode.
+    pub fn load_with_mode(
+        name: &str,
+        file_system: &dyn FileSystem,
+        mode: ParseMode,
+    ) -> Result<Theme, Error> {
+        Theme::load_in(name, file_system, base_directories(), mode)
+    }
+
+    /// Loads a theme like [`load_from`](Theme::load_from), searching the
+    /// given base directories only.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip(file_system, base_directories))
+    )]
+    pub(crate) fn load_in(
+        name: &str,
+        file_system: &dyn FileSystem,
+        base_directories: &[PathBuf],
+        mode: ParseMode,
+    ) -> Result<Theme, Error> {
+        let read_index = |name: &str| {
+            let path = find_index_theme(name, file_system, base_directories)?;
+            let (index, problems) =
+                IndexTheme::parse_with(&file_system.read_to_string(&path)?, mode)?;
+            index::warn_parse_problems(&path, problems);
+            Ok(index)
+        };
+
+        Theme::load_with(name, &read_index, &LoadedThemes::default(), &[])
+    }
+
+    /// `ancestors` are the themes inheriting from this theme, which are still
+    /// being loaded.
+    fn load_with(
+        name: &str,
+        read_index: ReadIndex,
+        loaded: &LoadedThemes,
+        ancestors: &[&str],
+    ) -> Result<Theme, Error> {
+        let index = read_index(name)?;
+
+        let mut ancestors = ancestors.to_vec();
+        ancestors.push(name);
+        let ancestors = &ancestors;
+
+        // Themes in the wild occasionally inherit from each other, following
+        // such a cycle would never end.
+        let parent_names = index
+            .inherits
+            .iter()
+            .filter(|parent| !ancestors.contains(&parent.as_str()));
+
+        #[cfg(feature = "parallel")]
+        let parents: Vec<_> = {
+            use rayon::prelude::*;
+
+            parent_names
+                .collect::<Vec<_>>()
+                .into_par_iter()
+                .map(|parent| Theme::load_parent(parent, read_index, loaded, ancestors))
+                .collect()
+        };
+        #[cfg(not(feature = "parallel"))]
+        let parents =
+            parent_names.map(|parent| Theme::load_parent(parent, read_index, loaded, ancestors));
+
+        let mut inherits = Vec::new();
+        for parent in parents {
+            match parent {
+                Ok(theme) => inherits.push(theme),
+                Err(Error::ThemeNotFound(_parent)) => {
+                    #[cfg(feature = "log")]
+                    log::debug!("{} inherits from {}, which is not installed", name, _parent);
+                    continue;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(Theme {
+            name: name.to_owned(),
+            comment: index.comment,
+            inherits,
+            directories: index.directories,
+            hidden: index.hidden,
+            translations: index.translations,
+            example: index.example,
+        })
+    }
+
+    fn load_parent(
+        name: &str,
+        read_index: ReadIndex,
+        loaded: &LoadedThemes,
+        ancestors: &[&str],
+    ) -> Result<Arc<Theme>, Error> {
+        let lock = || loaded.lock().unwrap_or_else(|error| error.into_inner());
+
+        if let Some(theme) = lock().get(name) {
+            return Ok(Arc::clone(theme));
+        }
+
+        let theme = Arc::new(Theme::load_with(name, read_index, loaded, ancestors)?);
+        Ok(Arc::clone(lock().entry(name.to_owned()).or_insert(theme)))
+    }
+}
+
+/// Lists the installed icon themes, sorted by name, e.g. for letting the user
+/// choose a theme. Hidden themes, which usually only exist to be inherited
+/// from, are left out unless `include_hidden` is set. Themes that fail to load
+/// are left out as well.
+pub fn list_themes(include_hidden: bool) -> Vec<Theme> {
+    installed_theme_names(&**file_system(), base_directories())
+        .iter()
+        .filter_map(|name| {
+            Theme::load(name)
+                .inspect_err(|_error| {
+                    #[cfg(feature = "log")]
+                    log::debug!("skipping icon theme {}: {}", name, _error);
+                })
+                .ok()
+        })
+        .filter(|theme| include_hidden || !theme.hidden)
+        .collect()
+}
+
+/// The names of the themes with an index.theme in any of the base
+/// directories, sorted and without duplicates.
+pub(crate) fn installed_theme_names(
+    file_system: &dyn FileSystem,
+    base_directories: &[PathBuf],
+) -> Vec<String> {
+    let mut names: Vec<String> = base_directories
+        .iter()
+        .filter_map(|directory| {
+            let entries = file_system
+                .read_dir(directory)
+                .inspect_err(|error| warning::warn_unreadable(directory, error))
+                .ok()?;
+            Some(entries.into_iter().filter(move |name| {
+                let index_theme = directory.join(name).join("index.theme");
+                file_system.is_file(&index_theme)
+            }))
+        })
+        .flatten()
+        .filter_map(|name| name.into_string().ok())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn find_index_theme(
+    name: &str,
+    file_system: &dyn FileSystem,
+    base_directories: &[PathBuf],
+) -> Result<PathBuf, Error> {
+    base_directories
+        .iter()
+        .map(|directory| directory.join(name).join("index.theme"))
+        .find(|path| file_system.is_file(path))
+        .ok_or_else(|| Error::ThemeNotFound(name.to_owned()))
 }
 
 /// # Per directory keys
 /// Each directory specified in the Directory key has a corresponding section
 /// with the same name as the directory. The contents of this section is listed
 /// in table 2 below.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ThemeDirectory {
     pub name: String,
     pub size: i16,
     pub scale: Option<i16>,
-    pub context: Option<String>,
+    pub context: Option<Context>,
     pub r#type: ThemeDirectoryType,
     pub max_size: Option<i16>,
     pub min_size: Option<i16>,
@@ -38,30 +499,132 @@ pub struct ThemeDirectory {
 /// # Per directory key types
 /// The type of icon sizes for the icons in this directory. Valid types are
 /// Fixed, Scalable and Threshold. The type decides what other keys in the
-/// section are used. If not specified, the default is Threshold. 
-/// TODO: Define Threshold as default 
+/// section are used. If not specified, the default is Threshold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
 pub enum ThemeDirectoryType {
     Fixed,
     Scalable,
+    #[default]
     Threshold,
 }
 
-// The fallback theme in this case is the hicolor theme, as mentioned in the specification.
+/// # Context
+/// The context the icon is normally used in. The standard contexts are listed
+/// in the Icon Naming Specification, themes may use other contexts as well.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Context {
+    Actions,
+    Animations,
+    Applications,
+    Categories,
+    Devices,
+    Emblems,
+    Emotes,
+    International,
+    MimeTypes,
+    Places,
+    Status,
+    Other(String),
+}
+
+impl Context {
+    /// The name of the context as used in the Context key of index.theme.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Context::Actions => "Actions",
+            Context::Animations => "Animations",
+            Context::Applications => "Applications",
+            Context::Categories => "Categories",
+            Context::Devices => "Devices",
+            Context::Emblems => "Emblems",
+            Context::Emotes => "Emotes",
+            Context::International => "International",
+            Context::MimeTypes => "MimeTypes",
+            Context::Places => "Places",
+            Context::Status => "Status",
+            Context::Other(name) => name,
+        }
+    }
+}
+
+impl From<&str> for Context {
+    fn from(name: &str) -> Context {
+        match name {
+            "Actions" => Context::Actions,
+            "Animations" => Context::Animations,
+            "Applications" => Context::Applications,
+            "Categories" => Context::Categories,
+            "Devices" => Context::Devices,
+            "Emblems" => Context::Emblems,
+            "Emotes" => Context::Emotes,
+            "International" => Context::International,
+            "MimeTypes" => Context::MimeTypes,
+            "Places" => Context::Places,
+            "Status" => Context::Status,
+            other => Context::Other(other.to_owned()),
+        }
+    }
+}
+
+/// # Icon formats
+/// The image file formats icons can be stored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IconFormat {
+    Png,
+    Svg,
+    Xpm,
+}
+
+impl IconFormat {
+    /// The file extension of icons in this format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            IconFormat::Png => "png",
+            IconFormat::Svg => "svg",
+            IconFormat::Xpm => "xpm",
+        }
+    }
 
-/// # Base directories
-/// Icons and themes are searched for in a set of directories, called base
-/// directories. The themes are stored in subdirectories of the base
-/// directories.
-const BASE_DIRECTORIES: [&str; 3] = ["~/.icons", "/usr/share/icons", "/usr/local/share/icons"];
+    /// Whether icons in this format can be returned, depending on the enabled
+    /// cargo features.
+    pub fn is_supported(self) -> bool {
+        ALLOWED_FORMATS.contains(&self)
+    }
+
+    /// The format belonging to a file extension, if it is a supported format.
+    pub fn from_extension(extension: &str) -> Option<IconFormat> {
+        ALLOWED_FORMATS
+            .iter()
+            .copied()
+            .find(|format| format.extension() == extension)
+    }
+
+    /// The format of file contents, detected from the PNG signature, the SVG
+    /// root element or the XPM comment, regardless of the enabled cargo
+    /// features.
+    pub fn detect(data: &[u8]) -> Option<IconFormat> {
+        load::detect_format(data)
+    }
+}
+
+// The fallback theme in this case is the hicolor theme, as mentioned in the specification.
+const FALLBACK_THEME: &str = "hicolor";
 
 /// An icon file is an image that can be loaded and used as an icon. The
 /// supported image file formats are PNG, XPM and SVG. PNG is the recommended
 /// bitmap format, and SVG is for vectorized icons. XPM is supported due to
 /// backwards compability reasons, and it is not recommended that new themes use
 /// XPM files. Support for SVGs is optional.
-
-// TODO: Make svg/xpm optional
-const ALLOWED_EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+///
+/// Support for SVG and XPM can be left out using the `svg` and `xpm` cargo
+/// features, for applications that are only able to load PNG files.
+const ALLOWED_FORMATS: &[IconFormat] = &[
+    IconFormat::Png,
+    #[cfg(feature = "svg")]
+    IconFormat::Svg,
+    #[cfg(feature = "xpm")]
+    IconFormat::Xpm,
+];
 const DEFAULT_THRESHOLD: i16 = 2;
 const DEFAULT_SCALE: i16 = 1;
 
@@ -85,24 +648,45 @@ const DEFAULT_SCALE: i16 = 1;
 /// icon that matches the name. If that fails we finally fall back on unthemed
 /// icons. If we fail to find any icon at all it is up to the application to
 /// pick a good fallback, as the correct choice depends on the context.
-pub fn find_icon(icon: &str, size: i16, scale: i16, user_selected_theme: Theme) -> Option<String> {
-    // TODO: Flatten this function
-    let fallback_theme: Theme = Theme {
-        name: "hicolor".to_owned(),
-        comment: "Default icon theme".to_owned(),
-        inherits: Vec::new(),
-        directories: vec![],
-    };
+///
+/// Icons can also be given as an absolute path or a file:// URI, as desktop
+/// files and notifications sometimes do. The path is returned if it is an
+/// icon file in one of the allowed formats.
+pub fn find_icon(icon: &str, size: i16, scale: i16, user_selected_theme: Theme) -> Option<PathBuf> {
+    find_icon_in(
+        icon,
+        &LookupOptions::new(size).scale(scale),
+        &user_selected_theme,
+        &[],
+    )
+    .map(|result| result.path)
+}
 
-    return match find_icon_helper(icon, size, scale, &user_selected_theme) {
-        Some(icon) => Some(icon),
-        None => {
-            return match find_icon_helper(icon, size, scale, &fallback_theme) {
-                Some(icon) => Some(icon),
-                None => return None,
-            };
-        }
-    };
+/// Looks up an icon like [`find_icon`], configured by the given options.
+pub fn find_icon_with(
+    icon: &str,
+    options: &LookupOptions,
+    user_selected_theme: Theme,
+) -> Option<PathBuf> {
+    find_icon_in(icon, options, &user_selected_theme, &[]).map(|result| result.path)
+}
+
+/// Resolves an icon once for looking it up at many sizes, e.g. in interfaces
+/// that zoom. The handle picks the file for a size like [`find_icon_with`]
+/// does, without searching the themes again.
+pub fn resolve_icon(icon: &str, options: &LookupOptions, user_selected_theme: Theme) -> IconHandle {
+    IconHandle::resolve(icon, options, &user_selected_theme, &[])
+}
+
+/// # Lookup results
+/// Looks up an icon like [`find_icon_with`], returning where the icon was
+/// found and how well it matches the request along with its path.
+pub fn lookup_icon_with(
+    icon: &str,
+    options: &LookupOptions,
+    user_selected_theme: Theme,
+) -> Option<LookupResult> {
+    find_icon_in(icon, options, &user_selected_theme, &[])
 }
 
 /// In some cases you don't always want to fall back to an icon in an inherited
@@ -112,40 +696,525 @@ pub fn find_icon(icon: &str, size: i16, scale: i16, user_selected_theme: Theme)
 /// list of icon names in the inheritance hierarchy. I.E. It would look
 /// something like this:
 pub fn find_best_icon(
-    // TODO: Flatten this function
     icon_list: Vec<&str>,
     size: i16,
     scale: i16,
     user_selected_theme: Theme,
-) -> Option<String> {
-    let fallback_theme: Theme = Theme {
-        name: "hicolor".to_owned(),
-        comment: "Default icon".to_owned(),
-        inherits: Vec::new(),
-        directories: vec![],
-    };
+) -> Option<PathBuf> {
+    find_best_icon_in(
+        &icon_list,
+        &LookupOptions::new(size).scale(scale),
+        &user_selected_theme,
+        &[],
+    )
+    .map(|result| result.path)
+}
 
-    return match find_best_icon_helper(&icon_list, size, scale, &user_selected_theme) {
-        Some(filename) => Some(filename),
-        None => {
-            return match find_best_icon_helper(&icon_list, size, scale, &fallback_theme) {
-                Some(filename) => Some(filename),
-                None => {
-                    for icon in icon_list {
-                        let filename = match lookup_fallback_icon(icon) {
-                            Some(filename) => filename,
-                            None => {
-                                continue;
-                            }
-                        };
-                        return Some(filename);
-                    }
-
-                    return None;
-                }
-            };
+/// Looks up an icon like [`find_icon`], with the lookup algorithm modified by
+/// the given flags.
+pub fn find_icon_with_flags(
+    icon: &str,
+    size: i16,
+    scale: i16,
+    user_selected_theme: Theme,
+    flags: LookupFlags,
+) -> Option<PathBuf> {
+    find_icon_in(
+        icon,
+        &LookupOptions::new(size).scale(scale).flags(flags),
+        &user_selected_theme,
+        &[],
+    )
+    .map(|result| result.path)
+}
+
+/// Looks up an icon like [`find_icon`], for display at a fractional scale
+/// such as 1.25 or 1.5.
+pub fn find_icon_fractional(
+    icon: &str,
+    size: i16,
+    scale: f32,
+    user_selected_theme: Theme,
+) -> Option<PathBuf> {
+    find_icon_in(
+        icon,
+        &LookupOptions::new(size).fractional_scale(scale),
+        &user_selected_theme,
+        &[],
+    )
+    .map(|result| result.path)
+}
+
+/// # Available sizes
+/// Lists the sizes an icon is available at, so applications can decide which
+/// size to request. Like [`find_icon`], only the first theme of the
+/// inheritance chain that provides the icon at all is considered, as icons
+/// of other themes will never be returned.
+pub fn available_sizes(icon: &str, user_selected_theme: Theme) -> Vec<IconSize> {
+    available_sizes_in(icon, &LookupOptions::new(0), &user_selected_theme, &[])
+}
+
+/// Lists every icon file matching the name across the inheritance chain, for
+/// applications that want to pick an icon themselves, e.g. the largest one
+/// available. Candidates are looked up lazily, one theme at a time. Within a
+/// theme they are ordered by their distance to the requested size.
+pub fn find_icon_candidates<'a>(
+    icon: &str,
+    size: i16,
+    scale: i16,
+    user_selected_theme: &'a Theme,
+) -> Candidates<'a> {
+    Candidates::new(
+        icon,
+        LookupOptions::new(size).scale(scale),
+        user_selected_theme,
+        &[],
+    )
+}
+
+/// Looks up an icon at several sizes at once, e.g. to build the set of icons
+/// of a window. The result is the same as calling [`find_icon`] for every
+/// size, but the inheritance chain is only traversed once.
+pub fn find_icon_sizes(
+    icon: &str,
+    sizes: &[i16],
+    scale: i16,
+    user_selected_theme: Theme,
+) -> Vec<Option<PathBuf>> {
+    find_icon_sizes_in(
+        icon,
+        sizes,
+        &LookupOptions::new(0).scale(scale),
+        &user_selected_theme,
+        &[],
+    )
+    .into_iter()
+    .map(|result| result.map(|result| result.path))
+    .collect()
+}
+
+/// A size, scale and directory type combination an icon is available at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct IconSize {
+    pub scale: i16,
+    pub size: i16,
+    pub r#type: ThemeDirectoryType,
+}
+
+/// Looks up an icon like [`find_icon`], only considering theme directories
+/// of the given context. File managers use this to look up icons for MIME
+/// types without accidentally matching an application icon of the same name.
+pub fn find_icon_in_context(
+    icon: &str,
+    size: i16,
+    scale: i16,
+    user_selected_theme: Theme,
+    context: Context,
+) -> Option<PathBuf> {
+    find_icon_in(
+        icon,
+        &LookupOptions::new(size).scale(scale).context(context),
+        &user_selected_theme,
+        &[],
+    )
+    .map(|result| result.path)
+}
+
+/// The hicolor theme, loaded the first time it is needed. Systems without
+/// hicolor get an empty theme, which simply provides no icons.
+fn fallback_theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+
+    THEME.get_or_init(|| {
+        Theme::load(FALLBACK_THEME).unwrap_or_else(|_| Theme {
+            name: FALLBACK_THEME.to_owned(),
+            comment: "Default icon theme".to_owned(),
+            inherits: Vec::new(),
+            directories: Vec::new(),
+            hidden: false,
+            translations: Translations::default(),
+            example: None,
+        })
+    })
+}
+
+/// The themes searched when the theme and its parents do not provide an
+/// icon: the given fallback themes followed by hicolor. Themes that were
+/// already searched before, e.g. because the theme inherits from hicolor, are
+/// left out.
+fn fallback_themes<'a>(theme: &'a Theme, fallbacks: &'a [Arc<Theme>]) -> Vec<&'a Theme> {
+    let mut searched = vec![theme];
+    let candidates = fallbacks
+        .iter()
+        .map(|fallback| &**fallback)
+        .chain(iter::once(fallback_theme()));
+
+    for fallback in candidates {
+        if !searched
+            .iter()
+            .any(|theme| inherits_from(theme, &fallback.name))
+        {
+            searched.push(fallback);
         }
+    }
+
+    searched.split_off(1)
+}
+
+/// Whether the theme is, or inherits from, the theme with the given name.
+fn inherits_from(theme: &Theme, name: &str) -> bool {
+    theme.name == name
+        || theme
+            .inherits
+            .iter()
+            .any(|parent| inherits_from(parent, name))
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(options, theme, fallbacks),
+        fields(size = options.size, scale = options.scale, theme = %theme.name)
+    )
+)]
+pub(crate) fn find_icon_in(
+    icon: &str,
+    options: &LookupOptions,
+    theme: &Theme,
+    fallbacks: &[Arc<Theme>],
+) -> Option<LookupResult> {
+    // Icons given as a file are used as is, they are never looked up in
+    // themes.
+    if let Some(path) = input::file_path(icon) {
+        return input::file_result(path, options);
+    }
+    let icon = input::icon_name(icon, options);
+
+    let names = lookup_names(icon, options);
+    if names.len() > 1 || direction_suffix(options.flags).is_some() {
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+
+        return find_names_in(&names, options, theme, fallbacks);
+    }
+
+    let profiler = &options.profiler;
+    // The first step of find_icon_helper is taken here, so the time spent in
+    // parent themes can be measured.
+    let result = lookup_icon(icon, options, theme)
+        .or_else(|| {
+            profiler.time(Phase::Parents, || {
+                theme
+                    .inherits
+                    .iter()
+                    .find_map(|parent| find_icon_helper(icon, options, parent))
+                    .or_else(|| {
+                        fallback_themes(theme, fallbacks)
+                            .into_iter()
+                            .find_map(|fallback| find_icon_helper(icon, options, fallback))
+                    })
+            })
+        })
+        .or_else(|| profiler.time(Phase::Unthemed, || lookup_fallback_icon(icon, options)));
+
+    // Directories skipped after cancelling may have held a better icon.
+    result
+        .filter(|_| !options.is_cancelled())
+        .map(|result| options.canonical_result(result))
+        .or_else(|| find_aliased_icon_in(&[icon], options, theme, fallbacks))
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "debug",
+        skip(options, theme, fallbacks),
+        fields(size = options.size, scale = options.scale, theme = %theme.name)
+    )
+)]
+pub(crate) fn find_best_icon_in(
+    icon_list: &[&str],
+    options: &LookupOptions,
+    theme: &Theme,
+    fallbacks: &[Arc<Theme>],
+) -> Option<LookupResult> {
+    let names: Vec<&str> = icon_list
+        .iter()
+        .map(|icon| input::icon_name(icon, options))
+        .collect();
+    find_names_in(&names, options, theme, fallbacks)
+}
+
+/// Looks up the first available icon of a list of names that are already
+/// normalised by [`input::icon_name`], trying the names with the direction
+/// suffix first.
+fn find_names_in(
+    names: &[&str],
+    options: &LookupOptions,
+    theme: &Theme,
+    fallbacks: &[Arc<Theme>],
+) -> Option<LookupResult> {
+    let directional = directional_names(names, options);
+    let icon_list: Vec<&str> = directional.iter().map(String::as_str).collect();
+    let icon_list = icon_list.as_slice();
+
+    let profiler = &options.profiler;
+    // The first step of find_best_icon_helper is taken here, so the time
+    // spent in parent themes can be measured.
+    let result = icon_list
+        .iter()
+        .find_map(|icon| lookup_icon(icon, options, theme))
+        .or_else(|| {
+            profiler.time(Phase::Parents, || {
+                theme
+                    .inherits
+                    .iter()
+                    .find_map(|parent| find_best_icon_helper(icon_list, options, parent))
+                    .or_else(|| {
+                        fallback_themes(theme, fallbacks)
+                            .into_iter()
+                            .find_map(|fallback| {
+                                find_best_icon_helper(icon_list, options, fallback)
+                            })
+                    })
+            })
+        })
+        .or_else(|| {
+            profiler.time(Phase::Unthemed, || {
+                icon_list
+                    .iter()
+                    .find_map(|icon| lookup_fallback_icon(icon, options))
+            })
+        });
+
+    result
+        .filter(|_| !options.is_cancelled())
+        .map(|result| options.canonical_result(result))
+        .or_else(|| find_aliased_icon_in(names, options, theme, fallbacks))
+}
+
+/// Looks up the names the legacy icon names are aliases of, once none of
+/// the names themselves is found. Aliases are not followed any further, so
+/// names that are aliases of each other do not loop.
+fn find_aliased_icon_in(
+    icon_list: &[&str],
+    options: &LookupOptions,
+    theme: &Theme,
+    fallbacks: &[Arc<Theme>],
+) -> Option<LookupResult> {
+    if options.is_cancelled() {
+        return None;
+    }
+    let aliases: Vec<&str> = icon_list
+        .iter()
+        .filter_map(|icon| options.alias(icon))
+        .collect();
+    if aliases.is_empty() {
+        return None;
+    }
+
+    let options = LookupOptions {
+        aliases: None,
+        ..options.clone()
     };
+    find_names_in(&aliases, &options, theme, fallbacks)
+}
+
+pub(crate) fn find_icon_sizes_in(
+    icon: &str,
+    sizes: &[i16],
+    options: &LookupOptions,
+    theme: &Theme,
+    fallbacks: &[Arc<Theme>],
+) -> Vec<Option<LookupResult>> {
+    let results: Vec<Option<LookupResult>> = find_icon_sizes_helper(icon, sizes, options, theme)
+        .or_else(|| {
+            fallback_themes(theme, fallbacks)
+                .into_iter()
+                .find_map(|fallback| find_icon_sizes_helper(icon, sizes, options, fallback))
+        })
+        .unwrap_or_else(|| {
+            let fallback = lookup_fallback_icon(icon, options);
+            sizes.iter().map(|_| fallback.clone()).collect()
+        });
+
+    if options.is_cancelled() {
+        return sizes.iter().map(|_| None).collect();
+    }
+    let exact_only = options.flags.contains(LookupFlags::EXACT_ONLY);
+    results
+        .into_iter()
+        .map(|result| {
+            result
+                .filter(|result| !exact_only || result.match_kind == MatchKind::Exact)
+                .map(|result| options.canonical_result(result))
+        })
+        .collect()
+}
+
+/// As the lookup stops at the first theme providing the icon at any size, the
+/// same theme provides the icon for every size. All files of the icon in
+/// that theme are gathered once, after which the best file is picked for
+/// each size. Sizes without a file the lookup would accept, e.g. with
+/// [`LookupFlags::EXACT_ONLY`], have none.
+pub(crate) fn find_icon_sizes_helper(
+    icon: &str,
+    sizes: &[i16],
+    options: &LookupOptions,
+    theme: &Theme,
+) -> Option<Vec<Option<LookupResult>>> {
+    let files = scan_directories(theme, options, |subdir| {
+        find_in_directory(icon, subdir, options, theme).map(|(path, format)| (subdir, path, format))
+    });
+
+    if files.is_empty() {
+        return theme
+            .inherits
+            .iter()
+            .find_map(|parent| find_icon_sizes_helper(icon, sizes, options, parent));
+    }
+
+    Some(
+        sizes
+            .iter()
+            .map(|&size| closest_file(&files, size, options, theme))
+            .collect(),
+    )
+}
+
+/// Picks the file for the given size like a lookup does, from files that
+/// were found before, following the lookup profile and fractional scale of
+/// the options.
+fn closest_file(
+    files: &[(&ThemeDirectory, PathBuf, IconFormat)],
+    size: i16,
+    options: &LookupOptions,
+    theme: &Theme,
+) -> Option<LookupResult> {
+    let options = LookupOptions {
+        size,
+        ..options.clone()
+    };
+
+    select_icon(&options, theme, &|subdir| {
+        files
+            .iter()
+            .find(|(directory, _, _)| std::ptr::eq(*directory, subdir))
+            .map(|(_, path, format)| (path.clone(), *format))
+    })
+}
+
+pub(crate) fn available_sizes_in(
+    icon: &str,
+    options: &LookupOptions,
+    theme: &Theme,
+    fallbacks: &[Arc<Theme>],
+) -> Vec<IconSize> {
+    let mut sizes = available_sizes_helper(icon, options, theme);
+    if sizes.is_empty() {
+        sizes = fallback_themes(theme, fallbacks)
+            .into_iter()
+            .map(|fallback| available_sizes_helper(icon, options, fallback))
+            .find(|sizes| !sizes.is_empty())
+            .unwrap_or_default();
+    }
+
+    // Directories skipped after cancelling may have held more sizes.
+    if options.is_cancelled() {
+        return Vec::new();
+    }
+    sizes
+}
+
+fn available_sizes_helper(icon: &str, options: &LookupOptions, theme: &Theme) -> Vec<IconSize> {
+    let mut sizes = scan_directories(theme, options, |subdir| {
+        find_in_directory(icon, subdir, options, theme).map(|_| IconSize {
+            scale: subdir.scale.unwrap_or(DEFAULT_SCALE),
+            size: subdir.size,
+            r#type: subdir.r#type,
+        })
+    });
+
+    if sizes.is_empty() {
+        return theme
+            .inherits
+            .iter()
+            .map(|parent| available_sizes_helper(icon, options, parent))
+            .find(|sizes| !sizes.is_empty())
+            .unwrap_or_default();
+    }
+
+    sizes.sort();
+    sizes.dedup();
+    sizes
+}
+
+/// Icon names consist of dash separated segments, going from generic to more
+/// specific. "network-wireless-signal-excellent" falls back to
+/// "network-wireless-signal", "network-wireless" and finally "network". The
+/// "-symbolic" suffix is kept on every generic name.
+fn generic_fallback_names(icon: &str) -> Vec<String> {
+    let (mut name, suffix) = match icon.strip_suffix("-symbolic") {
+        Some(name) => (name, "-symbolic"),
+        None => (icon, ""),
+    };
+
+    let mut names = vec![format!("{}{}", name, suffix)];
+    while let Some(index) = name.rfind('-') {
+        name = &name[..index];
+        names.push(format!("{}{}", name, suffix));
+    }
+
+    names
+}
+
+/// The names a lookup of the normalised icon name tries, best first,
+/// before the direction suffixes are added. Every theme is searched for all
+/// of the generic names of [`LookupFlags::GENERIC_FALLBACK`] before moving on
+/// to its parents, so a specific icon in an inherited theme does not win
+/// over a generic icon in the selected theme.
+pub(crate) fn lookup_names(icon: &str, options: &LookupOptions) -> Vec<String> {
+    let mut names = if options.flags.contains(LookupFlags::GENERIC_FALLBACK) {
+        generic_fallback_names(icon)
+    } else {
+        vec![icon.to_owned()]
+    };
+    if options.is_gtk_compatible() {
+        // GTK falls back to the regular icons when the symbolic ones are
+        // missing.
+        let regular: Vec<String> = names
+            .iter()
+            .filter_map(|name| name.strip_suffix("-symbolic"))
+            .map(str::to_owned)
+            .collect();
+        names.extend(regular);
+    }
+
+    names
+}
+
+/// The names with the variants for the text direction of the options, every
+/// name with the suffix before the name itself.
+pub(crate) fn directional_names(names: &[&str], options: &LookupOptions) -> Vec<String> {
+    match direction_suffix(options.flags) {
+        Some(suffix) => names
+            .iter()
+            .flat_map(|icon| [format!("{}{}", icon, suffix), (*icon).to_owned()])
+            .collect(),
+        None => names.iter().map(|icon| (*icon).to_owned()).collect(),
+    }
+}
+
+/// The suffix of the icon variants for the text direction of the flags, if
+/// any. Every name is tried with the suffix before the name itself.
+fn direction_suffix(flags: LookupFlags) -> Option<&'static str> {
+    if flags.contains(LookupFlags::DIR_RTL) {
+        Some("-rtl")
+    } else if flags.contains(LookupFlags::DIR_LTR) {
+        Some("-ltr")
+    } else {
+        None
+    }
 }
 
 /// # Implementation Notes
@@ -160,133 +1229,290 @@ pub fn find_best_icon(
 /// This means that any icon editor or theme installation program need only to
 /// change the mtime of the the toplevel directory where it changed the theme to
 /// make sure that the new icons will eventually get used.
-fn find_icon_helper(icon: &str, size: i16, scale: i16, theme: &Theme) -> Option<String> {
-    // TODO: Flatten this function
-    let filename = match lookup_icon(icon, size, scale, theme) {
-        Some(f) => Some(f),
-        None => {
-            // The check from the pseudocode can be left out because we force parents to be set.
-            for parent in &theme.inherits {
-                match find_icon_helper(icon, size, scale, &parent) {
-                    Some(f) => return Some(f),
-                    None => continue,
-                }
-            }
+fn find_icon_helper(icon: &str, options: &LookupOptions, theme: &Theme) -> Option<LookupResult> {
+    // The check from the pseudocode can be left out because we force parents to be set.
+    lookup_icon(icon, options, theme).or_else(|| {
+        theme
+            .inherits
+            .iter()
+            .find_map(|parent| find_icon_helper(icon, options, parent))
+    })
+}
 
-            return None;
-        }
-    };
+fn find_best_icon_helper(
+    icon_list: &[&str],
+    options: &LookupOptions,
+    theme: &Theme,
+) -> Option<LookupResult> {
+    icon_list
+        .iter()
+        .find_map(|icon| lookup_icon(icon, options, theme))
+        .or_else(|| {
+            theme
+                .inherits
+                .iter()
+                .find_map(|parent| find_best_icon_helper(icon_list, options, parent))
+        })
+}
 
-    return filename;
+/// The directories of the theme that are searched, restricted to the given
+/// context if any.
+fn context_directories<'a>(
+    theme: &'a Theme,
+    context: Option<&'a Context>,
+) -> impl Iterator<Item = &'a ThemeDirectory> {
+    theme
+        .directories
+        .iter()
+        .filter(move |subdir| context.is_none() || subdir.context.as_ref() == context)
 }
 
-fn find_best_icon_helper(
-    icon_list: &Vec<&str>,
-    size: i16,
-    scale: i16,
+/// Calls `scan` for every directory of the theme that is searched, keeping
+/// the results in the order of the directories. Themes like Papirus have
+/// hundreds of directories, with the `parallel` feature these are scanned on
+/// multiple threads.
+fn scan_directories<'a, T, F>(theme: &'a Theme, options: &'a LookupOptions, scan: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&'a ThemeDirectory) -> Option<T> + Sync + Send,
+{
+    let directories = context_directories(theme, options.context.as_ref());
+
+    #[cfg(feature = "parallel")]
+    {
+        use rayon::prelude::*;
+
+        directories
+            .collect::<Vec<_>>()
+            .into_par_iter()
+            .filter_map(scan)
+            .collect()
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        directories.filter_map(scan).collect()
+    }
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "trace", skip(options, theme), fields(theme = %theme.name))
+)]
+fn lookup_icon(icon_name: &str, options: &LookupOptions, theme: &Theme) -> Option<LookupResult> {
+    select_icon(options, theme, &|subdir| {
+        find_in_directory(icon_name, subdir, options, theme)
+    })
+}
+
+/// The file of a directory of the theme, if it has one for the icon.
+type FindInDirectory<'a> = &'a dyn Fn(&ThemeDirectory) -> Option<(PathBuf, IconFormat)>;
+
+/// Picks the directory of the theme that best matches the options, out of
+/// the ones `find` finds a file in.
+fn select_icon(
+    options: &LookupOptions,
     theme: &Theme,
-) -> Option<String> {
-    // TODO: Flatten this function
-    for icon in icon_list {
-        let filename = match lookup_icon(icon, size, scale, theme) {
-            Some(f) => f,
-            None => continue,
-        };
+    find: FindInDirectory,
+) -> Option<LookupResult> {
+    let profiler = &options.profiler;
+    if let Some(physical_size) = options.physical_size() {
+        return profiler.time(Phase::ClosestMatch, || {
+            lookup_icon_physical(physical_size, options, theme, find)
+        });
+    }
 
-        return Some(filename);
+    if options.is_gtk_compatible() {
+        return profiler.time(Phase::ClosestMatch, || {
+            lookup_icon_gtk(options, theme, find)
+        });
     }
 
-    for parent in &theme.inherits {
-        let filename = match find_best_icon_helper(icon_list, size, scale, &parent) {
-            Some(f) => f,
-            None => {
+    let (size, scale) = (options.size, options.scale);
+
+    let exact = profiler.time(Phase::ExactMatch, || {
+        context_directories(theme, options.context.as_ref())
+            .filter(|subdir| directory_matches_size(subdir, size, scale))
+            .find_map(|subdir| {
+                let (path, format) = find(subdir)?;
+                Some(theme_result(path, format, theme, subdir, MatchKind::Exact))
+            })
+    });
+    if exact.is_some() || options.flags.contains(LookupFlags::EXACT_ONLY) {
+        return exact;
+    }
+
+    // No exact match was found, compute the closest matching icon.
+    #[cfg(feature = "tracing")]
+    let _span = tracing::trace_span!("closest_match").entered();
+    profiler.time(Phase::ClosestMatch, || {
+        let mut minimal_rank = None;
+        let mut closest = None;
+
+        for subdir in context_directories(theme, options.context.as_ref()) {
+            let rank = options.closest_match_rank(subdir, size);
+            if minimal_rank.is_some_and(|minimal_rank| rank >= minimal_rank) {
                 continue;
             }
-        };
 
-        return Some(filename);
-    }
+            if let Some((path, format)) = find(subdir) {
+                // Found a better match, updating closest file
+                closest = Some(theme_result(
+                    path,
+                    format,
+                    theme,
+                    subdir,
+                    MatchKind::Closest,
+                ));
+                minimal_rank = Some(rank);
+            }
+        }
 
-    return None;
+        closest
+    })
 }
 
-fn lookup_icon(icon_name: &str, size: i16, scale: i16, theme: &Theme) -> Option<String> {
-    for subdir in &theme.directories {
-        for directory in &BASE_DIRECTORIES {
-            for extension in &ALLOWED_EXTENSIONS {
-                if directory_matches_size(subdir, size, scale) {
-                    let file_path = format!(
-                        "{directory}/{theme_name}/{subdir}/{icon_name}.{extension}",
-                        directory = directory,
-                        theme_name = theme.name,
-                        subdir = subdir.name,
-                        icon_name = icon_name,
-                        extension = extension
-                    );
+/// Looks up an icon in a theme like GTK does, see
+/// [`LookupProfile::GtkCompatible`].
+fn lookup_icon_gtk(
+    options: &LookupOptions,
+    theme: &Theme,
+    find: FindInDirectory,
+) -> Option<LookupResult> {
+    let (size, scale) = (options.size, options.scale);
+    let mut best: Option<(LookupResult, i16, bool)> = None;
 
-                    if Path::new(&file_path).exists() {
-                        return Some(file_path);
-                    }
-                }
+    for subdir in context_directories(theme, options.context.as_ref()) {
+        let distance = options.size_distance(subdir);
+        let smaller = directory_is_smaller(subdir, size, scale);
+        let better = match &best {
+            None => true,
+            Some((_, best_distance, best_smaller)) => {
+                (*best_smaller && !smaller)
+                    || (*best_smaller == smaller && distance < *best_distance)
             }
+        };
+        if !better {
+            continue;
         }
-    }
-
-    // No exact match was found, compute the closest matching icon.
-    // TODO: There is a more elegant solution than this
-    let mut minimal_size = i16::max_value();
-    let mut closest_filename = String::from("");
-
-    for subdir in &theme.directories {
-        for directory in &BASE_DIRECTORIES {
-            for extension in &ALLOWED_EXTENSIONS {
-                let file_path = format!(
-                    "{directory}/{theme_name}/{subdir}/{icon_name}.{extension}",
-                    directory = directory,
-                    theme_name = theme.name,
-                    subdir = subdir.name,
-                    icon_name = icon_name,
-                    extension = extension
-                );
 
-                let directory_size_distance = directory_size_distance(&subdir, size, scale);
-                if Path::new(&file_path).exists() && directory_size_distance < minimal_size {
-                    // Found a better match, updating closest file
-                    closest_filename = file_path;
-                    minimal_size = directory_size_distance;
-                }
+        if let Some((path, format)) = find(subdir) {
+            if distance == 0 {
+                return Some(theme_result(path, format, theme, subdir, MatchKind::Exact));
             }
+
+            let result = theme_result(path, format, theme, subdir, MatchKind::Closest);
+            best = Some((result, distance, smaller));
         }
     }
 
-    if minimal_size < i16::max_value() {
-        return Some(closest_filename);
+    best.map(|(result, _, _)| result)
+        .filter(|_| !options.flags.contains(LookupFlags::EXACT_ONLY))
+}
+
+fn theme_result(
+    path: PathBuf,
+    format: IconFormat,
+    theme: &Theme,
+    subdir: &ThemeDirectory,
+    match_kind: MatchKind,
+) -> LookupResult {
+    LookupResult {
+        path,
+        theme: Some(theme.name.clone()),
+        directory: Some(subdir.clone()),
+        match_kind,
+        format,
     }
-    return None;
 }
 
-fn lookup_fallback_icon(icon_name: &str) -> Option<String> {
-    for directory in &BASE_DIRECTORIES {
-        for extension in &ALLOWED_EXTENSIONS {
-            let file_path = format!(
-                "{directory}/{icon_name}.{extension}",
-                directory = directory,
-                icon_name = icon_name,
-                extension = extension
-            );
+/// Looks up an icon in a single directory of a theme, in any base directory.
+fn find_in_directory(
+    icon_name: &str,
+    subdir: &ThemeDirectory,
+    options: &LookupOptions,
+    theme: &Theme,
+) -> Option<(PathBuf, IconFormat)> {
+    options.base_directories().find_map(|directory| {
+        if options.is_cancelled() {
+            return None;
+        }
 
-            if Path::new(&file_path).exists() {
-                return Some(file_path);
-            }
+        let directory = directory.join(&theme.name).join(&subdir.name);
+        listing::icon_files(&directory, icon_name, options)
+            .into_iter()
+            .next()
+    })
+}
+
+/// Looks up the icon whose physical pixel size comes closest to the requested
+/// one. Used for fractional scales, which no theme directory matches exactly.
+fn lookup_icon_physical(
+    physical_size: f32,
+    options: &LookupOptions,
+    theme: &Theme,
+    find: FindInDirectory,
+) -> Option<LookupResult> {
+    let mut minimal_distance = f32::INFINITY;
+    let mut closest = None;
+
+    for subdir in context_directories(theme, options.context.as_ref()) {
+        let distance = directory_physical_distance(subdir, physical_size);
+        if distance >= minimal_distance {
+            continue;
+        }
+
+        if let Some((path, format)) = find(subdir) {
+            let match_kind = if distance == 0.0 {
+                MatchKind::Exact
+            } else {
+                MatchKind::Closest
+            };
+
+            closest = Some(theme_result(path, format, theme, subdir, match_kind));
+            minimal_distance = distance;
         }
     }
 
-    return None;
+    closest.filter(|result: &LookupResult| {
+        result.match_kind == MatchKind::Exact || !options.flags.contains(LookupFlags::EXACT_ONLY)
+    })
 }
 
-fn directory_matches_size(subdir: &ThemeDirectory, icon_size: i16, icon_scale: i16) -> bool {
-    let theme_directory = type_size_from_subdir(subdir);
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(level = "trace", skip(options))
+)]
+fn lookup_fallback_icon(icon_name: &str, options: &LookupOptions) -> Option<LookupResult> {
+    // Unthemed icons have no size to match.
+    if options.flags.contains(LookupFlags::EXACT_ONLY) {
+        return None;
+    }
+
+    options.unthemed_directories().find_map(|directory| {
+        if options.is_cancelled() {
+            return None;
+        }
+
+        let (path, format) = listing::icon_files(directory, icon_name, options)
+            .into_iter()
+            .next()?;
+
+        Some(LookupResult {
+            path,
+            theme: None,
+            directory: None,
+            match_kind: MatchKind::Unthemed,
+            format,
+        })
+    })
+}
 
+fn directory_matches_size(
+    theme_directory: &ThemeDirectory,
+    icon_size: i16,
+    icon_scale: i16,
+) -> bool {
     if icon_scale != theme_directory.scale.unwrap_or(DEFAULT_SCALE) {
         return false;
     }
@@ -295,83 +1521,386 @@ fn directory_matches_size(subdir: &ThemeDirectory, icon_size: i16, icon_scale: i
     let max_size = theme_directory.max_size.unwrap_or(theme_directory.size);
     let threshold = theme_directory.threshold.unwrap_or(DEFAULT_THRESHOLD);
 
-    return match theme_directory.r#type {
+    match theme_directory.r#type {
         ThemeDirectoryType::Fixed => theme_directory.size == icon_size,
         ThemeDirectoryType::Scalable => min_size <= icon_size && icon_size <= max_size,
         ThemeDirectoryType::Threshold => {
             theme_directory.size - threshold <= icon_size
                 && icon_size <= theme_directory.size + threshold
         }
+    }
+}
+
+/// Saturates a distance computed in i32 to i16. Sizes times scales do not
+/// fit in i16 for large sizes or scales.
+fn saturate(distance: i32) -> i16 {
+    distance.clamp(i16::MIN.into(), i16::MAX.into()) as i16
+}
+
+pub(crate) fn directory_size_distance(
+    theme_directory: &ThemeDirectory,
+    icon_size: i16,
+    icon_scale: i16,
+) -> i16 {
+    let theme_directory_scale = i32::from(theme_directory.scale.unwrap_or(DEFAULT_SCALE));
+    let size = i32::from(theme_directory.size) * theme_directory_scale;
+    let min_size =
+        i32::from(theme_directory.min_size.unwrap_or(theme_directory.size)) * theme_directory_scale;
+    let max_size =
+        i32::from(theme_directory.max_size.unwrap_or(theme_directory.size)) * theme_directory_scale;
+    let threshold =
+        i32::from(theme_directory.threshold.unwrap_or(DEFAULT_THRESHOLD)) * theme_directory_scale;
+    let icon_size = i32::from(icon_size) * i32::from(icon_scale);
+
+    let distance = match theme_directory.r#type {
+        ThemeDirectoryType::Fixed => (size - icon_size).abs(),
+        ThemeDirectoryType::Scalable if icon_size < min_size => min_size - icon_size,
+        ThemeDirectoryType::Scalable if icon_size > max_size => icon_size - max_size,
+        ThemeDirectoryType::Scalable => 0,
+        // The pseudocode in the specification measures against MinSize and
+        // MaxSize here, which are not defined for Threshold directories.
+        // Measure against the nominal size instead.
+        ThemeDirectoryType::Threshold if icon_size < size - threshold => size - icon_size,
+        ThemeDirectoryType::Threshold if icon_size > size + threshold => icon_size - size,
+        ThemeDirectoryType::Threshold => 0,
     };
+    saturate(distance)
 }
 
-fn directory_size_distance(subdir: &ThemeDirectory, icon_size: i16, icon_scale: i16) -> i16 {
-    let theme_directory = type_size_from_subdir(subdir);
+/// The distance as computed by the pseudocode of the specification, see
+/// [`DistanceStrategy::SpecPseudocode`].
+pub(crate) fn spec_directory_size_distance(
+    theme_directory: &ThemeDirectory,
+    icon_size: i16,
+    icon_scale: i16,
+) -> i16 {
+    if theme_directory.r#type != ThemeDirectoryType::Threshold {
+        return directory_size_distance(theme_directory, icon_size, icon_scale);
+    }
 
-    let min_size = theme_directory.min_size.unwrap_or(theme_directory.size);
-    let max_size = theme_directory.max_size.unwrap_or(theme_directory.size);
-    let threshold = theme_directory.threshold.unwrap_or(DEFAULT_THRESHOLD);
-    let theme_directory_scale = theme_directory.scale.unwrap_or(DEFAULT_SCALE);
-
-    return match theme_directory.r#type {
-        ThemeDirectoryType::Fixed => {
-            (theme_directory.size * theme_directory_scale - icon_size * icon_scale).abs()
-        },
-        ThemeDirectoryType::Scalable => {
-            if icon_size * icon_scale < min_size * theme_directory_scale {
-                return min_size * theme_directory_scale - icon_size * icon_scale;
-            }
+    let scale = i32::from(theme_directory.scale.unwrap_or(DEFAULT_SCALE));
+    let size = i32::from(theme_directory.size) * scale;
+    let min_size = i32::from(theme_directory.min_size.unwrap_or(theme_directory.size)) * scale;
+    let max_size = i32::from(theme_directory.max_size.unwrap_or(theme_directory.size)) * scale;
+    let threshold = i32::from(theme_directory.threshold.unwrap_or(DEFAULT_THRESHOLD)) * scale;
+    let icon_size = i32::from(icon_size) * i32::from(icon_scale);
 
-            if icon_size * icon_scale > max_size * theme_directory_scale {
-                return icon_size * icon_scale - max_size * theme_directory_scale;
-            }
+    // Icons outside the threshold but within MinSize and MaxSize get a
+    // negative distance from the pseudocode, which would win over every
+    // closer directory.
+    let distance = if icon_size < size - threshold {
+        min_size - icon_size
+    } else if icon_size > size + threshold {
+        icon_size - max_size
+    } else {
+        0
+    };
+    saturate(distance.max(0))
+}
+
+/// Whether the icons of a directory are smaller than the icon, in device
+/// pixels, so they would have to be scaled up.
+pub(crate) fn directory_is_smaller(
+    theme_directory: &ThemeDirectory,
+    icon_size: i16,
+    icon_scale: i16,
+) -> bool {
+    let max_size = match theme_directory.r#type {
+        ThemeDirectoryType::Fixed => theme_directory.size,
+        ThemeDirectoryType::Scalable => theme_directory.max_size.unwrap_or(theme_directory.size),
+        ThemeDirectoryType::Threshold => theme_directory
+            .size
+            .saturating_add(theme_directory.threshold.unwrap_or(DEFAULT_THRESHOLD)),
+    };
+
+    i32::from(max_size) * i32::from(theme_directory.scale.unwrap_or(DEFAULT_SCALE))
+        < i32::from(icon_size) * i32::from(icon_scale)
+}
 
-            return 0;
-        },
+/// The distance in physical pixels between the icons of a directory and an
+/// icon of the given physical size.
+fn directory_physical_distance(theme_directory: &ThemeDirectory, physical_size: f32) -> f32 {
+    let scale = f32::from(theme_directory.scale.unwrap_or(DEFAULT_SCALE));
+    let size = f32::from(theme_directory.size) * scale;
+
+    let (min_size, max_size) = match theme_directory.r#type {
+        ThemeDirectoryType::Fixed => (size, size),
+        ThemeDirectoryType::Scalable => (
+            f32::from(theme_directory.min_size.unwrap_or(theme_directory.size)) * scale,
+            f32::from(theme_directory.max_size.unwrap_or(theme_directory.size)) * scale,
+        ),
         ThemeDirectoryType::Threshold => {
-            if icon_size * icon_scale < (theme_directory.size - threshold) * theme_directory_scale {
-                return min_size * theme_directory_scale - icon_size * icon_scale;
-            }
+            let threshold = f32::from(theme_directory.threshold.unwrap_or(DEFAULT_THRESHOLD));
+            let min_size = size - threshold * scale;
+            let max_size = size + threshold * scale;
 
-            if icon_size * icon_scale > (theme_directory.size + threshold) * theme_directory_scale {
-                return icon_size * icon_scale - max_size * theme_directory_scale;
+            // Like directory_size_distance, measure against the nominal size
+            // when outside of the threshold.
+            if physical_size < min_size || physical_size > max_size {
+                return (size - physical_size).abs();
             }
-
-            return 0;
+            return 0.0;
         }
-    }
-}
-
-fn type_size_from_subdir(subdir: &ThemeDirectory) -> ThemeDirectory {
-    return ThemeDirectory {
-        name: "Main".to_owned(),
-        size: 512,
-        scale: Some(1),
-        context: Some("actions".to_owned()),
-        r#type: ThemeDirectoryType::Fixed,
-        min_size: None,
-        max_size: None,
-        threshold: None,
     };
+
+    if physical_size < min_size {
+        min_size - physical_size
+    } else if physical_size > max_size {
+        physical_size - max_size
+    } else {
+        0.0
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+    use std::path::Path;
+
+    /// A theme inheriting from a base theme, kept in memory so lookups do not
+    /// depend on the themes installed on the system.
+    pub(crate) fn memory_themes() -> Arc<MemoryFileSystem> {
+        let fs = MemoryFileSystem::new();
+        fs.add_file(
+            "/usr/share/icons/icon-finder-test/index.theme",
+            "[Icon Theme]\nName=Test\nComment=Test\nInherits=icon-finder-base\n\
+             Directories=16x16/apps,48x48/apps,scalable/apps\n\n\
+             [16x16/apps]\nSize=16\nType=Fixed\n\n\
+             [48x48/apps]\nSize=48\nType=Fixed\n\n\
+             [scalable/apps]\nSize=48\nMinSize=24\nMaxSize=32\nType=Scalable\n",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-base/index.theme",
+            "[Icon Theme]\nName=Base\nComment=Base\nDirectories=64x64/apps\n\n\
+             [64x64/apps]\nSize=64\nType=Fixed\n",
+        );
+
+        fs.add_file(
+            "/usr/share/icons/icon-finder-test/16x16/apps/editor.png",
+            "",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-test/48x48/apps/editor.png",
+            "",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-test/scalable/apps/editor.svg",
+            "",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-base/64x64/apps/editor.png",
+            "",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-base/64x64/apps/browser.png",
+            "",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-test/48x48/apps/go-next.png",
+            "",
+        );
+        fs.add_file(
+            "/usr/share/icons/icon-finder-test/48x48/apps/go-next-rtl.png",
+            "",
+        );
+        fs.add_file("/usr/share/pixmaps/icon-finder-legacy.xpm", "");
+
+        Arc::new(fs)
+    }
+
+    #[test]
+    #[cfg(feature = "svg")]
+    fn test_find_icon_in_memory() {
+        let fs: Arc<dyn FileSystem> = memory_themes();
+        let theme = Theme::load_from("icon-finder-test", &*fs).unwrap();
+        let find = |icon: &str, size: i16| {
+            let options = LookupOptions::new(size).with_file_system(Some(&fs));
+            find_icon_in(icon, &options, &theme, &[])
+        };
+
+        let exact = find("editor", 48).unwrap();
+        assert_eq!(
+            exact.path,
+            Path::new("/usr/share/icons/icon-finder-test/48x48/apps/editor.png")
+        );
+        assert_eq!(exact.match_kind, MatchKind::Exact);
+
+        let scalable = find("editor", 24).unwrap();
+        assert_eq!(scalable.format, IconFormat::Svg);
+        assert_eq!(scalable.match_kind, MatchKind::Exact);
+
+        // The theme provides the icon, so the closer size of the parent is not
+        // used.
+        let closest = find("editor", 64).unwrap();
+        assert_eq!(closest.theme.as_deref(), Some("icon-finder-test"));
+        assert_eq!(closest.match_kind, MatchKind::Closest);
+
+        let inherited = find("browser", 16).unwrap();
+        assert_eq!(inherited.theme.as_deref(), Some("icon-finder-base"));
+
+        assert_eq!(find("terminal", 16), None);
+    }
+
+    #[test]
+    #[cfg(feature = "xpm")]
+    fn test_find_unthemed_icon_in_memory() {
+        let fs: Arc<dyn FileSystem> = memory_themes();
+        let theme = Theme::load_from("icon-finder-test", &*fs).unwrap();
+        let options = LookupOptions::new(16).with_file_system(Some(&fs));
+
+        let unthemed = find_icon_in("icon-finder-legacy", &options, &theme, &[]).unwrap();
+        assert_eq!(
+            unthemed.path,
+            Path::new("/usr/share/pixmaps/icon-finder-legacy.xpm")
+        );
+        assert_eq!(unthemed.match_kind, MatchKind::Unthemed);
+    }
+
+    #[test]
+    fn test_find_directional_icon() {
+        let fs: Arc<dyn FileSystem> = memory_themes();
+        let theme = Theme::load_from("icon-finder-test", &*fs).unwrap();
+
+        let options = LookupOptions::new(48)
+            .flags(LookupFlags::DIR_RTL)
+            .with_file_system(Some(&fs));
+        assert_eq!(
+            find_icon_in("go-next", &options, &theme, &[]).unwrap().path,
+            Path::new("/usr/share/icons/icon-finder-test/48x48/apps/go-next-rtl.png")
+        );
+        let options = options.flags(LookupFlags::DIR_LTR);
+        assert_eq!(
+            find_icon_in("go-next", &options, &theme, &[]).unwrap().path,
+            Path::new("/usr/share/icons/icon-finder-test/48x48/apps/go-next.png")
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "svg")]
+    fn test_find_icon_gtk_compatible() {
+        let fs: Arc<dyn FileSystem> = memory_themes();
+        let theme = Theme::load_from("icon-finder-test", &*fs).unwrap();
+        let find = |icon: &str, size: i16, lookup_profile| {
+            let options = LookupOptions::new(size)
+                .lookup_profile(lookup_profile)
+                .with_file_system(Some(&fs));
+            find_icon_in(icon, &options, &theme, &[])
+        };
+
+        // GTK scales the larger scalable icon down instead of the 16x16 icon
+        // up, and falls back to regular icons.
+        let spec = LookupProfile::default();
+        let gtk = LookupProfile::GtkCompatible;
+        assert_eq!(find("editor", 20, spec).unwrap().format, IconFormat::Png);
+        assert_eq!(find("editor", 20, gtk).unwrap().format, IconFormat::Svg);
+        assert_eq!(
+            find("editor-symbolic", 48, gtk).unwrap().path,
+            Path::new("/usr/share/icons/icon-finder-test/48x48/apps/editor.png")
+        );
+        assert_eq!(find("editor-symbolic", 48, spec), None);
+    }
+
+    #[test]
+    #[cfg(feature = "svg")]
+    fn test_closest_match_policy() {
+        let fs: Arc<dyn FileSystem> = memory_themes();
+        let theme = Theme::load_from("icon-finder-test", &*fs).unwrap();
+        let closest = |size: i16, policy| {
+            let options = LookupOptions::new(size)
+                .closest_match_policy(policy)
+                .with_file_system(Some(&fs));
+            find_icon_in("editor", &options, &theme, &[]).unwrap().path
+        };
+
+        let test_theme = "/usr/share/icons/icon-finder-test";
+        assert_eq!(
+            closest(40, ClosestMatchPolicy::Nearest),
+            format!("{}/48x48/apps/editor.png", test_theme)
+        );
+        assert_eq!(
+            closest(40, ClosestMatchPolicy::PreferScalable),
+            format!("{}/scalable/apps/editor.svg", test_theme)
+        );
+        assert_eq!(
+            closest(20, ClosestMatchPolicy::Nearest),
+            format!("{}/16x16/apps/editor.png", test_theme)
+        );
+        assert_eq!(
+            closest(20, ClosestMatchPolicy::PreferLarger),
+            format!("{}/scalable/apps/editor.svg", test_theme)
+        );
+    }
+
+    #[test]
+    fn test_theme_on_memory_file_system() {
+        let fs: Arc<dyn FileSystem> = memory_themes();
+        let theme = Theme::load_from("icon-finder-test", &*fs).unwrap();
+
+        assert_eq!(
+            theme.locations_from(&*fs),
+            [PathBuf::from("/usr/share/icons/icon-finder-test")]
+        );
+        assert!(theme.locations().is_empty());
+        assert_eq!(
+            theme.icons_from(&fs),
+            ["browser", "editor", "go-next", "go-next-rtl"]
+        );
+        assert!(theme.icons().is_empty());
+    }
+
+    #[test]
+    fn test_exact_only() {
+        let fs: Arc<dyn FileSystem> = memory_themes();
+        let theme = Theme::load_from("icon-finder-test", &*fs).unwrap();
+        let exact_only = |icon: &str, size: i16| {
+            let options = LookupOptions::new(size)
+                .flags(LookupFlags::EXACT_ONLY)
+                .with_file_system(Some(&fs));
+            find_icon_in(icon, &options, &theme, &[])
+        };
+
+        assert_eq!(
+            exact_only("editor", 48).unwrap().match_kind,
+            MatchKind::Exact
+        );
+        assert_eq!(exact_only("editor", 40), None);
+        // Unthemed icons have no size to match.
+        assert_eq!(exact_only("icon-finder-legacy", 16), None);
+    }
+
+    #[test]
+    fn test_cancelled_lookup() {
+        let fs: Arc<dyn FileSystem> = memory_themes();
+        let theme = Theme::load_from("icon-finder-test", &*fs).unwrap();
+        let finder = IconFinder::builder(theme)
+            .file_system(fs)
+            .cache_capacity(16)
+            .build();
+
+        let token = CancellationToken::new();
+        let options = LookupOptions::new(48).cancellation(&token);
+        token.cancel();
+        assert_eq!(finder.lookup_icon_with("editor", &options), None);
+
+        // The cancelled lookup was not remembered as missing.
+        assert!(finder.find_icon("editor", 48, 1).is_some());
+    }
+
     #[test]
     fn test_directory_matches_size_different_scale() {
         let theme_directory = ThemeDirectory {
             name: "Main".to_owned(),
             size: 512,
             scale: Some(1),
-            context: Some("actions".to_owned()),
+            context: Some(Context::Actions),
             r#type: ThemeDirectoryType::Fixed,
             min_size: None,
             max_size: None,
             threshold: None,
         };
 
-        assert_eq!(directory_matches_size(&theme_directory, 512, 2), false);
+        assert!(!directory_matches_size(&theme_directory, 512, 2));
     }
 
     #[test]
@@ -380,15 +1909,15 @@ mod tests {
             name: "Main".to_owned(),
             size: 512,
             scale: Some(1),
-            context: Some("actions".to_owned()),
+            context: Some(Context::Actions),
             r#type: ThemeDirectoryType::Fixed,
             min_size: None,
             max_size: None,
             threshold: None,
         };
 
-        assert_eq!(directory_matches_size(&theme_directory, 512, 1), true);
-        assert_eq!(directory_matches_size(&theme_directory, 256, 1), false);
+        assert!(directory_matches_size(&theme_directory, 512, 1));
+        assert!(!directory_matches_size(&theme_directory, 256, 1));
     }
 
     #[test]
@@ -397,19 +1926,19 @@ mod tests {
             name: "Main".to_owned(),
             size: 512,
             scale: Some(1),
-            context: Some("actions".to_owned()),
+            context: Some(Context::Actions),
             r#type: ThemeDirectoryType::Scalable,
             min_size: Some(256),
             max_size: Some(1024),
             threshold: None,
         };
 
-        assert_eq!(directory_matches_size(&theme_directory, 128, 1), false);
-        assert_eq!(directory_matches_size(&theme_directory, 256, 1), true);
-        assert_eq!(directory_matches_size(&theme_directory, 511, 1), true);
-        assert_eq!(directory_matches_size(&theme_directory, 512, 1), true);
-        assert_eq!(directory_matches_size(&theme_directory, 1024, 1), true);
-        assert_eq!(directory_matches_size(&theme_directory, 2048, 1), false);
+        assert!(!directory_matches_size(&theme_directory, 128, 1));
+        assert!(directory_matches_size(&theme_directory, 256, 1));
+        assert!(directory_matches_size(&theme_directory, 511, 1));
+        assert!(directory_matches_size(&theme_directory, 512, 1));
+        assert!(directory_matches_size(&theme_directory, 1024, 1));
+        assert!(!directory_matches_size(&theme_directory, 2048, 1));
     }
 
     #[test]
@@ -418,18 +1947,362 @@ mod tests {
             name: "Main".to_owned(),
             size: 512,
             scale: Some(1),
-            context: Some("actions".to_owned()),
+            context: Some(Context::Actions),
             r#type: ThemeDirectoryType::Threshold,
             min_size: Some(256),
             max_size: Some(1024),
             threshold: Some(128),
         };
 
-        assert_eq!(directory_matches_size(&theme_directory, 128, 1), false);
-        assert_eq!(directory_matches_size(&theme_directory, 384, 1), true);
-        assert_eq!(directory_matches_size(&theme_directory, 512, 1), true);
-        assert_eq!(directory_matches_size(&theme_directory, 640, 1), true);
-        assert_eq!(directory_matches_size(&theme_directory, 1025, 1), false);
+        assert!(!directory_matches_size(&theme_directory, 128, 1));
+        assert!(directory_matches_size(&theme_directory, 384, 1));
+        assert!(directory_matches_size(&theme_directory, 512, 1));
+        assert!(directory_matches_size(&theme_directory, 640, 1));
+        assert!(!directory_matches_size(&theme_directory, 1025, 1));
+    }
+
+    #[test]
+    fn test_generic_fallback_names() {
+        assert_eq!(
+            generic_fallback_names("network-wireless-signal-excellent"),
+            vec![
+                "network-wireless-signal-excellent",
+                "network-wireless-signal",
+                "network-wireless",
+                "network",
+            ]
+        );
+        assert_eq!(
+            generic_fallback_names("folder-open-symbolic"),
+            vec!["folder-open-symbolic", "folder-symbolic"]
+        );
+        assert_eq!(generic_fallback_names("firefox"), vec!["firefox"]);
+    }
+
+    #[test]
+    fn test_context_directories() {
+        let directory = |name: &str, context: Option<Context>| ThemeDirectory {
+            name: name.to_owned(),
+            size: 48,
+            scale: None,
+            context,
+            r#type: ThemeDirectoryType::Threshold,
+            min_size: None,
+            max_size: None,
+            threshold: None,
+        };
+        let theme = Theme {
+            name: "Test".to_owned(),
+            comment: "Test theme".to_owned(),
+            inherits: Vec::new(),
+            directories: vec![
+                directory("48x48/apps", Some(Context::Applications)),
+                directory("48x48/mimetypes", Some(Context::MimeTypes)),
+                directory("48x48/misc", None),
+            ],
+            hidden: false,
+            translations: Translations::default(),
+            example: None,
+        };
+
+        let names = |context| -> Vec<String> {
+            context_directories(&theme, context)
+                .map(|subdir| subdir.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names(Some(&Context::MimeTypes)), vec!["48x48/mimetypes"]);
+        assert_eq!(names(None).len(), 3);
+    }
+
+    #[test]
+    fn test_inherits_from() {
+        let theme = |name: &str, inherits| Theme {
+            name: name.to_owned(),
+            comment: String::new(),
+            inherits,
+            directories: Vec::new(),
+            hidden: false,
+            translations: Translations::default(),
+            example: None,
+        };
+        let papirus = theme(
+            "Papirus",
+            vec![Arc::new(theme(
+                "breeze",
+                vec![Arc::new(theme(FALLBACK_THEME, Vec::new()))],
+            ))],
+        );
+
+        assert!(inherits_from(&papirus, "Papirus"));
+        assert!(inherits_from(&papirus, FALLBACK_THEME));
+        assert!(!inherits_from(&papirus, "Adwaita"));
+        assert!(fallback_themes(&papirus, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_fallback_themes() {
+        let theme = |name: &str, inherits| Theme {
+            name: name.to_owned(),
+            comment: String::new(),
+            inherits,
+            directories: Vec::new(),
+            hidden: false,
+            translations: Translations::default(),
+            example: None,
+        };
+        let hicolor = Arc::new(theme(FALLBACK_THEME, Vec::new()));
+        let papirus = theme("Papirus", Vec::new());
+        let names = |themes: Vec<&Theme>| -> Vec<String> {
+            themes.iter().map(|theme| theme.name.clone()).collect()
+        };
+
+        assert_eq!(names(fallback_themes(&papirus, &[])), vec![FALLBACK_THEME]);
+
+        let breeze = Arc::new(theme("breeze", vec![hicolor]));
+        assert_eq!(
+            names(fallback_themes(&papirus, std::slice::from_ref(&breeze))),
+            vec!["breeze"]
+        );
+
+        let fallbacks = [
+            Arc::new(theme("Adwaita", Vec::new())),
+            Arc::new(theme("Papirus", Vec::new())),
+            breeze,
+        ];
+        assert_eq!(
+            names(fallback_themes(&papirus, &fallbacks)),
+            vec!["Adwaita", "breeze"]
+        );
+    }
+
+    #[test]
+    fn test_ancestry() {
+        let theme = |name: &str, inherits| {
+            Arc::new(Theme {
+                name: name.to_owned(),
+                comment: String::new(),
+                inherits,
+                directories: Vec::new(),
+                hidden: false,
+                translations: Translations::default(),
+                example: None,
+            })
+        };
+        let names = |theme: &Theme| -> Vec<String> {
+            theme.ancestry().map(|theme| theme.name.clone()).collect()
+        };
+
+        let adwaita = theme("Adwaita", Vec::new());
+        let breeze = theme("breeze", vec![Arc::clone(&adwaita)]);
+        let papirus = theme("Papirus", vec![breeze, adwaita]);
+        assert_eq!(
+            names(&papirus),
+            ["Papirus", "breeze", "Adwaita", FALLBACK_THEME]
+        );
+        let chain: Vec<&str> = papirus
+            .inheritance_chain()
+            .iter()
+            .map(|theme| theme.name.as_str())
+            .collect();
+        assert_eq!(chain, ["Papirus", "breeze", "Adwaita"]);
+
+        let hicolor = theme(FALLBACK_THEME, Vec::new());
+        let early = theme("early", vec![hicolor, theme("late", Vec::new())]);
+        assert_eq!(names(&early), ["early", FALLBACK_THEME, "late"]);
+    }
+
+    #[test]
+    fn test_load_inheritance_cycle() {
+        let read_index = |name: &str| {
+            let inherits = match name {
+                "A" => "B,hicolor",
+                "B" => "C",
+                "C" => "A,B,C,hicolor",
+                "hicolor" => "",
+                _ => return Err(Error::ThemeNotFound(name.to_owned())),
+            };
+
+            IndexTheme::parse(&format!(
+                "[Icon Theme]\nName={}\nInherits={}\n",
+                name, inherits
+            ))
+        };
+
+        let theme = Theme::load_with("A", &read_index, &LoadedThemes::default(), &[]).unwrap();
+        let names = |theme: &Theme| -> Vec<String> {
+            theme
+                .inherits
+                .iter()
+                .map(|parent| parent.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names(&theme), vec!["B", "hicolor"]);
+        assert_eq!(names(&theme.inherits[0]), vec!["C"]);
+        assert_eq!(names(&theme.inherits[0].inherits[0]), vec!["hicolor"]);
+        // hicolor is loaded once and shared.
+        assert!(Arc::ptr_eq(
+            &theme.inherits[1],
+            &theme.inherits[0].inherits[0].inherits[0]
+        ));
+    }
+
+    #[test]
+    fn test_display_name() {
+        let mut theme = Theme {
+            name: "breeze-dark".to_owned(),
+            comment: "Breeze Dark by the KDE VDG".to_owned(),
+            inherits: Vec::new(),
+            directories: Vec::new(),
+            hidden: false,
+            translations: Translations {
+                name: "Breeze Dark".to_owned(),
+                names: vec![("nl".to_owned(), "Breeze Donker".to_owned())],
+                comments: vec![("nl".to_owned(), "Breeze Donker door de KDE VDG".to_owned())],
+            },
+            example: None,
+        };
+
+        assert_eq!(theme.display_name(Some("nl_NL.UTF-8")), "Breeze Donker");
+        assert_eq!(theme.display_name(Some("fr_FR.UTF-8")), "Breeze Dark");
+        assert_eq!(
+            theme.comment(Some("nl_BE")),
+            "Breeze Donker door de KDE VDG"
+        );
+        assert_eq!(theme.comment(Some("C")), "Breeze Dark by the KDE VDG");
+
+        theme.translations = Translations::default();
+        assert_eq!(theme.display_name(Some("nl_NL.UTF-8")), "breeze-dark");
+    }
+
+    #[test]
+    fn test_context_from_str() {
+        assert_eq!(Context::from("MimeTypes"), Context::MimeTypes);
+        assert_eq!(
+            Context::from("FileSystems"),
+            Context::Other("FileSystems".to_owned())
+        );
+        assert_eq!(Context::from("FileSystems").as_str(), "FileSystems");
+    }
+
+    #[test]
+    fn test_directory_physical_distance() {
+        let directory = |size, scale, r#type| ThemeDirectory {
+            name: "Main".to_owned(),
+            size,
+            scale: Some(scale),
+            context: None,
+            r#type,
+            min_size: Some(16),
+            max_size: Some(64),
+            threshold: Some(2),
+        };
+
+        let fixed = directory(48, 1, ThemeDirectoryType::Fixed);
+        assert_eq!(directory_physical_distance(&fixed, 60.0), 12.0);
+
+        let fixed_scaled = directory(48, 2, ThemeDirectoryType::Fixed);
+        assert_eq!(directory_physical_distance(&fixed_scaled, 72.0), 24.0);
+
+        let scalable = directory(48, 1, ThemeDirectoryType::Scalable);
+        assert_eq!(directory_physical_distance(&scalable, 60.0), 0.0);
+        assert_eq!(directory_physical_distance(&scalable, 72.0), 8.0);
+
+        let threshold = directory(64, 1, ThemeDirectoryType::Threshold);
+        assert_eq!(directory_physical_distance(&threshold, 60.0), 4.0);
+        assert_eq!(directory_physical_distance(&threshold, 63.0), 0.0);
+    }
+
+    #[test]
+    fn test_closest_file() {
+        let directory = |size, r#type| ThemeDirectory {
+            name: format!("{}x{}/apps", size, size),
+            size,
+            scale: None,
+            context: None,
+            r#type,
+            min_size: None,
+            max_size: None,
+            threshold: None,
+        };
+        let theme = Theme {
+            name: FALLBACK_THEME.to_owned(),
+            comment: String::new(),
+            inherits: Vec::new(),
+            directories: vec![
+                directory(16, ThemeDirectoryType::Fixed),
+                directory(48, ThemeDirectoryType::Fixed),
+            ],
+            hidden: false,
+            translations: Translations::default(),
+            example: None,
+        };
+        let files = vec![
+            (
+                &theme.directories[0],
+                PathBuf::from("small.png"),
+                IconFormat::Png,
+            ),
+            (
+                &theme.directories[1],
+                PathBuf::from("large.png"),
+                IconFormat::Png,
+            ),
+        ];
+
+        let result = closest_file(&files, 48, &LookupOptions::new(0), &theme).unwrap();
+        assert_eq!(result.path, Path::new("large.png"));
+        assert_eq!(result.match_kind, MatchKind::Exact);
+
+        let result = closest_file(&files, 22, &LookupOptions::new(0), &theme).unwrap();
+        assert_eq!(result.path, Path::new("small.png"));
+        assert_eq!(result.match_kind, MatchKind::Closest);
+
+        // Ties go to the first directory, like in lookup_icon.
+        let result = closest_file(&files, 32, &LookupOptions::new(0), &theme).unwrap();
+        assert_eq!(result.path, Path::new("small.png"));
+
+        let exact_only = LookupOptions::new(0).flags(LookupFlags::EXACT_ONLY);
+        assert_eq!(closest_file(&files, 32, &exact_only, &theme), None);
+    }
+
+    #[test]
+    fn test_find_icon_sizes_follows_lookup() {
+        let fixture = Fixture::in_memory(&[ThemeFixture::new("icon-finder-sizes")
+            .fixed_directory("16x16/apps", 16)
+            .fixed_directory("24x24/apps", 24)
+            .fixed_directory("32x32/apps", 32)
+            .scale(2)
+            .fixed_directory("48x48/apps", 48)
+            .icon("16x16/apps/editor.png")
+            .icon("24x24/apps/editor.png")
+            .icon("32x32/apps/editor.png")
+            .icon("48x48/apps/editor.png")]);
+        let theme = fixture.load_theme("icon-finder-sizes").unwrap();
+        let options = fixture.lookup_options();
+        let sizes = [8, 16, 20, 28, 36, 40, 64];
+
+        for options in [
+            options.clone(),
+            options.clone().lookup_profile(LookupProfile::GtkCompatible),
+            options.clone().fractional_scale(1.5),
+            options.clone().scale(2),
+        ] {
+            let results = find_icon_sizes_in("editor", &sizes, &options, &theme, &[]);
+            for (&size, result) in sizes.iter().zip(results) {
+                let options = LookupOptions {
+                    size,
+                    ..options.clone()
+                };
+                assert_eq!(
+                    result,
+                    find_icon_in("editor", &options, &theme, &[]),
+                    "{:?}",
+                    options
+                );
+            }
+        }
     }
 
     // Tests for directory_size_difference
@@ -439,7 +2312,7 @@ mod tests {
             name: "Main".to_owned(),
             size: 512,
             scale: Some(1),
-            context: Some("actions".to_owned()),
+            context: Some(Context::Actions),
             r#type: ThemeDirectoryType::Fixed,
             min_size: Some(256),
             max_size: Some(1024),
@@ -450,7 +2323,6 @@ mod tests {
         assert_eq!(directory_size_distance(&theme_directory, 256, 2), 0);
         assert_eq!(directory_size_distance(&theme_directory, 100, 1), 412);
         assert_eq!(directory_size_distance(&theme_directory, 1512, 1), 1000);
-
     }
 
     #[test]
@@ -459,7 +2331,7 @@ mod tests {
             name: "Main".to_owned(),
             size: 512,
             scale: Some(1),
-            context: Some("actions".to_owned()),
+            context: Some(Context::Actions),
             r#type: ThemeDirectoryType::Scalable,
             min_size: Some(256),
             max_size: Some(1024),
@@ -480,7 +2352,7 @@ mod tests {
             name: "Main".to_owned(),
             size: 512,
             scale: Some(1),
-            context: Some("actions".to_owned()),
+            context: Some(Context::Actions),
             r#type: ThemeDirectoryType::Threshold,
             min_size: Some(256),
             max_size: Some(1024),
@@ -492,5 +2364,53 @@ mod tests {
         assert_eq!(directory_size_distance(&theme_directory, 512, 1), 0);
         assert_eq!(directory_size_distance(&theme_directory, 640, 1), 0);
         assert_eq!(directory_size_distance(&theme_directory, 768, 1), 256);
+
+        // Saturates instead of overflowing.
+        assert_eq!(
+            directory_size_distance(&theme_directory, 48, i16::MAX),
+            i16::MAX
+        );
+    }
+
+    #[test]
+    fn test_distance_strategies() {
+        let theme_directory = ThemeDirectory {
+            name: "Main".to_owned(),
+            size: 512,
+            scale: Some(1),
+            context: Some(Context::Actions),
+            r#type: ThemeDirectoryType::Threshold,
+            min_size: Some(256),
+            max_size: Some(1024),
+            threshold: Some(128),
+        };
+        let distance =
+            |strategy: DistanceStrategy, size| strategy.distance(&theme_directory, size, 1);
+
+        assert_eq!(distance(DistanceStrategy::Corrected, 256), 256);
+        assert_eq!(distance(DistanceStrategy::Corrected, 768), 256);
+        assert_eq!(distance(DistanceStrategy::SpecPseudocode, 256), 0);
+        assert_eq!(distance(DistanceStrategy::SpecPseudocode, 768), 0);
+        assert_eq!(distance(DistanceStrategy::SpecPseudocode, 1280), 256);
+        assert_eq!(distance(DistanceStrategy::SpecPseudocode, 640), 0);
+
+        // Without MinSize and MaxSize, both measure against the nominal size.
+        let theme_directory = ThemeDirectory {
+            min_size: None,
+            max_size: None,
+            ..theme_directory
+        };
+        assert_eq!(
+            DistanceStrategy::SpecPseudocode.distance(&theme_directory, 256, 1),
+            DistanceStrategy::Corrected.distance(&theme_directory, 256, 1)
+        );
+
+        fn fixed_distance(_: &ThemeDirectory, _: i16, _: i16) -> i16 {
+            7
+        }
+        assert_eq!(
+            DistanceStrategy::Custom(fixed_distance).distance(&theme_directory, 256, 1),
+            7
+        );
     }
 }
@@ -0,0 +1,517 @@
+//! # Directory listings
+//! Instead of checking whether a file exists for every format an icon may come
+//! in, every directory is listed once and its listing is kept around. Adding
+//! or removing files changes the modification time of a directory, in which
+//! case the directory is listed again. The modification time is only looked
+//! at again once the revalidation interval passed, [`POLL_INTERVAL`] unless a
+//! finder sets its own. Every file system has listings of its own, which are
+//! dropped together with the file system.
+//!
+//! File names are kept as the file system returns them, so icons whose file
+//! names are not valid UTF-8 are still found. Such icons are listed under the
+//! name their file name reads as with invalid bytes replaced, which is also
+//! the name they are looked up by.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::filesystem::FileSystem;
+use crate::warning;
+use crate::{IconFormat, LookupOptions, POLL_INTERVAL};
+
+static LISTINGS: Mutex<Vec<Listings>> = Mutex::new(Vec::new());
+
+/// The listings of the directories of a file system. The file system is only
+/// referenced weakly, so its address is not reused while the listings exist.
+struct Listings {
+    file_system: Weak<dyn FileSystem>,
+    directories: HashMap<PathBuf, Listed>,
+}
+
+/// The listing of a directory, with when its modification time was last
+/// looked at.
+struct Listed {
+    checked: Instant,
+    listing: Arc<Listing>,
+}
+
+/// How long listings are used before the modification times of their
+/// directories are looked at again. Lookups with any interval share cached
+/// results, the interval is not part of the options they are cached by.
+#[derive(Clone, Copy)]
+pub(crate) struct RevalidationInterval(pub(crate) Duration);
+
+impl Default for RevalidationInterval {
+    fn default() -> RevalidationInterval {
+        RevalidationInterval(POLL_INTERVAL)
+    }
+}
+
+impl PartialEq for RevalidationInterval {
+    fn eq(&self, _: &RevalidationInterval) -> bool {
+        true
+    }
+}
+
+impl Eq for RevalidationInterval {}
+
+impl Hash for RevalidationInterval {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl fmt::Debug for RevalidationInterval {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// The icon files in a directory, grouped by icon name.
+#[derive(Debug, Default)]
+struct Listing {
+    modified: Option<SystemTime>,
+    icons: HashMap<String, Vec<(IconFormat, OsString)>>,
+}
+
+impl Listing {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(file_system, modified))
+    )]
+    fn read(
+        file_system: &dyn FileSystem,
+        directory: &Path,
+        modified: Option<SystemTime>,
+    ) -> io::Result<Listing> {
+        let mut listing = Listing {
+            modified,
+            icons: HashMap::new(),
+        };
+
+        for file_name in file_system.read_dir(directory)? {
+            if let Some((icon, format)) = split_file_name(&file_name) {
+                listing
+                    .icons
+                    .entry(icon.into_owned())
+                    .or_default()
+                    .push((format, file_name));
+            }
+        }
+
+        Ok(listing)
+    }
+}
+
+/// The empty listing of a directory that could not be read.
+fn unreadable(directory: &Path, error: &io::Error) -> Arc<Listing> {
+    #[cfg(feature = "log")]
+    log::trace!("could not read {}: {}", directory.display(), error);
+    warning::warn_unreadable(directory, error);
+
+    Arc::default()
+}
+
+/// Splits a file name into the icon name and format. Icon names may contain
+/// dots themselves, like "org.gnome.Nautilus.svg", so only the last dot
+/// separates the extension. Invalid UTF-8 in the icon name is replaced.
+fn split_file_name(file_name: &OsStr) -> Option<(Cow<'_, str>, IconFormat)> {
+    let path = Path::new(file_name);
+    let format = IconFormat::from_extension(path.extension()?.to_str()?)?;
+    let icon = path.file_stem()?;
+    if icon.is_empty() {
+        return None;
+    }
+
+    Some((icon.to_string_lossy(), format))
+}
+
+/// Runs `function` on the listings of the file system, holding the lock.
+/// Listings of file systems that were dropped are forgotten on the way.
+fn with_listings<T>(
+    file_system: &Arc<dyn FileSystem>,
+    function: impl FnOnce(&mut HashMap<PathBuf, Listed>) -> T,
+) -> T {
+    let mut all: MutexGuard<'_, Vec<Listings>> =
+        LISTINGS.lock().unwrap_or_else(|error| error.into_inner());
+    let position = all.iter().position(|listings| {
+        listings.file_system.as_ptr() as *const () == Arc::as_ptr(file_system) as *const ()
+    });
+
+    let position = match position {
+        Some(position) => position,
+        None => {
+            all.retain(|listings| listings.file_system.strong_count() > 0);
+            all.push(Listings {
+                file_system: Arc::downgrade(file_system),
+                directories: HashMap::new(),
+            });
+            all.len() - 1
+        }
+    };
+
+    function(&mut all[position].directories)
+}
+
+/// The listing of the directory, read again if the directory changed since
+/// it was last listed. Listings checked less than `interval` ago are used
+/// without looking at the directory.
+fn listing(
+    file_system: &Arc<dyn FileSystem>,
+    directory: &Path,
+    interval: Duration,
+) -> Arc<Listing> {
+    let cached = with_listings(file_system, |listings| {
+        listings.get(directory).map(|listed| {
+            let recent = listed.checked.elapsed() < interval;
+            (recent, Arc::clone(&listed.listing))
+        })
+    });
+    if let Some((true, listing)) = cached {
+        return listing;
+    }
+
+    let modified = match file_system.modified(directory) {
+        Ok(modified) => modified,
+        // Missing directories are common, every theme only exists in some of
+        // the base directories. These are not remembered.
+        Err(error) => return unreadable(directory, &error),
+    };
+
+    let listing = match cached {
+        Some((_, listing)) if listing.modified == Some(modified) => listing,
+        // The directory is read without holding the lock, so lookups in other
+        // directories are not blocked.
+        _ => match Listing::read(&**file_system, directory, Some(modified)) {
+            Ok(listing) => Arc::new(listing),
+            // Not remembered either, fixing the permissions of a directory
+            // does not change its modification time.
+            Err(error) => return unreadable(directory, &error),
+        },
+    };
+
+    with_listings(file_system, |listings| {
+        listings.insert(
+            directory.to_owned(),
+            Listed {
+                checked: Instant::now(),
+                listing: Arc::clone(&listing),
+            },
+        )
+    });
+    listing
+}
+
+/// Forgets the listings of the directories of the file system matching
+/// `forget`, returning the directories in alphabetical order.
+pub(crate) fn forget_listings(
+    file_system: &Arc<dyn FileSystem>,
+    forget: impl Fn(&Path) -> bool,
+) -> Vec<PathBuf> {
+    let mut forgotten = Vec::new();
+    with_listings(file_system, |listings| {
+        listings.retain(|directory, _| {
+            let keep = !forget(directory);
+            if !keep {
+                forgotten.push(directory.clone());
+            }
+            keep
+        })
+    });
+    forgotten.sort();
+    forgotten
+}
+
+/// Lists the directories matching `refresh` again right away if they changed
+/// since they were listed, instead of on their next lookup. Returns the
+/// changed directories in alphabetical order, directories that can no longer
+/// be read are forgotten.
+pub(crate) fn refresh_listings(
+    file_system: &Arc<dyn FileSystem>,
+    refresh: impl Fn(&Path) -> bool,
+) -> Vec<PathBuf> {
+    let listed: Vec<(PathBuf, Option<SystemTime>)> = with_listings(file_system, |listings| {
+        listings
+            .iter()
+            .filter(|(directory, _)| refresh(directory))
+            .map(|(directory, listed)| (directory.clone(), listed.listing.modified))
+            .collect()
+    });
+
+    // The directories are looked at without holding the lock, like in
+    // lookups.
+    let mut changed: Vec<PathBuf> = listed
+        .into_iter()
+        .filter(|(directory, modified)| file_system.modified(directory).ok() != *modified)
+        .map(|(directory, _)| directory)
+        .collect();
+    changed.sort();
+
+    for directory in &changed {
+        with_listings(file_system, |listings| listings.remove(directory));
+        listing(file_system, directory, Duration::ZERO);
+    }
+
+    changed
+}
+
+/// The listing of the directory in the file system of the lookup.
+fn directory_listing(directory: &Path, options: &LookupOptions) -> Arc<Listing> {
+    listing(
+        options.shared_file_system(),
+        directory,
+        options.revalidation_interval.0,
+    )
+}
+
+/// The files of the icon in the directory, in the order of the allowed
+/// formats.
+pub(crate) fn icon_files(
+    directory: &Path,
+    icon: &str,
+    options: &LookupOptions,
+) -> Vec<(PathBuf, IconFormat)> {
+    let listing = directory_listing(directory, options);
+    let files = match listing.icons.get(icon) {
+        Some(files) => files,
+        None => return Vec::new(),
+    };
+
+    options
+        .allowed_formats()
+        .filter_map(|format| {
+            files
+                .iter()
+                .find(|(file_format, _)| *file_format == format)
+                .map(|(_, file_name)| (directory.join(file_name), format))
+        })
+        .collect()
+}
+
+/// The names of the icons in the directory with a file in one of the allowed
+/// formats, in no particular order.
+pub(crate) fn icon_names(directory: &Path, options: &LookupOptions) -> Vec<String> {
+    let formats: Vec<IconFormat> = options.allowed_formats().collect();
+
+    directory_listing(directory, options)
+        .icons
+        .iter()
+        .filter(|(_, files)| files.iter().any(|(format, _)| formats.contains(format)))
+        .map(|(icon, _)| icon.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFileSystem;
+
+    #[test]
+    fn test_split_file_name() {
+        fn split(file_name: &str) -> Option<(Cow<'_, str>, IconFormat)> {
+            split_file_name(OsStr::new(file_name))
+        }
+
+        assert_eq!(
+            split("firefox.png"),
+            Some((Cow::from("firefox"), IconFormat::Png))
+        );
+        assert_eq!(
+            split("org.gnome.Nautilus.png"),
+            Some((Cow::from("org.gnome.Nautilus"), IconFormat::Png))
+        );
+        assert_eq!(split("index.theme"), None);
+        assert_eq!(split(".png"), None);
+        assert_eq!(split("README"), None);
+    }
+
+    #[test]
+    fn test_icon_files() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file("/icons/firefox.png", "");
+        fs.add_file("/icons/firefox.xpm", "");
+        fs.add_file("/icons/firefox-symbolic.png", "");
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+
+        let options = LookupOptions::new(48)
+            .formats(&[IconFormat::Png])
+            .with_file_system(Some(&fs));
+        let directory = Path::new("/icons");
+        assert_eq!(
+            icon_files(directory, "firefox", &options),
+            vec![(PathBuf::from("/icons/firefox.png"), IconFormat::Png)]
+        );
+
+        assert!(icon_files(directory, "thunderbird", &options).is_empty());
+        assert!(icon_files(Path::new("/nonexistent"), "firefox", &options).is_empty());
+
+        let mut names = icon_names(directory, &options);
+        names.sort();
+        assert_eq!(names, vec!["firefox", "firefox-symbolic"]);
+
+        // The files of another file system are not visible.
+        let empty: Arc<dyn FileSystem> = Arc::new(MemoryFileSystem::new());
+        let options = options.with_file_system(Some(&empty));
+        assert!(icon_files(directory, "firefox", &options).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_file_names() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let directory = Path::new(OsStr::from_bytes(b"/home/j\xf6rg/.icons"));
+        let file = directory.join(OsStr::from_bytes(b"caf\xe9.png"));
+        let fs = MemoryFileSystem::new();
+        fs.add_file(&file, "");
+        let fs: Arc<dyn FileSystem> = Arc::new(fs);
+        let options = LookupOptions::new(48).with_file_system(Some(&fs));
+
+        assert_eq!(icon_names(directory, &options), vec!["caf\u{fffd}"]);
+        assert_eq!(
+            icon_files(directory, "caf\u{fffd}", &options),
+            vec![(file, IconFormat::Png)]
+        );
+    }
+
+    #[test]
+    fn test_refresh_listings() {
+        use std::{env, fs, process};
+
+        let directory = env::temp_dir().join(format!("icon-finder-listings-{}", process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("firefox.png"), "").unwrap();
+        let file_system = crate::filesystem::file_system();
+        let options = LookupOptions::new(48);
+        let in_directory = |path: &Path| path == directory;
+
+        assert_eq!(icon_names(&directory, &options), ["firefox"]);
+        assert!(refresh_listings(file_system, in_directory).is_empty());
+
+        // Set the modification time explicitly, file systems with a coarse
+        // clock may not change it when adding the file.
+        fs::write(directory.join("thunderbird.png"), "").unwrap();
+        fs::File::open(&directory)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        assert_eq!(
+            refresh_listings(file_system, in_directory),
+            [directory.as_path()]
+        );
+        assert_eq!(icon_names(&directory, &options).len(), 2);
+
+        assert_eq!(
+            forget_listings(file_system, in_directory),
+            [directory.as_path()]
+        );
+        assert!(with_listings(file_system, |listings| !listings.contains_key(&directory)));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_listings_per_file_system() {
+        let first = MemoryFileSystem::new();
+        first.add_file("/icons/firefox.png", "");
+        let first: Arc<dyn FileSystem> = Arc::new(first);
+        let second: Arc<dyn FileSystem> = Arc::new(MemoryFileSystem::new());
+        let directory = Path::new("/icons");
+        let names = |file_system: &Arc<dyn FileSystem>, interval| {
+            let options = LookupOptions::new(48)
+                .with_file_system(Some(file_system))
+                .with_revalidation_interval(interval);
+            let mut names = icon_names(directory, &options);
+            names.sort();
+            names
+        };
+
+        assert_eq!(names(&first, POLL_INTERVAL), ["firefox"]);
+        assert!(names(&second, POLL_INTERVAL).is_empty());
+
+        // The listing is trusted until the interval passed.
+        let memory = Arc::new(MemoryFileSystem::new());
+        memory.add_file("/icons/firefox.png", "");
+        let file_system: Arc<dyn FileSystem> = memory.clone();
+        assert_eq!(names(&file_system, POLL_INTERVAL), ["firefox"]);
+        memory.add_file("/icons/thunderbird.png", "");
+        assert_eq!(names(&file_system, POLL_INTERVAL), ["firefox"]);
+        assert_eq!(
+            names(&file_system, Duration::ZERO),
+            ["firefox", "thunderbird"]
+        );
+
+        // Listings are dropped together with their file system.
+        let weak = Arc::downgrade(&first);
+        drop(first);
+        let third: Arc<dyn FileSystem> = Arc::new(MemoryFileSystem::new());
+        names(&third, POLL_INTERVAL);
+        assert!(LISTINGS
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|listings| !listings.file_system.ptr_eq(&weak)));
+    }
+
+    /// A file system that cannot read one of its directories.
+    struct DeniedFileSystem(MemoryFileSystem, &'static str);
+
+    impl FileSystem for DeniedFileSystem {
+        fn exists(&self, path: &Path) -> bool {
+            self.0.exists(path)
+        }
+
+        fn is_dir(&self, path: &Path) -> bool {
+            self.0.is_dir(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> io::Result<Vec<std::ffi::OsString>> {
+            if path == Path::new(self.1) {
+                return Err(io::ErrorKind::PermissionDenied.into());
+            }
+            self.0.read_dir(path)
+        }
+
+        fn read_to_string(&self, path: &Path) -> io::Result<String> {
+            self.0.read_to_string(path)
+        }
+
+        fn modified(&self, path: &Path) -> io::Result<SystemTime> {
+            self.0.modified(path)
+        }
+    }
+
+    #[test]
+    fn test_unreadable_directory() {
+        use crate::warning::capture_warnings;
+        use crate::Warning;
+
+        let memory = MemoryFileSystem::new();
+        memory.add_file("/denied/firefox.png", "");
+        memory.add_file("/allowed/firefox.png", "");
+        let fs: Arc<dyn FileSystem> = Arc::new(DeniedFileSystem(memory, "/denied"));
+        let options = LookupOptions::new(48).with_file_system(Some(&fs));
+
+        let files = |directory: &str| icon_files(Path::new(directory), "firefox", &options);
+        let ((), warnings) = capture_warnings(|| {
+            assert!(files("/denied").is_empty());
+            assert_eq!(files("/allowed").len(), 1);
+            assert!(files("/missing").is_empty());
+        });
+
+        // Missing directories are not reported.
+        assert_eq!(
+            warnings,
+            [Warning::UnreadableDirectory {
+                path: PathBuf::from("/denied"),
+                kind: io::ErrorKind::PermissionDenied,
+            }]
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! # Loading icons
+//! Most applications do not need the path of an icon, they need its contents
+//! to hand to an image decoder. Loading reads the file the lookup found
+//! through the file system of the lookup, so icons of in-memory themes load
+//! like icons on disk.
+//!
+//! The format is detected from the contents rather than the extension, as
+//! themes occasionally ship files with the wrong extension, like PNG images
+//! named .svg. Files whose format cannot be detected keep the format of
+//! their extension.
+
+use std::io;
+use std::path::PathBuf;
+
+use crate::{IconFormat, LookupOptions, LookupResult};
+
+/// The signature every PNG file starts with.
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+/// How far into the file the root element of an SVG image is looked for,
+/// past the XML declaration, comments and the doctype.
+const SVG_SNIFF_LENGTH: usize = 4096;
+
+/// The contents of an icon file, see
+/// [`IconFinder::load_icon`](crate::IconFinder::load_icon).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoadedIcon {
+    /// The path of the file the contents were read from.
+    pub path: PathBuf,
+    pub data: Vec<u8>,
+    /// The format of the contents.
+    pub format: IconFormat,
+}
+
+/// The pixels of an icon, decoded from a PNG image with the `image` feature
+/// or rendered from an SVG image with the `render` feature.
+#[cfg_attr(
+    feature = "image",
+    doc = "\nDecoded by [`IconFinder::load_icon_pixels`](crate::IconFinder::load_icon_pixels)."
+)]
+#[cfg_attr(
+    feature = "render",
+    doc = "\nRendered by [`IconFinder::render_icon`](crate::IconFinder::render_icon)."
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconPixels {
+    pub width: u32,
+    pub height: u32,
+    /// Four bytes per pixel, red, green, blue and alpha, row by row from the
+    /// top. The colors are not premultiplied by alpha.
+    pub data: Vec<u8>,
+}
+
+/// The format of the file contents, if they are a PNG, SVG or XPM image.
+pub(crate) fn detect_format(data: &[u8]) -> Option<IconFormat> {
+    if data.starts_with(PNG_SIGNATURE) {
+        return Some(IconFormat::Png);
+    }
+
+    let data = data.strip_prefix(b"\xef\xbb\xbf").unwrap_or(data);
+    let start = &data[..data.len().min(SVG_SNIFF_LENGTH)];
+    let text = String::from_utf8_lossy(start);
+    let text = text.trim_start();
+
+    if text.starts_with("/* XPM */") {
+        Some(IconFormat::Xpm)
+    } else if text.starts_with('<') && text.contains("<svg") {
+        Some(IconFormat::Svg)
+    } else {
+        None
+    }
+}
+
+/// Reads the file of the lookup result.
+pub(crate) fn load_result(result: LookupResult, options: &LookupOptions) -> io::Result<LoadedIcon> {
+    let data = options.file_system().read(&result.path)?;
+    let format = detect_format(&data).unwrap_or(result.format);
+
+    Ok(LoadedIcon {
+        path: result.path,
+        data,
+        format,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            detect_format(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            Some(IconFormat::Png)
+        );
+        assert_eq!(
+            detect_format(b"<?xml version=\"1.0\"?>\n<!-- icon -->\n<svg width=\"16\"/>"),
+            Some(IconFormat::Svg)
+        );
+        assert_eq!(
+            detect_format(b"\xef\xbb\xbf<svg xmlns=\"http://www.w3.org/2000/svg\"/>"),
+            Some(IconFormat::Svg)
+        );
+        assert_eq!(
+            detect_format(b"/* XPM */\nstatic char *icon[] = {"),
+            Some(IconFormat::Xpm)
+        );
+        assert_eq!(detect_format(b"GIF89a"), None);
+        assert_eq!(detect_format(b""), None);
+    }
+}
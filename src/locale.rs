@@ -0,0 +1,116 @@
+//! # Locales
+//! From: https://specifications.freedesktop.org/desktop-entry-spec/latest/localized-keys.html
+//! Keys like Name and Comment can be translated, the translations are stored
+//! under the same key suffixed with the locale, e.g. `Name[de]`. Locales have
+//! the form `lang_COUNTRY.ENCODING@MODIFIER`, where every part but the
+//! language is optional. The encoding is ignored when matching locales.
+
+use std::env;
+
+/// The human readable name of a theme and the translations of its Name and
+/// Comment keys, by locale.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Translations {
+    /// The untranslated Name key, used when no translation matches.
+    pub name: String,
+    pub names: Vec<(String, String)>,
+    pub comments: Vec<(String, String)>,
+}
+
+/// The locale messages are shown in, as set in the environment.
+pub(crate) fn current_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|variable| env::var(variable).ok())
+        .find(|locale| !locale.is_empty())
+}
+
+/// The languages written from right to left.
+const RTL_LANGUAGES: [&str; 11] = [
+    "ar", "ckb", "dv", "fa", "he", "iw", "ps", "sd", "ug", "ur", "yi",
+];
+
+/// Whether the language of the locale is written from right to left.
+pub(crate) fn is_rtl_locale(locale: &str) -> bool {
+    let language = locale.split(['_', '.', '@']).next().unwrap_or_default();
+
+    RTL_LANGUAGES.contains(&language)
+}
+
+/// The translation best matching the locale. For `lang_COUNTRY@MODIFIER`,
+/// the translations for `lang_COUNTRY@MODIFIER`, `lang_COUNTRY`,
+/// `lang@MODIFIER` and `lang` are tried in that order.
+pub(crate) fn translation<'a>(
+    translations: &'a [(String, String)],
+    locale: &str,
+) -> Option<&'a str> {
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let locale = locale.split('.').next().unwrap_or_default();
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+
+    let mut candidates = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        candidates.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        candidates.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        candidates.push(format!("{}@{}", lang, modifier));
+    }
+    candidates.push(lang.to_owned());
+
+    candidates.iter().find_map(|candidate| {
+        translations
+            .iter()
+            .find(|(locale, _)| locale == candidate)
+            .map(|(_, value)| value.as_str())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translation() {
+        let translations: Vec<(String, String)> = [
+            ("de", "Deutsch"),
+            ("de_AT", "Österreichisch"),
+            ("sr@latin", "Srpski"),
+            ("sr", "Српски"),
+        ]
+        .iter()
+        .map(|(locale, value)| (locale.to_string(), value.to_string()))
+        .collect();
+
+        assert_eq!(translation(&translations, "de_DE.UTF-8"), Some("Deutsch"));
+        assert_eq!(
+            translation(&translations, "de_AT.UTF-8"),
+            Some("Österreichisch")
+        );
+        assert_eq!(
+            translation(&translations, "sr_RS.UTF-8@latin"),
+            Some("Srpski")
+        );
+        assert_eq!(translation(&translations, "sr_RS"), Some("Српски"));
+        assert_eq!(translation(&translations, "C"), None);
+        assert_eq!(translation(&translations, "fr_FR"), None);
+    }
+
+    #[test]
+    fn test_is_rtl_locale() {
+        assert!(is_rtl_locale("ar_EG.UTF-8"));
+        assert!(is_rtl_locale("he"));
+        assert!(is_rtl_locale("fa_IR@latin"));
+        assert!(!is_rtl_locale("en_US.UTF-8"));
+        assert!(!is_rtl_locale("arn_CL"));
+        assert!(!is_rtl_locale("C"));
+    }
+}
@@ -0,0 +1,397 @@
+//! # MIME type icons
+//! File managers show files with the icon of their MIME type. The Icon Naming
+//! Specification names these icons after the MIME type, with the slash
+//! replaced by a dash, like text-x-rust for text/x-rust. No theme has an icon
+//! for every MIME type, so the generic icon of the type is used instead, like
+//! text-x-generic for any text file.
+//!
+//! The shared-mime-info database knows better generic icons for many types,
+//! like text-x-script for shell scripts. It is read from the mime directory of
+//! every data directory, with the entries of the user taking precedence. The
+//! globs of the database are used to guess the MIME type of a file from its
+//! name.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::filesystem::{file_system, FileSystem};
+use crate::paths::mime_directories;
+use crate::{find_best_icon, Theme};
+
+/// The MIME type of files the database has no glob for.
+pub(crate) const UNKNOWN_MIME_TYPE: &str = "application/octet-stream";
+
+/// The icon and glob data of the shared-mime-info database.
+#[derive(Debug, Default)]
+pub(crate) struct MimeDatabase {
+    /// Maps MIME types to the icon to use for them, where that is not the
+    /// name derived from the MIME type.
+    icons: HashMap<String, String>,
+    /// Maps MIME types to the icon to use when the theme does not have an
+    /// icon for the type itself.
+    generic_icons: HashMap<String, String>,
+    /// Maps deprecated MIME types to the type replacing them.
+    aliases: HashMap<String, String>,
+    globs: Vec<Glob>,
+}
+
+impl MimeDatabase {
+    pub(crate) fn load(directories: &[PathBuf], file_system: &dyn FileSystem) -> MimeDatabase {
+        let mut database = MimeDatabase::default();
+
+        for directory in directories {
+            let read = |name: &str, separator: char, map: &mut HashMap<String, String>| {
+                if let Ok(contents) = file_system.read_to_string(&directory.join(name)) {
+                    parse_entries(&contents, separator, map);
+                }
+            };
+
+            read("icons", ':', &mut database.icons);
+            read("generic-icons", ':', &mut database.generic_icons);
+            read("aliases", ' ', &mut database.aliases);
+
+            if let Ok(contents) = file_system.read_to_string(&directory.join("globs2")) {
+                database
+                    .globs
+                    .extend(contents.lines().filter_map(Glob::parse));
+            }
+        }
+
+        database
+    }
+
+    pub(crate) fn global() -> &'static MimeDatabase {
+        static DATABASE: OnceLock<MimeDatabase> = OnceLock::new();
+
+        DATABASE.get_or_init(|| MimeDatabase::load(mime_directories(), &**file_system()))
+    }
+
+    pub(crate) fn icon_names(&self, mime_type: &str) -> Vec<String> {
+        let mime_type = mime_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let mime_type = self.aliases.get(&mime_type).unwrap_or(&mime_type);
+
+        let (media_type, _) = match mime_type.split_once('/') {
+            Some(split) => split,
+            None => return Vec::new(),
+        };
+
+        let mut names = Vec::new();
+        let mut push = |name: String| {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        };
+
+        if let Some(icon) = self.icons.get(mime_type) {
+            push(icon.clone());
+        }
+        push(mime_type.replace('/', "-"));
+        match self.generic_icons.get(mime_type) {
+            Some(icon) => push(icon.clone()),
+            None => push(format!("{}-x-generic", media_type)),
+        }
+
+        names
+    }
+
+    /// The MIME type of the glob matching the file name, preferring globs of
+    /// a higher weight, then longer globs.
+    pub(crate) fn guess_mime_type(&self, file_name: &str) -> Option<&str> {
+        let lowercase = file_name.to_lowercase();
+
+        self.globs
+            .iter()
+            .filter(|glob| {
+                glob.matches(if glob.case_sensitive {
+                    file_name
+                } else {
+                    &lowercase
+                })
+            })
+            .max_by_key(|glob| (glob.weight, glob.len))
+            .map(|glob| glob.mime_type.as_str())
+    }
+}
+
+/// A line of a globs2 file, mapping file names to a MIME type.
+#[derive(Debug)]
+struct Glob {
+    weight: u32,
+    mime_type: String,
+    pattern: Pattern,
+    case_sensitive: bool,
+    /// The length of the glob, longer globs are more specific.
+    len: usize,
+}
+
+#[derive(Debug)]
+enum Pattern {
+    Literal(String),
+    /// A glob like `*.png`, matching file names ending in the suffix.
+    Suffix(String),
+    Wildcard(Vec<char>),
+}
+
+impl Glob {
+    /// Parses a line of the form `weight:type:glob[:flags]`.
+    fn parse(line: &str) -> Option<Glob> {
+        if line.starts_with('#') {
+            return None;
+        }
+
+        let mut fields = line.split(':');
+        let weight = fields.next()?.parse().ok()?;
+        let mime_type = fields.next()?;
+        let glob = fields.next()?;
+        let case_sensitive = fields
+            .next()
+            .is_some_and(|flags| flags.split(',').any(|flag| flag == "cs"));
+
+        // Removes the globs of the type in directories of lower precedence,
+        // which are not distinguished here.
+        if glob == "__NOGLOBS__" {
+            return None;
+        }
+
+        let glob = if case_sensitive {
+            glob.to_owned()
+        } else {
+            glob.to_lowercase()
+        };
+        let is_wildcard = |text: &str| text.contains(['*', '?', '[']);
+        let pattern = match glob.strip_prefix('*') {
+            Some(suffix) if !is_wildcard(suffix) => Pattern::Suffix(suffix.to_owned()),
+            _ if is_wildcard(&glob) => Pattern::Wildcard(glob.chars().collect()),
+            _ => Pattern::Literal(glob.clone()),
+        };
+
+        Some(Glob {
+            weight,
+            mime_type: mime_type.to_owned(),
+            pattern,
+            case_sensitive,
+            len: glob.chars().count(),
+        })
+    }
+
+    fn matches(&self, file_name: &str) -> bool {
+        match &self.pattern {
+            Pattern::Literal(literal) => file_name == literal,
+            Pattern::Suffix(suffix) => file_name.ends_with(suffix.as_str()),
+            Pattern::Wildcard(pattern) => {
+                wildcard_matches(pattern, &file_name.chars().collect::<Vec<_>>())
+            }
+        }
+    }
+}
+
+/// Matches a file name against a glob of `*`, `?` and character classes like
+/// `[0-9]`.
+fn wildcard_matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|skip| wildcard_matches(&pattern[1..], &name[skip..])),
+        Some('?') => !name.is_empty() && wildcard_matches(&pattern[1..], &name[1..]),
+        Some('[') => match pattern.iter().skip(2).position(|&c| c == ']') {
+            Some(end) => {
+                let class = &pattern[1..end + 2];
+                let (negated, class) = match class.split_first() {
+                    Some(('!', rest)) => (true, rest),
+                    _ => (false, class),
+                };
+
+                name.first()
+                    .is_some_and(|&c| class_matches(class, c) != negated)
+                    && wildcard_matches(&pattern[end + 3..], &name[1..])
+            }
+            // Without a closing bracket, the bracket is taken literally.
+            None => name.first() == Some(&'[') && wildcard_matches(&pattern[1..], &name[1..]),
+        },
+        Some(c) => name.first() == Some(c) && wildcard_matches(&pattern[1..], &name[1..]),
+    }
+}
+
+fn class_matches(class: &[char], c: char) -> bool {
+    let mut rest = class;
+
+    while let Some((&first, tail)) = rest.split_first() {
+        match tail {
+            ['-', last, tail @ ..] => {
+                if (first..=*last).contains(&c) {
+                    return true;
+                }
+                rest = tail;
+            }
+            _ => {
+                if first == c {
+                    return true;
+                }
+                rest = tail;
+            }
+        }
+    }
+
+    false
+}
+
+/// Parses the lines of a shared-mime-info file, mapping the MIME type at the
+/// start of the line to the value after the separator. Entries already in
+/// the map take precedence.
+fn parse_entries(contents: &str, separator: char, map: &mut HashMap<String, String>) {
+    for line in contents.lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some((mime_type, value)) = line.split_once(separator) {
+            map.entry(mime_type.trim().to_owned())
+                .or_insert_with(|| value.trim().to_owned());
+        }
+    }
+}
+
+/// The names of the icons for a MIME type, best first: the icon the
+/// shared-mime-info database names for the type, the name derived from the
+/// type and the generic icon of the type. Empty if the MIME type is not of
+/// the form media/subtype.
+///
+/// ```
+/// use icon_finder::mime_type_icon_names;
+///
+/// let names = mime_type_icon_names("text/x-rust");
+/// assert_eq!(names[0], "text-x-rust");
+/// assert!(names.len() >= 2);
+/// ```
+pub fn mime_type_icon_names(mime_type: &str) -> Vec<String> {
+    MimeDatabase::global().icon_names(mime_type)
+}
+
+/// Guesses the MIME type of a file from its name, using the globs of the
+/// shared-mime-info database, e.g. text/rust for main.rs. The contents of the
+/// file are not looked at.
+pub fn guess_mime_type(file_name: &str) -> Option<String> {
+    MimeDatabase::global()
+        .guess_mime_type(file_name)
+        .map(str::to_owned)
+}
+
+/// Looks up the icon for a MIME type, falling back to its generic icon, see
+/// [`mime_type_icon_names`].
+pub fn find_mime_type_icon(
+    mime_type: &str,
+    size: i16,
+    scale: i16,
+    user_selected_theme: Theme,
+) -> Option<PathBuf> {
+    let names = mime_type_icon_names(mime_type);
+
+    find_best_icon(
+        names.iter().map(String::as_str).collect(),
+        size,
+        scale,
+        user_selected_theme,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFileSystem;
+
+    #[test]
+    fn test_mime_type_icon_names() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file(
+            "/home/user/.local/share/mime/generic-icons",
+            "application/x-shellscript:utilities-terminal\n",
+        );
+        fs.add_file(
+            "/usr/share/mime/generic-icons",
+            "# comment\napplication/x-shellscript:text-x-script\n\
+             application/x-compressed-tar:package-x-generic\n",
+        );
+        fs.add_file(
+            "/usr/share/mime/icons",
+            "application/x-fictional:fictional-document\n",
+        );
+        fs.add_file(
+            "/usr/share/mime/aliases",
+            "application/x-sh application/x-shellscript\n",
+        );
+        let directories = [
+            PathBuf::from("/home/user/.local/share/mime"),
+            PathBuf::from("/usr/share/mime"),
+        ];
+        let database = MimeDatabase::load(&directories, &fs);
+
+        assert_eq!(
+            database.icon_names("text/x-rust"),
+            vec!["text-x-rust", "text-x-generic"]
+        );
+        assert_eq!(
+            database.icon_names("Text/Plain; charset=utf-8"),
+            vec!["text-plain", "text-x-generic"]
+        );
+        assert_eq!(
+            database.icon_names("application/x-compressed-tar"),
+            vec!["application-x-compressed-tar", "package-x-generic"]
+        );
+        assert_eq!(
+            database.icon_names("application/x-sh"),
+            vec!["application-x-shellscript", "utilities-terminal"]
+        );
+        assert_eq!(
+            database.icon_names("application/x-fictional"),
+            vec![
+                "fictional-document",
+                "application-x-fictional",
+                "application-x-generic"
+            ]
+        );
+        assert!(database.icon_names("text").is_empty());
+    }
+
+    #[test]
+    fn test_guess_mime_type() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file(
+            "/usr/share/mime/globs2",
+            "# comment\n\
+             50:text/x-csrc:*.c\n\
+             50:text/x-c++src:*.C:cs\n\
+             50:application/x-compressed-tar:*.tar.gz\n\
+             20:application/gzip:*.gz\n\
+             50:text/x-makefile:makefile\n\
+             10:text/x-readme:readme*\n\
+             50:application/x-sharedlib:*.so.[0-9]*\n\
+             50:text/x-troff-man:*.[1-9]\n\
+             50:text/x-changelog:__NOGLOBS__\n",
+        );
+        let database = MimeDatabase::load(&[PathBuf::from("/usr/share/mime")], &fs);
+
+        assert_eq!(database.guess_mime_type("main.c"), Some("text/x-csrc"));
+        assert_eq!(database.guess_mime_type("main.C"), Some("text/x-c++src"));
+        assert_eq!(
+            database.guess_mime_type("icons.tar.gz"),
+            Some("application/x-compressed-tar")
+        );
+        assert_eq!(database.guess_mime_type("log.gz"), Some("application/gzip"));
+        assert_eq!(
+            database.guess_mime_type("Makefile"),
+            Some("text/x-makefile")
+        );
+        assert_eq!(database.guess_mime_type("README.md"), Some("text/x-readme"));
+        assert_eq!(
+            database.guess_mime_type("libc.so.6"),
+            Some("application/x-sharedlib")
+        );
+        assert_eq!(database.guess_mime_type("ls.1"), Some("text/x-troff-man"));
+        assert_eq!(database.guess_mime_type("ls.10"), None);
+        assert_eq!(database.guess_mime_type("NOGLOBS"), None);
+    }
+}
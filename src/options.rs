@@ -0,0 +1,613 @@
+//! # Lookup options
+//! Besides the icon name, a lookup is configured by the nominal size and scale
+//! of the icon and a number of optional restrictions and modifications of the
+//! lookup algorithm. These are collected in [`LookupOptions`].
+
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::ops::{BitOr, BitOrAssign};
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::aliases::IconAliases;
+use crate::cancel::{Cancellation, CancellationToken};
+use crate::filesystem::{self, FileSystem, FileSystemRef};
+use crate::listing::RevalidationInterval;
+use crate::locale::{current_locale, is_rtl_locale};
+use crate::paths::{base_directories, unthemed_directories};
+use crate::profile::Profiler;
+use crate::{
+    directory_is_smaller, directory_size_distance, spec_directory_size_distance, Context,
+    IconFormat, LookupResult, ThemeDirectory, ThemeDirectoryType, ALLOWED_FORMATS, DEFAULT_SCALE,
+};
+
+/// # Lookup flags
+/// Flags modifying the icon lookup algorithm. Flags can be combined using `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LookupFlags(u32);
+
+impl LookupFlags {
+    /// Look up icons exactly as described by the specification.
+    pub const NONE: LookupFlags = LookupFlags(0);
+    /// If the icon is not found, fall back to more generic icon names by
+    /// stripping dash separated segments from the end of the name, as
+    /// described by the Icon Naming Specification.
+    pub const GENERIC_FALLBACK: LookupFlags = LookupFlags(1);
+    /// Looks the icon up again if the finder remembers it as missing, e.g.
+    /// right after the application installed it. Has no effect when the
+    /// finder does not cache lookups.
+    pub const BYPASS_NEGATIVE_CACHE: LookupFlags = LookupFlags(1 << 1);
+    /// Accepts icon names with the extension of a supported format, looking
+    /// up "firefox.png" as "firefox". The specification asks implementations
+    /// to do so for compatibility with legacy applications.
+    pub const STRIP_EXTENSION: LookupFlags = LookupFlags(1 << 2);
+    /// Looks up the variant of the icon for left-to-right text first, like
+    /// "go-next-ltr" for "go-next", as GTK does.
+    pub const DIR_LTR: LookupFlags = LookupFlags(1 << 3);
+    /// Looks up the variant of the icon for right-to-left text first, like
+    /// "go-next-rtl" for "go-next", as GTK does. Takes precedence over
+    /// [`DIR_LTR`](LookupFlags::DIR_LTR).
+    pub const DIR_RTL: LookupFlags = LookupFlags(1 << 4);
+    /// Only returns icons from directories matching the size and scale
+    /// exactly, never icons that would have to be scaled, nor unthemed
+    /// icons. For asset pipelines that must not scale icons silently.
+    pub const EXACT_ONLY: LookupFlags = LookupFlags(1 << 5);
+    /// Returns the file a symbolic link points to instead of the link, as
+    /// themes like Papirus link most of their icons to a few files. Lists of
+    /// candidates leave out the files that resolve to a file listed before.
+    pub const CANONICALIZE: LookupFlags = LookupFlags(1 << 6);
+
+    /// The direction flag for the text direction of the locale of the
+    /// process, [`DIR_RTL`](LookupFlags::DIR_RTL) for languages like Arabic
+    /// and Hebrew and [`DIR_LTR`](LookupFlags::DIR_LTR) otherwise.
+    pub fn locale_direction() -> LookupFlags {
+        match current_locale() {
+            Some(locale) if is_rtl_locale(&locale) => LookupFlags::DIR_RTL,
+            _ => LookupFlags::DIR_LTR,
+        }
+    }
+
+    /// Returns whether all of the given flags are set.
+    pub fn contains(self, flags: LookupFlags) -> bool {
+        self.0 & flags.0 == flags.0
+    }
+
+    /// Unsets the given flags.
+    pub(crate) fn remove(&mut self, flags: LookupFlags) {
+        self.0 &= !flags.0;
+    }
+}
+
+impl BitOr for LookupFlags {
+    type Output = LookupFlags;
+
+    fn bitor(self, other: LookupFlags) -> LookupFlags {
+        LookupFlags(self.0 | other.0)
+    }
+}
+
+impl BitOrAssign for LookupFlags {
+    fn bitor_assign(&mut self, other: LookupFlags) {
+        self.0 |= other.0;
+    }
+}
+
+/// The lookup algorithm to follow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LookupProfile {
+    /// The algorithm of the Icon Theme Specification.
+    #[default]
+    SpecStrict,
+    /// The algorithm of GTK, for applications that have to show the same
+    /// icons as GTK applications. GTK searches all directories of a theme in
+    /// one pass, comparing sizes in device pixels regardless of the scale of
+    /// a directory. When no directory matches, it prefers icons it has to
+    /// scale down over icons it has to scale up. Symbolic icons that are
+    /// missing fall back to the regular icon.
+    GtkCompatible,
+}
+
+/// How the distance between the size of an icon and the icons of a theme
+/// directory is measured, when no directory matches the size exactly. The
+/// directory at the smallest distance provides the icon. Distances are in
+/// device pixels.
+#[derive(Debug, Clone, Copy, Eq, Default)]
+pub enum DistanceStrategy {
+    /// The pseudocode of the specification, which measures the distance to
+    /// Threshold directories against their MinSize and MaxSize keys. These
+    /// keys are only defined for Scalable directories, so for most themes
+    /// this measures against the nominal size as well. Themes that do set
+    /// them get a distance of 0 for sizes outside the threshold but within
+    /// MinSize and MaxSize, where the pseudocode gives a negative distance.
+    SpecPseudocode,
+    /// Measures the distance to Threshold directories against their nominal
+    /// size, as the pseudocode most likely intends.
+    #[default]
+    Corrected,
+    /// Measures the distance with the given function, taking the directory
+    /// and the size and scale of the icon.
+    Custom(fn(&ThemeDirectory, i16, i16) -> i16),
+}
+
+impl DistanceStrategy {
+    pub(crate) fn distance(self, directory: &ThemeDirectory, size: i16, scale: i16) -> i16 {
+        match self {
+            DistanceStrategy::SpecPseudocode => {
+                spec_directory_size_distance(directory, size, scale)
+            }
+            DistanceStrategy::Corrected => directory_size_distance(directory, size, scale),
+            DistanceStrategy::Custom(distance) => distance(directory, size, scale),
+        }
+    }
+}
+
+/// Which icon to prefer when no directory matches the size exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ClosestMatchPolicy {
+    /// The icon closest in size, as the specification describes.
+    #[default]
+    Nearest,
+    /// A scalable icon, closest in size, over any bitmap icon. Scalable
+    /// icons are rendered at the requested size, without losing quality.
+    PreferScalable,
+    /// An icon at least as large as requested, closest in size, over smaller
+    /// icons. Scaling an icon down looks better than scaling it up.
+    PreferLarger,
+}
+
+/// Custom strategies compare by the address of their function. The same
+/// function may have several addresses, which at worst makes equal lookups
+/// miss the cache.
+impl PartialEq for DistanceStrategy {
+    fn eq(&self, other: &DistanceStrategy) -> bool {
+        match (self, other) {
+            (DistanceStrategy::Custom(distance), DistanceStrategy::Custom(other)) => {
+                ptr::fn_addr_eq(*distance, *other)
+            }
+            _ => mem::discriminant(self) == mem::discriminant(other),
+        }
+    }
+}
+
+impl Hash for DistanceStrategy {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        mem::discriminant(self).hash(state);
+    }
+}
+
+const FRACTIONAL_SCALE_DENOMINATOR: u32 = 120;
+
+/// Configuration of a single icon lookup.
+///
+/// ```
+/// use icon_finder::{Context, LookupFlags, LookupOptions};
+///
+/// let options = LookupOptions::new(48)
+///     .scale(2)
+///     .context(Context::Applications)
+///     .flags(LookupFlags::GENERIC_FALLBACK);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LookupOptions {
+    pub(crate) size: i16,
+    pub(crate) scale: i16,
+    pub(crate) fractional_scale: Option<u32>,
+    pub(crate) context: Option<Context>,
+    pub(crate) formats: Option<Vec<IconFormat>>,
+    pub(crate) flags: LookupFlags,
+    pub(crate) lookup_profile: Option<LookupProfile>,
+    pub(crate) distance_strategy: DistanceStrategy,
+    pub(crate) closest_match_policy: ClosestMatchPolicy,
+    pub(crate) aliases: Option<Arc<IconAliases>>,
+    pub(crate) theme_path: Option<PathBuf>,
+    /// Search paths of the finder, searched before the base directories.
+    pub(crate) prepended_paths: Vec<PathBuf>,
+    /// Search paths of the finder, searched after the base directories.
+    pub(crate) appended_paths: Vec<PathBuf>,
+    /// File system of the finder, `None` for the file system of the process.
+    pub(crate) file_system: Option<FileSystemRef>,
+    pub(crate) cancellation: Cancellation,
+    /// How long directory listings are trusted, set by the finder.
+    pub(crate) revalidation_interval: RevalidationInterval,
+    /// Profiler of the finder, recording the time spent in each phase.
+    pub(crate) profiler: Profiler,
+}
+
+impl LookupOptions {
+    /// Options for looking up an icon of the given nominal size, at scale 1,
+    /// in any context and in any of the supported formats. Formats are
+    /// preferred in the order of the finder, which defaults to PNG, SVG and
+    /// finally XPM.
+    pub fn new(size: i16) -> LookupOptions {
+        LookupOptions {
+            size,
+            scale: DEFAULT_SCALE,
+            fractional_scale: None,
+            context: None,
+            formats: None,
+            flags: LookupFlags::NONE,
+            lookup_profile: None,
+            distance_strategy: DistanceStrategy::default(),
+            closest_match_policy: ClosestMatchPolicy::default(),
+            aliases: None,
+            theme_path: None,
+            prepended_paths: Vec::new(),
+            appended_paths: Vec::new(),
+            file_system: None,
+            cancellation: Cancellation::default(),
+            revalidation_interval: RevalidationInterval::default(),
+            profiler: Profiler::default(),
+        }
+    }
+
+    /// Sets the scale the icon will be displayed at.
+    pub fn scale(mut self, scale: i16) -> LookupOptions {
+        self.scale = scale;
+        self.fractional_scale = None;
+        self
+    }
+
+    /// Sets a fractional scale the icon will be displayed at, as used by
+    /// e.g. Wayland compositors. No theme directory has a fractional scale,
+    /// so the directory whose icons come closest to the physical pixel size
+    /// of the icon is chosen. Scales that are not finite or not positive
+    /// are taken as 1, scales are limited to the range of [`i16`] and to at
+    /// least 1/120.
+    pub fn fractional_scale(mut self, scale: f32) -> LookupOptions {
+        let scale = if scale.is_finite() && scale > 0.0 {
+            scale
+        } else {
+            f32::from(DEFAULT_SCALE)
+        };
+        // Kept in 120ths, like the Wayland fractional scale protocol does, so
+        // options remain comparable.
+        let denominator = FRACTIONAL_SCALE_DENOMINATOR as f32;
+        let fractional_scale = (scale * denominator)
+            .round()
+            .clamp(1.0, f32::from(i16::MAX) * denominator) as u32;
+
+        if fractional_scale.is_multiple_of(FRACTIONAL_SCALE_DENOMINATOR) {
+            self.scale = (fractional_scale / FRACTIONAL_SCALE_DENOMINATOR) as i16;
+            self.fractional_scale = None;
+        } else {
+            self.scale = DEFAULT_SCALE;
+            self.fractional_scale = Some(fractional_scale);
+        }
+        self
+    }
+
+    /// Only considers theme directories of the given context.
+    pub fn context(mut self, context: Context) -> LookupOptions {
+        self.context = Some(context);
+        self
+    }
+
+    /// Only returns icons in one of the given formats. When an icon is
+    /// available in several of these formats for the same size, the format
+    /// listed first is returned. Formats disabled through cargo features are
+    /// never returned.
+    pub fn formats(mut self, formats: &[IconFormat]) -> LookupOptions {
+        self.formats = Some(formats.to_vec());
+        self
+    }
+
+    /// Sets the flags modifying the lookup algorithm.
+    pub fn flags(mut self, flags: LookupFlags) -> LookupOptions {
+        self.flags = flags;
+        self
+    }
+
+    /// Sets the lookup algorithm to follow, overriding the one of the finder.
+    /// Defaults to the algorithm of the specification.
+    pub fn lookup_profile(mut self, lookup_profile: LookupProfile) -> LookupOptions {
+        self.lookup_profile = Some(lookup_profile);
+        self
+    }
+
+    /// Sets how the closest size is chosen when no directory matches the size
+    /// exactly, see [`DistanceStrategy`].
+    pub fn distance_strategy(mut self, distance_strategy: DistanceStrategy) -> LookupOptions {
+        self.distance_strategy = distance_strategy;
+        self
+    }
+
+    /// Sets which icon is preferred when no directory matches the size
+    /// exactly, see [`ClosestMatchPolicy`].
+    pub fn closest_match_policy(mut self, policy: ClosestMatchPolicy) -> LookupOptions {
+        self.closest_match_policy = policy;
+        self
+    }
+
+    /// Looks up the name a legacy icon name is an alias of when the legacy
+    /// name is not found, like document-open for gtk-open, see
+    /// [`IconAliases`].
+    pub fn aliases(mut self, aliases: IconAliases) -> LookupOptions {
+        self.aliases = Some(Arc::new(aliases));
+        self
+    }
+
+    /// The distance between the icon and the icons of the directory.
+    pub(crate) fn size_distance(&self, directory: &ThemeDirectory) -> i16 {
+        self.distance_strategy
+            .distance(directory, self.size, self.scale)
+    }
+
+    /// Orders directories for the closest match to an icon of the given
+    /// size, the directory of the lowest rank wins. Directories the policy
+    /// does not prefer rank after all others.
+    pub(crate) fn closest_match_rank(&self, directory: &ThemeDirectory, size: i16) -> (bool, i16) {
+        let avoided = match self.closest_match_policy {
+            ClosestMatchPolicy::Nearest => false,
+            ClosestMatchPolicy::PreferScalable => directory.r#type != ThemeDirectoryType::Scalable,
+            ClosestMatchPolicy::PreferLarger => directory_is_smaller(directory, size, self.scale),
+        };
+
+        (
+            avoided,
+            self.distance_strategy.distance(directory, size, self.scale),
+        )
+    }
+
+    /// Searches the given directory before all others, for this lookup only.
+    /// The directory is searched like a base directory, it may hold icons
+    /// directly as well as themes, e.g. hicolor. Meant for the IconThemePath
+    /// of a StatusNotifierItem, which applications use to ship tray icons
+    /// that are not installed on the system.
+    pub fn theme_path(mut self, path: impl AsRef<Path>) -> LookupOptions {
+        self.theme_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Stops the lookup once the token is cancelled, after which it returns
+    /// `None`. Lookups that are cancelled are not cached.
+    pub fn cancellation(mut self, token: &CancellationToken) -> LookupOptions {
+        self.cancellation = Cancellation(Some(token.clone()));
+        self
+    }
+
+    /// Whether the lookup was cancelled, see
+    /// [`cancellation`](LookupOptions::cancellation).
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// The size of the icon in physical pixels, if it is displayed at a
+    /// fractional scale.
+    pub(crate) fn physical_size(&self) -> Option<f32> {
+        self.fractional_scale
+            .map(|scale| f32::from(self.size) * scale as f32 / FRACTIONAL_SCALE_DENOMINATOR as f32)
+    }
+
+    /// Uses the given format preference, unless formats were set explicitly.
+    pub(crate) fn with_default_formats(&self, formats: &[IconFormat]) -> LookupOptions {
+        LookupOptions {
+            formats: Some(self.formats.clone().unwrap_or_else(|| formats.to_vec())),
+            ..self.clone()
+        }
+    }
+
+    /// Follows the given lookup algorithm, unless one was set explicitly.
+    pub(crate) fn with_default_lookup_profile(
+        mut self,
+        lookup_profile: LookupProfile,
+    ) -> LookupOptions {
+        self.lookup_profile.get_or_insert(lookup_profile);
+        self
+    }
+
+    /// Whether the lookup follows the algorithm of GTK.
+    pub(crate) fn is_gtk_compatible(&self) -> bool {
+        self.lookup_profile == Some(LookupProfile::GtkCompatible)
+    }
+
+    /// Uses the given aliases, unless aliases were set explicitly.
+    pub(crate) fn with_default_aliases(
+        mut self,
+        aliases: Option<&Arc<IconAliases>>,
+    ) -> LookupOptions {
+        if self.aliases.is_none() {
+            self.aliases = aliases.cloned();
+        }
+        self
+    }
+
+    /// The name the legacy icon name is an alias of.
+    pub(crate) fn alias(&self, icon: &str) -> Option<&str> {
+        self.aliases.as_ref()?.get(icon)
+    }
+
+    /// Searches the given paths before and after the base directories.
+    pub(crate) fn with_search_paths(
+        mut self,
+        prepended_paths: &[PathBuf],
+        appended_paths: &[PathBuf],
+    ) -> LookupOptions {
+        self.prepended_paths = prepended_paths.to_vec();
+        self.appended_paths = appended_paths.to_vec();
+        self
+    }
+
+    /// Looks the icon up in the given file system instead of the file system
+    /// of the process.
+    pub(crate) fn with_file_system(
+        mut self,
+        file_system: Option<&Arc<dyn FileSystem>>,
+    ) -> LookupOptions {
+        self.file_system = file_system.cloned().map(FileSystemRef);
+        self
+    }
+
+    /// Records the phases of the lookup in the given profiler.
+    pub(crate) fn with_profiler(mut self, profiler: &Profiler) -> LookupOptions {
+        self.profiler = profiler.clone();
+        self
+    }
+
+    /// The file system icons are looked up in.
+    pub(crate) fn file_system(&self) -> &dyn FileSystem {
+        &**self.shared_file_system()
+    }
+
+    /// The file system of the lookup, for keeping state along with it.
+    pub(crate) fn shared_file_system(&self) -> &Arc<dyn FileSystem> {
+        match &self.file_system {
+            Some(file_system) => &file_system.0,
+            None => filesystem::file_system(),
+        }
+    }
+
+    /// Trusts directory listings for the given interval, see
+    /// [`IconFinderBuilder::revalidation_interval`](crate::IconFinderBuilder::revalidation_interval).
+    pub(crate) fn with_revalidation_interval(mut self, interval: Duration) -> LookupOptions {
+        self.revalidation_interval = RevalidationInterval(interval);
+        self
+    }
+
+    /// The path of the file the path links to if the lookup canonicalizes
+    /// paths, see [`LookupFlags::CANONICALIZE`]. Paths that cannot be
+    /// resolved are kept as they are.
+    pub(crate) fn canonical_path(&self, path: PathBuf) -> PathBuf {
+        if !self.flags.contains(LookupFlags::CANONICALIZE) {
+            return path;
+        }
+
+        self.file_system().canonicalize(&path).unwrap_or(path)
+    }
+
+    /// The result with its path canonicalized, see
+    /// [`canonical_path`](LookupOptions::canonical_path).
+    pub(crate) fn canonical_result(&self, result: LookupResult) -> LookupResult {
+        LookupResult {
+            path: self.canonical_path(result.path),
+            ..result
+        }
+    }
+
+    /// The directories themes are searched for in, in order of precedence.
+    pub(crate) fn base_directories(&self) -> impl Iterator<Item = &Path> + '_ {
+        self.theme_path
+            .iter()
+            .chain(&self.prepended_paths)
+            .chain(base_directories())
+            .chain(&self.appended_paths)
+            .map(PathBuf::as_path)
+    }
+
+    /// The directories unthemed icons are searched for in, in order of
+    /// precedence.
+    pub(crate) fn unthemed_directories(&self) -> impl Iterator<Item = &Path> + '_ {
+        self.theme_path
+            .iter()
+            .chain(&self.prepended_paths)
+            .chain(unthemed_directories())
+            .chain(&self.appended_paths)
+            .map(PathBuf::as_path)
+    }
+
+    /// The formats to look for, in order of preference.
+    pub(crate) fn allowed_formats(&self) -> impl Iterator<Item = IconFormat> + '_ {
+        self.formats
+            .as_deref()
+            .unwrap_or(ALLOWED_FORMATS)
+            .iter()
+            .copied()
+            .filter(|format| format.is_supported())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_flags() {
+        let flags = LookupFlags::NONE | LookupFlags::GENERIC_FALLBACK;
+
+        assert!(flags.contains(LookupFlags::GENERIC_FALLBACK));
+        assert!(!LookupFlags::NONE.contains(LookupFlags::GENERIC_FALLBACK));
+
+        let mut flags = flags | LookupFlags::BYPASS_NEGATIVE_CACHE;
+        flags.remove(LookupFlags::BYPASS_NEGATIVE_CACHE);
+        assert_eq!(flags, LookupFlags::GENERIC_FALLBACK);
+    }
+
+    #[test]
+    fn test_fractional_scale() {
+        let options = LookupOptions::new(48).fractional_scale(1.25);
+        assert_eq!(options.physical_size(), Some(60.0));
+
+        let options = options.fractional_scale(2.0);
+        assert_eq!(options.physical_size(), None);
+        assert_eq!(options.scale, 2);
+
+        let options = LookupOptions::new(48).fractional_scale(1.5).scale(1);
+        assert_eq!(options.physical_size(), None);
+
+        for scale in [0.0, -2.0, f32::NAN, f32::INFINITY] {
+            let options = LookupOptions::new(48).fractional_scale(scale);
+            assert_eq!((options.scale, options.physical_size()), (1, None));
+        }
+        let options = LookupOptions::new(48).fractional_scale(0.004);
+        assert_eq!((options.scale, options.physical_size()), (1, Some(0.4)));
+        let options = LookupOptions::new(48).fractional_scale(1e9);
+        assert_eq!(options.scale, i16::MAX);
+    }
+
+    #[test]
+    #[cfg(all(feature = "svg", feature = "xpm"))]
+    fn test_allowed_formats_preference_order() {
+        let options = LookupOptions::new(48);
+        assert_eq!(
+            options.allowed_formats().collect::<Vec<_>>(),
+            ALLOWED_FORMATS.to_vec()
+        );
+
+        let options = options.with_default_formats(&[IconFormat::Svg, IconFormat::Png]);
+        assert_eq!(
+            options.allowed_formats().collect::<Vec<_>>(),
+            vec![IconFormat::Svg, IconFormat::Png]
+        );
+
+        let options = LookupOptions::new(48)
+            .formats(&[IconFormat::Xpm])
+            .with_default_formats(&[IconFormat::Svg]);
+        assert_eq!(
+            options.allowed_formats().collect::<Vec<_>>(),
+            vec![IconFormat::Xpm]
+        );
+    }
+
+    #[test]
+    fn test_search_paths() {
+        let options = LookupOptions::new(48).with_search_paths(
+            &[PathBuf::from("/opt/app/icons")],
+            &[PathBuf::from("/opt/extra")],
+        );
+        let directories: Vec<&Path> = options.base_directories().collect();
+
+        assert_eq!(directories.first(), Some(&Path::new("/opt/app/icons")));
+        assert_eq!(directories.last(), Some(&Path::new("/opt/extra")));
+        assert_eq!(directories.len(), base_directories().len() + 2);
+
+        let options = options.theme_path("/tmp/tray-icons");
+        let directories: Vec<&Path> = options.unthemed_directories().collect();
+
+        assert_eq!(
+            directories[..2],
+            [Path::new("/tmp/tray-icons"), Path::new("/opt/app/icons")]
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "svg"))]
+    fn test_allowed_formats_without_svg() {
+        let options = LookupOptions::new(48).formats(&[IconFormat::Svg, IconFormat::Png]);
+
+        assert_eq!(
+            options.allowed_formats().collect::<Vec<_>>(),
+            vec![IconFormat::Png]
+        );
+    }
+}
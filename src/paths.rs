@@ -0,0 +1,350 @@
+//! # Base directories
+//! Icons and themes are searched for in a set of directories, called base
+//! directories. The themes are stored in subdirectories of the base
+//! directories. By the specification these are $HOME/.icons, the icons
+//! directory of every directory in $XDG_DATA_DIRS and finally
+//! /usr/share/pixmaps, which only holds unthemed icons.
+//!
+//! The colon-separated directories in $ICON_FINDER_PATH are searched before
+//! all others, e.g. to test a theme without installing it.
+
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::filesystem::file_system;
+use crate::sandbox::Sandbox;
+
+/// Environment variable listing extra base directories, separated by colons.
+const PATH_VARIABLE: &str = "ICON_FINDER_PATH";
+
+/// The data directories used when $XDG_DATA_DIRS is not set, as given by the
+/// XDG Base Directory Specification.
+const DEFAULT_DATA_DIRS: &str = "/usr/local/share:/usr/share";
+
+/// Searched even if $XDG_DATA_DIRS does not list them, as most applications
+/// install their icons here.
+const BASE_DIRECTORIES: [&str; 2] = ["/usr/share/icons", "/usr/local/share/icons"];
+
+/// Where Nix installs icons, for the user profile and the NixOS system
+/// profile. These are normally part of $XDG_DATA_DIRS on NixOS, but not
+/// always, e.g. for applications started from a systemd unit.
+const NIX_PROFILES: [&str; 2] = ["~/.nix-profile", "/run/current-system/sw"];
+
+/// The base directories, in order of precedence, starting with the ones in
+/// $ICON_FINDER_PATH and the home directory of the user. Inside a sandbox,
+/// the directories the sandbox makes the icons of the host available in come
+/// last.
+pub fn base_directories() -> &'static [PathBuf] {
+    static DIRECTORIES: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+    DIRECTORIES.get_or_init(|| {
+        let home = env::var_os("HOME").map(PathBuf::from);
+        let data_home = env::var_os("XDG_DATA_HOME").map(PathBuf::from);
+        let data_dirs = env::var_os("XDG_DATA_DIRS");
+        let extra_dirs = env::var_os(PATH_VARIABLE);
+
+        let mut directories = extra_base_directories(extra_dirs.as_deref());
+        for directory in user_base_directories(home.as_deref(), data_home.as_deref()) {
+            push_unique(&mut directories, directory);
+        }
+        for directory in system_base_directories(data_dirs.as_deref(), home.as_deref()) {
+            push_unique(&mut directories, directory);
+        }
+        if let Some(sandbox) = Sandbox::detect() {
+            for directory in sandbox.base_directories(home.as_deref()) {
+                push_unique(&mut directories, directory);
+            }
+        }
+
+        directories
+    })
+}
+
+/// Unthemed icons are looked up in the base directories, followed by the
+/// pixmaps directories many legacy applications still install their icons
+/// into.
+pub(crate) fn unthemed_directories() -> &'static [PathBuf] {
+    static DIRECTORIES: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+    DIRECTORIES.get_or_init(|| {
+        let mut directories = base_directories().to_vec();
+        for directory in pixmap_directories(env::var_os("XDG_DATA_DIRS").as_deref()) {
+            push_unique(&mut directories, directory);
+        }
+
+        directories
+    })
+}
+
+/// The mime directories of shared-mime-info, in order of precedence,
+/// starting with the one of the user.
+pub(crate) fn mime_directories() -> &'static [PathBuf] {
+    static DIRECTORIES: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+    DIRECTORIES.get_or_init(|| {
+        let home = env::var_os("HOME").map(PathBuf::from);
+        let data_home = env::var_os("XDG_DATA_HOME").map(PathBuf::from);
+        let data_dirs = env::var_os("XDG_DATA_DIRS");
+
+        data_subdirectories(
+            home.as_deref(),
+            data_home.as_deref(),
+            data_dirs.as_deref(),
+            "mime",
+        )
+    })
+}
+
+/// The directories holding the desktop entries of applications, in order of
+/// precedence, starting with the one of the user.
+pub(crate) fn application_directories() -> &'static [PathBuf] {
+    static DIRECTORIES: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+    DIRECTORIES.get_or_init(|| {
+        let home = env::var_os("HOME").map(PathBuf::from);
+        let data_home = env::var_os("XDG_DATA_HOME").map(PathBuf::from);
+        let data_dirs = env::var_os("XDG_DATA_DIRS");
+
+        data_subdirectories(
+            home.as_deref(),
+            data_home.as_deref(),
+            data_dirs.as_deref(),
+            "applications",
+        )
+    })
+}
+
+/// The directories listed in $ICON_FINDER_PATH. Unlike $XDG_DATA_DIRS, these
+/// are base directories themselves, not data directories.
+fn extra_base_directories(extra_dirs: Option<&OsStr>) -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+
+    for directory in split_path(extra_dirs.unwrap_or_default()) {
+        push_unique(&mut directories, directory);
+    }
+
+    directories
+}
+
+/// The data home of the user, $XDG_DATA_HOME or ~/.local/share if unset.
+fn data_home(home: Option<&Path>, data_home: Option<&Path>) -> Option<PathBuf> {
+    data_home
+        .filter(|data_home| !data_home.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .or_else(|| home.map(|home| home.join(".local/share")))
+}
+
+/// A theme can be spread over several base directories, e.g. when a user
+/// overrides a few icons of a system theme in ~/.local/share/icons. All
+/// directories of the same name together make up the theme, they are
+/// searched in order of precedence, so the icons of the user win.
+fn user_base_directories(home: Option<&Path>, data_home: Option<&Path>) -> Vec<PathBuf> {
+    let home = home.filter(|home| !home.as_os_str().is_empty());
+
+    home.map(|home| home.join(".icons"))
+        .into_iter()
+        .chain(self::data_home(home, data_home).map(|data_home| data_home.join("icons")))
+        .collect()
+}
+
+/// The icons directory of every data directory, followed by the usual
+/// system directories and the Nix profiles that exist on this system.
+fn system_base_directories(data_dirs: Option<&OsStr>, home: Option<&Path>) -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+
+    for directory in data_directories(data_dirs) {
+        push_unique(&mut directories, directory.join("icons"));
+    }
+    for directory in &BASE_DIRECTORIES {
+        push_unique(&mut directories, PathBuf::from(directory));
+    }
+    for profile in &NIX_PROFILES {
+        let profile = match (profile.strip_prefix("~/"), home) {
+            (Some(path), Some(home)) => home.join(path),
+            (Some(_), None) => continue,
+            (None, _) => PathBuf::from(profile),
+        };
+
+        let directory = profile.join("share/icons");
+        if file_system().is_dir(&directory) {
+            push_unique(&mut directories, directory);
+        }
+    }
+
+    directories
+}
+
+/// The subdirectory of $XDG_DATA_HOME, followed by the one of every data
+/// directory.
+fn data_subdirectories(
+    home: Option<&Path>,
+    data_home: Option<&Path>,
+    data_dirs: Option<&OsStr>,
+    name: &str,
+) -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+
+    let home = home.filter(|home| !home.as_os_str().is_empty());
+    if let Some(data_home) = self::data_home(home, data_home) {
+        directories.push(data_home.join(name));
+    }
+    for directory in data_directories(data_dirs) {
+        push_unique(&mut directories, directory.join(name));
+    }
+
+    directories
+}
+
+/// The pixmaps directory of every data directory, always including
+/// /usr/share/pixmaps as the specification requires.
+fn pixmap_directories(data_dirs: Option<&OsStr>) -> Vec<PathBuf> {
+    let mut directories = Vec::new();
+
+    for directory in data_directories(data_dirs) {
+        push_unique(&mut directories, directory.join("pixmaps"));
+    }
+    push_unique(&mut directories, PathBuf::from("/usr/share/pixmaps"));
+
+    directories
+}
+
+/// The directories listed in $XDG_DATA_DIRS, in order of precedence.
+fn data_directories(data_dirs: Option<&OsStr>) -> impl Iterator<Item = PathBuf> + '_ {
+    split_path(
+        data_dirs
+            .filter(|data_dirs| !data_dirs.is_empty())
+            .unwrap_or_else(|| OsStr::new(DEFAULT_DATA_DIRS)),
+    )
+}
+
+/// The entries of a colon-separated list of directories, skipping empty
+/// entries. The entries are kept as they are, paths that are not valid
+/// UTF-8 included.
+fn split_path(path: &OsStr) -> impl Iterator<Item = PathBuf> + '_ {
+    env::split_paths(path).filter(|directory| !directory.as_os_str().is_empty())
+}
+
+/// Adds the directory unless already present. Paths are compared by their
+/// components, so a trailing slash makes no difference.
+fn push_unique(directories: &mut Vec<PathBuf>, directory: PathBuf) {
+    if !directories.contains(&directory) {
+        directories.push(directory);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_extra_base_directories() {
+        assert_eq!(
+            extra_base_directories(Some(OsStr::new("/opt/icons/::/tmp/icons:/opt/icons"))),
+            paths(&["/opt/icons", "/tmp/icons"])
+        );
+        assert!(extra_base_directories(Some(OsStr::new(""))).is_empty());
+        assert!(extra_base_directories(None).is_empty());
+    }
+
+    #[test]
+    fn test_user_base_directories() {
+        let home = Some(Path::new("/home/user"));
+        assert_eq!(
+            user_base_directories(home, None),
+            paths(&["/home/user/.icons", "/home/user/.local/share/icons"])
+        );
+        assert_eq!(
+            user_base_directories(home, Some(Path::new("/data"))),
+            paths(&["/home/user/.icons", "/data/icons"])
+        );
+        assert_eq!(
+            user_base_directories(home, Some(Path::new(""))),
+            paths(&["/home/user/.icons", "/home/user/.local/share/icons"])
+        );
+        assert!(user_base_directories(None, None).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_non_utf8_base_directories() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let home = Path::new(OsStr::from_bytes(b"/home/j\xf6rg"));
+        let directories = user_base_directories(Some(home), None);
+        assert_eq!(
+            directories[0].as_os_str().as_bytes(),
+            b"/home/j\xf6rg/.icons"
+        );
+
+        let data_dirs = OsStr::from_bytes(b"/opt/\xff/share:/usr/share");
+        assert_eq!(
+            system_base_directories(Some(data_dirs), None)[0]
+                .as_os_str()
+                .as_bytes(),
+            b"/opt/\xff/share/icons"
+        );
+    }
+
+    #[test]
+    fn test_system_base_directories() {
+        assert_eq!(
+            system_base_directories(None, None),
+            paths(&["/usr/local/share/icons", "/usr/share/icons"])
+        );
+
+        let nix_data_dirs =
+            OsStr::new("/home/user/.nix-profile/share:/run/current-system/sw/share/");
+        assert_eq!(
+            system_base_directories(Some(nix_data_dirs), Some(Path::new("/nonexistent"))),
+            paths(&[
+                "/home/user/.nix-profile/share/icons",
+                "/run/current-system/sw/share/icons",
+                "/usr/share/icons",
+                "/usr/local/share/icons",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_data_subdirectories() {
+        assert_eq!(
+            data_subdirectories(Some(Path::new("/home/user")), None, None, "mime"),
+            paths(&[
+                "/home/user/.local/share/mime",
+                "/usr/local/share/mime",
+                "/usr/share/mime",
+            ])
+        );
+        assert_eq!(
+            data_subdirectories(
+                None,
+                Some(Path::new("/data")),
+                Some(OsStr::new("/opt/share/")),
+                "mime"
+            ),
+            paths(&["/data/mime", "/opt/share/mime"])
+        );
+    }
+
+    #[test]
+    fn test_pixmap_directories() {
+        assert_eq!(
+            pixmap_directories(None),
+            paths(&["/usr/local/share/pixmaps", "/usr/share/pixmaps"])
+        );
+        assert_eq!(
+            pixmap_directories(Some(OsStr::new("/opt/share/:/usr/share"))),
+            paths(&["/opt/share/pixmaps", "/usr/share/pixmaps"])
+        );
+        assert_eq!(
+            pixmap_directories(Some(OsStr::new("/opt/share"))),
+            paths(&["/opt/share/pixmaps", "/usr/share/pixmaps"])
+        );
+    }
+}
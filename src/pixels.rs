@@ -0,0 +1,84 @@
+//! # Decoding icons
+//! Toolkits without image loaders of their own, like softbuffer and minifb,
+//! draw raw pixels. Decoding turns a loaded icon into a buffer of RGBA pixels
+//! at the size of the file, which is not necessarily the size the icon was
+//! looked up at. Decoding is done by the image crate and requires the `image`
+//! feature. XPM images are decoded by a parser of our own when the `xpm`
+//! feature is enabled as well.
+
+use std::io;
+
+use image::ImageFormat;
+
+#[cfg(feature = "xpm")]
+use crate::xpm;
+use crate::{IconFormat, IconPixels, LoadedIcon};
+
+/// Decodes the contents of the icon. Fails with `Unsupported` for formats
+/// that cannot be decoded and with `InvalidData` for broken files.
+pub(crate) fn decode(icon: &LoadedIcon) -> io::Result<IconPixels> {
+    match icon.format {
+        IconFormat::Png => decode_image(&icon.data, ImageFormat::Png),
+        #[cfg(feature = "xpm")]
+        IconFormat::Xpm => xpm::decode(&icon.data),
+        format => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("{} icons cannot be decoded", format.extension()),
+        )),
+    }
+}
+
+fn decode_image(data: &[u8], format: ImageFormat) -> io::Result<IconPixels> {
+    let image = image::load_from_memory_with_format(data, format)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?
+        .into_rgba8();
+
+    Ok(IconPixels {
+        width: image.width(),
+        height: image.height(),
+        data: image.into_raw(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use std::io::Cursor;
+    use std::path::PathBuf;
+
+    fn loaded(data: Vec<u8>, format: IconFormat) -> LoadedIcon {
+        LoadedIcon {
+            path: PathBuf::from("/icons/icon.png"),
+            data,
+            format,
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut image = RgbaImage::new(3, 2);
+        image.put_pixel(2, 1, Rgba([255, 0, 0, 128]));
+        let mut png = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+            .unwrap();
+
+        let pixels = decode(&loaded(png, IconFormat::Png)).unwrap();
+        assert_eq!((pixels.width, pixels.height), (3, 2));
+        assert_eq!(pixels.data.len(), 3 * 2 * 4);
+        assert_eq!(pixels.data[20..], [255, 0, 0, 128]);
+
+        let broken = decode(&loaded(b"\x89PNG".to_vec(), IconFormat::Png)).unwrap_err();
+        assert_eq!(broken.kind(), io::ErrorKind::InvalidData);
+        let svg = decode(&loaded(b"<svg/>".to_vec(), IconFormat::Svg)).unwrap_err();
+        assert_eq!(svg.kind(), io::ErrorKind::Unsupported);
+
+        #[cfg(feature = "xpm")]
+        {
+            let xpm = b"/* XPM */\nstatic char *icon[] = {\"1 1 1 1\", \". c #00ff00\", \".\"};";
+            let pixels = decode(&loaded(xpm.to_vec(), IconFormat::Xpm)).unwrap();
+            assert_eq!(pixels.data, [0, 255, 0, 255]);
+        }
+    }
+}
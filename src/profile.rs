@@ -0,0 +1,250 @@
+//! # Profiling
+//! When resolving icons is slow on a machine, it helps to know which part of
+//! the lookup the time goes into. A finder built with
+//! [`IconFinderBuilder::profiling`](crate::IconFinderBuilder::profiling)
+//! measures the phases of every lookup, and summarizes them in a [`Profile`]
+//! that can be printed and attached to a bug report.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// The number of slowest lookups a profile keeps.
+const SLOWEST_LOOKUPS: usize = 10;
+
+/// A phase of the lookup. Phases are nested: the exact and closest match
+/// scans include the scans of parent themes, so the time spent in parent
+/// themes is part of those as well.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// Searching the directories matching the size exactly.
+    ExactMatch,
+    /// Searching all directories for the closest size, when no directory
+    /// matching the size exactly has the icon.
+    ClosestMatch,
+    /// Searching the inherited themes and the fallback themes, when the
+    /// theme itself does not have the icon.
+    Parents,
+    /// Searching the directories holding icons that are not part of a theme.
+    Unthemed,
+}
+
+impl Phase {
+    const ALL: [Phase; 4] = [
+        Phase::ExactMatch,
+        Phase::ClosestMatch,
+        Phase::Parents,
+        Phase::Unthemed,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Phase::ExactMatch => "exact match",
+            Phase::ClosestMatch => "closest match",
+            Phase::Parents => "parent themes",
+            Phase::Unthemed => "unthemed icons",
+        }
+    }
+}
+
+/// The time spent in a phase, over all lookups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PhaseTimings {
+    /// The number of times the phase was entered.
+    pub count: u64,
+    pub total: Duration,
+    /// The longest time a single run of the phase took.
+    pub max: Duration,
+}
+
+impl PhaseTimings {
+    fn record(&mut self, duration: Duration) {
+        self.count += 1;
+        self.total += duration;
+        self.max = self.max.max(duration);
+    }
+}
+
+/// Timings of the lookups of a finder, see
+/// [`IconFinder::profile`](crate::IconFinder::profile). Lookups answered from
+/// the cache are not included. Printing a profile gives a summary.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Profile {
+    lookups: PhaseTimings,
+    phases: [PhaseTimings; 4],
+    slowest: Vec<(String, Duration)>,
+}
+
+impl Profile {
+    /// The time spent in lookups as a whole.
+    pub fn lookups(&self) -> PhaseTimings {
+        self.lookups
+    }
+
+    /// The time spent in a phase of the lookups.
+    pub fn phase(&self, phase: Phase) -> PhaseTimings {
+        self.phases[phase as usize]
+    }
+
+    /// The icons whose lookups took the longest, with the time they took,
+    /// slowest first.
+    pub fn slowest(&self) -> &[(String, Duration)] {
+        &self.slowest
+    }
+
+    fn record_lookup(&mut self, icon: &str, duration: Duration) {
+        self.lookups.record(duration);
+
+        let index = self
+            .slowest
+            .iter()
+            .position(|(_, slow)| duration > *slow)
+            .unwrap_or(self.slowest.len());
+        if index < SLOWEST_LOOKUPS {
+            self.slowest.insert(index, (icon.to_owned(), duration));
+            self.slowest.truncate(SLOWEST_LOOKUPS);
+        }
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let row = |f: &mut fmt::Formatter, name: &str, timings: PhaseTimings| {
+            writeln!(
+                f,
+                "{:<16}{:>8}{:>14.3?}{:>14.3?}",
+                name, timings.count, timings.total, timings.max
+            )
+        };
+
+        writeln!(
+            f,
+            "{:<16}{:>8}{:>14}{:>14}",
+            "phase", "count", "total", "max"
+        )?;
+        row(f, "lookup", self.lookups)?;
+        for phase in Phase::ALL {
+            row(f, phase.name(), self.phase(phase))?;
+        }
+
+        if !self.slowest.is_empty() {
+            writeln!(f, "\nslowest lookups")?;
+            for (icon, duration) in &self.slowest {
+                writeln!(f, "{:<30}{:>14.3?}", icon, duration)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The profile lookups are recorded in, if profiling is enabled. Does not
+/// affect the result of a lookup, so all profilers compare equal and
+/// lookups share cached results regardless of profiling.
+#[derive(Clone, Default)]
+pub(crate) struct Profiler(pub(crate) Option<Arc<Mutex<Profile>>>);
+
+impl Profiler {
+    pub(crate) fn enabled() -> Profiler {
+        Profiler(Some(Arc::default()))
+    }
+
+    /// Runs a phase of the lookup, recording the time it takes.
+    pub(crate) fn time<T>(&self, phase: Phase, run: impl FnOnce() -> T) -> T {
+        self.measure(run, |profile, duration| {
+            profile.phases[phase as usize].record(duration)
+        })
+    }
+
+    /// Runs a whole lookup, recording the time it takes.
+    pub(crate) fn time_lookup<T>(&self, icon: &str, run: impl FnOnce() -> T) -> T {
+        self.measure(run, |profile, duration| {
+            profile.record_lookup(icon, duration)
+        })
+    }
+
+    pub(crate) fn profile(&self) -> Option<Profile> {
+        self.lock().map(|profile| profile.clone())
+    }
+
+    pub(crate) fn reset(&self) {
+        if let Some(mut profile) = self.lock() {
+            *profile = Profile::default();
+        }
+    }
+
+    fn measure<T>(
+        &self,
+        run: impl FnOnce() -> T,
+        record: impl FnOnce(&mut Profile, Duration),
+    ) -> T {
+        if self.0.is_none() {
+            return run();
+        }
+
+        let start = Instant::now();
+        let value = run();
+        let duration = start.elapsed();
+        if let Some(mut profile) = self.lock() {
+            record(&mut profile, duration);
+        }
+
+        value
+    }
+
+    fn lock(&self) -> Option<MutexGuard<'_, Profile>> {
+        let profile = self.0.as_ref()?;
+        Some(profile.lock().unwrap_or_else(|error| error.into_inner()))
+    }
+}
+
+impl PartialEq for Profiler {
+    fn eq(&self, _: &Profiler) -> bool {
+        true
+    }
+}
+
+impl Eq for Profiler {}
+
+impl Hash for Profiler {
+    fn hash<H: Hasher>(&self, _: &mut H) {}
+}
+
+impl fmt::Debug for Profiler {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Profiler")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiler() {
+        let profiler = Profiler::enabled();
+        for icon in ["firefox", "gimp", "thunderbird"] {
+            profiler.time_lookup(icon, || {
+                profiler.time(Phase::ExactMatch, || {});
+                if icon == "gimp" {
+                    profiler.time(Phase::Unthemed, || {
+                        std::thread::sleep(Duration::from_millis(5))
+                    });
+                }
+            });
+        }
+
+        let profile = profiler.profile().unwrap();
+        assert_eq!(profile.lookups().count, 3);
+        assert_eq!(profile.phase(Phase::ExactMatch).count, 3);
+        assert_eq!(profile.phase(Phase::ClosestMatch).count, 0);
+        assert!(profile.phase(Phase::Unthemed).max >= Duration::from_millis(5));
+        assert_eq!(profile.slowest()[0].0, "gimp");
+        assert!(profile.to_string().contains("unthemed icons"));
+
+        profiler.reset();
+        assert_eq!(profiler.profile(), Some(Profile::default()));
+        assert_eq!(Profiler::default().profile(), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! # Python bindings
+//! Exposes the lookup to Python, so scripts like launcher extensions and
+//! configuration generators find the same icons as Rust applications. The
+//! module is built with maturin, see pyproject.toml.
+//!
+//! ```python
+//! import icon_finder
+//!
+//! finder = icon_finder.IconFinder("Adwaita")
+//! finder.find_icon("firefox", size=48)
+//! icon_finder.list_themes()
+//! ```
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::{global, Error, Theme};
+
+impl From<Error> for PyErr {
+    fn from(error: Error) -> PyErr {
+        PyValueError::new_err(error.to_string())
+    }
+}
+
+/// Looks up icons in a theme, its parents and the hicolor fallback theme.
+#[pyclass(name = "IconFinder", frozen)]
+struct PyIconFinder {
+    finder: crate::IconFinder,
+}
+
+#[pymethods]
+impl PyIconFinder {
+    /// Creates a finder for the theme with the given internal name, or for
+    /// the theme selected by the user.
+    #[new]
+    #[pyo3(signature = (theme = None))]
+    fn new(theme: Option<&str>) -> PyResult<PyIconFinder> {
+        let theme = match theme {
+            Some(theme) => Theme::load(theme)?,
+            None => global().theme().clone(),
+        };
+
+        Ok(PyIconFinder {
+            finder: crate::IconFinder::new(theme),
+        })
+    }
+
+    /// The internal name of the theme icons are looked up in.
+    #[getter]
+    fn theme(&self) -> &str {
+        &self.finder.theme().name
+    }
+
+    /// The path of the icon, or None if it is not found.
+    #[pyo3(signature = (icon, size = 48, scale = 1))]
+    fn find_icon(&self, py: Python<'_>, icon: &str, size: i16, scale: i16) -> Option<OsString> {
+        py.detach(|| self.finder.find_icon(icon, size, scale))
+            .map(PathBuf::into_os_string)
+    }
+}
+
+/// The path of the icon in the theme selected by the user, or None if it is
+/// not found.
+#[pyfunction]
+#[pyo3(signature = (icon, size = 48, scale = 1))]
+fn find_icon(py: Python<'_>, icon: &str, size: i16, scale: i16) -> Option<OsString> {
+    py.detach(|| global().find_icon(icon, size, scale))
+        .map(PathBuf::into_os_string)
+}
+
+/// The internal names of the installed themes, sorted.
+#[pyfunction]
+#[pyo3(signature = (include_hidden = false))]
+fn list_themes(py: Python<'_>, include_hidden: bool) -> Vec<String> {
+    py.detach(|| {
+        crate::list_themes(include_hidden)
+            .into_iter()
+            .map(|theme| theme.name)
+            .collect()
+    })
+}
+
+#[pymodule]
+fn icon_finder(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyIconFinder>()?;
+    module.add_function(wrap_pyfunction!(find_icon, module)?)?;
+    module.add_function(wrap_pyfunction!(list_themes, module)?)?;
+    Ok(())
+}
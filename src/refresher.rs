@@ -0,0 +1,130 @@
+//! # Background refresher
+//! With a revalidation interval, the first lookup after the interval passed
+//! looks at the theme directories, and the first lookups after a change list
+//! the changed directories again. Interactive applications would rather not
+//! pay for that while drawing. A refresher does both on a thread of its own,
+//! see [`IconFinder::refresh`], and lookups trust the cached results in
+//! between.
+
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::IconFinder;
+
+/// The shortest interval the refresher waits between refreshes, so a zero
+/// interval does not keep a core busy refreshing.
+pub(crate) const MIN_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Refreshes a finder periodically, as returned by
+/// [`IconFinder::spawn_refresher`]. Dropping the refresher, or calling
+/// [`stop`](Refresher::stop), stops it, waiting for a refresh in progress to
+/// finish. It stops by itself once the finder is dropped.
+pub struct Refresher {
+    finder: Weak<IconFinder>,
+    stop: Option<Sender<()>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Refresher {
+    pub(crate) fn spawn(finder: &Arc<IconFinder>, interval: Duration) -> Refresher {
+        let interval = interval.max(MIN_INTERVAL);
+        let (stop, stopped) = mpsc::channel::<()>();
+        let weak = Arc::downgrade(finder);
+        finder.cache().add_refresher();
+
+        let worker = {
+            let finder = Weak::clone(&weak);
+            thread::spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+                    // The refresher does not keep the finder alive.
+                    match finder.upgrade() {
+                        Some(finder) => {
+                            finder.refresh();
+                        }
+                        None => return,
+                    }
+                }
+            })
+        };
+
+        Refresher {
+            finder: weak,
+            stop: Some(stop),
+            worker: Some(worker),
+        }
+    }
+
+    /// Stops refreshing, waiting for a refresh in progress to finish.
+    /// Lookups look at the theme directories themselves again afterwards.
+    pub fn stop(self) {}
+}
+
+impl Drop for Refresher {
+    fn drop(&mut self) {
+        // Disconnecting the stop channel wakes the worker up immediately.
+        self.stop.take();
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+        if let Some(finder) = self.finder.upgrade() {
+            finder.cache().remove_refresher();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::{FileSystem, MemoryFileSystem, Theme};
+
+    #[test]
+    fn test_refresher() {
+        let memory = Arc::new(MemoryFileSystem::new());
+        memory.add_file(
+            "/usr/share/icons/icon-finder-refresher/index.theme",
+            "[Icon Theme]\nName=Refresher\nDirectories=48x48/apps\n\n\
+             [48x48/apps]\nSize=48\nType=Fixed\n",
+        );
+        let directory = "/usr/share/icons/icon-finder-refresher";
+        let fs: Arc<dyn FileSystem> = memory.clone();
+        let theme = Theme::load_from("icon-finder-refresher", &*fs).unwrap();
+        let finder = Arc::new(
+            IconFinder::builder(theme)
+                .file_system(fs)
+                .cache_capacity(16)
+                .revalidation_interval(Duration::ZERO)
+                .build(),
+        );
+
+        let refresher = finder.spawn_refresher(Duration::from_millis(10));
+        assert!(finder.find_icon("browser", 48, 1).is_none());
+        memory.add_file(format!("{}/48x48/apps/browser.png", directory), "");
+        memory.add_file(format!("{}/icon-theme.cache", directory), "");
+
+        // Lookups trust the cache until the refresher noticed the change.
+        let start = Instant::now();
+        while finder.find_icon("browser", 48, 1).is_none() {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(finder.stats().revalidations, 0);
+
+        refresher.stop();
+        memory.add_file(format!("{}/48x48/apps/editor.png", directory), "");
+        memory.add_file(format!("{}/index.theme", directory), "");
+        assert!(finder.find_icon("browser", 48, 1).is_some());
+        assert_eq!(finder.stats().revalidations, 1);
+
+        // Stops by itself once the finder is gone. A zero interval waits the
+        // minimum interval instead of refreshing continuously.
+        let refresher = finder.spawn_refresher(Duration::ZERO);
+        thread::sleep(MIN_INTERVAL);
+        drop(finder);
+        drop(refresher);
+    }
+}
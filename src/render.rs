@@ -0,0 +1,88 @@
+//! # Rendering icons
+//! Scalable icons look sharp at any size, but only to consumers that can
+//! render SVG images. Rendering rasterizes the SVG image of an icon with
+//! resvg, at exactly the pixel size it is displayed at, so consumers that
+//! only draw bitmaps benefit from scalable themes as well. Requires the
+//! `render` feature.
+
+use std::io;
+
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{Options, Tree};
+
+use crate::{IconPixels, LoadedIcon};
+
+/// Renders the SVG image of the icon into a square bitmap of the given
+/// width in pixels. Images that are not square keep their aspect ratio and
+/// are centered. Fails with `InvalidData` if the image cannot be parsed.
+pub(crate) fn render(icon: &LoadedIcon, pixels: u32) -> io::Result<IconPixels> {
+    let options = Options {
+        resources_dir: icon.path.parent().map(|directory| directory.to_path_buf()),
+        ..Options::default()
+    };
+    let tree = Tree::from_data(&icon.data, &options)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+    let mut pixmap = Pixmap::new(pixels, pixels)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "icons cannot be empty"))?;
+    let size = tree.size();
+    let scale = (pixels as f32 / size.width()).min(pixels as f32 / size.height());
+    let transform = Transform::from_scale(scale, scale).post_translate(
+        (pixels as f32 - size.width() * scale) / 2.0,
+        (pixels as f32 - size.height() * scale) / 2.0,
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    let data = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    Ok(IconPixels {
+        width: pixels,
+        height: pixels,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::IconFormat;
+    use std::path::PathBuf;
+
+    fn loaded(svg: &str) -> LoadedIcon {
+        LoadedIcon {
+            path: PathBuf::from("/icons/icon.svg"),
+            data: svg.as_bytes().to_vec(),
+            format: IconFormat::Svg,
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        // A red image, twice as wide as high, with a transparent bottom half.
+        let icon = loaded(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"16\" height=\"8\">\
+             <rect width=\"16\" height=\"4\" fill=\"#ff0000\"/></svg>",
+        );
+
+        let pixels = render(&icon, 32).unwrap();
+        assert_eq!((pixels.width, pixels.height), (32, 32));
+        assert_eq!(pixels.data.len(), 32 * 32 * 4);
+
+        let pixel = |x: usize, y: usize| &pixels.data[(y * 32 + x) * 4..][..4];
+        // Centered vertically, the image covers rows 8 to 24.
+        assert_eq!(pixel(16, 4), [0, 0, 0, 0]);
+        assert_eq!(pixel(16, 12), [255, 0, 0, 255]);
+        assert_eq!(pixel(16, 20), [0, 0, 0, 0]);
+
+        let broken = render(&loaded("not an image"), 32).unwrap_err();
+        assert_eq!(broken.kind(), io::ErrorKind::InvalidData);
+        assert!(render(&icon, 0).is_err());
+    }
+}
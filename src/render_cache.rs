@@ -0,0 +1,364 @@
+//! # Render cache
+//! Rendering an SVG image costs far more than looking it up, and the same
+//! symbolic icons are rendered at 16×16 over and over again. The finder can
+//! remember recently rendered bitmaps in memory, and optionally keep them on
+//! disk so they survive the process.
+//!
+//! Bitmaps are keyed by the path of the image, the size and scale they were
+//! rendered at, the palette symbolic icons were recolored with and the
+//! modification time of the image, so updated images are rendered again.
+//! Bitmaps on disk are stored in files named by a hash of the key, holding
+//! the key itself, so colliding hashes are told apart, and the width and
+//! height followed by the pixels. The directory is kept below 64 MiB by
+//! removing the bitmaps written longest ago.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::{IconPixels, SymbolicPalette};
+
+/// Written at the start of every bitmap on disk, changed whenever the layout
+/// of the files changes.
+const MAGIC: &[u8] = b"icon-finder rgba 2\n";
+
+/// The number of bytes of bitmaps kept on disk.
+const DISK_CAPACITY: u64 = 64 * 1024 * 1024;
+
+/// The directory is pruned on the first write and after every this many
+/// writes, instead of adding up the sizes of its files on every write.
+const PRUNE_INTERVAL: u64 = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Key {
+    pub(crate) path: PathBuf,
+    pub(crate) size: i16,
+    pub(crate) scale: i16,
+    pub(crate) modified: SystemTime,
+    /// The palette symbolic icons were recolored with.
+    pub(crate) palette: Option<SymbolicPalette>,
+}
+
+/// The directory rendered bitmaps are kept in by default,
+/// $XDG_CACHE_HOME/icon-finder/rendered, or ~/.cache/icon-finder/rendered if
+/// $XDG_CACHE_HOME is not set. `None` if neither is set.
+pub fn default_render_cache_directory() -> Option<PathBuf> {
+    let home = env::var_os("HOME").map(PathBuf::from);
+    let cache_home = env::var_os("XDG_CACHE_HOME").map(PathBuf::from);
+
+    render_cache_directory(home.as_deref(), cache_home.as_deref())
+}
+
+fn render_cache_directory(home: Option<&Path>, cache_home: Option<&Path>) -> Option<PathBuf> {
+    cache_home
+        .filter(|cache_home| !cache_home.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+        .or_else(|| {
+            home.filter(|home| !home.as_os_str().is_empty())
+                .map(|home| home.join(".cache"))
+        })
+        .map(|cache_home| cache_home.join("icon-finder/rendered"))
+}
+
+/// Bounded cache of rendered bitmaps, evicting the least recently used one
+/// once it is full, backed by an optional directory on disk.
+pub(crate) struct RenderCache {
+    capacity: usize,
+    directory: Option<PathBuf>,
+    /// Incremented on every access, the entry with the lowest tick is the
+    /// least recently used one.
+    tick: u64,
+    entries: HashMap<Key, (u64, IconPixels)>,
+    recency: BTreeMap<u64, Key>,
+    /// The number of bitmaps written to disk.
+    writes: u64,
+}
+
+impl RenderCache {
+    pub(crate) fn new(capacity: usize, directory: Option<PathBuf>) -> RenderCache {
+        RenderCache {
+            capacity,
+            directory,
+            tick: 0,
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            writes: 0,
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.capacity > 0 || self.directory.is_some()
+    }
+
+    /// The bitmap rendered for the key, from memory or from disk.
+    pub(crate) fn get(&mut self, key: &Key) -> Option<IconPixels> {
+        self.tick += 1;
+        if let Some((last_used, pixels)) = self.entries.get_mut(key) {
+            self.recency.remove(last_used);
+            *last_used = self.tick;
+            self.recency.insert(self.tick, key.clone());
+            return Some(pixels.clone());
+        }
+
+        let pixels = read(&self.file(key)?, key).ok()?;
+        self.remember(key.clone(), pixels.clone());
+        Some(pixels)
+    }
+
+    /// Remembers the bitmap rendered for the key. Failing to write it to
+    /// disk is not an error, it is rendered again next time.
+    pub(crate) fn insert(&mut self, key: Key, pixels: &IconPixels) {
+        if let Some(file) = self.file(&key) {
+            if self.writes.is_multiple_of(PRUNE_INTERVAL) {
+                if let Some(directory) = file.parent() {
+                    prune(directory, DISK_CAPACITY);
+                }
+            }
+            self.writes += 1;
+
+            let _result = write(&file, &key, pixels);
+            #[cfg(feature = "log")]
+            if let Err(error) = _result {
+                log::debug!("not caching {}: {}", file.display(), error);
+            }
+        }
+
+        self.tick += 1;
+        self.remember(key, pixels.clone());
+    }
+
+    fn remember(&mut self, key: Key, pixels: IconPixels) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        if let Some((last_used, _)) = self.entries.remove(&key) {
+            self.recency.remove(&last_used);
+        }
+        while self.entries.len() >= self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&oldest);
+        }
+
+        self.recency.insert(self.tick, key.clone());
+        self.entries.insert(key, (self.tick, pixels));
+    }
+
+    fn file(&self, key: &Key) -> Option<PathBuf> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+
+        self.directory
+            .as_ref()
+            .map(|directory| directory.join(format!("{:016x}.rgba", hasher.finish())))
+    }
+}
+
+fn invalid() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "broken cached bitmap")
+}
+
+/// The key as written to the header of its bitmap.
+fn key_bytes(key: &Key) -> Vec<u8> {
+    format!("{:?}\n", key).into_bytes()
+}
+
+fn read(file: &Path, key: &Key) -> io::Result<IconPixels> {
+    let contents = fs::read(file)?;
+    let contents = contents.strip_prefix(MAGIC).ok_or_else(invalid)?;
+    let contents = contents
+        .strip_prefix(key_bytes(key).as_slice())
+        .ok_or_else(invalid)?;
+    if contents.len() < 8 {
+        return Err(invalid());
+    }
+
+    let (size, data) = contents.split_at(8);
+    let width = u32::from_le_bytes([size[0], size[1], size[2], size[3]]);
+    let height = u32::from_le_bytes([size[4], size[5], size[6], size[7]]);
+    if data.len() as u64 != u64::from(width) * u64::from(height) * 4 {
+        return Err(invalid());
+    }
+
+    Ok(IconPixels {
+        width,
+        height,
+        data: data.to_vec(),
+    })
+}
+
+/// Writes the bitmap to a temporary file first, so concurrent readers never
+/// see a partially written one.
+fn write(file: &Path, key: &Key, pixels: &IconPixels) -> io::Result<()> {
+    if let Some(directory) = file.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    let key = key_bytes(key);
+    let mut contents = Vec::with_capacity(MAGIC.len() + key.len() + 8 + pixels.data.len());
+    contents.extend_from_slice(MAGIC);
+    contents.extend_from_slice(&key);
+    contents.extend_from_slice(&pixels.width.to_le_bytes());
+    contents.extend_from_slice(&pixels.height.to_le_bytes());
+    contents.extend_from_slice(&pixels.data);
+
+    let temporary = file.with_extension(format!("tmp{}", std::process::id()));
+    if let Err(error) = fs::write(&temporary, contents).and_then(|_| fs::rename(&temporary, file)) {
+        let _ = fs::remove_file(&temporary);
+        return Err(error);
+    }
+    Ok(())
+}
+
+/// Removes the bitmaps written longest ago until the ones left take up at
+/// most `capacity` bytes. Files that cannot be looked at are left alone.
+fn prune(directory: &Path, capacity: u64) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return;
+    };
+
+    let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            if path.extension()? != "rgba" {
+                return None;
+            }
+            let metadata = fs::metadata(&path).ok()?;
+            Some((metadata.modified().ok()?, metadata.len(), path))
+        })
+        .collect();
+
+    let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort();
+    for (_, len, path) in files {
+        if size <= capacity {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            size -= len;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn key(path: &str, modified: u64) -> Key {
+        Key {
+            path: PathBuf::from(path),
+            size: 16,
+            scale: 1,
+            modified: SystemTime::UNIX_EPOCH + Duration::from_secs(modified),
+            palette: None,
+        }
+    }
+
+    fn pixels(value: u8) -> IconPixels {
+        IconPixels {
+            width: 1,
+            height: 2,
+            data: vec![value; 8],
+        }
+    }
+
+    #[test]
+    fn test_render_cache_directory() {
+        assert_eq!(
+            render_cache_directory(Some(Path::new("/home/user")), None),
+            Some(PathBuf::from("/home/user/.cache/icon-finder/rendered"))
+        );
+        assert_eq!(
+            render_cache_directory(Some(Path::new("/home/user")), Some(Path::new("/cache"))),
+            Some(PathBuf::from("/cache/icon-finder/rendered"))
+        );
+        assert_eq!(render_cache_directory(None, Some(Path::new(""))), None);
+    }
+
+    #[test]
+    fn test_memory_cache() {
+        let mut cache = RenderCache::new(2, None);
+        cache.insert(key("/a.svg", 1), &pixels(1));
+        cache.insert(key("/b.svg", 1), &pixels(2));
+
+        assert_eq!(cache.get(&key("/a.svg", 1)), Some(pixels(1)));
+        // An image that changed since it was rendered is rendered again.
+        assert_eq!(cache.get(&key("/a.svg", 2)), None);
+
+        // The least recently used bitmap is evicted.
+        cache.insert(key("/c.svg", 1), &pixels(3));
+        assert_eq!(cache.get(&key("/b.svg", 1)), None);
+        assert_eq!(cache.get(&key("/a.svg", 1)), Some(pixels(1)));
+        assert_eq!(cache.get(&key("/c.svg", 1)), Some(pixels(3)));
+    }
+
+    #[test]
+    fn test_disk_cache() {
+        let directory = env::temp_dir().join(format!("icon-finder-render-{}", std::process::id()));
+
+        RenderCache::new(0, Some(directory.clone())).insert(key("/a.svg", 1), &pixels(1));
+
+        let mut cache = RenderCache::new(0, Some(directory.clone()));
+        assert_eq!(cache.get(&key("/a.svg", 1)), Some(pixels(1)));
+        assert_eq!(cache.get(&key("/a.svg", 2)), None);
+
+        let file = cache.file(&key("/a.svg", 1)).unwrap();
+        // A bitmap stored under the hash of another key is not used.
+        fs::copy(&file, cache.file(&key("/b.svg", 1)).unwrap()).unwrap();
+        assert_eq!(cache.get(&key("/b.svg", 1)), None);
+
+        let mut broken = MAGIC.to_vec();
+        broken.extend(key_bytes(&key("/a.svg", 1)));
+        broken.extend(b"\x01\0\0\0");
+        fs::write(&file, broken).unwrap();
+        assert_eq!(cache.get(&key("/a.svg", 1)), None);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_prune() {
+        let directory = env::temp_dir().join(format!("icon-finder-prune-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        for (name, modified) in [("old.rgba", 1), ("new.rgba", 3), ("middle.rgba", 2)] {
+            let file = fs::File::create(directory.join(name)).unwrap();
+            file.set_len(10).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(modified))
+                .unwrap();
+        }
+        fs::write(directory.join("other"), [0; 100]).unwrap();
+
+        prune(&directory, 25);
+        let mut left: Vec<_> = fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        left.sort();
+        assert_eq!(left, ["middle.rgba", "new.rgba", "other"]);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_write_failure() {
+        let directory =
+            env::temp_dir().join(format!("icon-finder-render-fail-{}", std::process::id()));
+        // Renaming onto a directory fails.
+        let file = directory.join("taken.rgba");
+        fs::create_dir_all(file.join("inside")).unwrap();
+
+        assert!(write(&file, &key("/a.svg", 1), &pixels(1)).is_err());
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+//! # Lookup results
+//! Besides the path of the icon, toolkits need to know where an icon came
+//! from, e.g. to decide whether it has to be rescaled before it is displayed.
+
+use std::path::PathBuf;
+
+use crate::{IconData, IconFormat, ThemeDirectory, DEFAULT_SCALE};
+
+/// How well the returned icon matches the requested size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MatchKind {
+    /// The icon comes from a directory matching the requested size and scale.
+    Exact,
+    /// No directory matching the requested size provides the icon, the icon
+    /// comes from the directory with the closest size instead.
+    Closest,
+    /// The icon was not found in any theme, it was found directly in one of
+    /// the base directories.
+    Unthemed,
+    /// The icon was given as the path of an icon file rather than a name.
+    File,
+}
+
+/// An icon found by a lookup, together with its provenance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LookupResult {
+    /// Path of the icon file.
+    pub path: PathBuf,
+    /// Internal name of the theme that provided the icon, `None` for unthemed
+    /// icons.
+    pub theme: Option<String>,
+    /// The theme directory the icon was found in, `None` for unthemed icons.
+    pub directory: Option<ThemeDirectory>,
+    pub match_kind: MatchKind,
+    pub format: IconFormat,
+}
+
+impl LookupResult {
+    /// The nominal size of the icon, as given by its theme directory.
+    pub fn size(&self) -> Option<i16> {
+        self.directory.as_ref().map(|directory| directory.size)
+    }
+
+    /// The scale of the icon, as given by its theme directory.
+    pub fn scale(&self) -> Option<i16> {
+        self.directory
+            .as_ref()
+            .map(|directory| directory.scale.unwrap_or(DEFAULT_SCALE))
+    }
+
+    /// The data of the icon, read from the .icon file next to it. `None` if
+    /// the icon has no data file or the file could not be parsed.
+    pub fn icon_data(&self) -> Option<IconData> {
+        IconData::read(&self.path.with_extension("icon")).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ThemeDirectoryType;
+
+    #[test]
+    fn test_lookup_result_size() {
+        let mut result = LookupResult {
+            path: PathBuf::from("/usr/share/icons/hicolor/48x48@2/apps/firefox.png"),
+            theme: Some("hicolor".to_owned()),
+            directory: Some(ThemeDirectory {
+                name: "48x48@2/apps".to_owned(),
+                size: 48,
+                scale: Some(2),
+                context: None,
+                r#type: ThemeDirectoryType::Threshold,
+                min_size: None,
+                max_size: None,
+                threshold: None,
+            }),
+            match_kind: MatchKind::Exact,
+            format: IconFormat::Png,
+        };
+
+        assert_eq!(result.size(), Some(48));
+        assert_eq!(result.scale(), Some(2));
+
+        result.directory = None;
+        assert_eq!(result.size(), None);
+        assert_eq!(result.scale(), None);
+    }
+}
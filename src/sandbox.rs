@@ -0,0 +1,78 @@
+//! # Sandboxes
+//! Applications running in a Flatpak or Snap sandbox do not see the icon
+//! themes of the host system in the usual places. Both sandboxes make them
+//! available in directories of their own instead.
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::filesystem::file_system;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Sandbox {
+    Flatpak,
+    /// A Snap, with the directory the snap is mounted at.
+    Snap(PathBuf),
+}
+
+impl Sandbox {
+    /// The sandbox the application runs in, if any.
+    pub(crate) fn detect() -> Option<Sandbox> {
+        if file_system().is_file(Path::new("/.flatpak-info")) || env::var_os("FLATPAK_ID").is_some()
+        {
+            return Some(Sandbox::Flatpak);
+        }
+
+        env::var_os("SNAP").map(|snap| Sandbox::Snap(PathBuf::from(snap)))
+    }
+
+    /// Base directories the sandbox makes icons available in, besides the
+    /// usual ones.
+    pub(crate) fn base_directories(&self, home: Option<&Path>) -> Vec<PathBuf> {
+        match self {
+            Sandbox::Flatpak => {
+                let mut directories = vec![
+                    PathBuf::from("/run/host/user-share/icons"),
+                    PathBuf::from("/run/host/share/icons"),
+                ];
+                // Icons exported by installed Flatpak applications.
+                if let Some(home) = home {
+                    directories.push(home.join(".local/share/flatpak/exports/share/icons"));
+                }
+                directories.push(PathBuf::from("/var/lib/flatpak/exports/share/icons"));
+                directories
+            }
+            Sandbox::Snap(snap) => vec![
+                snap.join("usr/share/icons"),
+                PathBuf::from("/var/lib/snapd/desktop/icons"),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sandbox_base_directories() {
+        assert_eq!(
+            Sandbox::Flatpak.base_directories(Some(Path::new("/home/user"))),
+            [
+                "/run/host/user-share/icons",
+                "/run/host/share/icons",
+                "/home/user/.local/share/flatpak/exports/share/icons",
+                "/var/lib/flatpak/exports/share/icons",
+            ]
+            .map(PathBuf::from)
+        );
+        assert_eq!(
+            Sandbox::Snap(PathBuf::from("/snap/gimp/current")).base_directories(None),
+            [
+                "/snap/gimp/current/usr/share/icons",
+                "/var/lib/snapd/desktop/icons",
+            ]
+            .map(PathBuf::from)
+        );
+    }
+}
@@ -0,0 +1,216 @@
+//! # Icon search
+//! Icon pickers let the user search the icons of a theme by name, and a
+//! lookup that finds nothing can suggest the name that was probably meant.
+//! The query is matched against the names of all icons the theme, the themes
+//! it inherits from and the fallback themes provide. Names starting with the
+//! query come first, followed by names containing it and finally names that
+//! are a few typos away from it, like firefox for "firefx".
+//!
+//! Completion only offers the names starting with what was typed so far, in
+//! alphabetical order, as icon pickers and shell completion expect.
+
+use std::sync::Arc;
+
+use crate::enumerate::theme_chain_icon_names;
+use crate::{LookupOptions, Theme};
+
+/// How well an icon name matches a query, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum MatchQuality {
+    Exact,
+    Prefix,
+    Substring,
+    /// The name, or a prefix of it, is the given number of edits away from
+    /// the query.
+    Fuzzy(usize),
+}
+
+/// The number of edits needed to turn the query into the name, or into the
+/// prefix of the name it comes closest to.
+fn prefix_edit_distance(query: &[char], name: &[char]) -> usize {
+    // The distances to every prefix of the name, for the query so far.
+    let mut row: Vec<usize> = (0..=name.len()).collect();
+
+    for (i, query_char) in query.iter().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, name_char) in name.iter().enumerate() {
+            let substitution = previous + usize::from(query_char != name_char);
+            previous = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+        }
+    }
+
+    row.into_iter().min().unwrap_or(query.len())
+}
+
+/// How well the name matches the lowercase query, `None` if it does not.
+/// Short queries only match exactly, as every name is a few typos away from
+/// them.
+fn match_quality(query: &str, name: &str) -> Option<MatchQuality> {
+    let name = name.to_lowercase();
+    if name == query {
+        return Some(MatchQuality::Exact);
+    }
+    if name.starts_with(query) {
+        return Some(MatchQuality::Prefix);
+    }
+    if name.contains(query) {
+        return Some(MatchQuality::Substring);
+    }
+
+    let query: Vec<char> = query.chars().collect();
+    if query.len() < 3 {
+        return None;
+    }
+    let name: Vec<char> = name.chars().collect();
+    let distance = prefix_edit_distance(&query, &name);
+
+    (distance <= query.len() / 4 + 1).then_some(MatchQuality::Fuzzy(distance))
+}
+
+/// Ranks the names matching the query, best first. Names that match equally
+/// well are ordered by length, so the shortest completion comes first.
+pub(crate) fn rank_icon_names<'a>(
+    query: &str,
+    names: impl IntoIterator<Item = &'a String>,
+) -> Vec<String> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+
+    let mut matches: Vec<(MatchQuality, &String)> = names
+        .into_iter()
+        .filter_map(|name| Some((match_quality(&query, name)?, name)))
+        .collect();
+    matches.sort_by(|(a_quality, a), (b_quality, b)| {
+        (a_quality, a.len(), a).cmp(&(b_quality, b.len(), b))
+    });
+
+    matches.into_iter().map(|(_, name)| name.clone()).collect()
+}
+
+pub(crate) fn search_icons_in(
+    query: &str,
+    options: &LookupOptions,
+    theme: &Theme,
+    fallbacks: &[Arc<Theme>],
+) -> Vec<String> {
+    rank_icon_names(query, &theme_chain_icon_names(options, theme, fallbacks))
+}
+
+/// Searches the names of the icons provided by the theme, the themes it
+/// inherits from and hicolor, best match first. Names starting with the
+/// query come before names containing it, followed by names that only
+/// differ from it by a typo or two.
+///
+/// ```no_run
+/// use icon_finder::{search_icons, Theme};
+///
+/// let theme = Theme::load("Adwaita").unwrap();
+/// let suggestions = search_icons("firefx", theme);
+/// ```
+pub fn search_icons(query: &str, user_selected_theme: Theme) -> Vec<String> {
+    search_icons_in(query, &LookupOptions::new(0), &user_selected_theme, &[])
+}
+
+pub(crate) fn complete_icon_names_in(
+    prefix: &str,
+    options: &LookupOptions,
+    theme: &Theme,
+    fallbacks: &[Arc<Theme>],
+) -> impl Iterator<Item = String> {
+    let prefix = prefix.to_owned();
+
+    // The names are sorted, so the completions directly follow the prefix.
+    theme_chain_icon_names(options, theme, fallbacks)
+        .split_off(&prefix)
+        .into_iter()
+        .take_while(move |name| name.starts_with(&prefix))
+}
+
+/// The names of the icons starting with the prefix, like folder-download for
+/// "folder-", provided by the theme, the themes it inherits from and hicolor,
+/// in alphabetical order.
+///
+/// ```no_run
+/// use icon_finder::{complete_icon_names, Theme};
+///
+/// let theme = Theme::load("Adwaita").unwrap();
+/// for name in complete_icon_names("folder-", theme) {
+///     println!("{}", name);
+/// }
+/// ```
+pub fn complete_icon_names(
+    prefix: &str,
+    user_selected_theme: Theme,
+) -> impl Iterator<Item = String> {
+    complete_icon_names_in(prefix, &LookupOptions::new(0), &user_selected_theme, &[])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+
+    #[test]
+    fn test_prefix_edit_distance() {
+        let distance = |query: &str, name: &str| {
+            let query: Vec<char> = query.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            prefix_edit_distance(&query, &name)
+        };
+
+        assert_eq!(distance("firefx", "firefox"), 1);
+        assert_eq!(distance("firefx", "firefox-esr"), 1);
+        assert_eq!(distance("fierfox", "firefox"), 2);
+        assert_eq!(distance("abc", ""), 3);
+        assert_eq!(distance("", "firefox"), 0);
+    }
+
+    #[test]
+    fn test_search_icons() {
+        let mut theme = ThemeFixture::new("icon-finder-search")
+            .inherits("icon-finder-search-base")
+            .fixed_directory("48x48/apps", 48);
+        for icon in ["firefox", "firefox-esr", "org.mozilla.firefox", "fire"] {
+            theme = theme.icon(&format!("48x48/apps/{}.png", icon));
+        }
+        let fixture = Fixture::in_memory(&[
+            theme,
+            ThemeFixture::new("icon-finder-search-base")
+                .fixed_directory("16x16/apps", 16)
+                .icon("16x16/apps/Firefox-Beta.png")
+                .icon("16x16/apps/thunderbird.png"),
+        ]);
+        let theme = fixture.load_theme("icon-finder-search").unwrap();
+        let options = fixture.lookup_options();
+
+        assert_eq!(
+            search_icons_in("firefox", &options, &theme, &[]),
+            vec![
+                "firefox",
+                "firefox-esr",
+                "Firefox-Beta",
+                "org.mozilla.firefox"
+            ]
+        );
+        assert_eq!(
+            search_icons_in("firefx", &options, &theme, &[]),
+            vec!["firefox", "firefox-esr", "Firefox-Beta", "fire"]
+        );
+        assert_eq!(search_icons_in("fi", &options, &theme, &[]).len(), 5);
+        assert!(search_icons_in("chromium", &options, &theme, &[]).is_empty());
+        assert!(search_icons_in(" ", &options, &theme, &[]).is_empty());
+
+        let completions: Vec<String> =
+            complete_icon_names_in("firefox", &options, &theme, &[]).collect();
+        assert_eq!(completions, vec!["firefox", "firefox-esr"]);
+        assert_eq!(complete_icon_names_in("", &options, &theme, &[]).count(), 6);
+        assert_eq!(
+            complete_icon_names_in("g", &options, &theme, &[]).count(),
+            0
+        );
+    }
+}
@@ -0,0 +1,587 @@
+//! # User settings
+//! The icon theme is selected by the user through the settings of their
+//! desktop environment. On X11 the settings daemon of the desktop publishes
+//! the selection through XSETTINGS, read with the `xsettings` cargo feature.
+//! Sandboxed applications cannot see the settings of the host, the settings
+//! portal tells them instead. GNOME stores the selection in GSettings, which
+//! is read through the gsettings command, other GTK based desktops use the
+//! GTK settings file and KDE uses kdeglobals.
+//!
+//! Reading XSETTINGS, the portal and GSettings takes a round trip to another
+//! process, so their values are cached for [`POLL_INTERVAL`]. All callers and
+//! subscriptions share the cached values, however often they ask.
+//!
+//! Some distributions select the theme through a theme named default
+//! instead, usually ~/.icons/default/index.theme, which inherits from the
+//! actual theme. Cursor themes are selected the same way.
+
+use std::env;
+use std::path::PathBuf;
+#[cfg(not(target_family = "wasm"))]
+use std::process::Command;
+#[cfg(not(target_family = "wasm"))]
+use std::sync::Mutex;
+#[cfg(not(target_family = "wasm"))]
+use std::time::Instant;
+
+use crate::filesystem::file_system;
+use crate::paths::base_directories;
+use crate::FileSystem;
+#[cfg(not(target_family = "wasm"))]
+use crate::POLL_INTERVAL;
+
+/// The name of the theme pointing to the selected theme.
+const DEFAULT_THEME: &str = "default";
+
+/// The settings read from other processes, see [`desktop_settings`].
+#[derive(Debug, Clone, Default)]
+struct DesktopSettings {
+    icon_theme: Option<String>,
+    color_scheme: Option<ColorScheme>,
+}
+
+impl DesktopSettings {
+    /// Reads XSETTINGS, the settings portal and GSettings, in that order.
+    #[cfg(not(target_family = "wasm"))]
+    fn read() -> DesktopSettings {
+        let icon_theme = xsettings_string("Net/IconThemeName")
+            .or_else(|| portal_string("org.gnome.desktop.interface", "icon-theme"))
+            .or_else(|| gsettings_string("icon-theme"));
+        let color_scheme = portal_color_scheme().or_else(|| {
+            gsettings_string("color-scheme").map(|scheme| match scheme.as_str() {
+                "prefer-dark" => ColorScheme::PreferDark,
+                "prefer-light" => ColorScheme::PreferLight,
+                _ => ColorScheme::Default,
+            })
+        });
+
+        DesktopSettings {
+            icon_theme,
+            color_scheme,
+        }
+    }
+}
+
+/// The settings of the desktop, read at most once per [`POLL_INTERVAL`].
+/// Callers asking while the settings are read wait for that read instead
+/// of starting their own.
+#[cfg(not(target_family = "wasm"))]
+fn desktop_settings() -> DesktopSettings {
+    static CACHE: Mutex<Option<(Instant, DesktopSettings)>> = Mutex::new(None);
+
+    let mut cache = CACHE.lock().unwrap_or_else(|error| error.into_inner());
+    if let Some((read, settings)) = &*cache {
+        if read.elapsed() < POLL_INTERVAL {
+            return settings.clone();
+        }
+    }
+
+    let settings = DesktopSettings::read();
+    *cache = Some((Instant::now(), settings.clone()));
+    settings
+}
+
+/// WebAssembly cannot talk to other processes, the settings are not read
+/// there.
+#[cfg(target_family = "wasm")]
+fn desktop_settings() -> DesktopSettings {
+    DesktopSettings::default()
+}
+
+/// Returns the name of the icon theme selected by the user, or `None` if no
+/// desktop setting could be read. XSETTINGS, the settings portal and
+/// GSettings are read first, cached for [`POLL_INTERVAL`], then the GTK
+/// settings file and kdeglobals. A selected theme named default, or the
+/// default theme if no desktop setting could be read, is followed to the
+/// theme it inherits from.
+pub fn selected_icon_theme() -> Option<String> {
+    let selected = desktop_settings()
+        .icon_theme
+        .or_else(|| config_file_value("gtk-3.0/settings.ini", "Settings", "gtk-icon-theme-name"))
+        .or_else(|| config_file_value("kdeglobals", "Icons", "Theme"));
+
+    match selected.as_deref() {
+        None | Some(DEFAULT_THEME) => default_theme_target().or(selected),
+        Some(_) => selected,
+    }
+}
+
+/// The theme the default theme of the first base directory having one
+/// points to.
+fn default_theme_target() -> Option<String> {
+    default_theme_target_in(&**file_system(), base_directories())
+}
+
+fn default_theme_target_in(
+    file_system: &dyn FileSystem,
+    base_directories: &[PathBuf],
+) -> Option<String> {
+    let index_theme = |name: &str| {
+        base_directories.iter().find_map(|directory| {
+            file_system
+                .read_to_string(&directory.join(name).join("index.theme"))
+                .ok()
+        })
+    };
+    let contents = index_theme(DEFAULT_THEME)?;
+
+    parse_default_theme(&contents, |name| {
+        index_theme(name).is_some_and(|contents| has_icon_directories(&contents))
+    })
+}
+
+/// The theme the index.theme of a default theme points to: the first theme
+/// it inherits from that has icons, or the default theme itself if it has
+/// icons of its own. The default theme often selects the cursor theme as
+/// well, cursor themes have no icon directories.
+fn parse_default_theme(contents: &str, has_icons: impl Fn(&str) -> bool) -> Option<String> {
+    if has_icon_directories(contents) {
+        return Some(DEFAULT_THEME.to_owned());
+    }
+
+    parse_key_value(contents, "Icon Theme", "Inherits")?
+        .split(',')
+        .map(str::trim)
+        .find(|name| !name.is_empty() && *name != DEFAULT_THEME && has_icons(name))
+        .map(str::to_owned)
+}
+
+/// Whether the index.theme lists any icon directories.
+fn has_icon_directories(contents: &str) -> bool {
+    parse_key_value(contents, "Icon Theme", "Directories")
+        .is_some_and(|directories| directories.split(',').any(|name| !name.trim().is_empty()))
+}
+
+/// The color scheme preferred by the user, with the values of the
+/// color-scheme setting of the settings portal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ColorScheme {
+    /// No preference, applications use their default.
+    #[default]
+    Default,
+    PreferDark,
+    PreferLight,
+}
+
+/// Returns the color scheme preferred by the user, or
+/// [`ColorScheme::Default`] if no desktop setting could be read. The
+/// settings portal and GSettings are read first, like for
+/// [`selected_icon_theme`].
+pub fn preferred_color_scheme() -> ColorScheme {
+    desktop_settings()
+        .color_scheme
+        .or_else(|| {
+            config_file_value(
+                "gtk-3.0/settings.ini",
+                "Settings",
+                "gtk-application-prefer-dark-theme",
+            )
+            .map(|prefer_dark| match prefer_dark.as_str() {
+                "true" | "1" => ColorScheme::PreferDark,
+                _ => ColorScheme::Default,
+            })
+        })
+        .or_else(|| {
+            // KDE only knows the name of the color scheme, like BreezeDark.
+            config_file_value("kdeglobals", "General", "ColorScheme").map(|scheme| {
+                if scheme.to_lowercase().contains("dark") {
+                    ColorScheme::PreferDark
+                } else {
+                    ColorScheme::PreferLight
+                }
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// Reads a string key of the GNOME interface settings.
+#[cfg(not(target_family = "wasm"))]
+fn gsettings_string(key: &str) -> Option<String> {
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", key])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_gsettings_string(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// GSettings prints string values as GVariant text, e.g. `'Adwaita'`.
+#[cfg(not(target_family = "wasm"))]
+fn parse_gsettings_string(output: &str) -> Option<String> {
+    let value = output.trim().trim_matches('\'');
+
+    if value.is_empty() {
+        return None;
+    }
+
+    Some(value.to_owned())
+}
+
+/// Reads a setting of the settings portal as GVariant text, through the
+/// gdbus command. Waits at most a second for the portal to start.
+#[cfg(not(target_family = "wasm"))]
+fn portal_setting(namespace: &str, key: &str) -> Option<String> {
+    let output = Command::new("gdbus")
+        .args([
+            "call",
+            "--session",
+            "--timeout",
+            "1",
+            "--dest",
+            "org.freedesktop.portal.Desktop",
+            "--object-path",
+            "/org/freedesktop/portal/desktop",
+            "--method",
+            "org.freedesktop.portal.Settings.ReadOne",
+            namespace,
+            key,
+        ])
+        .output()
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    parse_portal_value(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// The portal replies with a tuple holding a variant, e.g.
+/// `(<'Adwaita'>,)` or `(<uint32 1>,)`.
+#[cfg(not(target_family = "wasm"))]
+fn parse_portal_value(output: &str) -> Option<String> {
+    let value = output
+        .trim()
+        .strip_prefix("(<")?
+        .strip_suffix(">,)")?
+        .trim();
+
+    Some(value.to_owned())
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn portal_string(namespace: &str, key: &str) -> Option<String> {
+    parse_gsettings_string(&portal_setting(namespace, key)?)
+}
+
+/// The color-scheme setting of the portal is a number: 1 prefers dark, 2
+/// prefers light and 0 has no preference.
+#[cfg(not(target_family = "wasm"))]
+fn portal_color_scheme() -> Option<ColorScheme> {
+    let value = portal_setting("org.freedesktop.appearance", "color-scheme")?;
+
+    match value.trim_start_matches("uint32").trim() {
+        "1" => Some(ColorScheme::PreferDark),
+        "2" => Some(ColorScheme::PreferLight),
+        "0" => Some(ColorScheme::Default),
+        _ => None,
+    }
+}
+
+/// Reads a string setting from the XSETTINGS manager of the default screen.
+#[cfg(all(feature = "xsettings", not(target_family = "wasm")))]
+fn xsettings_string(name: &str) -> Option<String> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ConnectionExt};
+
+    let (connection, screen) = x11rb::connect(None).ok()?;
+    let atom = |name: &str| {
+        connection
+            .intern_atom(false, name.as_bytes())
+            .ok()?
+            .reply()
+            .ok()
+            .map(|reply| reply.atom)
+    };
+
+    let selection = atom(&format!("_XSETTINGS_S{}", screen))?;
+    let owner = connection
+        .get_selection_owner(selection)
+        .ok()?
+        .reply()
+        .ok()?
+        .owner;
+    if owner == x11rb::NONE {
+        return None;
+    }
+
+    let property = atom("_XSETTINGS_SETTINGS")?;
+    let reply = connection
+        .get_property(false, owner, property, AtomEnum::ANY, 0, u32::MAX / 4)
+        .ok()?
+        .reply()
+        .ok()?;
+    connection.flush().ok()?;
+
+    parse_xsettings_string(&reply.value, name)
+}
+
+/// Without the `xsettings` feature, XSETTINGS is not read.
+#[cfg(all(not(feature = "xsettings"), not(target_family = "wasm")))]
+fn xsettings_string(_name: &str) -> Option<String> {
+    None
+}
+
+/// Finds a string setting in the contents of the _XSETTINGS_SETTINGS
+/// property: a byte order, a serial and the number of settings, followed by
+/// every setting with its type, name, serial and value. Names and string
+/// values are padded to a multiple of four bytes.
+#[cfg(all(feature = "xsettings", not(target_family = "wasm")))]
+fn parse_xsettings_string(data: &[u8], name: &str) -> Option<String> {
+    const INTEGER: u8 = 0;
+    const STRING: u8 = 1;
+    const COLOR: u8 = 2;
+
+    let big_endian = *data.first()? == 1;
+    let mut position = 4;
+    let mut take = |length: usize| {
+        let bytes = data.get(position..position + length)?;
+        position += length;
+        Some(bytes)
+    };
+    let card16 = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1]];
+        usize::from(if big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        })
+    };
+    let card32 = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        let value = if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        };
+        value as usize
+    };
+    let padded = |length: usize| length.div_ceil(4) * 4;
+
+    take(4)?;
+    let settings = card32(take(4)?);
+    for _ in 0..settings {
+        let header = take(4)?;
+        let setting_type = header[0];
+        let name_length = card16(&header[2..]);
+        let setting_name = &take(padded(name_length))?[..name_length];
+        take(4)?;
+
+        match setting_type {
+            INTEGER => {
+                take(4)?;
+            }
+            STRING => {
+                let length = card32(take(4)?);
+                let value = &take(padded(length))?[..length];
+                if setting_name == name.as_bytes() {
+                    return String::from_utf8(value.to_vec())
+                        .ok()
+                        .filter(|value| !value.is_empty());
+                }
+            }
+            COLOR => {
+                take(8)?;
+            }
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+fn config_file_value(file: &str, section: &str, key: &str) -> Option<String> {
+    let contents = file_system()
+        .read_to_string(&config_home()?.join(file))
+        .ok()?;
+
+    parse_key_value(&contents, section, key)
+}
+
+pub(crate) fn config_home() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+}
+
+/// Reads a single value from an ini style configuration file.
+fn parse_key_value(contents: &str, section: &str, key: &str) -> Option<String> {
+    let mut current_section = "";
+
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('[') && line.ends_with(']') {
+            current_section = &line[1..line.len() - 1];
+            continue;
+        }
+
+        if current_section != section {
+            continue;
+        }
+
+        if let Some((line_key, value)) = line.split_once('=') {
+            let value = value.trim().trim_matches('"');
+
+            if line_key.trim() == key && !value.is_empty() {
+                return Some(value.to_owned());
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFileSystem;
+
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn test_parse_gsettings_string() {
+        assert_eq!(
+            parse_gsettings_string("'Adwaita'\n"),
+            Some("Adwaita".to_owned())
+        );
+        assert_eq!(parse_gsettings_string("''\n"), None);
+    }
+
+    #[cfg(not(target_family = "wasm"))]
+    #[test]
+    fn test_parse_portal_value() {
+        assert_eq!(
+            parse_portal_value("(<'Papirus'>,)\n"),
+            Some("'Papirus'".to_owned())
+        );
+        assert_eq!(
+            parse_portal_value("(<uint32 1>,)\n"),
+            Some("uint32 1".to_owned())
+        );
+        assert_eq!(parse_portal_value("Error: No such key\n"), None);
+    }
+
+    #[cfg(all(feature = "xsettings", not(target_family = "wasm")))]
+    #[test]
+    fn test_parse_xsettings_string() {
+        let mut data = vec![0, 0, 0, 0];
+        data.extend(7u32.to_le_bytes());
+        data.extend(2u32.to_le_bytes());
+        // Net/DoubleClickTime, an integer.
+        data.extend([0, 0]);
+        data.extend(19u16.to_le_bytes());
+        data.extend(b"Net/DoubleClickTime\0");
+        data.extend(0u32.to_le_bytes());
+        data.extend(400u32.to_le_bytes());
+        // Net/IconThemeName, a string.
+        data.extend([1, 0]);
+        data.extend(17u16.to_le_bytes());
+        data.extend(b"Net/IconThemeName\0\0\0");
+        data.extend(0u32.to_le_bytes());
+        data.extend(7u32.to_le_bytes());
+        data.extend(b"Papirus\0");
+
+        assert_eq!(
+            parse_xsettings_string(&data, "Net/IconThemeName"),
+            Some("Papirus".to_owned())
+        );
+        assert_eq!(parse_xsettings_string(&data, "Net/ThemeName"), None);
+        assert_eq!(
+            parse_xsettings_string(&data[..40], "Net/IconThemeName"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_default_theme() {
+        let has_icons = |name: &str| name != "Breeze_Snow";
+        assert_eq!(
+            parse_default_theme("[Icon Theme]\nInherits=Papirus-Dark,hicolor\n", has_icons),
+            Some("Papirus-Dark".to_owned())
+        );
+        assert_eq!(
+            parse_default_theme("[Icon Theme]\nInherits=default, breeze\n", has_icons),
+            Some("breeze".to_owned())
+        );
+        assert_eq!(
+            parse_default_theme(
+                "[Icon Theme]\nInherits=Adwaita\nDirectories=48x48/apps\n",
+                has_icons
+            ),
+            Some("default".to_owned())
+        );
+        assert_eq!(
+            parse_default_theme("[Icon Theme]\nInherits=Breeze_Snow,Adwaita\n", has_icons),
+            Some("Adwaita".to_owned())
+        );
+        assert_eq!(
+            parse_default_theme(
+                "[Icon Theme]\nInherits=Breeze_Snow\nDirectories=\n",
+                has_icons
+            ),
+            None
+        );
+        assert_eq!(
+            parse_default_theme("[Icon Theme]\nName=Default\n", has_icons),
+            None
+        );
+    }
+
+    #[test]
+    fn test_default_theme_target() {
+        let fs = MemoryFileSystem::new();
+        let base_directories = [
+            PathBuf::from("/home/user/.icons"),
+            PathBuf::from("/usr/share/icons"),
+        ];
+        fs.add_file(
+            "/usr/share/icons/default/index.theme",
+            "[Icon Theme]\nInherits=breeze\n",
+        );
+        for name in ["Papirus", "breeze"] {
+            fs.add_file(
+                format!("/usr/share/icons/{}/index.theme", name),
+                "[Icon Theme]\nDirectories=48x48/apps\n",
+            );
+        }
+        fs.add_file(
+            "/usr/share/icons/Breeze_Snow/index.theme",
+            "[Icon Theme]\nName=Breeze Snow\n",
+        );
+        assert_eq!(
+            default_theme_target_in(&fs, &base_directories),
+            Some("breeze".to_owned())
+        );
+
+        // The default theme of the user takes precedence, cursor themes are
+        // skipped.
+        fs.add_file(
+            "/home/user/.icons/default/index.theme",
+            "[Icon Theme]\nInherits=Breeze_Snow,Papirus\n",
+        );
+        assert_eq!(
+            default_theme_target_in(&fs, &base_directories),
+            Some("Papirus".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_parse_key_value() {
+        let contents = "[Settings]\ngtk-theme-name=Adwaita\ngtk-icon-theme-name = Papirus\n";
+
+        assert_eq!(
+            parse_key_value(contents, "Settings", "gtk-icon-theme-name"),
+            Some("Papirus".to_owned())
+        );
+        assert_eq!(
+            parse_key_value(contents, "Settings", "gtk-cursor-theme-name"),
+            None
+        );
+        assert_eq!(
+            parse_key_value(contents, "Icons", "gtk-icon-theme-name"),
+            None
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! # Theme statistics
+//! Distribution maintainers and theme authors want to see at a glance how a
+//! theme is composed: how many icons it has in every context, at every size
+//! and in every format, and how much space it takes. Like
+//! [`walk_theme`](crate::walk_theme), only the directories of the theme
+//! itself are counted.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::walk::walk_theme_with;
+use crate::{Context, IconFormat, IconSize, LookupOptions, Theme, DEFAULT_SCALE};
+
+/// How a theme is composed, see [`Theme::stats`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ThemeStats {
+    /// The number of icon names the theme provides.
+    pub icons: usize,
+    /// The number of icon files, symbolic links included.
+    pub files: usize,
+    /// The size of the icon files in bytes. Symbolic links cost no space,
+    /// files linked to several times are counted once.
+    pub bytes: u64,
+    /// The number of icons in every context, `None` for icons in directories
+    /// without a context.
+    pub contexts: HashMap<Option<Context>, usize>,
+    /// The number of icons available at every size.
+    pub sizes: BTreeMap<IconSize, usize>,
+    /// The number of icons with a file in every format.
+    pub formats: HashMap<IconFormat, usize>,
+}
+
+pub(crate) fn theme_stats_with(theme: &Theme, options: &LookupOptions) -> ThemeStats {
+    let file_system = options.file_system();
+    let mut stats = ThemeStats::default();
+    let mut icons = HashSet::new();
+    let mut contexts = HashSet::new();
+    let mut sizes = HashSet::new();
+    let mut formats = HashSet::new();
+    let mut targets = HashSet::new();
+
+    walk_theme_with(theme, options, |subdir, file| {
+        stats.files += 1;
+        icons.insert(file.name.clone());
+
+        if contexts.insert((subdir.context.clone(), file.name.clone())) {
+            *stats.contexts.entry(subdir.context.clone()).or_default() += 1;
+        }
+        let size = IconSize {
+            scale: subdir.scale.unwrap_or(DEFAULT_SCALE),
+            size: subdir.size,
+            r#type: subdir.r#type,
+        };
+        if sizes.insert((size, file.name.clone())) {
+            *stats.sizes.entry(size).or_default() += 1;
+        }
+        if formats.insert((file.format, file.name.clone())) {
+            *stats.formats.entry(file.format).or_default() += 1;
+        }
+
+        let target = file_system
+            .canonicalize(&file.path)
+            .unwrap_or_else(|_| file.path.clone());
+        if targets.insert(target) {
+            stats.bytes += file_system.len(&file.path).unwrap_or(0);
+        }
+    });
+
+    stats.icons = icons.len();
+    stats
+}
+
+// The test counts SVG icons apart from PNG icons.
+#[cfg(all(test, feature = "svg"))]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+    use crate::ThemeDirectoryType;
+
+    #[test]
+    fn test_theme_stats() {
+        let fixture = Fixture::in_memory(&[ThemeFixture::new("icon-finder-stats")
+            .fixed_directory("16x16/apps", 16)
+            .context(Context::Applications)
+            .fixed_directory("16x16/places", 16)
+            .context(Context::Places)
+            .scalable_directory("scalable/apps", 16, 48)
+            .context(Context::Applications)
+            .fixed_directory("misc", 16)
+            .file("16x16/apps/editor.png", "1234")
+            .file("16x16/apps/editor.svg", "12")
+            .file("16x16/places/folder.png", "123")
+            .file("scalable/apps/editor.svg", "12345")
+            .file("misc/folder.png", "1")]);
+        let theme = fixture.load_theme("icon-finder-stats").unwrap();
+        let options = fixture.lookup_options();
+
+        let stats = theme_stats_with(&theme, &options);
+        assert_eq!((stats.icons, stats.files, stats.bytes), (2, 5, 15));
+        assert_eq!(stats.contexts[&Some(Context::Applications)], 1);
+        assert_eq!(stats.contexts[&Some(Context::Places)], 1);
+        assert_eq!(stats.contexts[&None], 1);
+        assert_eq!(stats.formats[&IconFormat::Png], 2);
+        assert_eq!(stats.formats[&IconFormat::Svg], 1);
+
+        let fixed = IconSize {
+            scale: 1,
+            size: 16,
+            r#type: ThemeDirectoryType::Fixed,
+        };
+        let scalable = IconSize {
+            scale: 1,
+            size: 48,
+            r#type: ThemeDirectoryType::Scalable,
+        };
+        assert_eq!(
+            stats.sizes.into_iter().collect::<Vec<_>>(),
+            [(fixed, 2), (scalable, 1)]
+        );
+    }
+}
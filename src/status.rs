@@ -0,0 +1,140 @@
+//! # Status icons
+//! Status bars show the state of the battery and the network with icons whose
+//! names encode the state, like battery-level-40-charging-symbolic. These
+//! helpers compute the names from the state, following the conventions of
+//! GNOME and the themes built for it. As older themes only have coarser
+//! icons, every name comes with fallbacks, best first, to be passed to
+//! [`find_best_icon`](crate::find_best_icon).
+
+/// The symbolic icon for the battery at the given charge, in steps of ten
+/// percent. A full battery that is still plugged in is charged.
+///
+/// ```
+/// use icon_finder::battery_icon_name;
+///
+/// assert_eq!(battery_icon_name(38, true), "battery-level-40-charging-symbolic");
+/// assert_eq!(battery_icon_name(100, true), "battery-level-100-charged-symbolic");
+/// ```
+pub fn battery_icon_name(percent: u8, charging: bool) -> String {
+    let level = (u16::from(percent.min(100)) + 5) / 10 * 10;
+
+    let state = match (charging, level) {
+        (true, 100) => "-charged",
+        (true, _) => "-charging",
+        (false, _) => "",
+    };
+
+    format!("battery-level-{}{}-symbolic", level, state)
+}
+
+/// The names of the icons for the battery, see [`battery_icon_name`],
+/// followed by the coarser names of older themes, like battery-low.
+pub fn battery_icon_names(percent: u8, charging: bool) -> Vec<String> {
+    let percent = percent.min(100);
+    let level = match percent {
+        0 => "empty",
+        1..=19 => "caution",
+        20..=39 => "low",
+        40..=79 => "good",
+        _ => "full",
+    };
+    let state = match (charging, percent) {
+        (true, 100) => "-charged",
+        (true, _) => "-charging",
+        (false, _) => "",
+    };
+
+    vec![
+        battery_icon_name(percent, charging),
+        format!("battery-{}{}-symbolic", level, state),
+        format!("battery-{}-symbolic", level),
+        "battery-symbolic".to_owned(),
+    ]
+}
+
+/// The symbolic icon for a wireless network of the given signal strength, in
+/// percent.
+///
+/// ```
+/// use icon_finder::network_signal_icon_name;
+///
+/// assert_eq!(network_signal_icon_name(60), "network-wireless-signal-good-symbolic");
+/// ```
+pub fn network_signal_icon_name(strength: u8) -> String {
+    let level = match strength {
+        0..=5 => "none",
+        6..=30 => "weak",
+        31..=55 => "ok",
+        56..=80 => "good",
+        _ => "excellent",
+    };
+
+    format!("network-wireless-signal-{}-symbolic", level)
+}
+
+/// The names of the icons for a wireless network, see
+/// [`network_signal_icon_name`], followed by the icon of a wireless network
+/// regardless of its signal.
+pub fn network_signal_icon_names(strength: u8) -> Vec<String> {
+    vec![
+        network_signal_icon_name(strength),
+        "network-wireless-connected-symbolic".to_owned(),
+        "network-wireless-symbolic".to_owned(),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_battery_icon_names() {
+        assert_eq!(battery_icon_name(0, false), "battery-level-0-symbolic");
+        assert_eq!(battery_icon_name(4, false), "battery-level-0-symbolic");
+        assert_eq!(
+            battery_icon_name(95, true),
+            "battery-level-100-charged-symbolic"
+        );
+        assert_eq!(battery_icon_name(255, false), "battery-level-100-symbolic");
+
+        assert_eq!(
+            battery_icon_names(15, true),
+            vec![
+                "battery-level-20-charging-symbolic",
+                "battery-caution-charging-symbolic",
+                "battery-caution-symbolic",
+                "battery-symbolic",
+            ]
+        );
+        assert_eq!(
+            battery_icon_names(100, true)[1],
+            "battery-full-charged-symbolic"
+        );
+        assert_eq!(
+            battery_icon_names(85, true)[1],
+            "battery-full-charging-symbolic"
+        );
+        assert_eq!(battery_icon_names(0, false)[1], "battery-empty-symbolic");
+    }
+
+    #[test]
+    fn test_network_signal_icon_names() {
+        assert_eq!(
+            network_signal_icon_name(0),
+            "network-wireless-signal-none-symbolic"
+        );
+        assert_eq!(
+            network_signal_icon_name(30),
+            "network-wireless-signal-weak-symbolic"
+        );
+        assert_eq!(
+            network_signal_icon_name(31),
+            "network-wireless-signal-ok-symbolic"
+        );
+        assert_eq!(
+            network_signal_icon_name(100),
+            "network-wireless-signal-excellent-symbolic"
+        );
+        assert_eq!(network_signal_icon_names(50).len(), 3);
+    }
+}
@@ -0,0 +1,169 @@
+//! # Symbolic icons
+//! Symbolic icons, the ones named with a -symbolic suffix, are monochrome
+//! SVG images meant to be drawn in the colors of the surrounding text. Like
+//! GTK, recoloring adds a style sheet to the image that fills every shape in
+//! the foreground color, and shapes of the success, warning and error classes
+//! in their own colors.
+//!
+//! Unlike the style sheet of GTK, the foreground rule is not marked as
+//! important, as resvg lets the first important rule win rather than the
+//! most specific one, which would paint the classes in the foreground color
+//! too. Shapes setting their fill in a style attribute keep it.
+
+use std::io;
+
+use crate::{IconFormat, LoadedIcon};
+
+/// The colors symbolic icons are drawn in, as red, green, blue and alpha.
+/// Defaults to the colors of the Adwaita theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SymbolicPalette {
+    pub foreground: [u8; 4],
+    pub success: [u8; 4],
+    pub warning: [u8; 4],
+    pub error: [u8; 4],
+}
+
+impl SymbolicPalette {
+    /// The default palette with the given foreground color.
+    pub fn new(foreground: [u8; 4]) -> SymbolicPalette {
+        SymbolicPalette {
+            foreground,
+            ..SymbolicPalette::default()
+        }
+    }
+
+    fn style(&self) -> String {
+        format!(
+            "<style type=\"text/css\">\
+             rect,circle,path{{fill:{};}}\
+             .success{{fill:{}!important;}}\
+             .warning{{fill:{}!important;}}\
+             .error{{fill:{}!important;}}\
+             </style>",
+            css_color(self.foreground),
+            css_color(self.success),
+            css_color(self.warning),
+            css_color(self.error),
+        )
+    }
+}
+
+impl Default for SymbolicPalette {
+    fn default() -> SymbolicPalette {
+        SymbolicPalette {
+            foreground: [0x2e, 0x34, 0x36, 0xff],
+            success: [0x4e, 0x9a, 0x06, 0xff],
+            warning: [0xf5, 0x79, 0x00, 0xff],
+            error: [0xcc, 0x00, 0x00, 0xff],
+        }
+    }
+}
+
+fn css_color([red, green, blue, alpha]: [u8; 4]) -> String {
+    format!(
+        "rgba({},{},{},{})",
+        red,
+        green,
+        blue,
+        f32::from(alpha) / 255.0
+    )
+}
+
+/// The offset of the `>` closing the start tag of the root element, skipping
+/// any `>` inside attribute values.
+fn root_tag_end(svg: &str) -> Option<usize> {
+    let start = svg.find("<svg")?;
+    let mut quote = None;
+
+    for (offset, c) in svg[start..].char_indices() {
+        match (quote, c) {
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (Some(open), c) if open == c => quote = None,
+            (None, '>') => return Some(start + offset),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+impl LoadedIcon {
+    /// Recolors a symbolic icon with the colors of the palette, returning the
+    /// recolored SVG image. Fails with `Unsupported` for icons that are not
+    /// SVG images and with `InvalidData` if the image has no root element.
+    pub fn recolor(&self, palette: &SymbolicPalette) -> io::Result<LoadedIcon> {
+        if self.format != IconFormat::Svg {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("{} icons cannot be recolored", self.format.extension()),
+            ));
+        }
+
+        let svg = std::str::from_utf8(&self.data)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        let end = root_tag_end(svg)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "no svg element"))?;
+
+        let style = palette.style();
+        let data = if svg[..end].ends_with('/') {
+            // An empty image, the style needs a closing tag to go into.
+            format!("{}>{}</svg>{}", &svg[..end - 1], style, &svg[end + 1..])
+        } else {
+            format!("{}{}{}", &svg[..=end], style, &svg[end + 1..])
+        };
+
+        Ok(LoadedIcon {
+            path: self.path.clone(),
+            data: data.into_bytes(),
+            format: IconFormat::Svg,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn loaded(data: &str, format: IconFormat) -> LoadedIcon {
+        LoadedIcon {
+            path: PathBuf::from("/icons/edit-symbolic.svg"),
+            data: data.as_bytes().to_vec(),
+            format,
+        }
+    }
+
+    #[test]
+    fn test_root_tag_end() {
+        assert_eq!(root_tag_end("<svg>"), Some(4));
+        assert_eq!(root_tag_end("<?xml?><svg a=\"x>y\" b='>'>"), Some(25));
+        assert_eq!(root_tag_end("<svg a=\">\""), None);
+        assert_eq!(root_tag_end("<html>"), None);
+    }
+
+    #[test]
+    fn test_recolor() {
+        let palette = SymbolicPalette::new([255, 0, 0, 255]);
+        let style = palette.style();
+        assert!(style.contains("rect,circle,path{fill:rgba(255,0,0,1);}"));
+        assert!(style.contains(".error{fill:rgba(204,0,0,1)!important;}"));
+
+        let icon = loaded("<?xml?>\n<svg width=\"16\"><path/></svg>", IconFormat::Svg);
+        assert_eq!(
+            icon.recolor(&palette).unwrap().data,
+            format!("<?xml?>\n<svg width=\"16\">{}<path/></svg>", style).into_bytes()
+        );
+
+        let empty = loaded("<svg/>", IconFormat::Svg);
+        assert_eq!(
+            empty.recolor(&palette).unwrap().data,
+            format!("<svg>{}</svg>", style).into_bytes()
+        );
+
+        let png = loaded("\u{89}PNG", IconFormat::Png).recolor(&palette);
+        assert_eq!(png.unwrap_err().kind(), io::ErrorKind::Unsupported);
+        let broken = loaded("<html/>", IconFormat::Svg).recolor(&palette);
+        assert_eq!(broken.unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,372 @@
+//! # Test kit
+//! Tests of applications using the finder should not depend on the themes
+//! installed on the machine running them. A [`ThemeFixture`] describes a fake
+//! theme, with the directories and icon files the test needs, and a
+//! [`Fixture`] writes a set of them to memory or to a temporary directory.
+//!
+//! ```
+//! use std::path::Path;
+//!
+//! use icon_finder::testing::{Fixture, ThemeFixture};
+//!
+//! let fixture = Fixture::in_memory(&[ThemeFixture::new("test")
+//!     .fixed_directory("48x48/apps", 48)
+//!     .icon("48x48/apps/firefox.png")]);
+//!
+//! let finder = fixture.finder("test").unwrap();
+//! assert_eq!(
+//!     finder.find_icon("firefox", 48, 1).as_deref(),
+//!     Some(Path::new("/icons/test/48x48/apps/firefox.png"))
+//! );
+//! ```
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::{
+    Context, Error, FileSystem, IconFinder, IndexTheme, MemoryFileSystem, ParseMode, StdFileSystem,
+    Theme, ThemeDirectory, ThemeDirectoryType, Translations,
+};
+
+/// The base directory in-memory fixtures are written to.
+const MEMORY_BASE_DIRECTORY: &str = "/icons";
+
+/// A fake theme, written to a [`Fixture`].
+#[derive(Debug, Clone)]
+pub struct ThemeFixture {
+    name: String,
+    display_name: Option<String>,
+    comment: String,
+    inherits: Vec<String>,
+    hidden: bool,
+    directories: Vec<ThemeDirectory>,
+    /// Paths relative to the theme directory, with their contents.
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl ThemeFixture {
+    /// A theme with the given internal name, without directories or icons.
+    pub fn new(name: &str) -> ThemeFixture {
+        ThemeFixture {
+            name: name.to_owned(),
+            display_name: None,
+            comment: "Test theme".to_owned(),
+            inherits: Vec::new(),
+            hidden: false,
+            directories: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Shows the theme under the given name instead of its internal name.
+    pub fn display_name(mut self, display_name: &str) -> ThemeFixture {
+        self.display_name = Some(display_name.to_owned());
+        self
+    }
+
+    /// Describes the theme with the given comment.
+    pub fn comment(mut self, comment: &str) -> ThemeFixture {
+        self.comment = comment.to_owned();
+        self
+    }
+
+    /// Inherits from the theme with the given name. Parents are searched in
+    /// the order they are added.
+    pub fn inherits(mut self, parent: &str) -> ThemeFixture {
+        self.inherits.push(parent.to_owned());
+        self
+    }
+
+    /// Hides the theme from lists of themes to choose from.
+    pub fn hidden(mut self) -> ThemeFixture {
+        self.hidden = true;
+        self
+    }
+
+    /// Adds a directory, described like in index.theme.
+    pub fn directory(mut self, directory: ThemeDirectory) -> ThemeFixture {
+        self.directories.push(directory);
+        self
+    }
+
+    /// Adds a directory of icons of exactly the given size.
+    pub fn fixed_directory(self, name: &str, size: i16) -> ThemeFixture {
+        self.directory(fixture_directory(name, size, ThemeDirectoryType::Fixed))
+    }
+
+    /// Adds a directory of icons that can be scaled to any size from
+    /// `min_size` up to `max_size`.
+    pub fn scalable_directory(self, name: &str, min_size: i16, max_size: i16) -> ThemeFixture {
+        self.directory(ThemeDirectory {
+            min_size: Some(min_size),
+            max_size: Some(max_size),
+            ..fixture_directory(name, max_size, ThemeDirectoryType::Scalable)
+        })
+    }
+
+    /// Adds a directory of icons that are used for sizes differing from the
+    /// given size by up to `threshold`.
+    pub fn threshold_directory(self, name: &str, size: i16, threshold: i16) -> ThemeFixture {
+        self.directory(ThemeDirectory {
+            threshold: Some(threshold),
+            ..fixture_directory(name, size, ThemeDirectoryType::Threshold)
+        })
+    }
+
+    /// Sets the context of the directory added last.
+    ///
+    /// # Panics
+    /// Panics if no directory was added yet.
+    pub fn context(mut self, context: Context) -> ThemeFixture {
+        self.last_directory().context = Some(context);
+        self
+    }
+
+    /// Sets the scale of the directory added last, for icons drawn for
+    /// displays with a scale factor.
+    ///
+    /// # Panics
+    /// Panics if no directory was added yet.
+    pub fn scale(mut self, scale: i16) -> ThemeFixture {
+        self.last_directory().scale = Some(scale);
+        self
+    }
+
+    /// Adds an empty icon file, at a path relative to the theme directory,
+    /// like "48x48/apps/firefox.png".
+    pub fn icon(self, path: &str) -> ThemeFixture {
+        self.file(path, "")
+    }
+
+    /// Adds a file with the given contents, at a path relative to the theme
+    /// directory, e.g. an SVG icon, a PNG image or an .icon file.
+    pub fn file(mut self, path: &str, contents: impl Into<Vec<u8>>) -> ThemeFixture {
+        self.files.push((path.to_owned(), contents.into()));
+        self
+    }
+
+    /// The contents of the index.theme file of the theme.
+    pub fn index_theme(&self) -> String {
+        IndexTheme {
+            name: self
+                .display_name
+                .clone()
+                .unwrap_or_else(|| self.name.clone()),
+            comment: self.comment.clone(),
+            inherits: self.inherits.clone(),
+            directories: self.directories.clone(),
+            hidden: self.hidden,
+            translations: Translations::default(),
+            example: None,
+        }
+        .to_string()
+    }
+
+    fn last_directory(&mut self) -> &mut ThemeDirectory {
+        self.directories
+            .last_mut()
+            .expect("a directory to be added first")
+    }
+}
+
+fn fixture_directory(name: &str, size: i16, r#type: ThemeDirectoryType) -> ThemeDirectory {
+    ThemeDirectory {
+        name: name.to_owned(),
+        size,
+        scale: None,
+        context: None,
+        r#type,
+        max_size: None,
+        min_size: None,
+        threshold: None,
+    }
+}
+
+/// A set of fake themes, written to a base directory of their own. Themes are
+/// loaded from that base directory only, and finders created by the fixture
+/// search it before all others.
+pub struct Fixture {
+    base_directory: PathBuf,
+    file_system: Arc<dyn FileSystem>,
+    /// Whether the base directory is a temporary directory on disk, which is
+    /// removed when the fixture is dropped.
+    temporary: bool,
+}
+
+impl Fixture {
+    /// Writes the themes to a [`MemoryFileSystem`]. Nothing installed on the
+    /// machine is visible to finders created by the fixture, not even the
+    /// hicolor theme, so tests using it are deterministic.
+    pub fn in_memory(themes: &[ThemeFixture]) -> Fixture {
+        let file_system = MemoryFileSystem::new();
+        let base_directory = PathBuf::from(MEMORY_BASE_DIRECTORY);
+
+        for theme in themes {
+            let directory = base_directory.join(&theme.name);
+            file_system.add_file(directory.join("index.theme"), theme.index_theme());
+            for theme_directory in &theme.directories {
+                file_system.add_dir(directory.join(&theme_directory.name));
+            }
+            for (path, contents) in &theme.files {
+                file_system.add_file(directory.join(path), contents.as_slice());
+            }
+        }
+
+        Fixture {
+            base_directory,
+            file_system: Arc::new(file_system),
+            temporary: false,
+        }
+    }
+
+    /// Writes the themes to a new temporary directory, which is removed when
+    /// the fixture is dropped. Finders created by the fixture still see the
+    /// themes and unthemed icons installed on the machine, like the hicolor
+    /// fallback theme.
+    pub fn on_disk(themes: &[ThemeFixture]) -> io::Result<Fixture> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let base_directory = env::temp_dir().join(format!(
+            "icon-finder-fixture-{}-{}",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        // Removes the directory again if writing a theme fails.
+        let fixture = Fixture {
+            base_directory,
+            file_system: Arc::new(StdFileSystem),
+            temporary: true,
+        };
+
+        for theme in themes {
+            let directory = fixture.base_directory.join(&theme.name);
+            fs::create_dir_all(&directory)?;
+            fs::write(directory.join("index.theme"), theme.index_theme())?;
+            for theme_directory in &theme.directories {
+                fs::create_dir_all(directory.join(&theme_directory.name))?;
+            }
+            for (path, contents) in &theme.files {
+                let path = directory.join(path);
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, contents)?;
+            }
+        }
+
+        Ok(fixture)
+    }
+
+    /// The directory the themes are written to, holding a directory for
+    /// every theme.
+    pub fn base_directory(&self) -> &Path {
+        &self.base_directory
+    }
+
+    /// The file system the themes are written to.
+    pub fn file_system(&self) -> Arc<dyn FileSystem> {
+        Arc::clone(&self.file_system)
+    }
+
+    /// Loads one of the themes, together with the themes of the fixture it
+    /// inherits from.
+    pub fn load_theme(&self, name: &str) -> Result<Theme, Error> {
+        Theme::load_in(
+            name,
+            &*self.file_system,
+            std::slice::from_ref(&self.base_directory),
+            ParseMode::Lenient,
+        )
+    }
+
+    /// Options looking up icons in the file system of the fixture, searching
+    /// its base directory first.
+    #[cfg(test)]
+    pub(crate) fn lookup_options(&self) -> crate::LookupOptions {
+        crate::LookupOptions::new(0)
+            .with_search_paths(std::slice::from_ref(&self.base_directory), &[])
+            .with_file_system(Some(&self.file_system))
+    }
+
+    /// A finder for one of the themes, looking up icons in the file system of
+    /// the fixture.
+    pub fn finder(&self, name: &str) -> Result<IconFinder, Error> {
+        Ok(IconFinder::builder(self.load_theme(name)?)
+            .file_system(self.file_system())
+            .prepend_search_path(&self.base_directory)
+            .build())
+    }
+}
+
+impl Drop for Fixture {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_dir_all(&self.base_directory);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn themes() -> Vec<ThemeFixture> {
+        vec![
+            ThemeFixture::new("icon-finder-fixture")
+                .inherits("icon-finder-fixture-base")
+                .fixed_directory("48x48/apps", 48)
+                .threshold_directory("32x32/apps", 32, 4)
+                .icon("48x48/apps/editor.png")
+                .icon("32x32/apps/editor.png"),
+            ThemeFixture::new("icon-finder-fixture-base")
+                .hidden()
+                .scalable_directory("scalable/apps", 16, 256)
+                .icon("scalable/apps/browser.png"),
+        ]
+    }
+
+    #[test]
+    fn test_fixtures() {
+        let memory = Fixture::in_memory(&themes());
+        let disk = Fixture::on_disk(&themes()).unwrap();
+
+        for fixture in [&memory, &disk] {
+            let theme = fixture.load_theme("icon-finder-fixture").unwrap();
+            assert_eq!(theme.directories.len(), 2);
+            assert!(theme.inherits[0].hidden);
+
+            let finder = fixture.finder("icon-finder-fixture").unwrap();
+            let base = fixture.base_directory();
+            let find = |icon: &str, size: i16| finder.find_icon(icon, size, 1);
+
+            assert_eq!(
+                find("editor", 48),
+                Some(base.join("icon-finder-fixture/48x48/apps/editor.png"))
+            );
+            assert_eq!(
+                find("editor", 30),
+                Some(base.join("icon-finder-fixture/32x32/apps/editor.png"))
+            );
+            assert_eq!(
+                find("browser", 128),
+                Some(base.join("icon-finder-fixture-base/scalable/apps/browser.png"))
+            );
+        }
+        assert_eq!(
+            memory
+                .finder("icon-finder-fixture")
+                .unwrap()
+                .find_icon("folder", 48, 1),
+            None
+        );
+
+        let base_directory = disk.base_directory().to_owned();
+        drop(disk);
+        assert!(!base_directory.exists());
+    }
+}
@@ -0,0 +1,382 @@
+//! # Validating themes
+//! Loading a theme stops at the first problem in its index.theme, and quietly
+//! skips problems that do not stop the lookup, like directories that were
+//! never installed. Theme authors want to hear about all of them at once,
+//! validating a theme lists every violation of the specification found.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::filesystem::{file_system, FileSystem};
+use crate::index::{parse_groups, Group, ICON_THEME_GROUP};
+use crate::paths::base_directories;
+use crate::Error;
+
+/// The keys of a directory group with a number as value.
+const NUMBER_KEYS: [&str; 5] = ["Size", "Scale", "MinSize", "MaxSize", "Threshold"];
+
+/// A problem found in an index.theme file, see [`validate_theme`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The line of the group the problem was found in.
+    pub line: usize,
+    pub kind: DiagnosticKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DiagnosticKind {
+    /// A line that is neither a group header nor a key/value pair.
+    UnexpectedLine(String),
+    /// A group that occurs more than once.
+    DuplicateGroup(String),
+    /// A key that is set more than once in the group.
+    DuplicateKey { group: String, key: String },
+    /// A group that does not exist, like the group of a listed directory.
+    MissingGroup(String),
+    /// A required key that is not set in the group.
+    MissingKey { group: String, key: &'static str },
+    /// A key that should be a number but is not.
+    InvalidNumber {
+        group: String,
+        key: &'static str,
+        value: String,
+    },
+    /// A key that should be true or false but is not.
+    InvalidBoolean {
+        group: String,
+        key: &'static str,
+        value: String,
+    },
+    /// A directory type other than Fixed, Scalable and Threshold.
+    UnknownType { group: String, value: String },
+    /// A scalable directory without the MinSize or MaxSize key. The sizes
+    /// default to the size of the directory, so its icons are never scaled.
+    MissingSizeLimits(String),
+    /// A listed directory that does not exist in the theme directory.
+    MissingDirectory(String),
+    /// A theme in the Inherits key that is not installed.
+    MissingParent(String),
+}
+
+impl fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiagnosticKind::UnexpectedLine(line) => write!(f, "unexpected line: {}", line),
+            DiagnosticKind::DuplicateGroup(group) => write!(f, "duplicate [{}] group", group),
+            DiagnosticKind::DuplicateKey { group, key } => {
+                write!(f, "duplicate {} key in [{}]", key, group)
+            }
+            DiagnosticKind::MissingGroup(group) => write!(f, "missing [{}] group", group),
+            DiagnosticKind::MissingKey { group, key } => {
+                write!(f, "missing {} key in [{}]", key, group)
+            }
+            DiagnosticKind::InvalidNumber { group, key, value } => {
+                write!(f, "{} in [{}] is not a number: {}", key, group, value)
+            }
+            DiagnosticKind::InvalidBoolean { group, key, value } => {
+                write!(f, "{} in [{}] is not a boolean: {}", key, group, value)
+            }
+            DiagnosticKind::UnknownType { group, value } => {
+                write!(f, "unknown directory type {} in [{}]", value, group)
+            }
+            DiagnosticKind::MissingSizeLimits(group) => {
+                write!(
+                    f,
+                    "scalable directory [{}] without MinSize and MaxSize",
+                    group
+                )
+            }
+            DiagnosticKind::MissingDirectory(directory) => {
+                write!(f, "directory {} does not exist", directory)
+            }
+            DiagnosticKind::MissingParent(theme) => {
+                write!(f, "inherited theme {} is not installed", theme)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl From<Diagnostic> for Error {
+    fn from(diagnostic: Diagnostic) -> Error {
+        Error::Parse {
+            line: diagnostic.line,
+            message: diagnostic.kind.to_string(),
+        }
+    }
+}
+
+/// Checks the groups of an index.theme file for the problems parsing has to
+/// recover from: duplicate groups and keys, listed directories without a
+/// group, missing sizes and values that cannot be used. Parsing and
+/// validating themes both report these.
+pub(crate) fn check_groups(groups: &[Group]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut report = |line, kind| diagnostics.push(Diagnostic { line, kind });
+
+    for (index, group) in groups.iter().enumerate() {
+        if groups[..index].iter().any(|other| other.name == group.name) {
+            report(
+                group.line,
+                DiagnosticKind::DuplicateGroup(group.name.to_owned()),
+            );
+        }
+        for key in group.duplicate_keys() {
+            report(
+                group.line,
+                DiagnosticKind::DuplicateKey {
+                    group: group.name.to_owned(),
+                    key: key.to_owned(),
+                },
+            );
+        }
+    }
+
+    let theme_group = match groups.iter().find(|group| group.name == ICON_THEME_GROUP) {
+        Some(group) => group,
+        None => {
+            report(1, DiagnosticKind::MissingGroup(ICON_THEME_GROUP.to_owned()));
+            return diagnostics;
+        }
+    };
+
+    if theme_group.get("Name").is_none() {
+        report(
+            theme_group.line,
+            DiagnosticKind::MissingKey {
+                group: ICON_THEME_GROUP.to_owned(),
+                key: "Name",
+            },
+        );
+    }
+    match theme_group.get("Hidden") {
+        None | Some("true") | Some("false") => {}
+        Some(value) => report(
+            theme_group.line,
+            DiagnosticKind::InvalidBoolean {
+                group: ICON_THEME_GROUP.to_owned(),
+                key: "Hidden",
+                value: value.to_owned(),
+            },
+        ),
+    }
+
+    for name in directory_names(theme_group) {
+        let group = match groups.iter().find(|group| group.name == name) {
+            Some(group) => group,
+            None => {
+                report(
+                    theme_group.line,
+                    DiagnosticKind::MissingGroup(name.to_owned()),
+                );
+                continue;
+            }
+        };
+        let mut report = |kind| report(group.line, kind);
+
+        if group.get("Size").is_none() {
+            report(DiagnosticKind::MissingKey {
+                group: group.name.to_owned(),
+                key: "Size",
+            });
+        }
+        for key in NUMBER_KEYS {
+            match group.get(key) {
+                Some(value) if value.parse::<i16>().is_err() => {
+                    report(DiagnosticKind::InvalidNumber {
+                        group: group.name.to_owned(),
+                        key,
+                        value: value.to_owned(),
+                    })
+                }
+                _ => {}
+            }
+        }
+        match group.get("Type") {
+            None | Some("Fixed") | Some("Scalable") | Some("Threshold") => {}
+            Some(other) => report(DiagnosticKind::UnknownType {
+                group: group.name.to_owned(),
+                value: other.to_owned(),
+            }),
+        }
+    }
+
+    diagnostics
+}
+
+/// The names of the directories listed in the [Icon Theme] group, including
+/// the ones listed under the ScaledDirectories key.
+pub(crate) fn directory_names<'a>(theme_group: &Group<'a>) -> impl Iterator<Item = &'a str> {
+    theme_group
+        .list("Directories")
+        .chain(theme_group.list("ScaledDirectories"))
+}
+
+/// Checks a directory group for problems that do not stop a lookup, but
+/// make the theme behave other than its author intended.
+fn validate_directory(
+    group: &Group,
+    theme_directory: &Path,
+    file_system: &dyn FileSystem,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut report = |kind| {
+        diagnostics.push(Diagnostic {
+            line: group.line,
+            kind,
+        })
+    };
+
+    if group.get("Type") == Some("Scalable")
+        && (group.get("MinSize").is_none() || group.get("MaxSize").is_none())
+    {
+        report(DiagnosticKind::MissingSizeLimits(group.name.to_owned()));
+    }
+
+    if !file_system.is_dir(&theme_directory.join(group.name)) {
+        report(DiagnosticKind::MissingDirectory(group.name.to_owned()));
+    }
+}
+
+pub(crate) fn validate_theme_in(
+    theme_directory: &Path,
+    file_system: &dyn FileSystem,
+    base_directories: &[PathBuf],
+) -> Result<Vec<Diagnostic>, Error> {
+    let contents = file_system.read_to_string(&theme_directory.join("index.theme"))?;
+    let groups = parse_groups(&contents)?;
+    let mut diagnostics = check_groups(&groups);
+
+    let theme_group = match groups.iter().find(|group| group.name == ICON_THEME_GROUP) {
+        Some(group) => group,
+        None => return Ok(diagnostics),
+    };
+
+    for name in directory_names(theme_group) {
+        if let Some(group) = groups.iter().find(|group| group.name == name) {
+            validate_directory(group, theme_directory, file_system, &mut diagnostics);
+        }
+    }
+
+    // The theme itself may not be installed yet, its parents may be
+    // installed next to it instead.
+    let siblings = theme_directory.parent();
+    for parent in theme_group.list("Inherits") {
+        let installed = base_directories
+            .iter()
+            .map(PathBuf::as_path)
+            .chain(siblings)
+            .any(|directory| file_system.is_file(&directory.join(parent).join("index.theme")));
+        if !installed {
+            diagnostics.push(Diagnostic {
+                line: theme_group.line,
+                kind: DiagnosticKind::MissingParent(parent.to_owned()),
+            });
+        }
+    }
+
+    // The order of the file, problems of a group in the order checked.
+    diagnostics.sort_by_key(|diagnostic| diagnostic.line);
+    Ok(diagnostics)
+}
+
+/// Checks the theme in the directory for violations of the specification,
+/// like directories without a size, listed directories that do not exist and
+/// parents that are not installed. Fails if the index.theme file cannot be
+/// read or is not a valid key file, returns the problems found otherwise.
+pub fn validate_theme(theme_directory: &Path) -> Result<Vec<Diagnostic>, Error> {
+    validate_theme_in(theme_directory, &**file_system(), base_directories())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFileSystem;
+
+    #[test]
+    fn test_validate_theme() {
+        let fs = MemoryFileSystem::new();
+        fs.add_file(
+            "/themes/broken/index.theme",
+            "[Icon Theme]\n\
+             Directories=16x16/apps,32x32/apps,scalable/apps,24x24/apps,missing\n\
+             Inherits=sibling,installed,nowhere\n\n\
+             [16x16/apps]\nSize=16\nType=Fixed\n\n\
+             [32x32/apps]\nContext=Applications\n\n\
+             [scalable/apps]\nSize=48\nType=Scalable\nMaxSize=256\n\n\
+             [24x24/apps]\nSize=24x24\nType=Stretched\n",
+        );
+        fs.add_dir("/themes/broken/16x16/apps");
+        fs.add_dir("/themes/broken/32x32/apps");
+        fs.add_dir("/themes/broken/scalable/apps");
+        fs.add_file("/themes/sibling/index.theme", "");
+        fs.add_file("/usr/share/icons/installed/index.theme", "");
+        let base_directories = [PathBuf::from("/usr/share/icons")];
+
+        let diagnostics =
+            validate_theme_in(Path::new("/themes/broken"), &fs, &base_directories).unwrap();
+        let kinds: Vec<(usize, DiagnosticKind)> = diagnostics
+            .into_iter()
+            .map(|diagnostic| (diagnostic.line, diagnostic.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (
+                    1,
+                    DiagnosticKind::MissingKey {
+                        group: "Icon Theme".to_owned(),
+                        key: "Name"
+                    }
+                ),
+                (1, DiagnosticKind::MissingGroup("missing".to_owned())),
+                (1, DiagnosticKind::MissingParent("nowhere".to_owned())),
+                (
+                    9,
+                    DiagnosticKind::MissingKey {
+                        group: "32x32/apps".to_owned(),
+                        key: "Size"
+                    }
+                ),
+                (
+                    12,
+                    DiagnosticKind::MissingSizeLimits("scalable/apps".to_owned())
+                ),
+                (
+                    17,
+                    DiagnosticKind::InvalidNumber {
+                        group: "24x24/apps".to_owned(),
+                        key: "Size",
+                        value: "24x24".to_owned()
+                    }
+                ),
+                (
+                    17,
+                    DiagnosticKind::UnknownType {
+                        group: "24x24/apps".to_owned(),
+                        value: "Stretched".to_owned()
+                    }
+                ),
+                (
+                    17,
+                    DiagnosticKind::MissingDirectory("24x24/apps".to_owned())
+                ),
+            ]
+        );
+
+        fs.add_file("/themes/empty/index.theme", "[Other]\n");
+        let diagnostics = validate_theme_in(Path::new("/themes/empty"), &fs, &[]).unwrap();
+        assert_eq!(
+            diagnostics[0].to_string(),
+            "line 1: missing [Icon Theme] group"
+        );
+
+        assert!(validate_theme_in(Path::new("/themes/none"), &fs, &[]).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! # Walking themes
+//! Tools like statistics, converters and validators need every icon file of
+//! a theme along with the directory it is in, without knowing where themes
+//! are installed or how their directories are laid out. Walking a theme
+//! visits every file of every directory of the theme, in every base
+//! directory. Like [`find_duplicate_icons`](crate::find_duplicate_icons),
+//! only the directories of the theme itself are walked, the themes it
+//! inherits from can be walked through [`Theme::ancestry`].
+
+use std::path::PathBuf;
+
+use crate::enumerate::visit_directories;
+use crate::listing;
+use crate::{IconFormat, LookupOptions, Theme, ThemeDirectory};
+
+/// An icon file found by [`walk_theme`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IconFile {
+    /// The name the icon is looked up by.
+    pub name: String,
+    pub path: PathBuf,
+    pub format: IconFormat,
+}
+
+pub(crate) fn walk_theme_with(
+    theme: &Theme,
+    options: &LookupOptions,
+    mut visit: impl FnMut(&ThemeDirectory, &IconFile),
+) {
+    visit_directories(theme, options, |subdir, directory, mut names| {
+        names.sort();
+        for name in names {
+            for (path, format) in listing::icon_files(directory, &name, options) {
+                let file = IconFile {
+                    name: name.clone(),
+                    path,
+                    format,
+                };
+                visit(subdir, &file);
+            }
+        }
+    });
+}
+
+/// Calls `visit` with every icon file of the theme and the directory of the
+/// theme it is in. Directories are visited in the order of the index.theme
+/// file, once for every base directory holding them, their icons in
+/// alphabetical order. An icon with files in several formats is visited once
+/// for every file, in the order of preference of the lookup.
+pub fn walk_theme(theme: &Theme, visit: impl FnMut(&ThemeDirectory, &IconFile)) {
+    walk_theme_with(theme, &LookupOptions::new(0), visit)
+}
+
+// The test tells SVG icons apart from PNG icons.
+#[cfg(all(test, feature = "svg"))]
+mod tests {
+    use super::*;
+    use crate::testing::{Fixture, ThemeFixture};
+
+    #[test]
+    fn test_walk_theme() {
+        let fixture = Fixture::in_memory(&[
+            ThemeFixture::new("icon-finder-walk")
+                .inherits("icon-finder-walk-parent")
+                .fixed_directory("16x16/apps", 16)
+                .scalable_directory("scalable/apps", 16, 48)
+                .icon("16x16/apps/terminal.png")
+                .icon("16x16/apps/editor.svg")
+                .icon("16x16/apps/editor.png")
+                .icon("16x16/apps/README")
+                .icon("scalable/apps/editor.svg"),
+            ThemeFixture::new("icon-finder-walk-parent")
+                .fixed_directory("16x16/apps", 16)
+                .icon("16x16/apps/browser.png"),
+        ]);
+        let theme = fixture.load_theme("icon-finder-walk").unwrap();
+        let options = fixture.lookup_options();
+        let directory = fixture.base_directory().join("icon-finder-walk");
+
+        let mut files = Vec::new();
+        walk_theme_with(&theme, &options, |subdir, file| {
+            files.push((subdir.name.clone(), file.clone()));
+        });
+        let file = |subdir: &str, name: &str, extension: &str, format| {
+            let path = directory
+                .join(subdir)
+                .join(format!("{}.{}", name, extension));
+            (
+                subdir.to_owned(),
+                IconFile {
+                    name: name.to_owned(),
+                    path,
+                    format,
+                },
+            )
+        };
+        assert_eq!(
+            files,
+            [
+                file("16x16/apps", "editor", "png", IconFormat::Png),
+                file("16x16/apps", "editor", "svg", IconFormat::Svg),
+                file("16x16/apps", "terminal", "png", IconFormat::Png),
+                file("scalable/apps", "editor", "svg", IconFormat::Svg),
+            ]
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! # Warnings
+//! Some problems with the installed themes do not stop a lookup, but may
+//! explain why an icon does not show up, like a symbolic link pointing to an
+//! icon that was removed. The lookup skips over these and reports them to
+//! the warning hook of the process, which ignores them unless set with
+//! [`set_warning_hook`].
+
+#[cfg(test)]
+use std::cell::RefCell;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+/// A problem the lookup skipped over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A symbolic link pointing to a file that does not exist.
+    BrokenLink(PathBuf),
+    /// A directory that exists but could not be read, e.g. for lack of
+    /// permissions. The lookup continues as if it held no icons.
+    UnreadableDirectory { path: PathBuf, kind: io::ErrorKind },
+    /// A problem in an index.theme file that parsing recovered from, see
+    /// [`ParseMode::Lenient`](crate::ParseMode::Lenient).
+    InvalidIndexTheme {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::BrokenLink(path) => write!(f, "broken symbolic link {}", path.display()),
+            Warning::UnreadableDirectory { path, kind } => {
+                write!(f, "could not read {}: {}", path.display(), kind)
+            }
+            Warning::InvalidIndexTheme {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+        }
+    }
+}
+
+type WarningHook = Arc<dyn Fn(&Warning) + Send + Sync>;
+
+static HOOK: RwLock<Option<WarningHook>> = RwLock::new(None);
+
+#[cfg(test)]
+thread_local! {
+    /// The warnings collected by [`capture_warnings`] on this thread.
+    static CAPTURED: RefCell<Option<Vec<Warning>>> = const { RefCell::new(None) };
+}
+
+/// Calls the hook with every warning of the lookups of the process, e.g. to
+/// show them in the log of an application. Replaces the previous hook.
+///
+/// ```
+/// icon_finder::set_warning_hook(|warning| eprintln!("icons: {}", warning));
+/// ```
+pub fn set_warning_hook(hook: impl Fn(&Warning) + Send + Sync + 'static) {
+    *HOOK.write().unwrap_or_else(|error| error.into_inner()) = Some(Arc::new(hook));
+}
+
+/// Reports the warning to the hook of the process.
+pub(crate) fn warn(warning: Warning) {
+    #[cfg(feature = "log")]
+    log::debug!("{}", warning);
+
+    #[cfg(test)]
+    let warning = match CAPTURED.with_borrow_mut(|captured| match captured {
+        Some(captured) => {
+            captured.push(warning);
+            None
+        }
+        None => Some(warning),
+    }) {
+        Some(warning) => warning,
+        None => return,
+    };
+
+    // The hook is called without holding the lock, so it may set another
+    // hook.
+    let hook = HOOK
+        .read()
+        .unwrap_or_else(|error| error.into_inner())
+        .clone();
+    if let Some(hook) = hook {
+        hook(&warning);
+    }
+}
+
+/// Runs the function, returning the warnings it reported on this thread
+/// instead of passing them to the hook of the process. Tests running in
+/// parallel each see their own warnings, without replacing the hook.
+#[cfg(test)]
+pub(crate) fn capture_warnings<T>(function: impl FnOnce() -> T) -> (T, Vec<Warning>) {
+    let outer = CAPTURED.replace(Some(Vec::new()));
+    let result = function();
+    let warnings = CAPTURED.replace(outer).unwrap_or_default();
+
+    (result, warnings)
+}
+
+/// Reports a directory that could not be read. Missing directories are not
+/// reported, every theme only exists in some of the base directories.
+pub(crate) fn warn_unreadable(path: &Path, error: &io::Error) {
+    if error.kind() != io::ErrorKind::NotFound {
+        warn(Warning::UnreadableDirectory {
+            path: path.to_owned(),
+            kind: error.kind(),
+        });
+    }
+}
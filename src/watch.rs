@@ -0,0 +1,269 @@
+//! # Watching for changes
+//! Long running applications, like panels and docks, need to refresh their
+//! icons when the user selects a different icon theme or when icons are
+//! installed into a theme. Following the implementation notes of the
+//! specification, changes on disk are detected by looking at the mtime of the
+//! toplevel theme directories. Settings applications need to update their
+//! list of themes when themes are installed or removed, which is detected by
+//! listing the base directories.
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::filesystem::FileSystem;
+use crate::installed_theme_names;
+
+/// A change that affects which icons an application should display.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThemeEvent {
+    /// The user selected a different icon theme, holding the name of the newly
+    /// selected theme.
+    ThemeChanged(String),
+    /// The contents of a toplevel theme directory changed on disk.
+    IconsChanged(PathBuf),
+    /// A theme was installed into one of the base directories, holding the
+    /// internal name of the theme.
+    ThemeInstalled(String),
+    /// A theme is no longer installed in any of the base directories,
+    /// holding the internal name of the theme.
+    ThemeRemoved(String),
+}
+
+/// Registration for theme events, as returned by
+/// [`IconFinder::subscribe`](crate::IconFinder::subscribe). Dropping the
+/// subscription stops watching for changes.
+pub struct Subscription {
+    receiver: Receiver<ThemeEvent>,
+    stop: Option<Sender<()>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Subscription {
+    pub(crate) fn spawn(watcher: Watcher, interval: Duration) -> Subscription {
+        let (sender, receiver) = mpsc::channel();
+        let (stop, worker) =
+            spawn_worker(watcher, interval, move |event| sender.send(event).is_ok());
+
+        Subscription {
+            receiver,
+            stop: Some(stop),
+            worker: Some(worker),
+        }
+    }
+
+    /// Blocks until the next event arrives.
+    pub fn recv(&self) -> Option<ThemeEvent> {
+        self.receiver.recv().ok()
+    }
+
+    /// Returns the next event if one is pending, without blocking.
+    pub fn try_recv(&self) -> Option<ThemeEvent> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// Blocks until the next event arrives or the timeout expires.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<ThemeEvent> {
+        self.receiver.recv_timeout(timeout).ok()
+    }
+}
+
+impl Iterator for Subscription {
+    type Item = ThemeEvent;
+
+    fn next(&mut self) -> Option<ThemeEvent> {
+        self.recv()
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // Disconnecting the stop channel wakes the worker up immediately.
+        self.stop.take();
+
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Polls the watcher on a thread of its own, passing every event to `send`
+/// until it returns false or the returned sender is dropped.
+pub(crate) fn spawn_worker<F>(
+    mut watcher: Watcher,
+    interval: Duration,
+    send: F,
+) -> (Sender<()>, JoinHandle<()>)
+where
+    F: Fn(ThemeEvent) -> bool + Send + 'static,
+{
+    let (stop, stopped) = mpsc::channel::<()>();
+
+    let worker = thread::spawn(move || {
+        while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+            for event in watcher.poll() {
+                if !send(event) {
+                    return;
+                }
+            }
+        }
+    });
+
+    (stop, worker)
+}
+
+/// Remembers the state of the watched theme setting and directories, so
+/// changes can be detected by comparing against it.
+pub(crate) struct Watcher {
+    read_theme: fn() -> Option<String>,
+    theme: Option<String>,
+    file_system: Arc<dyn FileSystem>,
+    directories: Vec<(PathBuf, Option<SystemTime>)>,
+    base_directories: Vec<PathBuf>,
+    /// The themes installed in the base directories.
+    themes: BTreeSet<String>,
+}
+
+impl Watcher {
+    pub(crate) fn new(
+        read_theme: fn() -> Option<String>,
+        directories: Vec<PathBuf>,
+        base_directories: Vec<PathBuf>,
+        file_system: Arc<dyn FileSystem>,
+    ) -> Watcher {
+        let directories = directories
+            .into_iter()
+            .map(|directory| {
+                let mtime = modified(&*file_system, &directory);
+                (directory, mtime)
+            })
+            .collect();
+
+        Watcher {
+            read_theme,
+            theme: read_theme(),
+            themes: installed_themes(&*file_system, &base_directories),
+            file_system,
+            directories,
+            base_directories,
+        }
+    }
+
+    /// Compares the current state against the last seen state and returns
+    /// the events describing the differences.
+    pub(crate) fn poll(&mut self) -> Vec<ThemeEvent> {
+        let mut events = Vec::new();
+
+        let theme = (self.read_theme)();
+        if theme != self.theme {
+            if let Some(name) = &theme {
+                events.push(ThemeEvent::ThemeChanged(name.clone()));
+            }
+            self.theme = theme;
+        }
+
+        let themes = installed_themes(&*self.file_system, &self.base_directories);
+        for name in themes.difference(&self.themes) {
+            events.push(ThemeEvent::ThemeInstalled(name.clone()));
+        }
+        for name in self.themes.difference(&themes) {
+            events.push(ThemeEvent::ThemeRemoved(name.clone()));
+        }
+        self.themes = themes;
+
+        for (directory, last_modified) in &mut self.directories {
+            let mtime = modified(&*self.file_system, directory);
+            if mtime != *last_modified {
+                events.push(ThemeEvent::IconsChanged(directory.clone()));
+                *last_modified = mtime;
+            }
+        }
+
+        events
+    }
+}
+
+fn installed_themes(
+    file_system: &dyn FileSystem,
+    base_directories: &[PathBuf],
+) -> BTreeSet<String> {
+    installed_theme_names(file_system, base_directories)
+        .into_iter()
+        .collect()
+}
+
+fn modified(file_system: &dyn FileSystem, directory: &Path) -> Option<SystemTime> {
+    file_system.modified(directory).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    fn no_theme() -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn test_watcher_detects_directory_changes() {
+        let directory = env::temp_dir().join(format!("icon-finder-watch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        let mut watcher = Watcher::new(
+            no_theme,
+            vec![directory.clone()],
+            Vec::new(),
+            Arc::new(crate::StdFileSystem),
+        );
+        assert_eq!(watcher.poll(), vec![]);
+
+        fs::create_dir_all(&directory).unwrap();
+        assert_eq!(
+            watcher.poll(),
+            vec![ThemeEvent::IconsChanged(directory.clone())]
+        );
+        assert_eq!(watcher.poll(), vec![]);
+
+        fs::remove_dir_all(&directory).unwrap();
+        assert_eq!(watcher.poll(), vec![ThemeEvent::IconsChanged(directory)]);
+    }
+
+    #[test]
+    fn test_watcher_detects_installed_themes() {
+        let fs = Arc::new(crate::MemoryFileSystem::new());
+        fs.add_file("/usr/share/icons/hicolor/index.theme", "");
+        fs.add_file("/home/user/.icons/cursors/cursors/left_ptr", "");
+        let base_directories = vec![
+            PathBuf::from("/home/user/.icons"),
+            PathBuf::from("/usr/share/icons"),
+        ];
+        let mut watcher = Watcher::new(no_theme, Vec::new(), base_directories, fs.clone());
+        assert_eq!(watcher.poll(), vec![]);
+
+        fs.add_file("/home/user/.icons/Papirus/index.theme", "");
+        assert_eq!(
+            watcher.poll(),
+            vec![ThemeEvent::ThemeInstalled("Papirus".to_owned())]
+        );
+
+        // Still installed in another base directory.
+        fs.add_file("/usr/share/icons/Papirus/index.theme", "");
+        fs.remove("/home/user/.icons/Papirus");
+        assert_eq!(watcher.poll(), vec![]);
+
+        fs.remove("/usr/share/icons/Papirus");
+        fs.remove("/usr/share/icons/hicolor/index.theme");
+        assert_eq!(
+            watcher.poll(),
+            vec![
+                ThemeEvent::ThemeRemoved("Papirus".to_owned()),
+                ThemeEvent::ThemeRemoved("hicolor".to_owned()),
+            ]
+        );
+    }
+}
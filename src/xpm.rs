@@ -0,0 +1,297 @@
+//! # XPM images
+//! XPM is the format of X11 pixmaps, which legacy applications still install
+//! their icons in. An XPM image is C source code declaring an array of
+//! strings: a header with the width, height, number of colors and characters
+//! per pixel, followed by the colors and finally the rows of pixels.
+//!
+//! ```text
+//! /* XPM */
+//! static char *icon[] = {
+//! "2 2 2 1",
+//! ". c None",
+//! "# c #ff0000",
+//! "#.",
+//! ".#"};
+//! ```
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::io;
+
+use crate::IconPixels;
+
+/// The X11 color names XPM images commonly use, besides the gray levels.
+const COLOR_NAMES: &[(&str, [u8; 3])] = &[
+    ("black", [0, 0, 0]),
+    ("white", [255, 255, 255]),
+    ("red", [255, 0, 0]),
+    ("green", [0, 255, 0]),
+    ("blue", [0, 0, 255]),
+    ("yellow", [255, 255, 0]),
+    ("cyan", [0, 255, 255]),
+    ("magenta", [255, 0, 255]),
+    ("gray", [190, 190, 190]),
+    ("grey", [190, 190, 190]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgrey", [169, 169, 169]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgrey", [211, 211, 211]),
+    ("orange", [255, 165, 0]),
+    ("brown", [165, 42, 42]),
+    ("navy", [0, 0, 128]),
+];
+
+/// The keys of the visuals a color can be given for, in order of preference.
+const COLOR_KEYS: [&str; 4] = ["c", "g", "g4", "m"];
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
+
+/// The string literals of the C source, in order, leaving out comments.
+fn string_literals(source: &str) -> Vec<String> {
+    let mut literals = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '"' => {
+                let mut literal = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => literal.extend(chars.next()),
+                        c => literal.push(c),
+                    }
+                }
+                literals.push(literal);
+            }
+            _ => {}
+        }
+    }
+
+    literals
+}
+
+/// The RGBA color of an XPM color value, a name, #RGB-style hex or None.
+fn parse_color(value: &str) -> Option<[u8; 4]> {
+    let name = value.to_ascii_lowercase().replace(' ', "");
+    if name == "none" {
+        return Some([0, 0, 0, 0]);
+    }
+
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.is_empty() || hex.len() % 3 != 0 || hex.len() > 12 {
+            return None;
+        }
+        let digits = hex.len() / 3;
+        let mut rgba = [0, 0, 0, 255];
+        for (channel, value) in rgba.iter_mut().zip(hex.as_bytes().chunks(digits)) {
+            let value = u16::from_str_radix(std::str::from_utf8(value).ok()?, 16).ok()?;
+            // Keep the most significant byte, scaling up short channels.
+            *channel = match digits {
+                1 => (value * 17) as u8,
+                2 => value as u8,
+                _ => (value >> (4 * (digits - 2))) as u8,
+            };
+        }
+        return Some(rgba);
+    }
+
+    for prefix in ["gray", "grey"] {
+        if let Some(level) = name.strip_prefix(prefix).filter(|level| !level.is_empty()) {
+            let level: u32 = level.parse().ok().filter(|level| *level <= 100)?;
+            let value = ((level * 255 + 50) / 100) as u8;
+            return Some([value, value, value, 255]);
+        }
+    }
+
+    COLOR_NAMES
+        .iter()
+        .find(|(color, _)| *color == name)
+        .map(|(_, [r, g, b])| [*r, *g, *b, 255])
+}
+
+/// The color of a color line without its pixel characters, like
+/// "c #ff0000 m black", preferring the color for color displays.
+fn line_color(line: &str) -> Option<[u8; 4]> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let mut values: HashMap<&str, String> = HashMap::new();
+
+    let mut i = 0;
+    while i < words.len() {
+        let key = words[i];
+        // Color names may consist of several words, like "light gray".
+        let end = words[i + 1..]
+            .iter()
+            .position(|word| COLOR_KEYS.contains(word) || *word == "s")
+            .map_or(words.len(), |position| i + 1 + position);
+        values.insert(key, words[i + 1..end].join(" "));
+        i = end.max(i + 1);
+    }
+
+    COLOR_KEYS
+        .iter()
+        .find_map(|key| values.get(key))
+        .and_then(|value| parse_color(value))
+}
+
+/// Decodes an XPM image into RGBA pixels.
+pub(crate) fn decode(data: &[u8]) -> io::Result<IconPixels> {
+    let source = String::from_utf8_lossy(data);
+    let literals = string_literals(&source);
+    let mut literals = literals.iter();
+
+    let header = literals
+        .next()
+        .ok_or_else(|| invalid("missing XPM header"))?;
+    let values: Vec<usize> = header
+        .split_whitespace()
+        .take(4)
+        .map(|value| value.parse().map_err(|_| invalid("invalid XPM header")))
+        .collect::<io::Result<_>>()?;
+    let (width, height, colors, chars_per_pixel) = match values[..] {
+        [width, height, colors, chars_per_pixel] if chars_per_pixel > 0 => {
+            (width, height, colors, chars_per_pixel)
+        }
+        _ => return Err(invalid("invalid XPM header")),
+    };
+
+    // Every pixel, color and row takes at least one character of the data,
+    // so headers claiming more than the data holds are rejected before
+    // anything is allocated for them.
+    let size = width
+        .checked_mul(height)
+        .filter(|pixels| *pixels <= source.len())
+        .zip(u32::try_from(width).ok())
+        .zip(u32::try_from(height).ok());
+    let lines = colors.checked_add(height);
+    let (pixel_count, width_u32, height_u32) = match (size, lines) {
+        (Some(((pixels, width), height)), Some(lines)) if lines <= literals.len() => {
+            (pixels, width, height)
+        }
+        _ => return Err(invalid("XPM header does not match its data")),
+    };
+
+    let mut palette = HashMap::with_capacity(colors);
+    for _ in 0..colors {
+        let line = literals
+            .next()
+            .ok_or_else(|| invalid("missing XPM color"))?;
+        let key = line
+            .get(..chars_per_pixel)
+            .ok_or_else(|| invalid("invalid XPM color"))?;
+        let color =
+            line_color(&line[chars_per_pixel..]).ok_or_else(|| invalid("unknown XPM color"))?;
+        palette.insert(key, color);
+    }
+
+    let mut pixels = Vec::with_capacity(pixel_count * 4);
+    for _ in 0..height {
+        let row = literals.next().ok_or_else(|| invalid("missing XPM row"))?;
+        let row: Vec<char> = row.chars().collect();
+        let row_length = width.checked_mul(chars_per_pixel);
+        if row_length.filter(|length| row.len() >= *length).is_none() {
+            return Err(invalid("XPM row is too short"));
+        }
+        for pixel in row.chunks(chars_per_pixel).take(width) {
+            let key: String = pixel.iter().collect();
+            let color = palette
+                .get(key.as_str())
+                .ok_or_else(|| invalid("XPM pixel has no color"))?;
+            pixels.extend_from_slice(color);
+        }
+    }
+
+    Ok(IconPixels {
+        width: width_u32,
+        height: height_u32,
+        data: pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("None"), Some([0, 0, 0, 0]));
+        assert_eq!(parse_color("#f00"), Some([255, 0, 0, 255]));
+        assert_eq!(parse_color("#00FF80"), Some([0, 255, 128, 255]));
+        assert_eq!(parse_color("#ffff00008000"), Some([255, 0, 128, 255]));
+        assert_eq!(parse_color("gray50"), Some([128, 128, 128, 255]));
+        assert_eq!(parse_color("Light Gray"), Some([211, 211, 211, 255]));
+        assert_eq!(parse_color("#ff"), None);
+        assert_eq!(parse_color("gray101"), None);
+        assert_eq!(parse_color("chartreuse"), None);
+    }
+
+    #[test]
+    fn test_decode() {
+        let xpm = b"/* XPM */\n\
+            static char *icon[] = {\n\
+            /* columns rows colors chars-per-pixel */\n\
+            \"3 2 3 2\",\n\
+            \"   c None\",\n\
+            \"rr s red c #ff0000 m black\",\n\
+            \"gg c light gray\",\n\
+            \"rr  gg\",\n\
+            \"gggg  \"};\n";
+
+        let pixels = decode(xpm).unwrap();
+        assert_eq!((pixels.width, pixels.height), (3, 2));
+        assert_eq!(
+            pixels.data,
+            [
+                [255, 0, 0, 255],
+                [0, 0, 0, 0],
+                [211, 211, 211, 255],
+                [211, 211, 211, 255],
+                [211, 211, 211, 255],
+                [0, 0, 0, 0],
+            ]
+            .concat()
+        );
+
+        assert!(decode(
+            b"/* XPM */ static char *icon[] = {\"1 1 1 1\", \". c chartreuse\", \".\"};"
+        )
+        .is_err());
+        assert!(
+            decode(b"/* XPM */ static char *icon[] = {\"2 1 1 1\", \". c red\", \".\"};").is_err()
+        );
+        assert!(decode(b"not an image").is_err());
+    }
+
+    #[test]
+    fn test_decode_oversized_header() {
+        let decode_header = |header: &str| {
+            let xpm = format!(
+                "/* XPM */ static char *icon[] = {{\"{}\", \". c red\", \".\"}};",
+                header
+            );
+            decode(xpm.as_bytes()).unwrap_err().to_string()
+        };
+        let mismatch = "XPM header does not match its data";
+
+        assert_eq!(decode_header("100000 100000 1 1"), mismatch);
+        assert_eq!(decode_header("1 1 4000000000 1"), mismatch);
+        assert_eq!(decode_header("1 18446744073709551615 1 1"), mismatch);
+        assert_eq!(decode_header("18446744073709551615 0 1 1"), mismatch);
+        assert_eq!(
+            decode_header("1 1 1 18446744073709551615"),
+            "invalid XPM color"
+        );
+    }
+}